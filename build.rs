@@ -0,0 +1,56 @@
+use std::{env, fs, path::Path, process::Command};
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(turboselect_tuned)");
+    println!("cargo::rustc-check-cfg=cfg(turboselect_nightly)");
+    println!("cargo::rerun-if-env-changed=TURBOSELECT_PARAMS");
+
+    #[cfg(feature = "cppbench")]
+    compile_nth_element();
+    compile_params();
+    detect_nightly();
+}
+
+/// With the `cppbench` feature, compile the shim that lets the benchmarks compare against
+/// `std::nth_element` from the C++ standard library.
+#[cfg(feature = "cppbench")]
+fn compile_nth_element() {
+    println!("cargo::rerun-if-changed=cpp/nth_element.cpp");
+    cc::Build::new()
+        .cpp(true)
+        .opt_level(3)
+        .file("cpp/nth_element.cpp")
+        .compile("nth_element");
+}
+
+/// With the `autotune` feature, compile in the thresholds from the file generated with
+/// `turboselect::tune`, if one is given. Relative paths are relative to this crate's root.
+fn compile_params() {
+    if env::var_os("CARGO_FEATURE_AUTOTUNE").is_none() {
+        return;
+    }
+    let Some(path) = env::var_os("TURBOSELECT_PARAMS") else {
+        return;
+    };
+    let out = Path::new(&env::var_os("OUT_DIR").unwrap()).join("params.rs");
+    println!("cargo::rerun-if-changed={}", Path::new(&path).display());
+    fs::copy(&path, out).expect("failed to copy the file named by TURBOSELECT_PARAMS");
+    println!("cargo::rustc-cfg=turboselect_tuned");
+}
+
+/// With the `simd` feature, use `core::simd` if the compiler is a nightly one, which is the only
+/// kind that allows the unstable `portable_simd` feature. Other compilers build the same kernels
+/// from scalar comparisons.
+fn detect_nightly() {
+    if env::var_os("CARGO_FEATURE_SIMD").is_none() {
+        return;
+    }
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let Ok(output) = Command::new(rustc).arg("--version").output() else {
+        return;
+    };
+    let version = String::from_utf8_lossy(&output.stdout);
+    if version.contains("nightly") || version.contains("-dev") {
+        println!("cargo::rustc-cfg=turboselect_nightly");
+    }
+}
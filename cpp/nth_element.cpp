@@ -0,0 +1,22 @@
+// Calls std::nth_element on buffers owned by the Rust benchmarks. Compiled by the build script with
+// the `cppbench` feature.
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdint>
+
+extern "C" {
+
+void turboselect_nth_element_u32(uint32_t *data, size_t len, size_t index) {
+    std::nth_element(data, data + index, data + len);
+}
+
+void turboselect_nth_element_u64(uint64_t *data, size_t len, size_t index) {
+    std::nth_element(data, data + index, data + len);
+}
+
+void turboselect_nth_element_bool(bool *data, size_t len, size_t index) {
+    std::nth_element(data, data + index, data + len);
+}
+
+}
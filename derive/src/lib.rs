@@ -0,0 +1,181 @@
+//! The derive macro for the `SelectKey` trait of `turboselect`. Use it through the `derive` feature
+//! of `turboselect`, which re-exports it next to the trait.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Index, Member, Meta, Type};
+
+/// The largest number of key fields, which is the longest tuple that implements `Ord`.
+const MAX_KEY_FIELDS: usize = 12;
+
+/// A field that is annotated with `#[select_key]`.
+struct KeyField {
+    member: Member,
+    ty: Type,
+    reverse: bool,
+}
+
+/// Derives `SelectKey` for a struct from the fields annotated with `#[select_key]`.
+///
+/// The key is the tuple of the annotated fields in the order in which they are declared, or the
+/// field itself if only one is annotated, so the records are ordered by the first key field, then
+/// by the second one, and so on. A field annotated with `#[select_key(reverse)]` is wrapped in
+/// `Reverse`, which orders it from the largest to the smallest. The key fields must implement `Ord`
+/// and `Clone`, since [`select_key`] clones them into the key, and the generated [`cmp_key`]
+/// compares the fields in place without cloning them.
+///
+/// [`select_key`]: ../turboselect/trait.SelectKey.html#tymethod.select_key
+/// [`cmp_key`]: ../turboselect/trait.SelectKey.html#method.cmp_key
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_by, select_nth_unstable_by_cached_key, SelectKey};
+///
+/// #[derive(SelectKey)]
+/// struct Order {
+///     #[select_key]
+///     price: u32,
+///     #[select_key(reverse)]
+///     quantity: u32,
+///     customer: String,
+/// }
+///
+/// let mut orders: Vec<Order> = [(30, 1), (10, 5), (10, 7), (20, 2)]
+///     .into_iter()
+///     .map(|(price, quantity)| Order { price, quantity, customer: String::new() })
+///     .collect();
+///
+/// // The cheapest order, and the largest one of those with the same price.
+/// let (_, first, _) = select_nth_unstable_by(&mut orders, 0, Order::cmp_key);
+/// assert_eq!((first.price, first.quantity), (10, 7));
+///
+/// // The key can also be cached for keys that are expensive to compare.
+/// select_nth_unstable_by_cached_key(&mut orders, 2, Order::select_key);
+/// assert_eq!((orders[2].price, orders[2].quantity), (20, 2));
+/// ```
+#[proc_macro_derive(SelectKey, attributes(select_key))]
+pub fn derive_select_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = key_fields(input)?;
+    let name = &input.ident;
+
+    let mut generics = input.generics.clone();
+    let where_clause = generics.make_where_clause();
+    for field in &fields {
+        let ty = &field.ty;
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(#ty: ::core::cmp::Ord + ::core::clone::Clone));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let key_types = fields.iter().map(|field| {
+        let ty = &field.ty;
+        match field.reverse {
+            true => quote!(::core::cmp::Reverse<#ty>),
+            false => quote!(#ty),
+        }
+    });
+    let key_values = fields.iter().map(|field| {
+        let member = &field.member;
+        let value = quote!(::core::clone::Clone::clone(&self.#member));
+        match field.reverse {
+            true => quote!(::core::cmp::Reverse(#value)),
+            false => value,
+        }
+    });
+    let (key_type, key_value) = match fields.len() {
+        1 => (quote!(#(#key_types)*), quote!(#(#key_values)*)),
+        _ => (quote!((#(#key_types,)*)), quote!((#(#key_values,)*))),
+    };
+
+    let comparisons = fields.iter().map(|field| {
+        let member = &field.member;
+        match field.reverse {
+            true => quote!(::core::cmp::Ord::cmp(&other.#member, &self.#member)),
+            false => quote!(::core::cmp::Ord::cmp(&self.#member, &other.#member)),
+        }
+    });
+    let mut comparisons = comparisons.collect::<Vec<_>>().into_iter();
+    let first = comparisons.next();
+
+    Ok(quote! {
+        impl #impl_generics ::turboselect::SelectKey for #name #ty_generics #where_clause {
+            type Key = #key_type;
+
+            #[inline]
+            fn select_key(&self) -> Self::Key {
+                #key_value
+            }
+
+            #[inline]
+            fn cmp_key(&self, other: &Self) -> ::core::cmp::Ordering {
+                #first #(.then_with(|| #comparisons))*
+            }
+        }
+    })
+}
+
+/// Collects the fields annotated with `#[select_key]`, in the order in which they are declared.
+fn key_fields(input: &DeriveInput) -> syn::Result<Vec<KeyField>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`SelectKey` can only be derived for structs",
+        ));
+    };
+    let mut fields = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let mut attrs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("select_key"));
+        let Some(attr) = attrs.next() else {
+            continue;
+        };
+        if let Some(duplicate) = attrs.next() {
+            return Err(Error::new_spanned(
+                duplicate,
+                "duplicate `#[select_key]` attribute",
+            ));
+        }
+        let mut reverse = false;
+        if let Meta::List(_) = attr.meta {
+            attr.parse_nested_meta(|meta| match meta.path.is_ident("reverse") {
+                true => {
+                    reverse = true;
+                    Ok(())
+                }
+                false => Err(meta.error("expected `reverse`")),
+            })?;
+        }
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        fields.push(KeyField {
+            member,
+            ty: field.ty.clone(),
+            reverse,
+        });
+    }
+    match fields.len() {
+        0 => Err(Error::new(
+            Span::call_site(),
+            "at least one field must be annotated with `#[select_key]`",
+        )),
+        n if n > MAX_KEY_FIELDS => Err(Error::new(
+            Span::call_site(),
+            "at most 12 fields can be annotated with `#[select_key]`",
+        )),
+        _ => Ok(fields),
+    }
+}
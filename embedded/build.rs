@@ -0,0 +1,9 @@
+use std::{env, fs, path::PathBuf};
+
+/// Puts `memory.x` where the linker script of `cortex-m-rt` finds it.
+fn main() {
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::write(out.join("memory.x"), include_bytes!("memory.x")).unwrap();
+    println!("cargo::rustc-link-search={}", out.display());
+    println!("cargo::rerun-if-changed=memory.x");
+}
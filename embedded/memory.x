@@ -0,0 +1,6 @@
+/* The memory map of the MPS2 AN386 board emulated by QEMU. */
+MEMORY
+{
+  FLASH : ORIGIN = 0x00000000, LENGTH = 4M
+  RAM : ORIGIN = 0x20000000, LENGTH = 4M
+}
@@ -0,0 +1,171 @@
+//! Runs the core selection tests on a Cortex-M4F under QEMU.
+//!
+//! ```text
+//! rustup target add thumbv7em-none-eabihf
+//! cargo run --release
+//! ```
+//!
+//! The host tests can't catch bugs that only show up on the target, e.g. from the alignment of the
+//! elements, a 32-bit `usize`, or the `libm` implementations of the floating point functions that
+//! size the pivot samples. The results are printed through semihosting, and the exit code of QEMU
+//! is nonzero if any test fails.
+
+#![no_std]
+#![no_main]
+
+use core::cmp::Ordering;
+
+use cortex_m_rt::entry;
+use cortex_m_semihosting::{debug, hprintln};
+use panic_semihosting as _;
+use turboselect::{
+    percentile_by, select_nth_unstable, select_nth_unstable_by, select_nth_unstable_with_options,
+    selftest, SelectOptions, Strategy,
+};
+
+/// The number of rounds of the randomized suite of the crate.
+const SELFTEST_ROUNDS: usize = 20;
+
+/// A named test, which returns `true` if it passed.
+type Test = (&'static str, fn() -> bool);
+
+/// A xorshift generator, which is good enough for test data.
+struct Rng(u64);
+
+impl Rng {
+    fn u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.u64() % n as u64) as usize
+    }
+}
+
+/// Returns `true` if the element at `index` of `data` is the one at `index` of `sorted`, and the
+/// elements before and after it are on the correct side of it according to `compare`.
+fn is_selected<T, F>(data: &[T], sorted: &[T], index: usize, mut compare: F) -> bool
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let nth = &data[index];
+    compare(nth, &sorted[index]) == Ordering::Equal
+        && data[..index].iter().all(|x| compare(x, nth) != Ordering::Greater)
+        && data[index..].iter().all(|x| compare(x, nth) != Ordering::Less)
+}
+
+/// Selects random indices from `L` elements made by `make`, with `select_nth_unstable`.
+fn selects<T, const L: usize>(rng: &mut Rng, mut make: impl FnMut(&mut Rng) -> T) -> bool
+where
+    T: Ord + Copy,
+{
+    let mut input = [make(rng); L];
+    input.iter_mut().for_each(|x| *x = make(rng));
+    let mut sorted = input;
+    sorted.sort_unstable();
+    (0..8).all(|_| {
+        let index = rng.below(L);
+        let mut data = input;
+        select_nth_unstable(&mut data, index);
+        is_selected(&data, &sorted, index, T::cmp)
+    })
+}
+
+/// The randomized suite of the crate, which covers the public functions on `u16` and `u64`.
+fn suite() -> bool {
+    let report = selftest(0x5eed, SELFTEST_ROUNDS);
+    if let Some(name) = report.first_failure {
+        hprintln!("  first failed check: {}", name);
+    }
+    report.is_ok()
+}
+
+/// Every pivot strategy on slices long enough for the sample sizes computed with `libm`, including
+/// the recursive sampling and Floyd–Rivest.
+fn strategies() -> bool {
+    const LEN: usize = 4000;
+    let mut rng = Rng(1);
+    let mut input = [0u32; LEN];
+    input.iter_mut().for_each(|x| *x = rng.u64() as u32);
+    let mut sorted = input;
+    sorted.sort_unstable();
+    let strategies = [
+        Strategy::Sampling,
+        Strategy::MedianOfNinthers,
+        Strategy::FloydRivest,
+        Strategy::SamplingWithReplacement,
+        Strategy::MedianOfMedians,
+    ];
+    strategies.into_iter().all(|strategy| {
+        let options = SelectOptions {
+            strategy,
+            ..Default::default()
+        };
+        [0, 1, LEN / 100, LEN / 3, LEN / 2, LEN - 1].into_iter().all(|index| {
+            let mut data = input;
+            select_nth_unstable_with_options(&mut data, index, &options);
+            is_selected(&data, &sorted, index, u32::cmp)
+        })
+    })
+}
+
+/// Floats ordered with `total_cmp`, which are emulated in software for `f64` on this target.
+fn floats() -> bool {
+    const LEN: usize = 1000;
+    let mut rng = Rng(2);
+    let mut input = [0f64; LEN];
+    input
+        .iter_mut()
+        .for_each(|x| *x = (rng.u64() as i64) as f64 / 1e6);
+    let mut sorted = input;
+    sorted.sort_unstable_by(f64::total_cmp);
+    let index = rng.below(LEN);
+    let mut data = input;
+    select_nth_unstable_by(&mut data, index, f64::total_cmp);
+    let selected = is_selected(&data, &sorted, index, f64::total_cmp);
+
+    let mut input = [0f32; LEN];
+    input.iter_mut().for_each(|x| *x = rng.u64() as i32 as f32);
+    let mut sorted = input;
+    sorted.sort_unstable_by(f32::total_cmp);
+    let mut data = input;
+    let p90 = *percentile_by(&mut data, 0.9, f32::total_cmp);
+    selected && p90 == sorted[(0.9 * (LEN - 1) as f64) as usize]
+}
+
+/// Elements whose size is not a multiple of their alignment, or whose alignment is larger than the
+/// word size.
+fn element_layouts() -> bool {
+    let mut rng = Rng(3);
+    selects::<u8, 777>(&mut rng, |rng| rng.u64() as u8)
+        && selects::<[u8; 3], 500>(&mut rng, |rng| [0, 1, 2].map(|_| rng.u64() as u8 % 4))
+        && selects::<(u16, u8), 500>(&mut rng, |rng| (rng.u64() as u16, rng.u64() as u8))
+        && selects::<u128, 300>(&mut rng, |rng| {
+            (rng.u64() as u128) << 64 | rng.u64() as u128
+        })
+}
+
+#[entry]
+fn main() -> ! {
+    let tests: [Test; 4] = [
+        ("suite", suite),
+        ("strategies", strategies),
+        ("floats", floats),
+        ("element_layouts", element_layouts),
+    ];
+    let mut failed = 0;
+    for (name, test) in tests {
+        let ok = test();
+        hprintln!("test {} ... {}", name, if ok { "ok" } else { "FAILED" });
+        failed += !ok as usize;
+    }
+    hprintln!("{} passed, {} failed", tests.len() - failed, failed);
+    match failed {
+        0 => debug::exit(debug::EXIT_SUCCESS),
+        _ => debug::exit(debug::EXIT_FAILURE),
+    }
+    loop {}
+}
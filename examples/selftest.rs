@@ -0,0 +1,27 @@
+//! Runs the randomized self-test of the crate and reports the number of passed and failed checks.
+//!
+//! ```text
+//! cargo run --release --features selftest --example selftest -- [rounds] [seed]
+//! ```
+//!
+//! On an embedded target, call [`turboselect::selftest`] from the firmware instead, e.g. at boot,
+//! and report the counts over the debug probe or a serial port.
+
+use std::{env, process};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let rounds = args
+        .next()
+        .map_or(100, |arg| arg.parse().expect("invalid number of rounds"));
+    let seed = args
+        .next()
+        .map_or(0x5eed, |arg| arg.parse().expect("invalid seed"));
+
+    let report = turboselect::selftest(seed, rounds);
+    println!("{} checks passed, {} failed", report.passed, report.failed);
+    if let Some(name) = report.first_failure {
+        println!("The first failed check was {name}.");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,187 @@
+//! Animates the partitioning rounds of a selection in the terminal.
+//!
+//! ```text
+//! cargo run --release --example visualize -- [len] [index] [strategy]
+//! ```
+//!
+//! The slice is drawn as a bar chart, with one bar per element, or per group of adjacent elements
+//! if the slice is wider than the terminal. The first frame shows the shuffled input. Each
+//! following frame shows one round of the [`SelectReport`] on the reordered slice: the elements
+//! put below the pivot are blue, the elements equal to the pivot, or between the two pivots, are
+//! yellow, the elements put above are red, and the elements ruled out by the previous rounds are
+//! dimmed. The last frame marks the subslice that was sorted at the end in green.
+//!
+//! The strategy is one of `sampling` (the default), `ninthers`, `floyd-rivest`, `replacement` and
+//! `medians`. A round that barely shrinks the highlighted part means that the pivot was far from
+//! the selected element, which also happens with comparison functions that are not consistent.
+
+use std::{env, process, thread, time::Duration};
+
+use colored::*;
+use turboselect::{
+    select_nth_unstable_with_report, RoundKind, SelectOptions, SelectReport, Strategy,
+};
+
+/// The number of rows of the bar chart.
+const HEIGHT: usize = 16;
+/// The maximum number of columns of the bar chart.
+const WIDTH: usize = 72;
+/// The time each frame is shown.
+const DELAY: Duration = Duration::from_millis(800);
+
+/// The part of the slice that an element belongs to in a frame.
+#[derive(Clone, Copy)]
+enum Part {
+    Input,
+    Settled,
+    Below,
+    Middle,
+    Above,
+    Sorted,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let len = args.first().map_or(WIDTH, |arg| parse(arg, "length"));
+    let index = args.get(1).map_or(len / 3, |arg| parse(arg, "index"));
+    let strategy = match args.get(2).map(String::as_str) {
+        None | Some("sampling") => Strategy::Sampling,
+        Some("ninthers") => Strategy::MedianOfNinthers,
+        Some("floyd-rivest") => Strategy::FloydRivest,
+        Some("replacement") => Strategy::SamplingWithReplacement,
+        Some("medians") => Strategy::MedianOfMedians,
+        Some(other) => fail(&format!("unknown strategy `{other}`")),
+    };
+    if index >= len {
+        fail("the index must be less than the length");
+    }
+
+    let mut data = shuffled(len);
+    let mut parts = vec![Part::Input; len];
+    draw(&data, &parts, index, "input");
+
+    let options = SelectOptions {
+        strategy,
+        deterministic: true,
+        ..Default::default()
+    };
+    let report = select_nth_unstable_with_report(&mut data, index, &options);
+    replay(&data, index, &report, &mut parts);
+}
+
+/// Draws a frame for every round of the report, keeping track of the subslice that each round
+/// partitioned.
+fn replay(data: &[u32], index: usize, report: &SelectReport, parts: &mut [Part]) {
+    parts.fill(Part::Settled);
+    let mut start = 0;
+    let total = report.rounds.len();
+    for (i, round) in report.rounds.iter().enumerate() {
+        let middle = start + round.below;
+        let above = middle + round.middle;
+        parts[start..middle].fill(Part::Below);
+        parts[middle..above].fill(Part::Middle);
+        parts[above..start + round.len].fill(Part::Above);
+        let title = format!(
+            "round {}/{total}: {:?}, {} elements, index {}, {} below, {} in the middle",
+            i + 1,
+            round.kind,
+            round.len,
+            round.index,
+            round.below,
+            round.middle,
+        );
+        draw(data, parts, index, &title);
+
+        parts[start..start + round.len].fill(Part::Settled);
+        match round.kind {
+            RoundKind::TwoPivots if round.bracketed => start = middle,
+            _ if round.index >= round.below + round.middle => start = above,
+            _ => {}
+        }
+    }
+
+    let title = if report.presorted {
+        "the slice was sorted already".to_string()
+    } else if report.already_partitioned {
+        "the slice was partitioned at the index already".to_string()
+    } else if report.sorted_len > 0 {
+        parts[start..start + report.sorted_len].fill(Part::Sorted);
+        format!("sorted the last {} elements", report.sorted_len)
+    } else {
+        "done".to_string()
+    };
+    draw(data, parts, index, &title);
+}
+
+/// Clears the terminal and draws the slice as a bar chart, with a marker under the element at
+/// `index`.
+fn draw(data: &[u32], parts: &[Part], index: usize, title: &str) {
+    let len = data.len();
+    let columns = len.min(WIDTH);
+    // The elements are grouped into columns, each drawn as its largest element and colored by the
+    // part of its middle element.
+    let group = |c: usize| c * len / columns..(c + 1) * len / columns;
+    let heights: Vec<usize> = (0..columns)
+        .map(|c| {
+            let max = data[group(c)].iter().max().copied().unwrap_or(0) as usize;
+            (max + 1) * HEIGHT / len
+        })
+        .collect();
+
+    let mut frame = String::from("\x1b[2J\x1b[H");
+    frame += &format!("{}\n\n", title.bold());
+    for row in (0..HEIGHT).rev() {
+        for (c, &height) in heights.iter().enumerate() {
+            let range = group(c);
+            let bar = match height > row {
+                true => "█",
+                false => " ",
+            };
+            frame += &paint(bar, parts[(range.start + range.end) / 2]).to_string();
+        }
+        frame.push('\n');
+    }
+    let marker = (0..columns).position(|c| group(c).contains(&index));
+    frame += &format!("{}^ {index}\n", " ".repeat(marker.unwrap_or(0)));
+    print!("{frame}");
+    thread::sleep(DELAY);
+}
+
+/// Colors a bar by the part of the slice it belongs to.
+fn paint(bar: &str, part: Part) -> ColoredString {
+    match part {
+        Part::Input => bar.white(),
+        Part::Settled => bar.dimmed(),
+        Part::Below => bar.blue(),
+        Part::Middle => bar.yellow(),
+        Part::Above => bar.red(),
+        Part::Sorted => bar.green(),
+    }
+}
+
+/// Returns a permutation of `0..len` shuffled with a fixed seed, so that runs are repeatable.
+fn shuffled(len: usize) -> Vec<u32> {
+    let mut data: Vec<u32> = (0..len as u32).collect();
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    for i in (1..len).rev() {
+        // Xorshift is good enough for a demo.
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+    data
+}
+
+fn parse(arg: &str, what: &str) -> usize {
+    arg.parse()
+        .unwrap_or_else(|_| fail(&format!("invalid {what} `{arg}`")))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {message}");
+    eprintln!(
+        "usage: visualize [len] [index] [sampling|ninthers|floyd-rivest|replacement|medians]"
+    );
+    process::exit(1);
+}
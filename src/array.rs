@@ -0,0 +1,101 @@
+use core::{cmp::Ordering, mem};
+
+use crate::{select_nth_unstable_by_lt, sort::network_sort, split_partition};
+
+/// The largest array length that is sorted with a sorting network specialized for the length.
+const MAX_NETWORK_LEN: usize = 16;
+
+/// Reorders the fixed-size array `data` such that the element at `index` is at its final sorted
+/// position.
+///
+/// This behaves like [`select_nth_unstable`](crate::select_nth_unstable), but since the length is
+/// known at compile time, arrays of up to 16 elements are sorted with the sorting network for
+/// exactly `N` elements, without any dispatch on the length. This makes it a good fit for small
+/// buffers in embedded code. Longer arrays are handled like slices.
+///
+/// # Panics
+///
+/// Panics when `index >= N`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_array;
+/// let mut v = [9u16, 3, 7, 1, 5, 8, 2, 6];
+/// let (_, median, _) = select_nth_unstable_array(&mut v, 4);
+/// assert_eq!(*median, 6);
+/// ```
+#[inline]
+pub fn select_nth_unstable_array<T, const N: usize>(
+    data: &mut [T; N],
+    index: usize,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Ord,
+{
+    select_nth_unstable_array_by(data, index, T::cmp)
+}
+
+/// Reorders the fixed-size array `data` with a comparator function such that the element at
+/// `index` is at its final sorted position.
+///
+/// This is the comparator form of [`select_nth_unstable_array`].
+///
+/// # Panics
+///
+/// Panics when `index >= N`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_array_by;
+/// let mut v = [9u16, 3, 7, 1, 5, 8, 2, 6];
+/// let (_, second, _) = select_nth_unstable_array_by(&mut v, 1, |a, b| b.cmp(a));
+/// assert_eq!(*second, 8);
+/// ```
+#[inline]
+pub fn select_nth_unstable_array_by<T, F, const N: usize>(
+    data: &mut [T; N],
+    index: usize,
+    mut compare: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_array_by_lt(data, index, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the fixed-size array `data` with a "less than" function such that the element at
+/// `index` is at its final sorted position.
+///
+/// This is the "less than" form of [`select_nth_unstable_array`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= N`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_array_by_lt;
+/// let mut v = [9u16, 3, 7, 1, 5, 8, 2, 6];
+/// let (_, second, _) = select_nth_unstable_array_by_lt(&mut v, 1, |a, b| a > b);
+/// assert_eq!(*second, 8);
+/// ```
+#[inline]
+pub fn select_nth_unstable_array_by_lt<T, F, const N: usize>(
+    data: &mut [T; N],
+    index: usize,
+    mut lt: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(index < N);
+    if N > MAX_NETWORK_LEN || mem::size_of::<T>() == 0 {
+        return select_nth_unstable_by_lt(data, index, lt);
+    }
+    network_sort::<_, _, N>(data, &mut lt);
+    split_partition(data, index)
+}
@@ -0,0 +1,352 @@
+use core::arch::x86_64::*;
+
+use crate::{
+    lanes::{offsets_ge_by, offsets_lt_rev_by, LANES},
+    partition_at_classified, Classify, SortOrder,
+};
+
+/// Returns `true` if the processor supports AVX2. The result of the detection is cached by `std`,
+/// so this is cheap to call before every kernel.
+#[inline]
+pub(crate) fn is_available() -> bool {
+    std::is_x86_feature_detected!("avx2")
+}
+
+/// A primitive integer type with AVX2 kernels, which are built from the operations on the lanes of
+/// a vector below.
+///
+/// All of the methods are safe to call only if AVX2 is available.
+pub trait Avx2Element: Copy + Ord {
+    /// The number of elements in a vector.
+    const VECTOR: usize;
+
+    /// Returns a vector with every lane set to `x`.
+    unsafe fn splat(x: Self) -> __m256i;
+
+    /// Returns a vector whose lanes are all ones where `a < b`, and zeros elsewhere.
+    unsafe fn lanes_lt(a: __m256i, b: __m256i) -> __m256i;
+
+    /// Returns the lanewise minimum of `a` and `b`.
+    unsafe fn lanes_min(a: __m256i, b: __m256i) -> __m256i;
+
+    /// Returns the lanewise maximum of `a` and `b`.
+    unsafe fn lanes_max(a: __m256i, b: __m256i) -> __m256i;
+
+    /// Returns a bitmask of the lanes of `mask` that are all ones.
+    unsafe fn bitmask(mask: __m256i) -> u8;
+}
+
+/// Implements [`Avx2Element`] for a 32-bit integer type. The elements are XORed with `$bias`
+/// before comparing them, which maps the unsigned order to the signed order of the comparison.
+macro_rules! avx2_element_32 {
+    ($t:ty, $bias:expr, $min:ident, $max:ident) => {
+        impl Avx2Element for $t {
+            const VECTOR: usize = 8;
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn splat(x: Self) -> __m256i {
+                _mm256_set1_epi32(x as i32)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn lanes_lt(a: __m256i, b: __m256i) -> __m256i {
+                let bias = _mm256_set1_epi32($bias);
+                _mm256_cmpgt_epi32(_mm256_xor_si256(b, bias), _mm256_xor_si256(a, bias))
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn lanes_min(a: __m256i, b: __m256i) -> __m256i {
+                $min(a, b)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn lanes_max(a: __m256i, b: __m256i) -> __m256i {
+                $max(a, b)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn bitmask(mask: __m256i) -> u8 {
+                _mm256_movemask_ps(_mm256_castsi256_ps(mask)) as u8
+            }
+        }
+    };
+}
+
+/// Implements [`Avx2Element`] for a 64-bit integer type, like `avx2_element_32`. AVX2 has no
+/// minimum or maximum of 64-bit lanes, so these are blended from a comparison.
+macro_rules! avx2_element_64 {
+    ($t:ty, $bias:expr) => {
+        impl Avx2Element for $t {
+            const VECTOR: usize = 4;
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn splat(x: Self) -> __m256i {
+                _mm256_set1_epi64x(x as i64)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn lanes_lt(a: __m256i, b: __m256i) -> __m256i {
+                let bias = _mm256_set1_epi64x($bias);
+                _mm256_cmpgt_epi64(_mm256_xor_si256(b, bias), _mm256_xor_si256(a, bias))
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn lanes_min(a: __m256i, b: __m256i) -> __m256i {
+                _mm256_blendv_epi8(a, b, Self::lanes_lt(b, a))
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn lanes_max(a: __m256i, b: __m256i) -> __m256i {
+                _mm256_blendv_epi8(a, b, Self::lanes_lt(a, b))
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn bitmask(mask: __m256i) -> u8 {
+                _mm256_movemask_pd(_mm256_castsi256_pd(mask)) as u8
+            }
+        }
+    };
+}
+
+avx2_element_32!(u32, i32::MIN, _mm256_min_epu32, _mm256_max_epu32);
+avx2_element_32!(i32, 0, _mm256_min_epi32, _mm256_max_epi32);
+avx2_element_64!(u64, i64::MIN);
+avx2_element_64!(i64, 0);
+
+/// Loads a vector from `ptr`, which must be valid for reads of [`Avx2Element::VECTOR`] elements.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn load<T>(ptr: *const T) -> __m256i {
+    _mm256_loadu_si256(ptr.cast())
+}
+
+/// Partitions `data` around the element at `pivot` like `partition_at`, finding the out-of-order
+/// elements of the blocks with AVX2. The partitioning is compiled with AVX2 enabled as a whole, so
+/// that the kernels are inlined into it.
+///
+/// # Safety
+///
+/// AVX2 must be available.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn partition_at<T>(data: &mut [T], pivot: usize, block: usize) -> (usize, usize)
+where
+    T: Avx2Element,
+{
+    partition_at_classified(data, pivot, &mut Avx2, block)
+}
+
+/// Finds the out-of-order elements of a block with the AVX2 comparisons of the element type. Must
+/// only be used if AVX2 is available.
+struct Avx2;
+
+impl<T> Classify<T> for Avx2
+where
+    T: Avx2Element,
+{
+    #[inline(always)]
+    fn lt(&mut self, a: &T, b: &T) -> bool {
+        a < b
+    }
+
+    #[inline(always)]
+    unsafe fn offsets_ge(
+        &mut self,
+        block: *const T,
+        len: usize,
+        pivot: &T,
+        offsets: *mut u8,
+    ) -> usize {
+        find_offsets_ge(block, len, *pivot, offsets)
+    }
+
+    #[inline(always)]
+    unsafe fn offsets_lt_rev(
+        &mut self,
+        end: *const T,
+        len: usize,
+        pivot: &T,
+        offsets: *mut u8,
+    ) -> usize {
+        find_offsets_lt_rev(end, len, *pivot, offsets)
+    }
+}
+
+/// The AVX2 kernel of [`Avx2::offsets_ge`](Classify::offsets_ge).
+#[target_feature(enable = "avx2")]
+unsafe fn find_offsets_ge<T>(block: *const T, len: usize, pivot: T, offsets: *mut u8) -> usize
+where
+    T: Avx2Element,
+{
+    let splat = T::splat(pivot);
+    offsets_ge_by(block, len, pivot, offsets, |lanes| {
+        lt_mask::<T>(lanes, splat)
+    })
+}
+
+/// The AVX2 kernel of [`Avx2::offsets_lt_rev`](Classify::offsets_lt_rev).
+#[target_feature(enable = "avx2")]
+unsafe fn find_offsets_lt_rev<T>(end: *const T, len: usize, pivot: T, offsets: *mut u8) -> usize
+where
+    T: Avx2Element,
+{
+    let splat = T::splat(pivot);
+    offsets_lt_rev_by(end, len, pivot, offsets, |lanes| lt_mask::<T>(lanes, splat))
+}
+
+/// Returns a mask whose bit `i` is set if `*lanes.add(i)` is less than the lanes of `pivot`, for
+/// `i < LANES`. `lanes` must be valid for reads of `LANES` elements.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn lt_mask<T>(lanes: *const T, pivot: __m256i) -> u8
+where
+    T: Avx2Element,
+{
+    let mut mask = 0;
+    for i in (0..LANES).step_by(T::VECTOR) {
+        mask |= T::bitmask(T::lanes_lt(load(lanes.add(i)), pivot)) << i;
+    }
+    mask
+}
+
+/// Returns the position of the first minimum of `data`, which must not be empty.
+///
+/// # Safety
+///
+/// AVX2 must be available.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn position_of_min<T>(data: &[T]) -> usize
+where
+    T: Avx2Element,
+{
+    position_of_extreme::<T, false>(data)
+}
+
+/// Returns the position of the first maximum of `data`, which must not be empty.
+///
+/// # Safety
+///
+/// AVX2 must be available.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn position_of_max<T>(data: &[T]) -> usize
+where
+    T: Avx2Element,
+{
+    position_of_extreme::<T, true>(data)
+}
+
+/// Returns the position of the first maximum of `data` if `MAX` is `true`, and of the first
+/// minimum otherwise.
+///
+/// The slice is scanned in a single pass, in blocks of a few vectors. The extremes of the lanes of
+/// each block are found without branching, and compared with the extreme so far. Only if the block
+/// improves on it, which is rare after the first few blocks of random data, is the block scanned
+/// again for its extreme. At the end, the block of the extreme is scanned for its position.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn position_of_extreme<T, const MAX: bool>(data: &[T]) -> usize
+where
+    T: Avx2Element,
+{
+    const BLOCK: usize = 64;
+
+    let is_better = |a: &T, b: &T| match MAX {
+        true => a > b,
+        false => a < b,
+    };
+    let (mut extreme, mut start) = (data[0], 0);
+    let mut blocks = data.chunks_exact(BLOCK);
+    for (block, offset) in blocks.by_ref().zip((0..).step_by(BLOCK)) {
+        let ptr = block.as_ptr();
+        let mut lanes = load(ptr);
+        for i in (T::VECTOR..BLOCK).step_by(T::VECTOR) {
+            lanes = match MAX {
+                true => T::lanes_max(lanes, load(ptr.add(i))),
+                false => T::lanes_min(lanes, load(ptr.add(i))),
+            };
+        }
+        let improves = match MAX {
+            true => T::lanes_lt(T::splat(extreme), lanes),
+            false => T::lanes_lt(lanes, T::splat(extreme)),
+        };
+        if T::bitmask(improves) != 0 {
+            let best = block
+                .iter()
+                .reduce(|a, b| if is_better(b, a) { b } else { a });
+            (extreme, start) = (*best.unwrap(), offset);
+        }
+    }
+    let rest = blocks.remainder();
+    let offset = data.len() - rest.len();
+    for (i, x) in rest.iter().enumerate() {
+        if is_better(x, &extreme) {
+            (extreme, start) = (*x, offset + i);
+        }
+    }
+    start + data[start..].iter().position(|x| *x == extreme).unwrap()
+}
+
+/// Returns the order of `data` like `sort_order`, comparing each element with its successor a
+/// vector at a time.
+///
+/// # Safety
+///
+/// AVX2 must be available.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn sort_order<T>(data: &[T]) -> SortOrder
+where
+    T: Avx2Element,
+{
+    if is_sorted::<T, false>(data) {
+        SortOrder::Ascending
+    } else if is_sorted::<T, true>(data) {
+        SortOrder::Descending
+    } else {
+        SortOrder::Unsorted
+    }
+}
+
+/// Returns `true` if `data` is sorted in descending order if `DESCENDING` is `true`, and in
+/// ascending order otherwise.
+///
+/// Each vector of elements is compared with the vector loaded one element later, i.e. with the
+/// successors of its lanes. The scan stops at the first block with an element out of order.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn is_sorted<T, const DESCENDING: bool>(data: &[T]) -> bool
+where
+    T: Avx2Element,
+{
+    const BLOCK: usize = 32;
+
+    let ptr = data.as_ptr();
+    let mut i = 0;
+    // The last vector of a block reads the element at `i + BLOCK`.
+    while i + BLOCK < data.len() {
+        let mut stopped = 0;
+        for j in (i..i + BLOCK).step_by(T::VECTOR) {
+            let (a, b) = (load(ptr.add(j)), load(ptr.add(j + 1)));
+            stopped |= T::bitmask(match DESCENDING {
+                true => T::lanes_lt(a, b),
+                false => T::lanes_lt(b, a),
+            });
+        }
+        if stopped != 0 {
+            return false;
+        }
+        i += BLOCK;
+    }
+    data[i..].windows(2).all(|w| match DESCENDING {
+        true => w[1] <= w[0],
+        false => w[0] <= w[1],
+    })
+}
@@ -1,9 +1,12 @@
 #[cfg(feature = "std")]
 extern crate std;
 
-use std::vec::Vec;
+use std::{string::String, vec::Vec};
 
-use crate::{select_nth_unstable, sort::tinysort, wyrand::WyRng};
+use crate::{
+    select_nth_unstable, select_nth_unstable_by_cached_key, select_nth_unstable_by_key,
+    select_nth_unstable_with_options, sort::tinysort, wyrand::WyRng, SelectOptions, Strategy,
+};
 
 /// Returns a vector of integers where approximately 90% of the elements are in sorted order. The
 /// maximum is randomized and in the range `0..count`.
@@ -11,7 +14,7 @@ fn mostly_sorted_u32s(count: usize, rng: &mut WyRng) -> Vec<u32> {
     let mut data = Vec::with_capacity(count);
     let max = rng.bounded_u32(0, count as u32);
     for index in 0..count {
-        data.push((max * index as u32) / (count as u32));
+        data.push((max as u64 * index as u64 / count as u64) as u32);
     }
     for _ in 0..count / 10 {
         let index = rng.bounded_usize(0, count);
@@ -49,13 +52,19 @@ fn random_dups_u32s(count: usize, rng: &mut WyRng) -> Vec<u32> {
     data
 }
 
+/// Returns a vector of `count` random strings of printable ASCII characters, with lengths in the
+/// range `0..16`.
+fn random_strings(count: usize, rng: &mut WyRng) -> Vec<String> {
+    rng.vec(count, count + 1, |rng| rng.ascii_string(0, 16))
+}
+
 /// Returns a vector of integers in reversed order. The maximum is randomized and in the range
 /// `0..count`.
 fn reversed_u32s(count: usize, rng: &mut WyRng) -> Vec<u32> {
     let mut data = Vec::with_capacity(count);
     let max = rng.bounded_u32(0, count as u32);
     for index in 0..count {
-        data.push((max * (count - index + 1) as u32) / (count as u32));
+        data.push((max as u64 * (count - index + 1) as u64 / count as u64) as u32);
     }
     data
 }
@@ -79,7 +88,7 @@ fn sorted_u32s(count: usize, rng: &mut WyRng) -> Vec<u32> {
     let mut data = Vec::with_capacity(count);
     let max = rng.bounded_u32(0, count as u32);
     for index in 0..count {
-        data.push((max * index as u32) / (count as u32));
+        data.push((max as u64 * index as u64 / count as u64) as u32);
     }
     data
 }
@@ -139,27 +148,677 @@ fn bench<D, P: FnMut() -> D, T: FnMut(&mut D), B: FnMut(&mut D), C: FnMut(D) ->
     durations
 }
 
+/// Benchmark settings read from environment variables, so that large runs don't need changes to
+/// the code:
+///
+/// - `TURBOSELECT_BENCH_LENS`: comma-separated slice lengths, e.g. `1000,1e6,1e9`.
+/// - `TURBOSELECT_BENCH_RUNS`: the number of runs for each length and index. By default, shorter
+///   slices are run more times.
+/// - `TURBOSELECT_BENCH_TYPES`: comma-separated labels of the data types to run, e.g.
+///   `random_u32,sorted_u32`. By default, all data types are run.
+/// - `TURBOSELECT_BENCH_REPORT`: a file to write a [`Report`] of the results to, as HTML if the
+///   name ends with `.html` and as markdown otherwise.
+/// - `TURBOSELECT_BENCH_THREADS`: comma-separated thread counts for the parallel benchmarks, e.g.
+///   `1,2,4,8`. By default, the powers of two up to the available parallelism.
+struct BenchConfig {
+    lens: Vec<usize>,
+    runs: Option<usize>,
+    types: Option<Vec<String>>,
+    report: Option<String>,
+    #[cfg(feature = "rayon")]
+    threads: Option<Vec<usize>>,
+}
+
+impl BenchConfig {
+    /// Reads the settings from the environment, using `lens` if `TURBOSELECT_BENCH_LENS` isn't set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a variable is set but can't be parsed.
+    fn from_env(lens: &[usize]) -> Self {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        }
+        fn parse_len(value: &str) -> usize {
+            let value = value.trim().replace('_', "");
+            value
+                .parse()
+                .ok()
+                .or_else(|| value.parse::<f64>().ok().map(|len| len as usize))
+                .filter(|&len| len > 0)
+                .unwrap_or_else(|| panic!("invalid length in TURBOSELECT_BENCH_LENS: {value}"))
+        }
+
+        let lens = var("TURBOSELECT_BENCH_LENS")
+            .map(|lens| lens.split(',').map(parse_len).collect())
+            .unwrap_or_else(|| lens.to_vec());
+        let runs = var("TURBOSELECT_BENCH_RUNS").map(|runs| {
+            runs.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid TURBOSELECT_BENCH_RUNS: {runs}"))
+        });
+        let types = var("TURBOSELECT_BENCH_TYPES")
+            .map(|types| types.split(',').map(|t| String::from(t.trim())).collect());
+        let report = var("TURBOSELECT_BENCH_REPORT");
+        #[cfg(feature = "rayon")]
+        let threads = var("TURBOSELECT_BENCH_THREADS").map(|threads| {
+            let parse = |t: &str| t.trim().parse().ok().filter(|&t: &usize| t > 0);
+            threads
+                .split(',')
+                .map(|t| {
+                    parse(t)
+                        .unwrap_or_else(|| panic!("invalid TURBOSELECT_BENCH_THREADS: {threads}"))
+                })
+                .collect()
+        });
+        Self {
+            lens,
+            runs,
+            types,
+            report,
+            #[cfg(feature = "rayon")]
+            threads,
+        }
+    }
+
+    /// Returns the number of runs for slices of length `len`, or `default` if the number isn't
+    /// set.
+    fn runs(&self, default: usize) -> usize {
+        self.runs.unwrap_or(default).max(1)
+    }
+
+    /// Returns the thread counts to run the parallel benchmarks with.
+    #[cfg(feature = "rayon")]
+    fn threads(&self) -> Vec<usize> {
+        let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+        self.threads.clone().unwrap_or_else(|| {
+            core::iter::successors(Some(1), |&t| Some(2 * t))
+                .take_while(|&t| t < 2 * available)
+                .map(|t| t.min(available))
+                .collect()
+        })
+    }
+
+    /// Returns `true` if the data type with the given label should be run.
+    fn includes(&self, label: &str) -> bool {
+        self.types
+            .as_ref()
+            .is_none_or(|types| types.iter().any(|t| t == label))
+    }
+}
+
+/// A throughput measurement of one slice length and index.
+struct ReportRow {
+    len: usize,
+    index: usize,
+    /// Our throughput in millions of elements per second.
+    ours: f64,
+    /// The throughput of the baseline in millions of elements per second.
+    baseline: f64,
+    /// Our throughput in gigabytes of slice data per second.
+    bytes: f64,
+}
+
+/// The results of a benchmark, grouped by data type, rendered as a self-contained markdown or HTML
+/// document for sharing, e.g. in issues. The HTML document also has a bar chart of the throughput
+/// ratios of each data type, drawn as inline SVG.
+struct Report {
+    title: String,
+    description: String,
+    baseline: String,
+    sections: Vec<(String, Vec<ReportRow>)>,
+}
+
+impl Report {
+    const HEADER: [&'static str; 6] = [
+        "slice length",
+        "index",
+        "throughput, M el/s",
+        "GB/s",
+        "baseline, M el/s",
+        "ratio",
+    ];
+
+    fn new(title: &str, description: &str, baseline: &str) -> Self {
+        Self {
+            title: title.into(),
+            description: description.into(),
+            baseline: baseline.into(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Returns the cells of a table row.
+    fn cells(row: &ReportRow) -> [String; 6] {
+        use std::format;
+
+        [
+            format!("{}", row.len),
+            format!("{}", row.index),
+            format!("{:.3}", row.ours),
+            format!("{:.3}", row.bytes),
+            format!("{:.3}", row.baseline),
+            format!("{:.3}", row.ours / row.baseline),
+        ]
+    }
+
+    fn to_markdown(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        writeln!(out, "# {}\n\n{}\n", self.title, self.description).unwrap();
+        writeln!(out, "The baseline is {}.", self.baseline).unwrap();
+        for (label, rows) in &self.sections {
+            writeln!(out, "\n## {label}\n").unwrap();
+            writeln!(out, "| {} |", Self::HEADER.join(" | ")).unwrap();
+            writeln!(out, "|{}", " --- |".repeat(Self::HEADER.len())).unwrap();
+            for row in rows {
+                writeln!(out, "| {} |", Self::cells(row).join(" | ")).unwrap();
+            }
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>",
+            escape(&self.title)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             th, td {{ border: 1px solid #ccc; padding: 2px 8px; text-align: right; }}</style>"
+        )
+        .unwrap();
+        writeln!(out, "</head>\n<body>\n<h1>{}</h1>", escape(&self.title)).unwrap();
+        writeln!(out, "<p>{}</p>", escape(&self.description)).unwrap();
+        writeln!(out, "<p>The baseline is {}.</p>", escape(&self.baseline)).unwrap();
+        for (label, rows) in &self.sections {
+            writeln!(out, "<h2>{}</h2>", escape(label)).unwrap();
+            out.push_str(&bar_chart(rows));
+            out.push_str("<table>\n<tr>");
+            for name in Self::HEADER {
+                write!(out, "<th>{name}</th>").unwrap();
+            }
+            out.push_str("</tr>\n");
+            for row in rows {
+                out.push_str("<tr>");
+                for cell in Self::cells(row) {
+                    write!(out, "<td>{cell}</td>").unwrap();
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    /// Writes the report to `path`, as HTML if the name ends with `.html` and as markdown
+    /// otherwise.
+    fn write(&self, path: &str) {
+        let text = match path.ends_with(".html") {
+            true => self.to_html(),
+            false => self.to_markdown(),
+        };
+        std::fs::write(path, text).unwrap_or_else(|e| panic!("can't write report to {path}: {e}"));
+    }
+}
+
+/// Escapes the characters that have a special meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Draws the throughput ratios of the rows as an SVG bar chart, with a line at the ratio of 1.
+/// Ratios above 1 are green and the others red.
+fn bar_chart(rows: &[ReportRow]) -> String {
+    use std::fmt::Write;
+
+    let (width, height, margin) = (40.0 * rows.len().max(1) as f64 + 60.0, 240.0, 40.0);
+    let max = rows
+        .iter()
+        .map(|row| row.ours / row.baseline)
+        .filter(|ratio| ratio.is_finite())
+        .fold(2.0f64, f64::max)
+        .ceil();
+    let y = |ratio: f64| height - margin - (height - 2.0 * margin) * ratio.min(max) / max;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         font-size=\"10\">"
+    )
+    .unwrap();
+    for tick in 0..=max as usize {
+        let ty = y(tick as f64);
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{tick}</text>",
+            margin - 4.0,
+            ty + 3.0
+        )
+        .unwrap();
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let ratio = row.ours / row.baseline;
+        let x = margin + 40.0 * i as f64 + 8.0;
+        let top = y(ratio);
+        let color = if ratio > 1.0 { "#2a2" } else { "#c33" };
+        writeln!(
+            svg,
+            "<rect x=\"{x}\" y=\"{top:.1}\" width=\"24\" height=\"{:.1}\" fill=\"{color}\">\
+             <title>{} at {}: {ratio:.3}</title></rect>",
+            y(0.0) - top,
+            row.len,
+            row.index
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x + 12.0,
+            height - margin + 12.0,
+            row.len
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x + 12.0,
+            height - margin + 24.0,
+            row.index
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        "<line x1=\"{margin}\" y1=\"{0:.1}\" x2=\"{1}\" y2=\"{0:.1}\" stroke=\"#000\" \
+         stroke-dasharray=\"4 2\"/>",
+        y(1.0),
+        width - 10.0
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[test]
+fn report_rendering() {
+    let mut report = Report::new("Results", "Random data <1e6>.", "`core`");
+    let row = |len, ours| ReportRow {
+        len,
+        index: len / 2,
+        ours,
+        baseline: 100.0,
+        bytes: ours * 4.0 / 1000.0,
+    };
+    report.sections.push((
+        "random_u32".into(),
+        std::vec![row(1000, 250.0), row(10_000, 50.0)],
+    ));
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("## random_u32"));
+    assert!(markdown.contains("| 1000 | 500 | 250.000 | 1.000 | 100.000 | 2.500 |"));
+    assert!(markdown.contains("| 10000 | 5000 | 50.000 | 0.200 | 100.000 | 0.500 |"));
+
+    let html = report.to_html();
+    assert!(html.contains("Random data &lt;1e6&gt;."));
+    assert_eq!(html.matches("<rect").count(), 2);
+    assert!(html.contains("fill=\"#2a2\"") && html.contains("fill=\"#c33\""));
+}
+
+#[test]
+#[ignore]
+fn cached_key_perf() {
+    // cargo test -r cached_key_perf -- --nocapture --ignored
+    use colored::*;
+    use std::{eprintln, format};
+
+    fn run<K, F>(label: &str, key: F)
+    where
+        K: Ord,
+        F: Fn(&u32) -> K + Copy,
+    {
+        let lens = [1_000, 10_000, 100_000];
+        let runs = |len: usize| 200_000 / ((len as f32).sqrt() as usize);
+        let check = |index: usize| {
+            move |data: Vec<u32>| {
+                let nth = key(&data[index]);
+                data[..index].iter().all(|x| key(x) <= nth)
+                    && data[index..].iter().all(|x| key(x) >= nth)
+            }
+        };
+        let mut rng = WyRng::new(123456789);
+
+        for len in lens {
+            let index = len / 2;
+            let sorted = bench(
+                || random_u32s(len, rng.as_mut()),
+                |data| select_nth_unstable_by_cached_key(data, index, key),
+                |data| data.sort_by_cached_key(key),
+                check(index),
+                runs(len),
+            );
+            let uncached = bench(
+                || random_u32s(len, rng.as_mut()),
+                |data| select_nth_unstable_by_cached_key(data, index, key),
+                |data| {
+                    select_nth_unstable_by_key(data, index, key);
+                },
+                check(index),
+                runs(len),
+            );
+            let (our_tput, sort_tput) = sorted.throughputs(len);
+            let (_, uncached_tput) = uncached.throughputs(len);
+            let ratio = |baseline: f64| {
+                let ratio = our_tput / baseline;
+                if ratio > 1.0 {
+                    format!("{:5.03}", ratio).green()
+                } else {
+                    format!("{:5.03}", ratio).red()
+                }
+            };
+            eprintln!(
+                "| {label:<12} | {len:<12} | {our_tput:<16.03} | {sort_tput:<16.03} | {:<5} | {uncached_tput:<16.03} | {:<5} |",
+                ratio(sort_tput),
+                ratio(uncached_tput),
+            );
+        }
+    }
+
+    eprintln!("Benchmarking select_nth_unstable_by_cached_key against sort_by_cached_key and select_nth_unstable_by_key.");
+    eprintln!("The median of random u32s is selected. The runs are randomly interleaved, and data preparation is ignored in the timing.\n");
+
+    eprintln!("| key          | slice length | cached, M el/s   | sort, M el/s     | ratio | by_key, M el/s   | ratio |");
+    eprintln!("| ------------ | ------------ | ---------------- | ---------------- | ----- | ---------------- | ----- |");
+
+    run("u32", |x| *x);
+    run("u32_hash", |x| {
+        (*x as u64)
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            .rotate_left(17)
+    });
+    run("string", |x| format!("item-{x:08}"));
+}
+
+/// Returns the median of the durations in milliseconds.
+#[cfg(feature = "rayon")]
+fn median_ms(mut nanos: Vec<u128>) -> f64 {
+    nanos.sort_unstable();
+    nanos[nanos.len() / 2] as f64 / 1e6
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+#[ignore]
+fn parallel_scaling_perf() {
+    // cargo test -r --features rayon parallel_scaling_perf -- --nocapture --ignored
+    // TURBOSELECT_BENCH_THREADS=1,2,4,8,16 TURBOSELECT_BENCH_LENS=1e4,1e5,1e6,1e7 \
+    //     cargo test -r --features rayon parallel_scaling_perf -- --nocapture --ignored
+    // TURBOSELECT_BENCH_TYPES=par_select TURBOSELECT_BENCH_LENS=1e5,1e6,1e7 \
+    //     cargo test -r --features rayon parallel_scaling_perf -- --nocapture --ignored
+    use crate::{par_select_nth_unstable_by_cached_key, parallel::Parallel, Decorate};
+    use std::{eprintln, hint::black_box, time::Instant};
+
+    /// Times `func` on fresh data of length `len` and returns the median in milliseconds.
+    fn time<F: FnMut(&mut Vec<u32>)>(config: &BenchConfig, len: usize, mut func: F) -> f64 {
+        let mut rng = WyRng::new(123456789);
+        let runs = config.runs((2_000_000 / len).clamp(3, 100));
+        let nanos = (0..runs)
+            .map(|_| {
+                let mut data = random_u32s(len, &mut rng);
+                let now = Instant::now();
+                func(black_box(&mut data));
+                now.elapsed().as_nanos()
+            })
+            .collect();
+        median_ms(nanos)
+    }
+
+    /// Times the parallel selection of the median on `threads` threads, and separately the
+    /// parallel extraction of the keys, which is the first phase of the selection. The rest is the
+    /// serial phase: the selection of the cached keys and the permutation of the slice.
+    fn phases<K, F>(config: &BenchConfig, len: usize, threads: usize, key: F) -> (f64, f64)
+    where
+        K: Ord + Send,
+        F: Fn(&u32) -> K + Sync + Copy,
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let total = time(config, len, |data| {
+            pool.install(|| par_select_nth_unstable_by_cached_key(data, len / 2, key))
+        });
+        let keys = time(config, len, |data| {
+            black_box(pool.install(|| Parallel(key).decorate(data, |i| i as u32)));
+        });
+        (total, keys)
+    }
+
+    fn run<K, F>(config: &BenchConfig, label: &str, key: F)
+    where
+        K: Ord + Send,
+        F: Fn(&u32) -> K + Sync + Copy,
+    {
+        use std::format;
+
+        if !config.includes(label) {
+            return;
+        }
+        let threads = config.threads();
+
+        eprintln!("\nStrong scaling of {label} keys, with a fixed slice length:\n");
+        eprintln!("| slice length | threads | total, ms  | keys, ms   | serial, ms | speedup | efficiency |");
+        eprintln!("| ------------ | ------- | ---------- | ---------- | ---------- | ------- | ---------- |");
+        let mut cutovers = Vec::new();
+        for &len in &config.lens {
+            let serial = time(config, len, |data| {
+                select_nth_unstable_by_cached_key(data, len / 2, key)
+            });
+            for &t in &threads {
+                let (total, keys) = phases(config, len, t, key);
+                let speedup = serial / total;
+                eprintln!(
+                    "| {len:<12} | {t:<7} | {total:<10.3} | {keys:<10.3} | {:<10.3} | {speedup:<7.2} | {:<10.2} |",
+                    (total - keys).max(0.0),
+                    speedup / t as f64,
+                );
+                if speedup > 1.0 && !cutovers.iter().any(|&(c, _)| c == t) {
+                    cutovers.push((t, len));
+                }
+            }
+        }
+        for &t in threads.iter().filter(|&&t| t > 1) {
+            let cutover = cutovers.iter().find(|&&(c, _)| c == t);
+            let text = match cutover {
+                Some((_, len)) => format!("from {len} elements"),
+                None => "at none of the lengths".into(),
+            };
+            eprintln!("With {t} threads, parallel beats serial extraction {text}.");
+        }
+
+        eprintln!("\nWeak scaling of {label} keys, with a fixed number of elements per thread:\n");
+        eprintln!("| per thread   | threads | total, ms  | keys, ms   | serial, ms | efficiency |");
+        eprintln!("| ------------ | ------- | ---------- | ---------- | ---------- | ---------- |");
+        for &len in &config.lens {
+            let (one, _) = phases(config, len, 1, key);
+            for &t in &threads {
+                let (total, keys) = phases(config, len * t, t, key);
+                eprintln!(
+                    "| {len:<12} | {t:<7} | {total:<10.3} | {keys:<10.3} | {:<10.3} | {:<10.2} |",
+                    (total - keys).max(0.0),
+                    one / total,
+                );
+            }
+        }
+    }
+
+    let config = BenchConfig::from_env(&[10_000, 100_000, 1_000_000]);
+
+    eprintln!("Benchmarking the scaling of par_select_nth_unstable_by_cached_key with the number of threads.");
+    eprintln!(
+        "The median of random u32s is selected, and the median time of the runs is reported. The \
+         keys phase extracts the keys in parallel, and the serial phase selects the cached keys \
+         and permutes the slice on the calling thread. The serial phase is the difference between \
+         the medians of the two, so it is noisy for short slices. The speedup is relative to \
+         select_nth_unstable_by_cached_key, and the efficiency is the speedup per thread."
+    );
+
+    run(&config, "u32_hash", |x| {
+        (*x as u64)
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            .rotate_left(17)
+    });
+    run(&config, "string", |x| std::format!("item-{x:08}"));
+    run_select(&config);
+}
+
+/// Benchmarks the scaling of `par_select_nth_unstable` with the number of threads, and of its first
+/// partitioning round by phase and by chunk length.
+#[cfg(feature = "rayon")]
+fn run_select(config: &BenchConfig) {
+    use crate::{
+        par_select_nth_unstable,
+        parallel::{chunk_len, par_partition, par_partition_in_chunks, partition_chunks},
+    };
+    use std::{eprintln, format, hint::black_box, time::Instant};
+
+    /// Times `func` on fresh data of length `len` and returns the median in milliseconds.
+    fn time<F: FnMut(&mut Vec<u32>)>(config: &BenchConfig, len: usize, mut func: F) -> f64 {
+        let mut rng = WyRng::new(123456789);
+        let runs = config.runs((20_000_000 / len).clamp(3, 100));
+        let nanos = (0..runs)
+            .map(|_| {
+                let mut data = random_u32s(len, &mut rng);
+                let now = Instant::now();
+                func(black_box(&mut data));
+                now.elapsed().as_nanos()
+            })
+            .collect();
+        median_ms(nanos)
+    }
+
+    if !config.includes("par_select") {
+        return;
+    }
+    let threads = config.threads();
+    let block = crate::params::PARAMS.partition_block;
+    // The first round splits random u32s about in half.
+    let is_left = |x: &u32| *x < u32::MAX / 2;
+    let pools: Vec<_> = threads
+        .iter()
+        .map(|&t| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(t)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    eprintln!("\nBenchmarking the scaling of par_select_nth_unstable with the number of threads.");
+    eprintln!(
+        "The median of random u32s is selected, and the median time of the runs is reported. The \
+         round is a single parallel partition of the whole slice around its median, of which the \
+         chunks phase partitions the chunks in parallel and the fix-up phase swaps the misplaced \
+         runs between them. The fix-up phase is the difference between the medians of the two. \
+         The speedup is relative to select_nth_unstable, and the efficiency is the speedup per \
+         thread. On a single thread, the selection is serial, but the round is still timed."
+    );
+    eprintln!("\n| slice length | threads | total, ms  | round, ms  | chunks, ms | fix-up, ms | serial, ms | speedup | efficiency |");
+    eprintln!("| ------------ | ------- | ---------- | ---------- | ---------- | ---------- | ---------- | ------- | ---------- |");
+    let mut cutovers = Vec::new();
+    for &len in &config.lens {
+        let serial = time(config, len, |data| {
+            select_nth_unstable(data, len / 2);
+        });
+        for (&t, pool) in threads.iter().zip(&pools) {
+            let total = time(config, len, |data| {
+                pool.install(|| par_select_nth_unstable(data, len / 2));
+            });
+            let round = time(config, len, |data| {
+                black_box(pool.install(|| par_partition(data, &is_left, block)));
+            });
+            let chunks = time(config, len, |data| {
+                let chunk = pool.install(|| chunk_len(data.len()));
+                black_box(pool.install(|| partition_chunks(data, &is_left, block, chunk)));
+            });
+            let speedup = serial / total;
+            eprintln!(
+                "| {len:<12} | {t:<7} | {total:<10.3} | {round:<10.3} | {chunks:<10.3} | {:<10.3} | {serial:<10.3} | {speedup:<7.2} | {:<10.2} |",
+                (round - chunks).max(0.0),
+                speedup / t as f64,
+            );
+            if t > 1 && speedup > 1.0 && !cutovers.iter().any(|&(c, _)| c == t) {
+                cutovers.push((t, len));
+            }
+        }
+    }
+    for &t in threads.iter().filter(|&&t| t > 1) {
+        let cutover = cutovers.iter().find(|&&(c, _)| c == t);
+        let text = match cutover {
+            Some((_, len)) => format!("from {len} elements"),
+            None => "at none of the lengths".into(),
+        };
+        eprintln!("With {t} threads, par_select_nth_unstable beats select_nth_unstable {text}.");
+    }
+
+    let len = *config.lens.iter().max().unwrap();
+    eprintln!("\nThe round on {len} elements by the length of the chunks:\n");
+    eprintln!("| chunk length | threads | round, ms  | chunks, ms | fix-up, ms |");
+    eprintln!("| ------------ | ------- | ---------- | ---------- | ---------- |");
+    for chunk in (10..=20).step_by(2).map(|shift| 1 << shift) {
+        for (&t, pool) in threads.iter().zip(&pools) {
+            let round = time(config, len, |data| {
+                black_box(pool.install(|| par_partition_in_chunks(data, &is_left, block, chunk)));
+            });
+            let chunks = time(config, len, |data| {
+                black_box(pool.install(|| partition_chunks(data, &is_left, block, chunk)));
+            });
+            eprintln!(
+                "| {chunk:<12} | {t:<7} | {round:<10.3} | {chunks:<10.3} | {:<10.3} |",
+                (round - chunks).max(0.0),
+            );
+        }
+    }
+}
+
 #[test]
 #[ignore]
 fn turboselect_perf() {
     // cargo test -r turboselect_perf -- --nocapture --ignored
     // cargo flamegraph --unit-test -- turboselect_perf --ignored
+    // TURBOSELECT_BENCH_LENS=1e8,1e9 TURBOSELECT_BENCH_RUNS=3 TURBOSELECT_BENCH_TYPES=random_u32 \
+    //     cargo test -r turboselect_perf -- --nocapture --ignored
+    // TURBOSELECT_BENCH_REPORT=report.html cargo test -r turboselect_perf -- --nocapture --ignored
     use std::{eprintln, format};
 
-    fn run<P, T>(label: &str, mut prep: P)
+    fn run<P, T>(config: &BenchConfig, report: &mut Report, label: &str, mut prep: P)
     where
         P: FnMut(usize, &mut WyRng) -> Vec<T> + Copy,
         T: Ord,
     {
         use colored::*;
-        use std::io::Write;
 
-        let lens = [
-            1_000, 10_000, 100_000, // 1_000_000, 10_000_000, 100_000_000
-        ];
+        if !config.includes(label) {
+            return;
+        }
         let percentiles = [0.001, 0.01, 0.05, 0.25, 0.5];
         let percentile = |count: usize, p: f64| (count as f64 * p) as usize;
-        let runs = |len: usize| 1_000_000 / ((len as f32).sqrt() as usize);
+        let runs = |len: usize| config.runs(1_000_000 / ((len as f32).sqrt() as usize));
         let mut rng = WyRng::new(123456789);
 
         let mut compare = |len, index| {
@@ -179,15 +838,14 @@ fn turboselect_perf() {
             )
         };
 
-        // std::fs::create_dir_all("bench_results").unwrap();
-        // let mut output = std::fs::File::create(format!("bench_results/{label}.csv")).unwrap();
-        let mut results = Vec::new();
-        writeln!(results, "target,len,percentile,nanosecs").unwrap();
-        for len in lens {
+        let mut rows = Vec::new();
+        for &len in &config.lens {
             for p in percentiles {
                 let index = percentile(len, p);
                 let durations = compare(len, index);
                 let (our_tput, baseline_tput) = durations.throughputs(len);
+                // The bytes of the slice itself, e.g. not the heap data of strings.
+                let our_bytes = our_tput * core::mem::size_of::<T>() as f64 / 1000.0;
                 let ratio = our_tput / baseline_tput;
                 let ratio = if ratio > 1.0 {
                     format!("{:5.03}", ratio).green()
@@ -195,31 +853,140 @@ fn turboselect_perf() {
                     format!("{:5.03}", ratio).red()
                 };
                 eprintln!(
-                    "| {label:<18} | {len:<12} | {index:<11} | {our_tput:<20.03} | {baseline_tput:<18.03} | {ratio} |",
+                    "| {label:<18} | {len:<12} | {index:<11} | {our_tput:<20.03} | {our_bytes:<10.03} | {baseline_tput:<18.03} | {ratio} |",
                 );
+                rows.push(ReportRow {
+                    len,
+                    index,
+                    ours: our_tput,
+                    baseline: baseline_tput,
+                    bytes: our_bytes,
+                });
+            }
+        }
+        report.sections.push((label.into(), rows));
+    }
 
-                for duration in &durations.ours {
-                    writeln!(
-                        results,
-                        "turboselect,{len},p{ptile},{duration}",
-                        ptile = 100. * p,
-                    )
-                    .unwrap();
-                }
-                for duration in &durations.baseline {
-                    writeln!(
-                        results,
-                        "core::slice,{len},p{ptile},{duration}",
-                        ptile = 100. * p,
-                    )
-                    .unwrap();
+    let config = BenchConfig::from_env(&[1_000, 10_000, 100_000]);
+
+    eprintln!("Benchmarking turboselect against core::slice::select_nth_unstable. The runs are randomly interleaved.");
+    eprintln!("Data preparation is ignored in the timing.\n");
+
+    eprintln!("| data type          | slice length | index       | throughput, M el/s   | GB/s       | baseline, M el /s  | ratio |");
+    eprintln!("| ------------------ | ------------ | ----------- | -------------------- | ---------- | ------------------ | ----- |");
+
+    let mut report = Report::new(
+        "turboselect benchmark results",
+        "The runs were randomly interleaved, and data preparation was ignored in the timing. \
+         Throughput is in millions of elements per second, and in gigabytes of slice data per \
+         second.",
+        "core::slice::select_nth_unstable",
+    );
+    let r = &mut report;
+    run(&config, r, "random_u32", random_u32s);
+    run(&config, r, "sorted_u32", sorted_u32s);
+    run(&config, r, "mostlysort_u32", mostly_sorted_u32s);
+    run(&config, r, "sawtooth_u32", sawtooth_u32s);
+    run(&config, r, "reversed_u32", reversed_u32s);
+    run(&config, r, "randomdup_u32", random_dups_u32s);
+    run(&config, r, "random_u64", |count, rng| {
+        rng.vec(count, count + 1, WyRng::u64)
+    });
+    run(&config, r, "random_bool", random_bools);
+    run(&config, r, "random_string", random_strings);
+    if let Some(path) = &config.report {
+        report.write(path);
+        eprintln!("\nWrote the report to {path}.");
+    }
+}
+
+/// Bindings to the shim in `cpp/nth_element.cpp`, which calls `std::nth_element` from the C++
+/// standard library.
+#[cfg(feature = "cppbench")]
+mod cpp {
+    extern "C" {
+        fn turboselect_nth_element_u32(data: *mut u32, len: usize, index: usize);
+        fn turboselect_nth_element_u64(data: *mut u64, len: usize, index: usize);
+        fn turboselect_nth_element_bool(data: *mut bool, len: usize, index: usize);
+    }
+
+    pub(super) trait NthElement: Sized {
+        /// Reorders `data` with `std::nth_element` such that the element at `index` is at its
+        /// final sorted position.
+        fn nth_element(data: &mut [Self], index: usize);
+    }
+
+    macro_rules! impl_nth_element {
+        ($t:ty, $f:ident) => {
+            impl NthElement for $t {
+                fn nth_element(data: &mut [Self], index: usize) {
+                    assert!(index < data.len());
+                    // SAFETY: The pointer and the length come from a valid slice, and the index is
+                    // in bounds. `std::nth_element` only permutes the elements.
+                    unsafe { $f(data.as_mut_ptr(), data.len(), index) }
                 }
             }
+        };
+    }
+
+    impl_nth_element!(u32, turboselect_nth_element_u32);
+    impl_nth_element!(u64, turboselect_nth_element_u64);
+    impl_nth_element!(bool, turboselect_nth_element_bool);
+}
+
+#[cfg(feature = "cppbench")]
+#[test]
+#[ignore]
+fn nth_element_perf() {
+    // cargo test -r --features cppbench nth_element_perf -- --nocapture --ignored
+    use colored::*;
+    use cpp::NthElement;
+    use std::{eprintln, format};
+
+    fn run<P, T>(label: &str, mut prep: P)
+    where
+        P: FnMut(usize, &mut WyRng) -> Vec<T> + Copy,
+        T: Ord + NthElement,
+    {
+        let lens = [1_000, 10_000, 100_000];
+        let percentiles = [0.001, 0.01, 0.05, 0.25, 0.5];
+        let percentile = |count: usize, p: f64| (count as f64 * p) as usize;
+        let runs = |len: usize| 1_000_000 / ((len as f32).sqrt() as usize);
+        let mut rng = WyRng::new(123456789);
+
+        for len in lens {
+            for p in percentiles {
+                let index = percentile(len, p);
+                let durations = bench(
+                    || prep(len, rng.as_mut()),
+                    |data| {
+                        select_nth_unstable(data, index);
+                    },
+                    |data| T::nth_element(data, index),
+                    |data| {
+                        let nth = &data[index];
+                        data[..index].iter().all(|x| x <= nth)
+                            && data[index..].iter().all(|x| x >= nth)
+                    },
+                    runs(len),
+                );
+                let (our_tput, baseline_tput) = durations.throughputs(len);
+                let ratio = our_tput / baseline_tput;
+                let ratio = if ratio > 1.0 {
+                    format!("{:5.03}", ratio).green()
+                } else {
+                    format!("{:5.03}", ratio).red()
+                };
+                eprintln!(
+                    "| {label:<18} | {len:<12} | {index:<11} | {our_tput:<20.03} | {baseline_tput:<18.03} | {ratio} |",
+                );
+            }
         }
-        // output.write_all(&results).unwrap();
     }
 
-    eprintln!("Benchmarking turboselect against core::slice::select_nth_unstable. The runs are randomly interleaved.");
+    eprintln!(
+        "Benchmarking turboselect against C++ std::nth_element. The runs are randomly interleaved."
+    );
     eprintln!("Data preparation is ignored in the timing.\n");
 
     eprintln!("| data type          | slice length | index       | throughput, M el/s   | baseline, M el /s  | ratio |");
@@ -231,6 +998,9 @@ fn turboselect_perf() {
     run("sawtooth_u32", sawtooth_u32s);
     run("reversed_u32", reversed_u32s);
     run("randomdup_u32", random_dups_u32s);
+    run("random_u64", |count, rng| {
+        rng.vec(count, count + 1, WyRng::u64)
+    });
     run("random_bool", random_bools);
 }
 
@@ -247,7 +1017,7 @@ fn tinysort_perf() {
         P: FnMut(usize, &mut WyRng) -> Vec<T> + Copy,
         T: Ord,
     {
-        let lens = [4, 8, 16, 24, 32, 64];
+        let lens = [4, 8, 16, 24, 32, 48, 64, 96];
         let runs = |len: usize| 2_000_000 / ((len as f32).sqrt() as usize);
         let mut rng = WyRng::new(123456789);
 
@@ -295,3 +1065,239 @@ fn tinysort_perf() {
     run("randomdups_u32", random_dups_u32s);
     run("random_bool", random_bools);
 }
+
+#[test]
+#[ignore]
+fn partition_equal_perf() {
+    // cargo test -r partition_equal_perf -- --nocapture --ignored
+    use crate::{
+        partition_at, partition_equal, partition_equal_min, partition_three_way_in_blocks,
+    };
+    use colored::*;
+    use std::{eprintln, format};
+
+    fn run<P>(label: &str, mut prep: P)
+    where
+        P: FnMut(usize, &mut WyRng) -> Vec<u32> + Copy,
+    {
+        let lens = [1_000, 100_000, 1_000_000];
+        let ranks = [0.02, 0.1, 0.25, 0.5];
+        let runs = |len: usize| 20_000_000 / len;
+        let block = crate::params::PARAMS.partition_block;
+        let mut rng = WyRng::new(123456789);
+
+        for len in lens {
+            for rank in ranks {
+                // The pivot is the element at `rank` of the sorted slice.
+                let mut prep = || {
+                    let data = prep(len, rng.as_mut());
+                    let mut sorted = data.clone();
+                    sorted.sort_unstable();
+                    let pivot = sorted[(rank * len as f64) as usize];
+                    let index = data.iter().position(|&x| x == pivot).unwrap();
+                    (data, index, pivot)
+                };
+                // The share of the slice that is partitioned in a single pass.
+                let (mut data, index, _) = prep();
+                data.swap(0, index);
+                let (pivot, tail) = data.split_first_mut().unwrap();
+                let done = partition_three_way_in_blocks(tail, pivot, &mut u32::lt, block).2;
+                let single = done as f64 / tail.len() as f64;
+
+                let durations = bench(
+                    &mut prep,
+                    |(data, index, _)| {
+                        partition_equal(data, *index, &mut u32::lt, block);
+                    },
+                    // Partitioning in two and then grouping the elements equal to the pivot.
+                    |(data, index, _)| {
+                        let (_, v) = partition_at(data, *index, &mut u32::lt, block);
+                        partition_equal_min(&mut data[v..], 0, &mut u32::lt);
+                    },
+                    |(data, _, pivot)| {
+                        data.windows(2)
+                            .all(|w| w[0].cmp(&pivot) <= w[1].cmp(&pivot))
+                    },
+                    runs(len),
+                );
+                let (our_tput, baseline_tput) = durations.throughputs(len);
+                let ratio = our_tput / baseline_tput;
+                let ratio = if ratio > 1.0 {
+                    format!("{:5.03}", ratio).green()
+                } else {
+                    format!("{:5.03}", ratio).red()
+                };
+                eprintln!(
+                    "| {label:<18} | {len:<12} | {rank:<5} | {single:<11.03} | {our_tput:<20.03} | {baseline_tput:<18.03} | {ratio:<5.03} |",
+                );
+            }
+        }
+    }
+
+    eprintln!("Benchmarking the partitioning around a repeated pivot against partitioning in two and then grouping the elements equal to the pivot. The runs are randomly interleaved.");
+    eprintln!("Data preparation is ignored in the timing. The single pass column is the share of the slice partitioned in a single pass.\n");
+
+    eprintln!(
+        "| data type          | slice length | rank  | single pass | throughput, M el/s   | baseline, M el /s  | ratio |"
+    );
+    eprintln!(
+        "| ------------------ | ------------ | ----- | ----------- | -------------------- | ------------------ | ----- |"
+    );
+
+    run("randomdups_u32", random_dups_u32s);
+    run("random16_u32", |count, rng| {
+        rng.vec(count, count + 1, |rng| rng.bounded_u32(0, 16))
+    });
+    run("mostlysort_u32", mostly_sorted_u32s);
+}
+
+#[test]
+#[ignore]
+fn min_max_perf() {
+    // cargo test -r min_max_perf -- --nocapture --ignored
+    use colored::*;
+    use std::{eprintln, format};
+
+    fn run<P, T>(label: &str, mut prep: P)
+    where
+        P: FnMut(usize, &mut WyRng) -> Vec<T> + Copy,
+        T: Ord,
+    {
+        let lens = [100, 10_000, 1_000_000];
+        let runs = |len: usize| 20_000_000 / len;
+        let mut rng = WyRng::new(123456789);
+
+        for len in lens {
+            for (name, index) in [("min", 0), ("max", len - 1)] {
+                // The baseline finds the position of the extreme with `Iterator::min_by_key` or
+                // `Iterator::max_by_key` and swaps it into place.
+                let durations = bench(
+                    || prep(len, rng.as_mut()),
+                    |data| {
+                        select_nth_unstable(data, index);
+                    },
+                    |data| {
+                        let iter = data.iter().enumerate();
+                        let extreme = match index {
+                            0 => iter.min_by_key(|&(_, x)| x),
+                            _ => iter.max_by_key(|&(_, x)| x),
+                        };
+                        let i = extreme.unwrap().0;
+                        data.swap(i, index);
+                    },
+                    |data| {
+                        let nth = &data[index];
+                        data.iter().all(|x| x >= nth) || data.iter().all(|x| x <= nth)
+                    },
+                    runs(len),
+                );
+                let (our_tput, baseline_tput) = durations.throughputs(len);
+                let ratio = our_tput / baseline_tput;
+                let ratio = if ratio > 1.0 {
+                    format!("{:5.03}", ratio).green()
+                } else {
+                    format!("{:5.03}", ratio).red()
+                };
+                eprintln!(
+                    "| {label:<18} | {len:<12} | {name:<5} | {our_tput:<20.03} | {baseline_tput:<18.03} | {ratio:<5.03} |",
+                );
+            }
+        }
+    }
+
+    eprintln!("Benchmarking the selection of the minimum and the maximum against Iterator::min and Iterator::max. The runs are randomly interleaved.");
+    eprintln!("Data preparation is ignored in the timing.\n");
+
+    eprintln!(
+        "| data type          | slice length | index | throughput, M el/s   | baseline, M el /s  | ratio |"
+    );
+    eprintln!(
+        "| ------------------ | ------------ | ----- | -------------------- | ------------------ | ----- |"
+    );
+
+    run("random_u32", random_u32s);
+    run("sorted_u32", sorted_u32s);
+    run("reversed_u32", reversed_u32s);
+    run("random_u64", |count, rng| {
+        rng.vec(count, count + 1, WyRng::u64)
+    });
+    run("random_string", random_strings);
+}
+
+#[test]
+#[ignore]
+fn strategy_perf() {
+    // cargo test -r strategy_perf -- --nocapture --ignored
+    use colored::*;
+    use std::{eprintln, format};
+
+    fn run<P, T>(label: &str, strategy: Strategy, mut prep: P)
+    where
+        P: FnMut(usize, &mut WyRng) -> Vec<T> + Copy,
+        T: Ord,
+    {
+        let lens = [1_000, 10_000, 100_000, 1_000_000];
+        let percentiles = [0.001, 0.01, 0.05, 0.25, 0.5];
+        let runs = |len: usize| 1_000_000 / ((len as f32).sqrt() as usize);
+        let mut rng = WyRng::new(123456789);
+        let options = SelectOptions {
+            strategy,
+            ..Default::default()
+        };
+
+        let mut compare = |len, index| {
+            bench(
+                || prep(len, rng.as_mut()),
+                |data| {
+                    select_nth_unstable_with_options(data, index, &options);
+                },
+                |data| {
+                    select_nth_unstable(data, index);
+                },
+                |data| {
+                    let nth = &data[index];
+                    data[..index].iter().all(|x| x <= nth) && data[index..].iter().all(|x| x >= nth)
+                },
+                runs(len),
+            )
+        };
+
+        for len in lens {
+            for p in percentiles {
+                let index = (len as f64 * p) as usize;
+                let durations = compare(len, index);
+                let (our_tput, baseline_tput) = durations.throughputs(len);
+                let ratio = our_tput / baseline_tput;
+                let ratio = if ratio > 1.0 {
+                    format!("{:5.03}", ratio).green()
+                } else {
+                    format!("{:5.03}", ratio).red()
+                };
+                eprintln!(
+                    "| {strategy:<16?} | {label:<18} | {len:<12} | {index:<11} | {our_tput:<20.03} | {baseline_tput:<18.03} | {ratio} |",
+                );
+            }
+        }
+    }
+
+    eprintln!("Benchmarking the alternative pivot strategies against the default sampling strategy. The runs are randomly interleaved.");
+    eprintln!("Data preparation is ignored in the timing.\n");
+
+    eprintln!("| strategy         | data type          | slice length | index       | throughput, M el/s   | sampling, M el/s   | ratio |");
+    eprintln!("| ---------------- | ------------------ | ------------ | ----------- | -------------------- | ------------------ | ----- |");
+
+    for strategy in [
+        Strategy::SamplingWithReplacement,
+        Strategy::MedianOfNinthers,
+        Strategy::FloydRivest,
+        Strategy::MedianOfMedians,
+    ] {
+        run("random_u32", strategy, random_u32s);
+        run("sorted_u32", strategy, sorted_u32s);
+        run("mostlysort_u32", strategy, mostly_sorted_u32s);
+        run("sawtooth_u32", strategy, sawtooth_u32s);
+        run("reversed_u32", strategy, reversed_u32s);
+        run("randomdup_u32", strategy, random_dups_u32s);
+        run("random_bool", strategy, random_bools);
+    }
+}
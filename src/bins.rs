@@ -0,0 +1,264 @@
+use core::{cmp::Ordering, hint, mem};
+use std::vec::Vec;
+
+use crate::{math::mul_div, multi::select_ranks};
+
+/// Labels every element of the slice with the number of its equi-depth bin, from `0` to
+/// `n_bins - 1`, e.g. for computing `NTILE` or an equi-depth histogram.
+///
+/// The `n_bins - 1` splitters are the elements at ranks `k * len / n_bins` for `k` in
+/// `1..n_bins`. They are selected from a copy of the slice, so the slice itself is left untouched,
+/// and then every element is labelled with the number of splitters that are less than or equal to
+/// it in one pass, by a branchless binary search over the splitters. Every bin
+/// holds `len / n_bins` elements, give or take one, unless there are elements equal to a splitter
+/// outside of its bin: these are put in the last bin that starts with them, so the bins can be
+/// uneven or even empty if there are many duplicates.
+///
+/// Returns the labels in the order of the elements.
+///
+/// # Panics
+///
+/// Panics if `n_bins` is `0` or greater than `256`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::quantile_bins;
+/// let v = [50, 10, 80, 30, 20, 70, 40, 60];
+/// assert_eq!(quantile_bins(&v, 4), [2, 0, 3, 1, 0, 3, 1, 2]);
+/// ```
+pub fn quantile_bins<T>(data: &[T], n_bins: usize) -> Vec<u8>
+where
+    T: Ord + Clone,
+{
+    quantile_bins_by_lt(data, n_bins, T::lt)
+}
+
+/// Labels every element of the slice with the number of its equi-depth bin with a comparator
+/// function.
+///
+/// See [`quantile_bins`] for details.
+///
+/// # Panics
+///
+/// Panics if `n_bins` is `0` or greater than `256`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::quantile_bins_by;
+/// let v = [0.5, 0.1, 0.8, 0.3];
+/// assert_eq!(quantile_bins_by(&v, 2, f64::total_cmp), [1, 0, 1, 0]);
+/// ```
+pub fn quantile_bins_by<T, F>(data: &[T], n_bins: usize, mut compare: F) -> Vec<u8>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    quantile_bins_by_lt(data, n_bins, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Labels every element of the slice with the number of its equi-depth bin with a "less than"
+/// function.
+///
+/// This is the "less than" form of [`quantile_bins`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics if `n_bins` is `0` or greater than `256`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::quantile_bins_by_lt;
+/// let v = [5, 1, 8, 3];
+/// assert_eq!(quantile_bins_by_lt(&v, 2, |a, b| a > b), [0, 1, 0, 1]);
+/// ```
+pub fn quantile_bins_by_lt<T, F>(data: &[T], n_bins: usize, mut lt: F) -> Vec<u8>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(n_bins > 0 && n_bins <= 256);
+    if data.is_empty() {
+        return Vec::new();
+    }
+    let splitters = splitters(data, n_bins, &mut lt);
+    data.iter()
+        .map(|x| bin_of(x, &splitters, &mut lt) as u8)
+        .collect()
+}
+
+/// Selects the elements at the ranks `k * len / n_bins` for `k` in `1..n_bins`. The slice must not
+/// be empty.
+pub(crate) fn splitters<T, F>(data: &[T], n_bins: usize, lt: &mut F) -> Vec<T>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = data.len();
+    let ranks: Vec<usize> = (1..n_bins).map(|k| mul_div(k, len, n_bins)).collect();
+    // On slices shorter than `n_bins`, some of the ranks are repeated.
+    let mut distinct = ranks.clone();
+    distinct.dedup();
+    let mut copy = data.to_vec();
+    if mem::size_of::<T>() > 0 {
+        select_ranks(&mut copy, &distinct, 0, lt);
+    }
+    ranks.iter().map(|&r| copy[r].clone()).collect()
+}
+
+/// Returns the number of `splitters` that are less than or equal to `x`. The splitters must be
+/// sorted.
+#[inline]
+pub(crate) fn bin_of<T, F>(x: &T, splitters: &[T], lt: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if splitters.is_empty() {
+        return 0;
+    }
+    // The number of halvings depends only on the number of splitters, and the comparisons are
+    // turned into conditional moves instead of branches, which are mispredicted half of the time.
+    let (mut base, mut size) = (0, splitters.len());
+    while size > 1 {
+        let half = size / 2;
+        base = hint::select_unpredictable(lt(x, &splitters[base + half]), base, base + half);
+        size -= half;
+    }
+    base + !lt(x, &splitters[base]) as usize
+}
+
+/// Counts of elements in buckets bounded by a sorted list of edges.
+///
+/// Bucket `k` holds the elements that are greater than or equal to `k` edges, i.e. the elements
+/// below the first edge are in bucket `0` and the elements at or above the last edge are in the
+/// last bucket, so there is one more bucket than there are edges. The edges are either selected
+/// from a slice with [`equi_depth`](Histogram::equi_depth), like the splitters of
+/// [`quantile_bins`], or given explicitly with [`with_edges`](Histogram::with_edges), e.g. equally
+/// spaced ones. Each element is counted with a branchless binary search over the edges.
+///
+/// The edges stay fixed as more batches of elements are [added](Histogram::add), so buckets
+/// selected from an early sample drift from equal depths if the distribution changes. Build a new
+/// histogram from recent data to rebalance them.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::Histogram;
+/// let first: Vec<u32> = (0..100).collect();
+/// let mut histogram = Histogram::equi_depth(&first, 4);
+/// assert_eq!(histogram.edges(), [25, 50, 75]);
+/// assert_eq!(histogram.counts(), [25, 25, 25, 25]);
+/// histogram.add(&[10, 60, 99, 1000]);
+/// assert_eq!(histogram.counts(), [26, 25, 26, 27]);
+/// assert_eq!(histogram.total(), 104);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Histogram<T> {
+    /// The sorted boundaries between the buckets.
+    edges: Vec<T>,
+    /// The number of elements in each bucket.
+    counts: Vec<usize>,
+}
+
+impl<T> Histogram<T>
+where
+    T: Ord + Clone,
+{
+    /// Counts the elements of `batch` into the buckets.
+    pub fn add(&mut self, batch: &[T]) {
+        for x in batch {
+            self.counts[bin_of(x, &self.edges, &mut T::lt)] += 1;
+        }
+    }
+
+    /// Returns the bucket that `value` would be counted in.
+    pub fn bucket_of(&self, value: &T) -> usize {
+        bin_of(value, &self.edges, &mut T::lt)
+    }
+
+    /// Returns the number of elements in each bucket.
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Returns the boundaries between the buckets.
+    pub fn edges(&self) -> &[T] {
+        &self.edges
+    }
+
+    /// Returns a histogram of `data` with `n_buckets` buckets of equal depth. The edges are the
+    /// elements at ranks `k * len / n_buckets` for `k` in `1..n_buckets`, and `data` is left
+    /// untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_buckets == 0` or if `data` is empty.
+    pub fn equi_depth(data: &[T], n_buckets: usize) -> Self {
+        assert!(n_buckets > 0, "there must be at least one bucket");
+        assert!(
+            !data.is_empty(),
+            "the edges can't be selected from an empty slice"
+        );
+        let mut histogram = Self::with_edges(splitters(data, n_buckets, &mut T::lt));
+        histogram.add(data);
+        histogram
+    }
+
+    /// Returns the total number of elements counted.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Returns an empty histogram with the given bucket boundaries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edges` is not sorted.
+    pub fn with_edges(edges: Vec<T>) -> Self {
+        assert!(
+            edges.windows(2).all(|w| w[0] <= w[1]),
+            "the edges must be sorted"
+        );
+        let counts = std::vec![0; edges.len() + 1];
+        Self { edges, counts }
+    }
+}
+
+/// Checks that the edges are sorted and that there is a count for every bucket.
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Histogram<T>
+where
+    T: Ord + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Histogram")]
+        struct State<T> {
+            edges: Vec<T>,
+            counts: Vec<usize>,
+        }
+
+        let state = State::<T>::deserialize(deserializer)?;
+        if !state.edges.windows(2).all(|w| w[0] <= w[1]) {
+            return Err(D::Error::custom("the edges must be sorted"));
+        }
+        if state.counts.len() != state.edges.len() + 1 {
+            return Err(D::Error::custom(
+                "there must be one more count than there are edges",
+            ));
+        }
+        Ok(Self {
+            edges: state.edges,
+            counts: state.counts,
+        })
+    }
+}
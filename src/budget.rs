@@ -0,0 +1,323 @@
+use core::{cell::Cell, cmp::Ordering, mem, ops::Range};
+
+use crate::{
+    choose_pivot, is_repeated_in_sample, params::params, partition_equal_min, sample, sample_rank,
+    select_max, select_min, sort::tinyselect, strategy::medians_of_fives, BadRounds,
+    BlockPartition, Params, PartitionStrategy,
+};
+
+/// The outcome of [`select_nth_unstable_with_budget`].
+///
+/// The slice is split into three parts: every element of `below` is less than or equal to every
+/// element of `candidates`, which in turn are less than or equal to every element of `above`. The
+/// element that belongs at the selected index is one of the `candidates`. If the selection
+/// finished within the budget, `candidates` holds exactly that element.
+#[derive(Debug)]
+pub struct Budgeted<'a, T> {
+    /// The elements known to be at or before the selected index.
+    pub below: &'a mut [T],
+    /// The elements that were not yet partitioned when the budget ran out.
+    pub candidates: &'a mut [T],
+    /// The elements known to be at or after the selected index.
+    pub above: &'a mut [T],
+    /// The number of comparisons made.
+    pub comparisons: usize,
+}
+
+impl<T> Budgeted<'_, T> {
+    /// Returns `true` if the selection finished, i.e. the only candidate is the selected element.
+    pub fn is_exact(&self) -> bool {
+        self.candidates.len() == 1
+    }
+
+    /// Returns the range of ranks that the candidates occupy. The selected index is always in this
+    /// range.
+    pub fn ranks(&self) -> Range<usize> {
+        self.below.len()..self.below.len() + self.candidates.len()
+    }
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, making at
+/// most `budget` comparisons. If the budget runs out, the selection stops and the slice is
+/// left partitioned around the range of candidates that the element at `index` is known to be in.
+///
+/// This is for systems with soft deadlines, which would rather have a tight bracket of the result
+/// in time than the exact result late. Each partitioning round narrows the candidates down, and a
+/// round is only started if an upper bound of its cost still fits in the budget, so the budget is
+/// never exceeded. The actual number of comparisons is reported in the result. A budget of about
+/// four times the length of the slice is usually enough for an exact result.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_budget;
+/// let mut v: Vec<u32> = (0..10_000).map(|x| (x * 7919) % 10_000).collect();
+///
+/// // Too few comparisons for an exact result, but some candidates are ruled out.
+/// let result = select_nth_unstable_with_budget(&mut v, 5000, 20_500);
+/// assert!(result.ranks().contains(&5000));
+/// assert!(result.candidates.len() < 10_000);
+/// assert!(result.comparisons <= 20_500);
+///
+/// let result = select_nth_unstable_with_budget(&mut v, 5000, 40_000);
+/// assert!(result.is_exact());
+/// assert_eq!(result.candidates[0], 5000);
+/// ```
+pub fn select_nth_unstable_with_budget<T>(
+    data: &mut [T],
+    index: usize,
+    budget: usize,
+) -> Budgeted<'_, T>
+where
+    T: Ord,
+{
+    select_nth_unstable_with_budget_by(data, index, budget, T::cmp)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, making at most `budget` comparisons.
+///
+/// See [`select_nth_unstable_with_budget`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_budget_by;
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// let result = select_nth_unstable_with_budget_by(&mut v, 0, 10_000, |a, b| b.cmp(a));
+/// assert_eq!(result.candidates, [999]);
+/// ```
+pub fn select_nth_unstable_with_budget_by<T, F>(
+    data: &mut [T],
+    index: usize,
+    budget: usize,
+    mut compare: F,
+) -> Budgeted<'_, T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_budget_by_lt(data, index, budget, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, making at most `budget` comparisons.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_budget`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_budget_by_lt;
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// let result = select_nth_unstable_with_budget_by_lt(&mut v, 0, 10_000, |a, b| a > b);
+/// assert_eq!(result.candidates, [999]);
+/// ```
+pub fn select_nth_unstable_with_budget_by_lt<T, F>(
+    data: &mut [T],
+    index: usize,
+    budget: usize,
+    mut lt: F,
+) -> Budgeted<'_, T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(index < data.len());
+    let comparisons = Cell::new(0);
+    let range = if data.len() < 2 || mem::size_of::<T>() == 0 {
+        index..index + 1
+    } else {
+        let mut lt = |a: &T, b: &T| {
+            comparisons.set(comparisons.get() + 1);
+            lt(a, b)
+        };
+        narrow(
+            data,
+            index,
+            &mut lt,
+            &comparisons,
+            budget,
+            BadRounds::default(),
+        )
+    };
+    let (below, rest) = data.split_at_mut(range.start);
+    let (candidates, above) = rest.split_at_mut(range.len());
+    Budgeted {
+        below,
+        candidates,
+        above,
+        comparisons: comparisons.get(),
+    }
+}
+
+/// Partitions `data` in rounds until the element at `index` is found or the next round would not
+/// fit in `budget`. Returns the range of candidates for the element at `index`. Like the unbounded
+/// selection, the pivots are chosen with the median of medians after too many `bad_rounds`.
+fn narrow<T, F>(
+    mut data: &mut [T],
+    mut index: usize,
+    lt: &mut F,
+    spent: &Cell<usize>,
+    budget: usize,
+    mut bad_rounds: BadRounds,
+) -> Range<usize>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let params = params::<T>();
+    let partition = BlockPartition {
+        block: Some(params.partition_block),
+    };
+    let fits = |cost: usize| spent.get().saturating_add(cost) <= budget;
+    let mut offset = 0;
+    let mut previous_pivot: Option<&T> = None;
+
+    while data.len() > params.tinysort_cutoff {
+        let len = data.len();
+        let (u, v) = if index == 0 || index == len - 1 {
+            if !fits(2 * len) {
+                return offset..offset + len;
+            }
+            match index {
+                0 => select_min(data, lt),
+                _ => select_max(data, lt),
+            }
+        } else {
+            // The pivot is chosen from a small sample, which costs much less than partitioning.
+            if !fits(len) {
+                return offset..offset + len;
+            }
+            // One comparison is left for checking the pivot against the previous one.
+            let budget = budget.saturating_sub(1);
+            let pivot = match bad_rounds.exceeded(&params) {
+                false => choose_pivot_within(data, index, lt, spent, budget, &params),
+                true => median_of_medians_within(data, lt, spent, budget),
+            };
+            let Some((p, is_repeated)) = pivot else {
+                return offset..offset + len;
+            };
+            // The pivot is equal to a previous pivot from the left, so it is the minimum.
+            let is_minimum = previous_pivot.is_some_and(|was| !lt(was, &data[p]));
+            // Grouping the elements equal to the pivot takes a second pass over the slice.
+            let cost = if is_repeated || is_minimum {
+                4 * len
+            } else {
+                2 * len
+            };
+            if !fits(cost) {
+                return offset..offset + len;
+            }
+            match is_minimum {
+                true => partition_equal_min(data, p, lt),
+                false => partition.partition(data, p, is_repeated, lt),
+            }
+        };
+
+        if index < u {
+            data = data[..u].as_mut();
+        } else if index > v {
+            let (head, tail) = data.split_at_mut(v + 1);
+            (data, previous_pivot) = (tail, head.last());
+            offset += v + 1;
+            index -= v + 1;
+        } else {
+            return offset + index..offset + index + 1;
+        }
+        bad_rounds.count(len, data.len());
+    }
+    // Sorting the remaining elements makes fewer comparisons than there are pairs of elements.
+    let len = data.len();
+    if !fits(len.saturating_mul(len.saturating_sub(1)) / 2) {
+        return offset..offset + len;
+    }
+    tinyselect(data, index, lt);
+    offset + index..offset + index + 1
+}
+
+/// Chooses a pivot like [`choose_pivot`] if it can be done within `budget`. Returns `None` if it
+/// can't, leaving the elements reordered but not partitioned.
+///
+/// The `kth-of-nths` strategies sort `N + 1` groups of `N` elements, which compares each pair in a
+/// group at most once. The sample of a longer slice is narrowed down within the same budget, and
+/// no pivot is chosen if that doesn't finish.
+fn choose_pivot_within<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: &mut F,
+    spent: &Cell<usize>,
+    budget: usize,
+    params: &Params,
+) -> Option<(usize, bool)>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let n = match data.len() {
+        len if len <= params.kth_of_3_max => 3,
+        len if len <= params.kth_of_5_max => 5,
+        len if len <= params.kth_of_7_max => 7,
+        len => {
+            let (count, k) = sample_rank(len, index);
+            // One comparison is left for checking whether the pivot is repeated.
+            let sample = sample(data, count);
+            let range = narrow(
+                sample,
+                k,
+                lt,
+                spent,
+                budget.saturating_sub(1),
+                BadRounds::default(),
+            );
+            let is_exact = range.len() == 1;
+            return is_exact.then(|| (k, is_repeated_in_sample(&data[..count], k, lt)));
+        }
+    };
+    // Checking whether the pivot is repeated compares at most two more pairs.
+    let cost = (n + 1) * n * (n - 1) / 2 + 2;
+    let fits = spent.get().saturating_add(cost) <= budget;
+    fits.then(|| choose_pivot(data, index, lt, params))
+}
+
+/// Chooses a pivot like [`median_of_medians`](crate::strategy::median_of_medians) if it can be done
+/// within `budget`. Returns `None` if it can't, leaving the elements reordered but not
+/// partitioned.
+///
+/// The medians of the groups are narrowed down within the same budget, choosing all of their
+/// pivots with the median of medians too.
+fn median_of_medians_within<T, F>(
+    data: &mut [T],
+    lt: &mut F,
+    spent: &Cell<usize>,
+    budget: usize,
+) -> Option<(usize, bool)>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let groups = data.len() / 5;
+    if spent.get().saturating_add(9 * groups) > budget {
+        return None;
+    }
+    medians_of_fives(data, lt);
+    let range = narrow(
+        &mut data[..groups],
+        groups / 2,
+        lt,
+        spent,
+        budget,
+        BadRounds::EXCEEDED,
+    );
+    (range.len() == 1).then_some((groups / 2, true))
+}
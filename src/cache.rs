@@ -0,0 +1,94 @@
+use std::{format, fs, sync::OnceLock};
+
+/// The sizes of the data caches of the processor, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CacheSizes {
+    /// The size of the level 1 data cache.
+    pub l1: usize,
+    /// The size of the level 2 cache.
+    pub l2: usize,
+}
+
+/// Returns the cache sizes of the processor, or `None` if they could not be detected. The sizes
+/// are detected on the first call.
+pub(crate) fn sizes() -> Option<CacheSizes> {
+    static SIZES: OnceLock<Option<CacheSizes>> = OnceLock::new();
+    *SIZES.get_or_init(|| sysfs().or_else(cpuid))
+}
+
+/// Reads the cache sizes of the first processor from sysfs.
+fn sysfs() -> Option<CacheSizes> {
+    let mut sizes = CacheSizes { l1: 0, l2: 0 };
+    for index in 0.. {
+        let dir = format!("/sys/devices/system/cpu/cpu0/cache/index{index}");
+        let Ok(level) = fs::read_to_string(format!("{dir}/level")) else {
+            break;
+        };
+        let kind = fs::read_to_string(format!("{dir}/type")).ok()?;
+        let size = fs::read_to_string(format!("{dir}/size")).ok()?;
+        let size = parse_size(size.trim())?;
+        match (level.trim(), kind.trim()) {
+            ("1", "Data" | "Unified") => sizes.l1 = size,
+            ("2", "Data" | "Unified") => sizes.l2 = size,
+            _ => {}
+        }
+    }
+    (sizes.l1 > 0 && sizes.l2 > 0).then_some(sizes)
+}
+
+/// Parses a size such as `48K` or `2M` into bytes.
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.as_bytes().last()? {
+        b'K' => (&size[..size.len() - 1], 1 << 10),
+        b'M' => (&size[..size.len() - 1], 1 << 20),
+        _ => (size, 1),
+    };
+    digits.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+/// Reads the cache sizes with the `cpuid` instruction. Tries the deterministic cache parameters
+/// leaf used by Intel first, and then the extended leaves used by AMD.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpuid() -> Option<CacheSizes> {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::{__cpuid_count, CpuidResult};
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::{__cpuid_count, CpuidResult};
+
+    // `__cpuid_count` is an unsafe function on older compilers.
+    #[allow(unused_unsafe)]
+    let cpuid =
+        |leaf: u32, subleaf: u32| -> CpuidResult { unsafe { __cpuid_count(leaf, subleaf) } };
+
+    let mut sizes = CacheSizes { l1: 0, l2: 0 };
+    if cpuid(0, 0).eax >= 4 {
+        for subleaf in 0..16 {
+            let CpuidResult { eax, ebx, ecx, .. } = cpuid(4, subleaf);
+            let kind = eax & 0x1f;
+            if kind == 0 {
+                break;
+            }
+            let ways = (ebx >> 22) as usize + 1;
+            let partitions = ((ebx >> 12) & 0x3ff) as usize + 1;
+            let line = (ebx & 0xfff) as usize + 1;
+            let sets = ecx as usize + 1;
+            let size = ways * partitions * line * sets;
+            // Type 1 is a data cache and type 3 a unified cache.
+            match ((eax >> 5) & 0x7, kind) {
+                (1, 1 | 3) => sizes.l1 = size,
+                (2, 1 | 3) => sizes.l2 = size,
+                _ => {}
+            }
+        }
+    }
+    if (sizes.l1 == 0 || sizes.l2 == 0) && cpuid(0x8000_0000, 0).eax >= 0x8000_0006 {
+        sizes.l1 = (cpuid(0x8000_0005, 0).ecx >> 24) as usize * 1024;
+        sizes.l2 = (cpuid(0x8000_0006, 0).ecx >> 16) as usize * 1024;
+    }
+    (sizes.l1 > 0 && sizes.l2 > 0).then_some(sizes)
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn cpuid() -> Option<CacheSizes> {
+    None
+}
@@ -0,0 +1,99 @@
+//! Combinators for building `lt` comparators, i.e. functions that return `true` if the first
+//! argument is less than the second, for use with
+//! [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+//!
+//! # Examples
+//!
+//! ```
+//! use turboselect::{cmp, select_nth_unstable_by_lt};
+//! let mut v = [(2, 0.5), (1, 0.25), (2, -1.0), (1, 3.0)];
+//!
+//! // Order by the first field descending, then by the second field ascending.
+//! let lt = cmp::then_by(
+//!     cmp::reversed(cmp::by_key(|x: &(i32, f64)| x.0)),
+//!     |a: &(i32, f64), b: &(i32, f64)| cmp::float_total()(&a.1, &b.1),
+//! );
+//! select_nth_unstable_by_lt(&mut v, 1, lt);
+//! assert_eq!(v[1], (2, 0.5));
+//! ```
+
+use core::cmp::Ordering;
+
+/// Floating point types that can be compared with a total order.
+pub trait Float: Copy {
+    /// Returns the ordering between `self` and `other` according to the `totalOrder` predicate
+    /// defined in IEEE 754.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+impl Float for f32 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f32::total_cmp(self, other)
+    }
+}
+
+impl Float for f64 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+/// Implements [`Float`] for the wrapper types of the `ordered-float` crate by comparing the
+/// wrapped floats. For `OrderedFloat`, this differs from its `Ord` implementation in that `-0.0`
+/// is less than `0.0` and NaNs are ordered by their sign and payload.
+#[cfg(feature = "ordered-float")]
+macro_rules! impl_float_for_wrapper {
+    ($($wrapper:ident<$float:ty>),*) => {
+        $(
+            impl Float for ordered_float::$wrapper<$float> {
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    self.into_inner().total_cmp(&other.into_inner())
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "ordered-float")]
+impl_float_for_wrapper!(
+    OrderedFloat<f32>,
+    OrderedFloat<f64>,
+    NotNan<f32>,
+    NotNan<f64>
+);
+
+/// Returns a comparator that compares the keys extracted with `f`.
+pub fn by_key<T, K, F>(mut f: F) -> impl FnMut(&T, &T) -> bool
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    move |a, b| f(a) < f(b)
+}
+
+/// Returns a comparator that orders floats by their total order, where negative NaNs are less
+/// than all other values and positive NaNs are greater than all other values.
+pub fn float_total<T>() -> impl FnMut(&T, &T) -> bool
+where
+    T: Float,
+{
+    |a, b| a.total_cmp(b) == Ordering::Less
+}
+
+/// Returns a comparator that reverses the order of `lt`.
+pub fn reversed<T, C>(mut lt: C) -> impl FnMut(&T, &T) -> bool
+where
+    C: FnMut(&T, &T) -> bool,
+{
+    move |a, b| lt(b, a)
+}
+
+/// Returns a comparator that compares with `first`, and if the elements are equal according to
+/// `first`, with `second`.
+pub fn then_by<T, C1, C2>(mut first: C1, mut second: C2) -> impl FnMut(&T, &T) -> bool
+where
+    C1: FnMut(&T, &T) -> bool,
+    C2: FnMut(&T, &T) -> bool,
+{
+    move |a, b| first(a, b) || (!first(b, a) && second(a, b))
+}
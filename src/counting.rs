@@ -0,0 +1,67 @@
+use crate::{select_nth_unstable, split_partition};
+
+/// Slices of at most this length are selected by comparing the elements, since counting them
+/// costs a pass over the 256 counters regardless of the length.
+const COUNTING_CUTOFF: usize = 384;
+
+/// Reorders the slice of bytes such that the element at `index` is at its final sorted position,
+/// like [`select_nth_unstable`](crate::select_nth_unstable), but by counting the elements instead
+/// of comparing them.
+///
+/// The occurrences of each of the 256 values are counted in a single pass, which gives the value
+/// at `index` directly. Since bytes equal in value are indistinguishable, the slice is then
+/// overwritten with the counted values in sorted order, which takes a second pass. This is `O(n)`
+/// without any comparisons, and much faster than comparison-based selection for long slices, which
+/// have many duplicates.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable). Unlike it, the
+/// subslices are sorted, unless the slice is short enough to be selected by comparisons.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_u8;
+/// let mut v = *b"turboselect";
+/// let (_, median, _) = select_nth_unstable_u8(&mut v, 5);
+/// assert_eq!(*median, b'o');
+/// ```
+pub fn select_nth_unstable_u8(data: &mut [u8], index: usize) -> (&mut [u8], &mut u8, &mut [u8]) {
+    assert!(index < data.len());
+    if data.len() <= COUNTING_CUTOFF {
+        return select_nth_unstable(data, index);
+    }
+
+    let mut start = 0;
+    for (value, count) in count(data).into_iter().enumerate() {
+        data[start..start + count].fill(value as u8);
+        start += count;
+    }
+    split_partition(data, index)
+}
+
+/// Returns the number of occurrences of each value in `data`. Four sets of counters are used in
+/// turns, so that runs of equal values don't serialize the increments.
+fn count(data: &[u8]) -> [usize; 256] {
+    let mut counts = [[0; 256]; 4];
+    let mut chunks = data.chunks_exact(4);
+    for chunk in chunks.by_ref() {
+        for (counts, &x) in counts.iter_mut().zip(chunk) {
+            counts[usize::from(x)] += 1;
+        }
+    }
+    for &x in chunks.remainder() {
+        counts[0][usize::from(x)] += 1;
+    }
+    let [mut total, rest @ ..] = counts;
+    for counts in rest {
+        for (total, count) in total.iter_mut().zip(counts) {
+            *total += count;
+        }
+    }
+    total
+}
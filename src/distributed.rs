@@ -0,0 +1,223 @@
+//! Helpers for exact selection over data that is split into shards, e.g. across machines.
+//!
+//! The selection proceeds in rounds. In each round, the coordinator picks a candidate pivot,
+//! typically from a small sample gathered from the shards, and broadcasts it. Each shard partitions
+//! its active elements around the pivot with [`partition_by_value`] and reports the resulting
+//! [`Counts`]. The coordinator sums the counts and calls [`next_step`] to find out whether the
+//! pivot is the element at the requested rank, or which side of the pivot each shard should keep
+//! for the next round, and at which rank. The shards then narrow their active elements with
+//! [`narrow`].
+//!
+//! # Examples
+//!
+//! ```
+//! use turboselect::distributed::{narrow, next_step, partition_by_value, Counts, Step};
+//!
+//! let mut shards = [vec![9, 2, 7, 4], vec![1, 8, 3], vec![6, 5, 0]];
+//! let mut active: Vec<&mut [i32]> = shards.iter_mut().map(|s| s.as_mut_slice()).collect();
+//! let mut index = 6;
+//!
+//! let nth = loop {
+//!     // Use the first active element of any shard as the pivot.
+//!     let pivot = *active.iter().find_map(|shard| shard.first()).unwrap();
+//!     let counts: Vec<Counts> = active
+//!         .iter_mut()
+//!         .map(|shard| partition_by_value(shard, &pivot))
+//!         .collect();
+//!     let step = next_step(counts.iter().copied().sum(), index);
+//!     if step == Step::Found {
+//!         break pivot;
+//!     }
+//!     active = active
+//!         .into_iter()
+//!         .zip(&counts)
+//!         .map(|(shard, &counts)| narrow(shard, counts, step))
+//!         .collect();
+//!     index = step.index();
+//! };
+//! assert_eq!(nth, 6);
+//! ```
+
+use core::{
+    cmp::Ordering,
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+use crate::{math::floor, params::params, partition_in_blocks};
+
+/// The number of elements less than, equal to, and greater than a pivot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// The number of elements less than the pivot.
+    pub less: usize,
+    /// The number of elements equal to the pivot.
+    pub equal: usize,
+    /// The number of elements greater than the pivot.
+    pub greater: usize,
+}
+
+impl Counts {
+    /// Returns the total number of elements.
+    pub fn total(&self) -> usize {
+        self.less + self.equal + self.greater
+    }
+}
+
+impl Add for Counts {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            less: self.less + rhs.less,
+            equal: self.equal + rhs.equal,
+            greater: self.greater + rhs.greater,
+        }
+    }
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Counts {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// The outcome of a selection round, as decided by the coordinator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// The pivot is the element at the requested rank.
+    Found,
+    /// The element is among the elements less than the pivot, at rank `index` among them.
+    Less { index: usize },
+    /// The element is among the elements greater than the pivot, at rank `index` among them.
+    Greater { index: usize },
+}
+
+impl Step {
+    /// Returns the rank to select in the next round. For [`Step::Found`], returns 0.
+    pub fn index(&self) -> usize {
+        match *self {
+            Step::Found => 0,
+            Step::Less { index } | Step::Greater { index } => index,
+        }
+    }
+}
+
+/// Returns the sub-slice of a shard partitioned with [`partition_by_value`] that remains active
+/// after `step`. Returns an empty slice for [`Step::Found`].
+pub fn narrow<T>(data: &mut [T], counts: Counts, step: Step) -> &mut [T] {
+    match step {
+        Step::Found => &mut [],
+        Step::Less { .. } => &mut data[..counts.less],
+        Step::Greater { .. } => &mut data[counts.less + counts.equal..],
+    }
+}
+
+/// Decides the outcome of a round, given the `counts` summed over all shards and the rank `index`
+/// to select among the active elements.
+///
+/// # Panics
+///
+/// Panics if `index >= counts.total()`.
+pub fn next_step(counts: Counts, index: usize) -> Step {
+    assert!(index < counts.total());
+    if index < counts.less {
+        Step::Less { index }
+    } else if index < counts.less + counts.equal {
+        Step::Found
+    } else {
+        Step::Greater {
+            index: index - counts.less - counts.equal,
+        }
+    }
+}
+
+/// Partitions `data` into the elements less than `pivot`, followed by the elements equal to it,
+/// followed by the elements greater than it. Returns the sizes of the three parts.
+pub fn partition_by_value<T>(data: &mut [T], pivot: &T) -> Counts
+where
+    T: Ord,
+{
+    partition_by_value_by_lt(data, pivot, T::lt)
+}
+
+/// Partitions `data` with a comparator function like [`partition_by_value`].
+pub fn partition_by_value_by<T, F>(data: &mut [T], pivot: &T, mut compare: F) -> Counts
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    partition_by_value_by_lt(data, pivot, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Partitions `data` with a "less than" function like [`partition_by_value`].
+pub fn partition_by_value_by_lt<T, F>(data: &mut [T], pivot: &T, mut lt: F) -> Counts
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let block = params::<T>().partition_block;
+    let less = partition_in_blocks(data, pivot, &mut lt, block);
+    let equal = partition_in_blocks(&mut data[less..], pivot, &mut |a, b| !lt(b, a), block);
+    Counts {
+        less,
+        equal,
+        greater: data.len() - less - equal,
+    }
+}
+
+/// Counts the elements of `data` less than, equal to and greater than `pivot` without reordering
+/// the slice.
+pub fn rank_of<T>(data: &[T], pivot: &T) -> Counts
+where
+    T: Ord,
+{
+    rank_of_by_lt(data, pivot, T::lt)
+}
+
+/// Counts the elements of `data` with a comparator function like [`rank_of`].
+pub fn rank_of_by<T, F>(data: &[T], pivot: &T, mut compare: F) -> Counts
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut counts = Counts::default();
+    for elem in data {
+        match compare(elem, pivot) {
+            Ordering::Less => counts.less += 1,
+            Ordering::Equal => counts.equal += 1,
+            Ordering::Greater => counts.greater += 1,
+        }
+    }
+    counts
+}
+
+/// Counts the elements of `data` with a "less than" function like [`rank_of`].
+pub fn rank_of_by_lt<T, F>(data: &[T], pivot: &T, mut lt: F) -> Counts
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut counts = Counts::default();
+    for elem in data {
+        counts.less += lt(elem, pivot) as usize;
+        counts.greater += lt(pivot, elem) as usize;
+    }
+    counts.equal = data.len() - counts.less - counts.greater;
+    counts
+}
+
+/// Returns the rank within a combined sample of `sample_len` elements, drawn from `total` active
+/// elements, of the element to use as the next pivot when selecting the rank `index`.
+///
+/// # Panics
+///
+/// Panics if `sample_len == 0` or if `index >= total`.
+pub fn sample_rank(index: usize, total: usize, sample_len: usize) -> usize {
+    assert!(sample_len > 0);
+    assert!(index < total);
+    let rank = floor((index as f64 + 0.5) * sample_len as f64 / total as f64) as usize;
+    rank.min(sample_len - 1)
+}
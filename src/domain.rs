@@ -0,0 +1,253 @@
+use core::{mem, ops::RangeInclusive};
+use std::{vec, vec::Vec};
+
+use crate::{params::params, partition_in_blocks, select, split_partition};
+
+/// Domains of more values than this are counted into buckets, since the counters of each value
+/// would no longer fit in the L1 cache.
+const MAX_COUNTS: u64 = 1 << 10;
+/// Slices with fewer elements per value of the domain than this are counted into buckets, since
+/// rewriting the slice from the counts costs a pass over the counters regardless of the length.
+const MIN_COUNT: u64 = 128;
+/// The maximum number of buckets the keys are counted into.
+const MAX_BUCKETS: usize = 1 << 12;
+/// Slices of at most this length are selected by comparing the keys.
+const DOMAIN_CUTOFF: usize = 64;
+
+/// Integer types that can be counted by their distance from the lower bound of a domain.
+pub trait DomainKey: Copy + Ord {
+    /// Returns the distance from `min` to `self`, where `min <= self`. Distances that don't fit in
+    /// a `u64` saturate to `u64::MAX`.
+    fn distance(self, min: Self) -> u64;
+
+    /// Returns the value at `distance` from `min`, where `distance` is at most the distance from
+    /// `min` to the maximum value of the type.
+    fn offset(min: Self, distance: u64) -> Self;
+}
+
+macro_rules! impl_domain_key {
+    ($($int:ty => $unsigned:ty),*) => {
+        $(
+            impl DomainKey for $int {
+                fn distance(self, min: Self) -> u64 {
+                    // In two's complement, the wrapping difference is the distance for signed
+                    // types too.
+                    let distance = (self as $unsigned).wrapping_sub(min as $unsigned);
+                    u64::try_from(distance).unwrap_or(u64::MAX)
+                }
+
+                fn offset(min: Self, distance: u64) -> Self {
+                    (min as $unsigned).wrapping_add(distance as $unsigned) as $int
+                }
+            }
+        )*
+    };
+}
+
+impl_domain_key!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize
+);
+
+/// Reorders the slice of integers such that the element at `index` is at its final sorted
+/// position, when all elements are known to be within `bounds`, e.g. telemetry readings with a
+/// fixed range.
+///
+/// If `bounds` spans at most 1024 values and the slice has at least 128 elements per value, the
+/// occurrences of each value are counted in a single pass, which gives the value at `index`
+/// directly. Since integers equal in value are indistinguishable, the slice is then overwritten
+/// with the counted values in sorted order, which takes a second pass, like
+/// [`select_nth_unstable_u8`](crate::select_nth_unstable_u8). This makes no comparisons, its cost
+/// doesn't depend on the order of the input, and it is faster than comparison-based selection,
+/// e.g. about 20% less time for a million `u16`s in a domain of 1000 values and about 40% less in
+/// a domain of 16 values. Otherwise, the elements are counted into buckets like in
+/// [`select_nth_unstable_by_domain_key`].
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable). If the elements are
+/// counted by value, the subslices are sorted.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices. Also panics if `bounds`
+/// is empty or an element is outside `bounds`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_in_domain;
+/// // Sensor readings known to be between 0 and 1000.
+/// let mut v: Vec<u16> = (0..100_000).map(|x| (x * 7919 % 1001) as u16).collect();
+/// let (_, median, _) = select_nth_unstable_in_domain(&mut v, 50_000, 0..=1000);
+/// assert_eq!(*median, 500);
+/// ```
+pub fn select_nth_unstable_in_domain<T>(
+    data: &mut [T],
+    index: usize,
+    bounds: RangeInclusive<T>,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: DomainKey,
+{
+    assert!(index < data.len());
+    let (min, max) = bounds.into_inner();
+    assert!(min <= max, "the bounds must not be empty");
+    let size = max.distance(min).saturating_add(1);
+    if size > MAX_COUNTS || size.saturating_mul(MIN_COUNT) > data.len() as u64 {
+        return select_nth_unstable_by_domain_key(data, index, min..=max, |&x| x);
+    }
+
+    let mut start = 0;
+    for (distance, count) in count(data, min, size as usize).into_iter().enumerate() {
+        data[start..start + count].fill(T::offset(min, distance as u64));
+        start += count;
+    }
+    split_partition(data, index)
+}
+
+/// Reorders the slice with a key extraction function such that the element at `index` is at its
+/// final sorted position, when all keys are known to be within `bounds`, e.g. records keyed by a
+/// telemetry field with a fixed range.
+///
+/// Instead of choosing pivots from samples, the keys are counted into up to 4096 buckets of equal
+/// width that cover `bounds`, which finds the bucket that contains the key at `index`. The slice is
+/// then partitioned once into the elements in lower buckets, the elements in that bucket, and the
+/// elements in higher buckets. If each bucket holds a single key, the selection is done. Otherwise,
+/// the selection continues by comparing the keys within the bucket.
+///
+/// This takes a counting pass and a partitioning pass over the slice, and its cost doesn't depend
+/// on the order of the input or on the choice of pivots. On data that fits in memory and is cheap
+/// to compare, [`select_nth_unstable_by_key`](crate::select_nth_unstable_by_key) is usually faster,
+/// since it reads the slice fewer times.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices. Also panics if `bounds`
+/// is empty or a key is outside `bounds`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_by_domain_key;
+/// let mut v: Vec<(i8, char)> = vec![(3, 'a'), (-2, 'b'), (0, 'c'), (3, 'd'), (-1, 'e')];
+/// let (_, nth, _) = select_nth_unstable_by_domain_key(&mut v, 3, -5..=5, |x| x.0);
+/// assert_eq!(nth.0, 3);
+/// ```
+pub fn select_nth_unstable_by_domain_key<T, K, F>(
+    data: &mut [T],
+    index: usize,
+    bounds: RangeInclusive<K>,
+    mut f: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    K: DomainKey,
+    F: FnMut(&T) -> K,
+{
+    assert!(index < data.len());
+    let (min, max) = bounds.into_inner();
+    assert!(min <= max, "the bounds must not be empty");
+    let mut key_in_bounds = |elem: &T| {
+        let key = f(elem);
+        assert!(min <= key && key <= max, "a key is outside the bounds");
+        key
+    };
+    if data.len() <= DOMAIN_CUTOFF || mem::size_of::<T>() == 0 {
+        data.iter().for_each(|elem| {
+            key_in_bounds(elem);
+        });
+        if data.len() > 1 && mem::size_of::<T>() > 0 {
+            select(data, index, &mut |a, b| f(a) < f(b));
+        }
+        return split_partition(data, index);
+    }
+
+    // Divide the domain into buckets whose width is a power of two, so that the bucket of a key is
+    // found with a shift. Using more buckets than elements would only make counting slower.
+    let size = max.distance(min).saturating_add(1);
+    let buckets = MAX_BUCKETS.min(data.len().next_power_of_two()) as u64;
+    let shift = size.div_ceil(buckets).next_power_of_two().trailing_zeros();
+    let bucket = |key: K| (key.distance(min) >> shift) as usize;
+
+    // Count the elements in each bucket, and find the bucket of the element at `index`, along with
+    // the number of elements in lower buckets.
+    let mut counts = vec![0; (size.saturating_sub(1) >> shift) as usize + 1];
+    for elem in data.iter() {
+        counts[bucket(key_in_bounds(elem))] += 1;
+    }
+    let (mut target, mut less) = (0, 0);
+    while less + counts[target] <= index {
+        less += counts[target];
+        target += 1;
+    }
+    let equal = counts[target];
+
+    if equal < data.len() {
+        // Move an element of the target bucket to the front and partition the rest around its
+        // bucket with the block partitioning.
+        let p = data
+            .iter()
+            .position(|elem| bucket(f(elem)) == target)
+            .unwrap();
+        data.swap(0, p);
+        let (pivot, rest) = data.split_first_mut().unwrap();
+        let block = params::<T>().partition_block;
+        // The block partitioning always passes the pivot as the second argument. If the buckets
+        // hold a single key each, comparing the keys is cheaper than finding their buckets.
+        let u = if shift == 0 {
+            let key = f(pivot);
+            let u = partition_in_blocks(rest, pivot, &mut |x, _| f(x) < key, block);
+            partition_in_blocks(&mut rest[u..], pivot, &mut |x, _| f(x) <= key, block);
+            u
+        } else {
+            let u = partition_in_blocks(rest, pivot, &mut |x, _| bucket(f(x)) < target, block);
+            partition_in_blocks(
+                &mut rest[u..],
+                pivot,
+                &mut |x, _| bucket(f(x)) <= target,
+                block,
+            );
+            u
+        };
+        data.swap(0, u);
+    }
+    if shift > 0 && equal > 1 {
+        let tied = &mut data[less..less + equal];
+        select(tied, index - less, &mut |a, b| f(a) < f(b));
+    }
+    split_partition(data, index)
+}
+
+/// Returns the number of occurrences of each of the `size` values from `min` on in `data`. Four
+/// counters are used for each value in turns, so that runs of equal values don't serialize the
+/// increments.
+fn count<T>(data: &[T], min: T, size: usize) -> Vec<usize>
+where
+    T: DomainKey,
+{
+    let mut counts = vec![[0; 4]; size];
+    let mut chunks = data.chunks_exact(4);
+    for chunk in chunks.by_ref() {
+        for (lane, &x) in chunk.iter().enumerate() {
+            counters(&mut counts, x, min)[lane] += 1;
+        }
+    }
+    for &x in chunks.remainder() {
+        counters(&mut counts, x, min)[0] += 1;
+    }
+    counts.into_iter().map(|lanes| lanes.iter().sum()).collect()
+}
+
+/// Returns the counters of the value `x`. Panics if `x` is outside the domain, including when it
+/// is below `min`, since its distance then wraps around past the domain.
+fn counters<T>(counts: &mut [[usize; 4]], x: T, min: T) -> &mut [usize; 4]
+where
+    T: DomainKey,
+{
+    usize::try_from(x.distance(min))
+        .ok()
+        .and_then(|distance| counts.get_mut(distance))
+        .expect("an element is outside the bounds")
+}
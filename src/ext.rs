@@ -0,0 +1,179 @@
+use core::cmp::Ordering;
+
+use crate::{
+    select_nth_unstable, select_nth_unstable_array, select_nth_unstable_array_by,
+    select_nth_unstable_array_by_lt, select_nth_unstable_by, select_nth_unstable_by_key,
+    select_nth_unstable_by_lt,
+};
+
+/// An extension trait for calling the selection functions of the crate with the method-call syntax
+/// of the slice methods they replace, e.g. `data.turbo_select_nth_unstable(k)` instead of
+/// `data.select_nth_unstable(k)`.
+///
+/// The methods are prefixed with `turbo_`, so that they don't clash with the inherent methods of
+/// slices, which would otherwise take precedence. They are implemented for slices, and can also be
+/// called on anything that dereferences to a slice, such as `Vec`. They are also implemented for
+/// arrays, which are selected like [`select_nth_unstable_array`](crate::select_nth_unstable_array)
+/// with the sorting network for their length.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::TurboSelect;
+/// let mut v = vec![-5i32, 4, 1, -3, 2];
+///
+/// let (_, median, _) = v.turbo_select_nth_unstable(2);
+/// assert_eq!(*median, 1);
+///
+/// let (_, median, _) = v.turbo_select_nth_unstable_by_key(2, |a| a.abs());
+/// assert_eq!(*median, -3);
+/// ```
+pub trait TurboSelect<T> {
+    /// Reorders the slice such that the element at `index` is at its final sorted position. See
+    /// [`select_nth_unstable`](crate::select_nth_unstable).
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index >= len()`, meaning it always panics on empty slices.
+    fn turbo_select_nth_unstable(&mut self, index: usize) -> (&mut [T], &mut T, &mut [T])
+    where
+        T: Ord;
+
+    /// Reorders the slice with a comparator function such that the element at `index` is at its
+    /// final sorted position. See [`select_nth_unstable_by`](crate::select_nth_unstable_by).
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index >= len()`, meaning it always panics on empty slices.
+    fn turbo_select_nth_unstable_by<F>(
+        &mut self,
+        index: usize,
+        compare: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    /// Reorders the slice with a key extraction function such that the element at `index` is at
+    /// its final sorted position. See
+    /// [`select_nth_unstable_by_key`](crate::select_nth_unstable_by_key).
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index >= len()`, meaning it always panics on empty slices.
+    fn turbo_select_nth_unstable_by_key<K, F>(
+        &mut self,
+        index: usize,
+        f: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T) -> K,
+        K: Ord;
+
+    /// Reorders the slice with a "less than" function such that the element at `index` is at its
+    /// final sorted position. See [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index >= len()`, meaning it always panics on empty slices.
+    fn turbo_select_nth_unstable_by_lt<F>(
+        &mut self,
+        index: usize,
+        lt: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T, &T) -> bool;
+}
+
+impl<T> TurboSelect<T> for [T] {
+    #[inline]
+    fn turbo_select_nth_unstable(&mut self, index: usize) -> (&mut [T], &mut T, &mut [T])
+    where
+        T: Ord,
+    {
+        select_nth_unstable(self, index)
+    }
+
+    #[inline]
+    fn turbo_select_nth_unstable_by<F>(
+        &mut self,
+        index: usize,
+        compare: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        select_nth_unstable_by(self, index, compare)
+    }
+
+    #[inline]
+    fn turbo_select_nth_unstable_by_key<K, F>(
+        &mut self,
+        index: usize,
+        f: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        select_nth_unstable_by_key(self, index, f)
+    }
+
+    #[inline]
+    fn turbo_select_nth_unstable_by_lt<F>(
+        &mut self,
+        index: usize,
+        lt: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        select_nth_unstable_by_lt(self, index, lt)
+    }
+}
+
+impl<T, const N: usize> TurboSelect<T> for [T; N] {
+    #[inline]
+    fn turbo_select_nth_unstable(&mut self, index: usize) -> (&mut [T], &mut T, &mut [T])
+    where
+        T: Ord,
+    {
+        select_nth_unstable_array(self, index)
+    }
+
+    #[inline]
+    fn turbo_select_nth_unstable_by<F>(
+        &mut self,
+        index: usize,
+        compare: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        select_nth_unstable_array_by(self, index, compare)
+    }
+
+    #[inline]
+    fn turbo_select_nth_unstable_by_key<K, F>(
+        &mut self,
+        index: usize,
+        mut f: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        select_nth_unstable_array_by_lt(self, index, |a, b| f(a) < f(b))
+    }
+
+    #[inline]
+    fn turbo_select_nth_unstable_by_lt<F>(
+        &mut self,
+        index: usize,
+        lt: F,
+    ) -> (&mut [T], &mut T, &mut [T])
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        select_nth_unstable_array_by_lt(self, index, lt)
+    }
+}
@@ -0,0 +1,87 @@
+use core::array;
+use std::vec::Vec;
+
+use crate::{
+    select_nth_unstable,
+    sort::{median25, median9},
+};
+
+/// Applies a `size` × `size` median filter to the row-major image `src` with `width` columns and
+/// writes the result into `dst`.
+///
+/// Each output pixel is the median of the `size` × `size` window centered at the corresponding
+/// input pixel. Pixels outside the image are replaced with the nearest edge pixel. Windows of size
+/// 3 and 5 are handled with fixed median networks; larger windows are gathered into a scratch
+/// buffer, which is allocated once and reused for every pixel.
+///
+/// # Panics
+///
+/// Panics if `size` is even, if `width == 0`, if `src.len()` is not a multiple of `width`, or if
+/// `src` and `dst` have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_filter;
+/// let src = [
+///     1, 1, 1, 1,
+///     1, 9, 1, 1,
+///     1, 1, 1, 1,
+/// ];
+/// let mut dst = [0; 12];
+/// median_filter(&src, &mut dst, 4, 3);
+/// assert_eq!(dst, [1; 12]);
+/// ```
+pub fn median_filter<T>(src: &[T], dst: &mut [T], width: usize, size: usize)
+where
+    T: Ord + Copy,
+{
+    assert!(size % 2 == 1, "the window size must be odd");
+    assert!(width > 0, "the image must have at least one column");
+    assert!(
+        src.len().is_multiple_of(width),
+        "the length of the image must be a multiple of the width"
+    );
+    assert_eq!(src.len(), dst.len(), "the images must have the same length");
+
+    let height = src.len() / width;
+    let radius = size / 2;
+
+    // Returns the pixel at `(x + dx - radius, y + dy - radius)`, clamped to the image.
+    let at = |x: usize, y: usize, dx: usize, dy: usize| {
+        let x = (x + dx).saturating_sub(radius).min(width - 1);
+        let y = (y + dy).saturating_sub(radius).min(height - 1);
+        src[y * width + x]
+    };
+
+    match size {
+        1 => dst.copy_from_slice(src),
+        3 => {
+            for (i, out) in dst.iter_mut().enumerate() {
+                let (x, y) = (i % width, i / width);
+                let mut window: [T; 9] = array::from_fn(|j| at(x, y, j % 3, j / 3));
+                median9(&mut window, &mut T::lt);
+                *out = window[4];
+            }
+        }
+        5 => {
+            for (i, out) in dst.iter_mut().enumerate() {
+                let (x, y) = (i % width, i / width);
+                let mut window: [T; 25] = array::from_fn(|j| at(x, y, j % 5, j / 5));
+                median25(&mut window, &mut T::lt);
+                *out = window[12];
+            }
+        }
+        _ => {
+            let mut window = Vec::with_capacity(size * size);
+            for (i, out) in dst.iter_mut().enumerate() {
+                let (x, y) = (i % width, i / width);
+                window.clear();
+                for dy in 0..size {
+                    window.extend((0..size).map(|dx| at(x, y, dx, dy)));
+                }
+                *out = *select_nth_unstable(&mut window, size * size / 2).1;
+            }
+        }
+    }
+}
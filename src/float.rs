@@ -0,0 +1,139 @@
+use core::fmt;
+
+use crate::{select_nth_unstable_by_lt, split_partition};
+
+/// Where [`select_nth_unstable_float`] orders NaNs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NanPolicy {
+    /// NaNs are ordered before all other values, regardless of their sign.
+    First,
+    /// NaNs are ordered after all other values, regardless of their sign.
+    #[default]
+    Last,
+    /// The selection fails with a [`NanError`] if the slice contains a NaN.
+    Error,
+}
+
+/// The error returned by [`select_nth_unstable_float`] with [`NanPolicy::Error`] if the slice
+/// contains a NaN.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NanError {
+    /// The position of the first NaN in the slice.
+    pub index: usize,
+}
+
+impl fmt::Display for NanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the slice contains a NaN at position {}", self.index)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NanError {}
+
+mod sealed {
+    pub trait Sealed: Copy {
+        /// A signed integer of the same width, whose order is the total order of the floats.
+        type Key: Copy + Ord;
+
+        /// Returns `true` if `self` is NaN.
+        fn is_nan(self) -> bool;
+
+        /// Returns the key of `self` in the total order of the floats, rotated so that the NaNs
+        /// come first if `NANS_FIRST` is `true`, and last otherwise.
+        fn key<const NANS_FIRST: bool>(self) -> Self::Key;
+    }
+}
+
+/// A floating point type that [`select_nth_unstable_float`] can select from. This trait is sealed
+/// and implemented for `f32` and `f64`.
+pub trait FloatElement: sealed::Sealed {}
+
+macro_rules! float_element {
+    ($($float:ty => $int:ty, $uint:ty),*) => {$(
+        impl sealed::Sealed for $float {
+            type Key = $int;
+
+            #[inline]
+            fn is_nan(self) -> bool {
+                <$float>::is_nan(self)
+            }
+
+            #[inline]
+            fn key<const NANS_FIRST: bool>(self) -> $int {
+                // The same bit trick as `total_cmp`: the bits of negative floats are inverted,
+                // except for the sign bit, so that they compare in reverse as signed integers.
+                let bits = self.to_bits() as $int;
+                let key = bits ^ (((bits >> (<$int>::BITS - 1)) as $uint) >> 1) as $int;
+                // In this order, the negative NaNs come first and the positive NaNs last, and
+                // there are as many of them as there are mantissas. Shifting the keys by that
+                // much wraps the NaNs of one sign around to the other end, next to the others.
+                let nans = ((1 as $uint) << (<$float>::MANTISSA_DIGITS - 1)) as $int - 1;
+                match NANS_FIRST {
+                    true => key.wrapping_add(nans),
+                    false => key.wrapping_sub(nans),
+                }
+            }
+        }
+
+        impl FloatElement for $float {}
+    )*};
+}
+
+float_element!(f32 => i32, u32, f64 => i64, u64);
+
+/// Reorders the slice of floats such that the element at `index` is at its final sorted position,
+/// with the NaNs ordered according to `nans`.
+///
+/// The other values are ordered like [`f64::total_cmp`], so `-0.0` comes before `0.0`. Each float
+/// is compared through an integer key computed from its bits, which is about as fast as comparing
+/// the floats with `total_cmp` and, unlike comparing them with `partial_cmp(..).unwrap()`, can't panic
+/// or leave the slice partitioned inconsistently when it contains NaNs. The NaNs are ordered among
+/// themselves by their sign and payload.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable). With
+/// [`NanPolicy::Error`], returns a [`NanError`] with the position of the first NaN instead, and
+/// leaves the slice unchanged.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_float, NanError, NanPolicy};
+/// let mut v = [2.5, f64::NAN, -1.0, 0.0, -0.0];
+///
+/// let (_, nth, _) = select_nth_unstable_float(&mut v, 4, NanPolicy::Last).unwrap();
+/// assert!(nth.is_nan());
+/// let (_, nth, _) = select_nth_unstable_float(&mut v, 1, NanPolicy::First).unwrap();
+/// assert_eq!(*nth, -1.0);
+///
+/// let mut v = [2.5, f64::NAN, -1.0];
+/// let result = select_nth_unstable_float(&mut v, 0, NanPolicy::Error);
+/// assert_eq!(result, Err(NanError { index: 1 }));
+/// ```
+pub fn select_nth_unstable_float<T>(
+    data: &mut [T],
+    index: usize,
+    nans: NanPolicy,
+) -> Result<(&mut [T], &mut T, &mut [T]), NanError>
+where
+    T: FloatElement,
+{
+    assert!(index < data.len());
+    if nans == NanPolicy::Error {
+        if let Some(index) = data.iter().position(|x| x.is_nan()) {
+            return Err(NanError { index });
+        }
+    }
+    match nans {
+        NanPolicy::First => {
+            select_nth_unstable_by_lt(data, index, |a, b| a.key::<true>() < b.key::<true>())
+        }
+        _ => select_nth_unstable_by_lt(data, index, |a, b| a.key::<false>() < b.key::<false>()),
+    };
+    Ok(split_partition(data, index))
+}
@@ -0,0 +1,143 @@
+use core::cmp::Ordering;
+
+use crate::select_nth_unstable_by_lt;
+
+/// The outcome of [`select_nth_unstable_with_fold`].
+///
+/// The slice is split around the selected element like the result of
+/// [`select_nth_unstable`](crate::select_nth_unstable), and the accumulators hold the fold over the
+/// elements on either side of it. The selected element itself is not folded into either.
+#[derive(Debug)]
+pub struct Folded<'a, T, A> {
+    /// The elements before the selected index.
+    pub below: &'a mut [T],
+    /// The selected element.
+    pub nth: &'a mut T,
+    /// The elements after the selected index.
+    pub above: &'a mut [T],
+    /// The fold over the elements of `below`.
+    pub fold_below: A,
+    /// The fold over the elements of `above`.
+    pub fold_above: A,
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, and folds
+/// `fold` over the elements on either side of it, starting from `init`, e.g. to get the sum or the
+/// count of the elements below a quantile.
+///
+/// The slice is selected like [`select_nth_unstable`](crate::select_nth_unstable), and each side is
+/// folded afterwards. Folding the parts ruled out by each round while they are still in the cache
+/// was no faster, since most of a large slice is ruled out by the first few rounds. The elements
+/// are folded in an unspecified order, so `fold` should not depend on it, like a sum, a minimum or
+/// a count.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_fold;
+/// let mut v: Vec<u64> = (1..=100).rev().collect();
+///
+/// // The median and the sums of the elements on either side of it.
+/// let result = select_nth_unstable_with_fold(&mut v, 49, 0, |sum, x| sum + x);
+/// assert_eq!(*result.nth, 50);
+/// assert_eq!(result.fold_below, (1..50).sum());
+/// assert_eq!(result.fold_above, (51..=100).sum());
+/// ```
+pub fn select_nth_unstable_with_fold<T, A, G>(
+    data: &mut [T],
+    index: usize,
+    init: A,
+    fold: G,
+) -> Folded<'_, T, A>
+where
+    T: Ord,
+    A: Clone,
+    G: FnMut(A, &T) -> A,
+{
+    select_nth_unstable_with_fold_by_lt(data, index, init, fold, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, and folds `fold` over the elements on either side of it.
+///
+/// See [`select_nth_unstable_with_fold`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_fold_by;
+/// let mut v = [0.5, -1.0, 2.0, 0.25, 4.0];
+/// let result = select_nth_unstable_with_fold_by(&mut v, 1, 0.0, |s, x| s + x, f64::total_cmp);
+/// assert_eq!(*result.nth, 0.25);
+/// assert_eq!(result.fold_below, -1.0);
+/// assert_eq!(result.fold_above, 6.5);
+/// ```
+pub fn select_nth_unstable_with_fold_by<T, A, G, F>(
+    data: &mut [T],
+    index: usize,
+    init: A,
+    fold: G,
+    mut compare: F,
+) -> Folded<'_, T, A>
+where
+    A: Clone,
+    G: FnMut(A, &T) -> A,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_fold_by_lt(data, index, init, fold, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, and folds `fold` over the elements on either side of it.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_fold`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_fold_by_lt;
+/// let mut v: Vec<u32> = (0..1000).collect();
+///
+/// // The 10th largest element and the number of odd elements larger than it.
+/// let result = select_nth_unstable_with_fold_by_lt(&mut v, 9, 0, |n, x| n + x % 2, |a, b| a > b);
+/// assert_eq!(*result.nth, 990);
+/// assert_eq!(result.fold_below, 5);
+/// ```
+pub fn select_nth_unstable_with_fold_by_lt<T, A, G, F>(
+    data: &mut [T],
+    index: usize,
+    init: A,
+    mut fold: G,
+    lt: F,
+) -> Folded<'_, T, A>
+where
+    A: Clone,
+    G: FnMut(A, &T) -> A,
+    F: FnMut(&T, &T) -> bool,
+{
+    let (below, nth, above) = select_nth_unstable_by_lt(data, index, lt);
+    let fold_below = below.iter().fold(init.clone(), &mut fold);
+    let fold_above = above.iter().fold(init, &mut fold);
+    Folded {
+        below,
+        nth,
+        above,
+        fold_below,
+        fold_above,
+    }
+}
@@ -0,0 +1,200 @@
+use core::cmp::Ordering;
+use std::vec::Vec;
+
+use crate::select_nth_unstable_by_lt;
+
+/// Returns the element that would be at position `k` if the elements of `iter` were sorted, or
+/// `None` if `iter` has at most `k` elements.
+///
+/// The elements are collected into a vector, which is reordered with
+/// [`select_nth_unstable`](crate::select_nth_unstable), and the element at `k` is moved out of it.
+/// To reuse the vector across calls, or to get the other elements partitioned around the selected
+/// one, use an [`IterSelector`].
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_from_iter;
+/// let text = "the quick brown fox jumps over the lazy dog";
+/// let median = select_nth_from_iter(text.split(' ').map(str::len), 4);
+/// assert_eq!(median, Some(4));
+/// assert_eq!(select_nth_from_iter(0..3, 3), None);
+/// ```
+pub fn select_nth_from_iter<I>(iter: I, k: usize) -> Option<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    IterSelector::new().select_nth(iter, k)
+}
+
+/// Returns the element that would be at position `k` if the elements of `iter` were sorted with
+/// respect to a comparator function, or `None` if `iter` has at most `k` elements. See
+/// [`select_nth_from_iter`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_from_iter_by;
+/// let nth = select_nth_from_iter_by([2.5, -1.0, 0.5, 3.0], 1, f64::total_cmp);
+/// assert_eq!(nth, Some(0.5));
+/// ```
+pub fn select_nth_from_iter_by<I, F>(iter: I, k: usize, compare: F) -> Option<I::Item>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    IterSelector::new().select_nth_by(iter, k, compare)
+}
+
+/// Returns the element that would be at position `k` if the elements of `iter` were sorted with
+/// respect to a "less than" function, or `None` if `iter` has at most `k` elements. See
+/// [`select_nth_from_iter`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_from_iter_by_lt;
+/// let words = ["select", "a", "partition", "the", "pivot"];
+/// let longest = select_nth_from_iter_by_lt(words, 0, |a, b| a.len() > b.len());
+/// assert_eq!(longest, Some("partition"));
+/// ```
+pub fn select_nth_from_iter_by_lt<I, F>(iter: I, k: usize, lt: F) -> Option<I::Item>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> bool,
+{
+    IterSelector::new().select_nth_by_lt(iter, k, lt)
+}
+
+/// Selects elements from iterators, collecting them into a scratch vector that is kept between
+/// calls, so that repeated selections don't allocate once the vector has grown to the longest
+/// input.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::IterSelector;
+/// let mut selector = IterSelector::with_capacity(100);
+/// for day in 0..7u32 {
+///     let readings = (0..100).map(|i| (i * 37 + day * 11) % 100);
+///     assert_eq!(selector.select_nth(readings, 50), Some(50));
+/// }
+///
+/// // The other elements can be kept too, partitioned around the selected one.
+/// let (below, nth, above) = selector.partition_nth([5, 1, 4, 2, 3], 1).unwrap();
+/// assert_eq!(*nth, 2);
+/// assert_eq!(below, [1]);
+/// assert!(above.iter().all(|x| *x > 2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct IterSelector<T> {
+    /// The elements of the current input. Emptied at the start of each call.
+    scratch: Vec<T>,
+}
+
+impl<T> Default for IterSelector<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IterSelector<T> {
+    /// Returns a selector with an empty scratch vector.
+    pub fn new() -> Self {
+        Self {
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Returns a selector whose scratch vector can hold `capacity` elements without allocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            scratch: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the element at position `k` of the sorted elements of `iter`, or `None` if `iter`
+    /// has at most `k` elements. The other elements are dropped.
+    pub fn select_nth<I>(&mut self, iter: I, k: usize) -> Option<T>
+    where
+        I: IntoIterator<Item = T>,
+        T: Ord,
+    {
+        self.select_nth_by_lt(iter, k, T::lt)
+    }
+
+    /// Like [`select_nth`](Self::select_nth), but with a comparator function.
+    pub fn select_nth_by<I, F>(&mut self, iter: I, k: usize, mut compare: F) -> Option<T>
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.select_nth_by_lt(iter, k, |a, b| compare(a, b) == Ordering::Less)
+    }
+
+    /// Like [`select_nth`](Self::select_nth), but with a "less than" function.
+    pub fn select_nth_by_lt<I, F>(&mut self, iter: I, k: usize, lt: F) -> Option<T>
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(&T, &T) -> bool,
+    {
+        let found = self.partition_nth_by_lt(iter, k, lt).is_some();
+        // The order of the other elements doesn't matter anymore, since they are dropped.
+        let nth = found.then(|| self.scratch.swap_remove(k));
+        self.scratch.clear();
+        nth
+    }
+
+    /// Collects the elements of `iter` and reorders them such that the element at `k` is at its
+    /// final sorted position, like [`select_nth_unstable`](crate::select_nth_unstable). Returns the
+    /// elements before `k`, the element at `k` and the elements after `k`, which stay in the
+    /// scratch vector until the next call, or `None` if `iter` has at most `k` elements.
+    pub fn partition_nth<I>(&mut self, iter: I, k: usize) -> Option<(&mut [T], &mut T, &mut [T])>
+    where
+        I: IntoIterator<Item = T>,
+        T: Ord,
+    {
+        self.partition_nth_by_lt(iter, k, T::lt)
+    }
+
+    /// Like [`partition_nth`](Self::partition_nth), but with a comparator function.
+    pub fn partition_nth_by<I, F>(
+        &mut self,
+        iter: I,
+        k: usize,
+        mut compare: F,
+    ) -> Option<(&mut [T], &mut T, &mut [T])>
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.partition_nth_by_lt(iter, k, |a, b| compare(a, b) == Ordering::Less)
+    }
+
+    /// Like [`partition_nth`](Self::partition_nth), but with a "less than" function.
+    pub fn partition_nth_by_lt<I, F>(
+        &mut self,
+        iter: I,
+        k: usize,
+        lt: F,
+    ) -> Option<(&mut [T], &mut T, &mut [T])>
+    where
+        I: IntoIterator<Item = T>,
+        F: FnMut(&T, &T) -> bool,
+    {
+        self.scratch.clear();
+        self.scratch.extend(iter);
+        if k >= self.scratch.len() {
+            return None;
+        }
+        Some(select_nth_unstable_by_lt(&mut self.scratch, k, lt))
+    }
+
+    /// Returns the elements of the last call to [`partition_nth`](Self::partition_nth) or its
+    /// variants, in their partitioned order. The scratch vector is handed over, so the next call
+    /// starts with a new, empty one.
+    pub fn take(&mut self) -> Vec<T> {
+        core::mem::take(&mut self.scratch)
+    }
+}
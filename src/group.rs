@@ -0,0 +1,71 @@
+use crate::select_nth_unstable_by_key;
+
+/// Reorders the slice with a key extraction function such that the element at `index` is at its
+/// final sorted position, and gathers all elements with the same key as it into a contiguous block.
+///
+/// Returns a triplet of the following from the reordered slice: the subslice of elements with a
+/// smaller key, the block of elements with a key equal to the key of the element at `index`, and
+/// the subslice of elements with a larger key. The block always contains `index`. This is useful
+/// when the elements tied with the selected one need a secondary ordering, e.g. for deciding which
+/// of the candidates tied at the cutoff of a ranking are kept.
+///
+/// After the selection, the keys of the elements are extracted again to find the ones equal to the
+/// selected key, so this takes *O*(*n*) time on average.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_and_group;
+/// let mut v = [(3, 'a'), (1, 'b'), (2, 'c'), (2, 'd'), (5, 'e'), (2, 'f')];
+/// let (below, tied, above) = select_and_group(&mut v, 2, |x| x.0);
+/// assert_eq!(below, [(1, 'b')]);
+/// assert_eq!(tied.len(), 3);
+/// assert!(tied.iter().all(|x| x.0 == 2));
+/// assert_eq!(above.len(), 2);
+/// ```
+pub fn select_and_group<T, K, F>(
+    data: &mut [T],
+    index: usize,
+    mut f: F,
+) -> (&mut [T], &mut [T], &mut [T])
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    select_nth_unstable_by_key(data, index, &mut f);
+    let key = f(&data[index]);
+    // The keys before `index` are not greater than the selected key, so the equal ones are moved
+    // to the end of that part. Likewise, the equal keys after `index` are moved to its beginning.
+    let start = partition_in_place(&mut data[..index], |x| f(x) < key);
+    let end = index + 1 + partition_in_place(&mut data[index + 1..], |x| f(x) <= key);
+    let (below, rest) = data.split_at_mut(start);
+    let (tied, above) = rest.split_at_mut(end - start);
+    (below, tied, above)
+}
+
+/// Moves the elements for which `pred` returns `true` to the beginning of `data` and returns their
+/// count. The order of the elements is not preserved.
+fn partition_in_place<T, P>(data: &mut [T], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    let (mut l, mut r) = (0, data.len());
+    loop {
+        while l < r && pred(&data[l]) {
+            l += 1;
+        }
+        while l < r && !pred(&data[r - 1]) {
+            r -= 1;
+        }
+        if l == r {
+            return l;
+        }
+        data.swap(l, r - 1);
+        l += 1;
+        r -= 1;
+    }
+}
@@ -0,0 +1,236 @@
+use core::mem;
+
+/// The largest heap used by `heapselect`. A heap of a few elements is cheap to update, and an
+/// element rarely makes it into the heap, so the scan costs about one comparison per element.
+/// Larger heaps are updated too often for the scan to beat partitioning.
+const HEAP_MAX: usize = 8;
+
+/// The number of elements scanned at a time. The elements of a block are first compared with the
+/// root of the heap without branching, which the compiler can vectorize, and only the blocks with
+/// an element smaller than the root are scanned again to update the heap.
+const BLOCK: usize = 64;
+
+/// Returns `true` if selecting `index` with `heapselect` is likely to be faster than partitioning,
+/// i.e. if `index` is among the first or the last `HEAP_MAX` positions of a slice that is long
+/// compared to the heap. The first and the last position are found faster without a heap.
+#[inline]
+pub(crate) fn is_heap_index(len: usize, index: usize) -> bool {
+    let heap = index.min(len - 1 - index) + 1;
+    (2..=HEAP_MAX).contains(&heap) && heap * BLOCK <= len
+}
+
+/// Selects the element at `index` with a heap of the smallest `index + 1` elements, or of the
+/// largest `len - index` elements if that is smaller. The other elements are scanned once, and each
+/// element that belongs in the heap replaces its root.
+///
+/// Returns `false` if the heap was updated so often that the slice is likely to be sorted in the
+/// opposite order, in which case the slice is left reordered but not partitioned.
+pub(crate) fn heapselect<T, F>(data: &mut [T], index: usize, lt: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if index < data.len() - 1 - index {
+        // A max-heap of the smallest elements, whose root ends up at `index`.
+        let (heap, rest) = data.split_at_mut(index + 1);
+        if !scan(heap, rest, lt) {
+            return false;
+        }
+        data.swap(0, index);
+    } else {
+        // A min-heap of the largest elements, whose root is at `index` already.
+        let (rest, heap) = data.split_at_mut(index);
+        if !scan(heap, rest, &mut |a, b| lt(b, a)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Heapifies `heap` as a max-heap, and swaps the elements of `rest` that are smaller than its root
+/// into the heap. Gives up and returns `false` after `2 * heap.len() * log2(rest.len())` swaps,
+/// about three times as many as a random order needs on average.
+fn scan<T, F>(heap: &mut [T], rest: &mut [T], lt: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    heapify(heap, lt);
+    let mut budget = 2 * heap.len() * rest.len().max(1).ilog2() as usize;
+    let mut blocks = rest.chunks_exact_mut(BLOCK);
+    for block in blocks.by_ref() {
+        // Not short-circuiting, so that the elements are compared at once.
+        if block.iter().fold(false, |any, x| any | lt(x, &heap[0]))
+            && !block.iter_mut().all(|x| push(heap, x, lt, &mut budget))
+        {
+            return false;
+        }
+    }
+    blocks
+        .into_remainder()
+        .iter_mut()
+        .all(|x| push(heap, x, lt, &mut budget))
+}
+
+/// Swaps `x` with the root of `heap` if it is smaller, and restores the heap. Returns `false`
+/// instead if `x` is smaller but the `budget` of swaps is used up.
+#[inline]
+fn push<T, F>(heap: &mut [T], x: &mut T, lt: &mut F, budget: &mut usize) -> bool
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if lt(x, &heap[0]) {
+        if *budget == 0 {
+            return false;
+        }
+        *budget -= 1;
+        mem::swap(x, &mut heap[0]);
+        sift_down(heap, 0, lt);
+    }
+    true
+}
+
+/// Reorders `heap` into a max-heap.
+pub(crate) fn heapify<T, F>(heap: &mut [T], lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for node in (0..heap.len() / 2).rev() {
+        sift_down(heap, node, lt);
+    }
+}
+
+/// Restores the max-heap property of `heap` below `node`, whose children are max-heaps.
+pub(crate) fn sift_down<T, F>(heap: &mut [T], mut node: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    loop {
+        let mut child = 2 * node + 1;
+        if child >= heap.len() {
+            return;
+        }
+        if child + 1 < heap.len() && lt(&heap[child], &heap[child + 1]) {
+            child += 1;
+        }
+        if !lt(&heap[node], &heap[child]) {
+            return;
+        }
+        heap.swap(node, child);
+        node = child;
+    }
+}
+
+/// A min-max heap stored in a slice: the elements on even levels of the tree, starting with the
+/// root, are the smallest of their subtrees, and the elements on odd levels the largest. This gives
+/// both the minimum and the maximum in constant time, and replaces the minimum in logarithmic time.
+#[cfg(feature = "std")]
+pub(crate) mod minmax {
+    /// Returns `true` if position `i` is on a level of minima.
+    fn is_min_level(i: usize) -> bool {
+        (i + 1).ilog2() & 1 == 0
+    }
+
+    /// Returns the position of the maximum of `heap`, which must not be empty. The maximum is the
+    /// root or one of its children.
+    pub(crate) fn position_of_max<T, F>(heap: &[T], lt: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        match heap.len() {
+            1 => 0,
+            2 => 1,
+            _ if lt(&heap[1], &heap[2]) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Restores the heap after the element at position `i` was added or decreased, i.e. moves it
+    /// up while it is out of order with its parent or grandparent.
+    pub(crate) fn sift_up<T, F>(heap: &mut [T], mut i: usize, lt: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        if i == 0 {
+            return;
+        }
+        let parent = (i - 1) / 2;
+        let min_level = is_min_level(i);
+        // An element on a level of minima that is larger than its parent belongs on the levels
+        // of maxima above, and vice versa.
+        let max = if min_level == lt(&heap[parent], &heap[i]) {
+            heap.swap(i, parent);
+            i = parent;
+            min_level
+        } else {
+            !min_level
+        };
+        while i > 2 {
+            let grandparent = ((i - 1) / 2 - 1) / 2;
+            let out_of_order = match max {
+                true => lt(&heap[grandparent], &heap[i]),
+                false => lt(&heap[i], &heap[grandparent]),
+            };
+            if !out_of_order {
+                return;
+            }
+            heap.swap(i, grandparent);
+            i = grandparent;
+        }
+    }
+
+    /// Restores the heap after the element at position `i` was replaced, if the subtrees below it
+    /// are heaps.
+    pub(crate) fn sift_down<T, F>(heap: &mut [T], i: usize, lt: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        match is_min_level(i) {
+            true => sift_down_by::<T, F, false>(heap, i, lt),
+            false => sift_down_by::<T, F, true>(heap, i, lt),
+        }
+    }
+
+    /// Moves the element at position `i`, which is on a level of maxima if `MAX` is `true` and of
+    /// minima otherwise, down until it is in order with its children and grandchildren.
+    fn sift_down_by<T, F, const MAX: bool>(heap: &mut [T], mut i: usize, lt: &mut F)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let mut is_better = |a: &T, b: &T| match MAX {
+            true => lt(b, a),
+            false => lt(a, b),
+        };
+        loop {
+            // The best of the children and the grandchildren, which are contiguous.
+            let first = 2 * i + 1;
+            if first >= heap.len() {
+                return;
+            }
+            let mut best = first;
+            for j in [
+                first + 1,
+                2 * first + 1,
+                2 * first + 2,
+                2 * first + 3,
+                2 * first + 4,
+            ] {
+                if j < heap.len() && is_better(&heap[j], &heap[best]) {
+                    best = j;
+                }
+            }
+            if !is_better(&heap[best], &heap[i]) {
+                return;
+            }
+            heap.swap(best, i);
+            if best <= first + 1 {
+                return;
+            }
+            // The element moved down to a grandchild may be out of order with its new parent, on
+            // a level of the other kind.
+            let parent = (best - 1) / 2;
+            if is_better(&heap[parent], &heap[best]) {
+                heap.swap(best, parent);
+            }
+            i = best;
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use core::cmp::Ordering;
+use std::vec::Vec;
+
+use crate::{select, sort::tinysort, Ties};
+
+/// Returns the indices of the `k` smallest elements of `data` in ascending order of the elements,
+/// without modifying `data`. Equal elements are ordered by their indices. If `k > data.len()`, the
+/// indices of all elements are returned.
+///
+/// The indices are selected indirectly with the same algorithm as
+/// [`select_nth_unstable`](crate::select_nth_unstable), after which the first `k` indices are
+/// sorted. This is equivalent to sorting the result of `numpy.argpartition`.
+///
+/// # Panics
+///
+/// Panics if `data.len() > u32::MAX as usize`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::arg_partial_sort;
+/// let v = [5, 1, 4, 1, 3];
+/// assert_eq!(arg_partial_sort(&v, 3), [1, 3, 4]);
+/// ```
+pub fn arg_partial_sort<T>(data: &[T], k: usize) -> Vec<u32>
+where
+    T: Ord,
+{
+    arg_partial_sort_with_ties(data, k, Ties::ByIndex)
+}
+
+/// Returns the indices of the `k` smallest elements of `data` in ascending order of the elements,
+/// like [`arg_partial_sort`], with `ties` specifying how equal elements are ordered.
+///
+/// # Panics
+///
+/// Panics if `data.len() > u32::MAX as usize`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{arg_partial_sort_with_ties, Ties};
+/// let v = [5, 1, 4, 1, 3];
+/// let indices = arg_partial_sort_with_ties(&v, 2, Ties::Unordered);
+/// assert!(indices == [1, 3] || indices == [3, 1]);
+/// ```
+pub fn arg_partial_sort_with_ties<T>(data: &[T], k: usize, ties: Ties) -> Vec<u32>
+where
+    T: Ord,
+{
+    assert!(data.len() <= u32::MAX as usize);
+
+    let k = k.min(data.len());
+    let mut indices: Vec<u32> = (0..data.len() as u32).collect();
+    if k == 0 {
+        indices.clear();
+        return indices;
+    }
+
+    let mut lt = |&a: &u32, &b: &u32| match data[a as usize].cmp(&data[b as usize]) {
+        Ordering::Less => true,
+        Ordering::Equal => ties == Ties::ByIndex && a < b,
+        Ordering::Greater => false,
+    };
+    if k < indices.len() {
+        select(&mut indices, k, &mut lt);
+        indices.truncate(k);
+    }
+    tinysort(&mut indices, &mut lt);
+    indices
+}
+
+/// Returns the `k` largest elements of `data` with their indices, in descending order of the
+/// elements. Equal elements are ordered by their indices. Elements that are unordered with
+/// themselves, such as NaN, are ignored. If there are fewer than `k` such elements, all of them
+/// are returned.
+///
+/// This is optimized for small `k` and large `data`, which is the common case when picking the
+/// top scores or logits. The elements are scanned once while keeping at most `2 * k` candidates,
+/// and elements that cannot be among the `k` largest are skipped with a single comparison.
+///
+/// # Panics
+///
+/// Panics if `data.len() > u32::MAX as usize`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::top_k_with_indices;
+/// let logits = [0.1, 2.5, -1.0, f32::NAN, 2.5, 0.7];
+/// assert_eq!(top_k_with_indices(&logits, 3), [(1, 2.5), (4, 2.5), (5, 0.7)]);
+/// ```
+pub fn top_k_with_indices<T>(data: &[T], k: usize) -> Vec<(u32, T)>
+where
+    T: PartialOrd + Copy,
+{
+    let mut top = Vec::new();
+    top_k_into(data, k, &mut top);
+    top
+}
+
+/// Returns the `k` largest elements with their column indices for every row of the row-major
+/// matrix `data` with `n_cols` columns, as in [`top_k_with_indices`]. The candidate buffer is
+/// allocated once and reused for every row.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0`, if `data.len()` is not a multiple of `n_cols`, or if
+/// `n_cols > u32::MAX as usize`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::top_k_with_indices_by_row;
+/// let scores = [
+///     0.1, 0.9, 0.5,
+///     0.8, 0.2, 0.3,
+/// ];
+/// let top = top_k_with_indices_by_row(&scores, 3, 2);
+/// assert_eq!(top, [vec![(1, 0.9), (2, 0.5)], vec![(0, 0.8), (2, 0.3)]]);
+/// ```
+pub fn top_k_with_indices_by_row<T>(data: &[T], n_cols: usize, k: usize) -> Vec<Vec<(u32, T)>>
+where
+    T: PartialOrd + Copy,
+{
+    assert!(n_cols > 0, "the matrix must have at least one column");
+    assert!(
+        data.len().is_multiple_of(n_cols),
+        "the length of the slice must be a multiple of the number of columns"
+    );
+
+    let mut buffer = Vec::new();
+    data.chunks_exact(n_cols)
+        .map(|row| {
+            top_k_into(row, k, &mut buffer);
+            buffer.clone()
+        })
+        .collect()
+}
+
+/// Writes the `k` largest elements of `data` with their indices into `top`, in descending order.
+fn top_k_into<T>(data: &[T], k: usize, top: &mut Vec<(u32, T)>)
+where
+    T: PartialOrd + Copy,
+{
+    assert!(data.len() <= u32::MAX as usize);
+
+    top.clear();
+    if k == 0 {
+        return;
+    }
+
+    // Orders the candidates by descending value, and equal values by ascending index.
+    let mut gt = |a: &(u32, T), b: &(u32, T)| a.1 > b.1 || (a.1 == b.1 && a.0 < b.0);
+
+    let capacity = k.saturating_mul(2).min(data.len());
+    top.reserve(capacity);
+    let mut threshold = None;
+    for (i, &x) in data.iter().enumerate() {
+        // Skip NaNs and the elements that cannot make it to the top `k`. Since the indices are
+        // increasing, an element equal to the threshold cannot make it either.
+        if x.partial_cmp(&x).is_none() || threshold.is_some_and(|t| x <= t) {
+            continue;
+        }
+        top.push((i as u32, x));
+        if top.len() == capacity && capacity > k {
+            select(top, k - 1, &mut gt);
+            top.truncate(k);
+            threshold = Some(top[k - 1].1);
+        }
+    }
+    if top.len() > k {
+        select(top, k - 1, &mut gt);
+        top.truncate(k);
+    }
+    tinysort(top, &mut gt);
+}
@@ -0,0 +1,53 @@
+use core::cmp::Ordering;
+
+/// Records that are selected by a key made of some of their fields, e.g. for passing
+/// [`select_key`](SelectKey::select_key) to
+/// [`select_nth_unstable_by_cached_key`](crate::select_nth_unstable_by_cached_key) or
+/// [`cmp_key`](SelectKey::cmp_key) to [`select_nth_unstable_by`](crate::select_nth_unstable_by).
+///
+/// With the `derive` feature, the trait can be derived for structs by annotating the key fields
+/// with `#[select_key]`, or with `#[select_key(reverse)]` for fields ordered from the largest to
+/// the smallest.
+///
+/// # Examples
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use turboselect::{select_nth_unstable_by, SelectKey};
+///
+/// struct Player {
+///     name: &'static str,
+///     score: u32,
+/// }
+///
+/// impl SelectKey for Player {
+///     type Key = u32;
+///
+///     fn select_key(&self) -> u32 {
+///         self.score
+///     }
+/// }
+///
+/// let mut players = [("a", 30), ("b", 10), ("c", 20)].map(|(name, score)| Player { name, score });
+/// let (_, median, _) = select_nth_unstable_by(&mut players, 1, Player::cmp_key);
+/// assert_eq!(median.name, "c");
+/// ```
+pub trait SelectKey {
+    /// The type of the key.
+    type Key: Ord;
+
+    /// Returns the key of the record.
+    fn select_key(&self) -> Self::Key;
+
+    /// Compares the keys of two records. The default implementation compares the results of
+    /// [`select_key`](SelectKey::select_key), and the derived one compares the key fields in place.
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.select_key().cmp(&other.select_key())
+    }
+
+    /// Returns `true` if the key of the record is less than the key of `other`, for the `_by_lt`
+    /// forms of the functions.
+    fn lt_key(&self, other: &Self) -> bool {
+        self.cmp_key(other) == Ordering::Less
+    }
+}
@@ -0,0 +1,107 @@
+use core::ptr;
+
+/// The number of elements compared at once.
+pub(crate) const LANES: usize = 8;
+
+/// The offsets of the set bits of each 8-bit mask, packed into the bytes of a `u64` from the
+/// least significant byte up. The bytes after the offsets are zero.
+const OFFSETS: [u64; 256] = {
+    let mut table = [0; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let (mut packed, mut count, mut bit) = (0, 0, 0);
+        while bit < LANES {
+            if mask >> bit & 1 == 1 {
+                packed |= (bit as u64) << (8 * count);
+                count += 1;
+            }
+            bit += 1;
+        }
+        table[mask] = packed;
+        mask += 1;
+    }
+    table
+};
+
+/// Like [`Classify::offsets_ge`](crate::Classify::offsets_ge), but compares eight elements at a
+/// time with `lt_mask`, which returns a mask whose bit `i` is set if the element `i` of the eight
+/// elements at the given pointer is less than the pivot.
+///
+/// # Safety
+///
+/// Same as [`Classify::offsets_ge`](crate::Classify::offsets_ge).
+#[inline(always)]
+pub(crate) unsafe fn offsets_ge_by<T, M>(
+    block: *const T,
+    len: usize,
+    pivot: T,
+    offsets: *mut u8,
+    mut lt_mask: M,
+) -> usize
+where
+    T: Copy + Ord,
+    M: FnMut(*const T) -> u8,
+{
+    // The offsets are written eight at a time at `count <= i`, which stays within the first
+    // `i + LANES <= len` bytes of `offsets`.
+    let (mut i, mut count) = (0, 0);
+    while i + LANES <= len {
+        count += push_offsets(offsets.add(count), i, !lt_mask(block.add(i)));
+        i += LANES;
+    }
+    while i < len {
+        *offsets.add(count) = i as u8;
+        count += (*block.add(i) >= pivot) as usize;
+        i += 1;
+    }
+    count
+}
+
+/// Like [`Classify::offsets_lt_rev`](crate::Classify::offsets_lt_rev), but compares eight
+/// elements at a time with `lt_mask`, like [`offsets_ge_by`].
+///
+/// # Safety
+///
+/// Same as [`Classify::offsets_lt_rev`](crate::Classify::offsets_lt_rev).
+#[inline(always)]
+pub(crate) unsafe fn offsets_lt_rev_by<T, M>(
+    end: *const T,
+    len: usize,
+    pivot: T,
+    offsets: *mut u8,
+    mut lt_mask: M,
+) -> usize
+where
+    T: Copy + Ord,
+    M: FnMut(*const T) -> u8,
+{
+    let (mut i, mut count) = (0, 0);
+    while i + LANES <= len {
+        // The lanes are in memory order, so the last lane is at offset `i`.
+        let mask = lt_mask(end.sub(i + LANES)).reverse_bits();
+        count += push_offsets(offsets.add(count), i, mask);
+        i += LANES;
+    }
+    while i < len {
+        *offsets.add(count) = i as u8;
+        count += (*end.sub(i + 1) < pivot) as usize;
+        i += 1;
+    }
+    count
+}
+
+/// Writes the offsets `base + j` of the set bits `j` of `mask` to `offsets` in increasing order,
+/// and returns their number. Always writes eight bytes, the extra ones being overwritten by the
+/// next call or ignored.
+///
+/// # Safety
+///
+/// `offsets` must be valid for writes of eight bytes, and `base + 7` must fit in a `u8`.
+#[inline(always)]
+unsafe fn push_offsets(offsets: *mut u8, base: usize, mask: u8) -> usize {
+    debug_assert!(base + LANES <= 256);
+    // Adding `base` to every byte can't carry, since none of them exceeds `u8::MAX`.
+    let packed = OFFSETS[usize::from(mask)] + base as u64 * 0x0101_0101_0101_0101;
+    ptr::write_unaligned(offsets.cast::<[u8; 8]>(), packed.to_le_bytes());
+    mask.count_ones() as usize
+}
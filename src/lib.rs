@@ -1,30 +1,218 @@
 #![no_std]
+#![cfg_attr(all(feature = "simd", turboselect_nightly), feature(portable_simd))]
 
 #[cfg(feature = "std")]
 extern crate std;
 
-mod math;
+// The derived implementations refer to the trait through the name of the crate.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as turboselect;
 
+mod math;
+mod multi;
+#[cfg(feature = "std")]
+mod order_statistics;
+#[cfg(feature = "std")]
+mod pairwise;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod params;
+mod partial;
+mod radix;
+mod range;
+#[cfg(feature = "std")]
+mod records;
+#[cfg(feature = "regression")]
+mod regression;
+mod relaxed;
+mod report;
+#[cfg(feature = "std")]
+mod retain;
+mod scratch;
+#[cfg(feature = "selftest")]
+mod selftest;
+#[cfg(feature = "simd")]
+mod simd;
+
+mod array;
+#[cfg(feature = "avx2")]
+#[cfg(target_arch = "x86_64")]
+mod avx2;
 #[cfg(feature = "std")]
 #[cfg(test)]
 mod benches;
+#[cfg(feature = "std")]
+mod bins;
+mod budget;
+#[cfg(feature = "std")]
+mod cache;
+pub mod cmp;
+mod counting;
+pub mod distributed;
+#[cfg(feature = "std")]
+mod domain;
+mod ext;
+#[cfg(feature = "filter")]
+mod filter;
+mod float;
+mod fold;
+#[cfg(feature = "std")]
+mod from_iter;
+mod group;
+mod heapselect;
+#[cfg(feature = "std")]
+mod indices;
+mod key;
+#[cfg(any(turboselect_nightly, all(feature = "avx2", target_arch = "x86_64")))]
+mod lanes;
 mod sort;
+mod stats;
+mod strategy;
+pub mod streaming;
+#[cfg(feature = "std")]
+mod strings;
+mod swaps;
+#[cfg(feature = "std")]
+mod topk;
+#[cfg(feature = "autotune")]
+mod tune;
+mod warm;
+#[cfg(feature = "std")]
+mod window;
 
 #[cfg(feature = "std")]
 #[cfg(test)]
 mod tests;
 
-#[cfg(test)]
 mod wyrand;
 
 use core::{
-    cmp::{self, Ordering},
+    cmp::Ordering,
+    hint,
     mem::{self, ManuallyDrop, MaybeUninit},
     ops::{ControlFlow, Deref, DerefMut, Range},
     ptr,
 };
-use math::{ceil, lerp, sqrt};
-use sort::tinysort;
+use heapselect::{heapselect, is_heap_index};
+use math::{ceil, lerp, mul_div, sqrt};
+#[cfg(not(feature = "autotune"))]
+use params::Params;
+use params::{params, MAX_PARTITION_BLOCK};
+use report::Observer;
+use sort::{tinyselect, tinysort};
+use strategy::{adaptive_pivot, floyd_rivest, median_of_medians, replacement_pivot};
+
+pub use array::{
+    select_nth_unstable_array, select_nth_unstable_array_by, select_nth_unstable_array_by_lt,
+};
+#[cfg(feature = "std")]
+pub use bins::{quantile_bins, quantile_bins_by, quantile_bins_by_lt, Histogram};
+pub use budget::{
+    select_nth_unstable_with_budget, select_nth_unstable_with_budget_by,
+    select_nth_unstable_with_budget_by_lt, Budgeted,
+};
+pub use counting::select_nth_unstable_u8;
+#[cfg(feature = "std")]
+pub use domain::{select_nth_unstable_by_domain_key, select_nth_unstable_in_domain, DomainKey};
+pub use ext::TurboSelect;
+#[cfg(feature = "filter")]
+pub use filter::median_filter;
+pub use float::{select_nth_unstable_float, FloatElement, NanError, NanPolicy};
+pub use fold::{
+    select_nth_unstable_with_fold, select_nth_unstable_with_fold_by,
+    select_nth_unstable_with_fold_by_lt, Folded,
+};
+#[cfg(feature = "std")]
+pub use from_iter::{
+    select_nth_from_iter, select_nth_from_iter_by, select_nth_from_iter_by_lt, IterSelector,
+};
+pub use group::select_and_group;
+#[cfg(feature = "std")]
+pub use indices::{
+    arg_partial_sort, arg_partial_sort_with_ties, top_k_with_indices, top_k_with_indices_by_row,
+};
+pub use key::SelectKey;
+pub use multi::{
+    select_many_nth_unstable, select_many_nth_unstable_by, select_many_nth_unstable_by_lt,
+    select_two_ranks, select_two_ranks_by, select_two_ranks_by_lt,
+};
+#[cfg(feature = "std")]
+pub use order_statistics::OrderStatistics;
+#[cfg(feature = "std")]
+pub use pairwise::{kth_smallest_pairwise_diff, kth_smallest_sum};
+#[cfg(feature = "rayon")]
+pub use parallel::{
+    par_partition_at, par_partition_at_by, par_partition_at_by_lt, par_select_nth_unstable,
+    par_select_nth_unstable_by, par_select_nth_unstable_by_cached_key,
+    par_select_nth_unstable_by_lt,
+};
+#[cfg(feature = "autotune")]
+pub use params::Params;
+pub use partial::{
+    k_largest, k_largest_by, k_largest_by_key, k_largest_by_lt, k_smallest, k_smallest_by,
+    k_smallest_by_key, k_smallest_by_lt, partial_sort, partial_sort_by, partial_sort_by_key,
+    partial_sort_by_lt, select_range, select_range_by, select_range_by_key, select_range_by_lt,
+};
+pub use radix::{select_nth_unstable_by_byte_key, select_nth_unstable_by_radix_key, RadixKey};
+pub use range::{partition_by_range, partition_by_range_by, partition_by_range_by_lt};
+#[cfg(feature = "std")]
+pub use records::select_nth_record_unstable;
+#[cfg(feature = "regression")]
+pub use regression::theil_sen;
+pub use relaxed::{select_rank_in, select_rank_in_by, select_rank_in_by_lt};
+#[cfg(feature = "std")]
+pub use report::{
+    select_nth_unstable_with_report, select_nth_unstable_with_report_by,
+    select_nth_unstable_with_report_by_lt, select_nth_unstable_with_stats,
+    select_nth_unstable_with_stats_by, select_nth_unstable_with_stats_by_lt, SelectReport,
+};
+pub use report::{Round, RoundKind};
+#[cfg(feature = "std")]
+pub use retain::{
+    retain_smallest_k, retain_smallest_k_by, retain_smallest_k_by_lt, truncate_to_top_k,
+    truncate_to_top_k_by, truncate_to_top_k_by_lt,
+};
+pub use scratch::{
+    select_nth_with_scratch, select_nth_with_scratch_by, select_nth_with_scratch_by_lt,
+};
+#[cfg(feature = "selftest")]
+pub use selftest::{selftest, SelfTestReport};
+#[cfg(feature = "simd")]
+pub use simd::{select_nth_unstable_simd, SimdElement};
+pub use sort::{tinysort_stable, tinysort_stable_by, tinysort_stable_by_lt};
+#[cfg(feature = "num-traits")]
+pub use stats::percentile_interpolated;
+#[cfg(feature = "std")]
+pub use stats::{median_each_column, median_each_column_by, median_each_column_by_lt};
+pub use stats::{
+    median_each_row, median_each_row_by, median_each_row_by_lt, percentile, percentile_by,
+    percentile_by_lt, percentile_each_group, percentile_each_group_by, percentile_each_group_by_lt,
+    select_quantiles, select_quantiles_by, select_quantiles_by_lt, trim_percentiles,
+    trim_percentiles_by, trim_percentiles_by_lt, winsorize, winsorize_by, winsorize_by_lt,
+};
+pub use strategy::{
+    select_nth_unstable_with_options, select_nth_unstable_with_options_by,
+    select_nth_unstable_with_options_by_lt, select_nth_unstable_with_partition,
+    select_nth_unstable_with_partition_by, select_nth_unstable_with_partition_by_lt,
+    BlockPartition, PartitionStrategy, SelectOptions, Strategy, TernaryPartition,
+};
+#[cfg(feature = "std")]
+pub use strings::select_nth_unstable_by_prefix;
+pub use swaps::{
+    select_nth_unstable_with_swaps, select_nth_unstable_with_swaps_by,
+    select_nth_unstable_with_swaps_by_lt,
+};
+#[cfg(feature = "std")]
+pub use topk::{k_smallest_iter, k_smallest_iter_by, k_smallest_iter_by_lt, BoundedTopK};
+#[cfg(feature = "autotune")]
+pub use tune::tune;
+#[cfg(feature = "derive")]
+pub use turboselect_derive::SelectKey;
+pub use warm::{
+    reselect_nth_unstable, reselect_nth_unstable_by, reselect_nth_unstable_by_lt, SelectionState,
+};
+#[cfg(feature = "std")]
+pub use window::WindowQuantile;
 
 /// Represents an element removed from a slice. When dropped, copies the value into `dst`.
 struct Elem<T> {
@@ -96,57 +284,102 @@ macro_rules! le {
     };
 }
 
-#[derive(PartialEq, Eq)]
-/// An enumeration representing the sort order of a slice.
-#[repr(isize)]
-enum SortOrder {
+/// The order of a slice, as returned by [`detect_order`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SortOrder {
     /// The slice is sorted in ascending order.
-    Ascending = 1,
+    Ascending,
     /// The slice is sorted in descending order.
-    Descending = -1,
+    Descending,
     /// The slice is not sorted.
-    Unsorted = 0,
-}
-
-impl From<isize> for SortOrder {
-    fn from(value: isize) -> Self {
-        match value {
-            1 => Self::Ascending,
-            -1 => Self::Descending,
-            _ => Self::Unsorted,
-        }
-    }
+    Unsorted,
 }
 
 /// Selects the pivot element for partitioning the slice. Returns `(p, is_repeated)` where `p` is
 /// the index of the pivot element and `is_repeated` is a boolean indicating if the pivot is likely
 /// to have many duplicates.
-fn choose_pivot<T, F>(data: &mut [T], index: usize, lt: &mut F) -> (usize, bool)
+fn choose_pivot<T, F>(data: &mut [T], index: usize, lt: &mut F, params: &Params) -> (usize, bool)
 where
     F: FnMut(&T, &T) -> bool,
 {
     match data.len() {
         // For relatively small slices, we use a `kth-of-nths` strategy.
-        len if len <= 256 => kth_of_nths::<3, _, _>(data, index, lt),
-        len if len <= 1024 => kth_of_nths::<5, _, _>(data, index, lt),
-        len if len <= 4096 => kth_of_nths::<7, _, _>(data, index, lt),
+        len if len <= params.kth_of_3_max => kth_of_nths::<3, _, _>(data, index, lt),
+        len if len <= params.kth_of_5_max => kth_of_nths::<5, _, _>(data, index, lt),
+        len if len <= params.kth_of_7_max => kth_of_nths::<7, _, _>(data, index, lt),
         // Larger slices benefit from more accurate pivot selection.
         len => {
-            const ALPHA: f64 = 0.75;
-            let count = ceil(ALPHA * sqrt(len as f64)) as usize;
-            let index = nudge(index, len);
-            let k = (count * index) / len;
-            select(sample(data, count), k, lt);
-            let is_repeated = match k {
-                0 => ge!(&data[k], &data[k + 1], lt),
-                k if k == count - 1 => ge!(&data[k - 1], &data[k], lt),
-                _ => ge!(&data[k - 1], &data[k + 1], lt),
-            };
-            (k, is_repeated)
+            let (count, k) = sample_rank(len, index);
+            select_with(sample(data, count), k, lt, params);
+            (k, is_repeated_in_sample(&data[..count], k, lt))
         }
     }
 }
 
+/// Counts the bad rounds of a selection, which keep more than three quarters of the slice. After
+/// [`Params::bad_round_limit`] of them, the pivots are chosen with the median of medians, which
+/// always discards at least a quarter of the slice, so that inputs that defeat the sampling still
+/// take linear time.
+#[derive(Clone, Copy, Debug, Default)]
+struct BadRounds(usize);
+
+impl BadRounds {
+    /// A count past any limit, for selections that choose every pivot with the median of medians.
+    const EXCEEDED: Self = Self(usize::MAX);
+
+    /// Returns `true` if the rest of the pivots should be chosen with the median of medians.
+    fn exceeded(self, params: &Params) -> bool {
+        self.0 >= params.bad_round_limit
+    }
+
+    /// Counts a round that reduced a slice of length `len` to `remaining` elements.
+    fn count(&mut self, len: usize, remaining: usize) {
+        if 4 * remaining > 3 * len {
+            self.0 = self.0.saturating_add(1);
+        }
+    }
+
+    /// Chooses the pivot for `index` like [`choose_pivot`], or with the median of medians after
+    /// too many bad rounds.
+    fn choose_pivot<T, F>(
+        self,
+        data: &mut [T],
+        index: usize,
+        lt: &mut F,
+        params: &Params,
+    ) -> (usize, bool)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        match self.exceeded(params) {
+            false => choose_pivot(data, index, lt, params),
+            true => median_of_medians(data, lt, params),
+        }
+    }
+}
+
+/// Returns the size of the pivot sample for a slice of length `len` that is too long for the
+/// `kth-of-nths` strategy, and the rank in the sample of the pivot for `index`.
+fn sample_rank(len: usize, index: usize) -> (usize, usize) {
+    const ALPHA: f64 = 0.75;
+    let count = ceil(ALPHA * sqrt(len as f64)) as usize;
+    let index = nudge(index, len);
+    (count, mul_div(count, index, len))
+}
+
+/// Returns `true` if the pivot at `k` in a sample partitioned at `k` is equal to one of its
+/// neighbors, meaning that it is likely to have many duplicates in the slice.
+fn is_repeated_in_sample<T, F>(sample: &[T], k: usize, lt: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    match k {
+        0 => ge!(&sample[k], &sample[k + 1], lt),
+        k if k == sample.len() - 1 => ge!(&sample[k - 1], &sample[k], lt),
+        _ => ge!(&sample[k - 1], &sample[k + 1], lt),
+    }
+}
+
 #[inline]
 /// Chooses a randomized pivot for the given index. First, puts a `N * N` random sample to the
 /// beginning of the slice. Then sorts `N` groups of `N` elements in the sample, each `N` elements
@@ -160,7 +393,7 @@ where
     // - `k`: sample index corresponding to the pivot location.
     // - `g`: index of the `N` element group where the pivot is located.
     let len = data.len();
-    let k = (N * N * index) / len;
+    let k = mul_div(N * N, index, len);
     let g = k / N;
 
     // Take the sample and sort the groups
@@ -214,20 +447,68 @@ where
     count == 0
 }
 
-fn detect_order<T, F>(data: &[T], lt: &mut F) -> Option<Ordering>
+/// Returns `true` if `data` is already partitioned at `index`, i.e. no element before `index` is
+/// greater than `data[index]` and no element after `index` is less than it. The elements are
+/// scanned in blocks, and the scan stops at the first block with an element on the wrong side.
+fn is_partitioned<T, F>(data: &[T], index: usize, lt: &mut F) -> bool
 where
     F: FnMut(&T, &T) -> bool,
 {
-    if data.is_empty() {
-        return None;
+    const BLOCK: usize = 32;
+    let (left, rest) = data.split_at(index);
+    let (nth, right) = rest.split_first().unwrap();
+    for block in right.chunks(BLOCK) {
+        let mut stopped = false;
+        for elem in block {
+            stopped |= lt(elem, nth);
+        }
+        if stopped {
+            return false;
+        }
+    }
+    for block in left.chunks(BLOCK) {
+        let mut stopped = false;
+        for elem in block {
+            stopped |= lt(nth, elem);
+        }
+        if stopped {
+            return false;
+        }
     }
+    true
+}
+
+/// Returns whether `data` is sorted in ascending or descending order with respect to `compare`.
+///
+/// The check is unrolled in blocks of 32 elements and stops at the first block with an element out
+/// of order, so it is cheap compared to selecting or sorting. Slices in which all elements are
+/// equal, including slices with less than two elements, are reported as ascending.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{detect_order, SortOrder};
+/// assert_eq!(detect_order(&[1, 2, 2, 5], i32::cmp), SortOrder::Ascending);
+/// assert_eq!(detect_order(&[5, 3, 3, 1], i32::cmp), SortOrder::Descending);
+/// assert_eq!(detect_order(&[1, 3, 2], i32::cmp), SortOrder::Unsorted);
+/// ```
+pub fn detect_order<T, F>(data: &[T], mut compare: F) -> SortOrder
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    sort_order(data, &mut |x: &T, y: &T| compare(x, y) == Ordering::Less)
+}
 
-    if is_sorted(data, lt) {
-        Some(Ordering::Greater)
+fn sort_order<T, F>(data: &[T], lt: &mut F) -> SortOrder
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if data.len() < 2 || is_sorted(data, lt) {
+        SortOrder::Ascending
     } else if is_sorted(data, &mut |a, b| lt(b, a)) {
-        Some(Ordering::Less)
+        SortOrder::Descending
     } else {
-        None
+        SortOrder::Unsorted
     }
 }
 
@@ -261,9 +542,23 @@ fn nudge(index: usize, len: usize) -> usize {
 /// ```
 ///
 /// Panics if `index` is out of bounds.
-fn partition_at<T, F>(data: &mut [T], index: usize, lt: &mut F) -> (usize, usize)
+fn partition_at<T, F>(data: &mut [T], index: usize, lt: &mut F, block: usize) -> (usize, usize)
 where
     F: FnMut(&T, &T) -> bool,
+{
+    partition_at_classified(data, index, lt, block)
+}
+
+/// Like `partition_at`, but compares the elements with `classify`, which finds the out-of-order
+/// elements of the blocks scanned by `partition_in_blocks_classified`.
+fn partition_at_classified<T, C>(
+    data: &mut [T],
+    index: usize,
+    classify: &mut C,
+    block: usize,
+) -> (usize, usize)
+where
+    C: Classify<T>,
 {
     // This ensures that the index is in bounds.
     data.swap(0, index);
@@ -281,16 +576,16 @@ where
 
         // SAFETY: The calls to get_unchecked are safe, because the slice is non-empty and we
         // ensure that `l <= r`.
-        while l < r && unsafe { lt(&*ptr.add(l), &*pivot) } {
+        while l < r && unsafe { classify.lt(&*ptr.add(l), &*pivot) } {
             l += 1;
         }
-        while l < r && unsafe { ge!(&*ptr.add(r - 1), &*pivot, lt) } {
+        while l < r && unsafe { !classify.lt(&*ptr.add(r - 1), &*pivot) } {
             r -= 1;
         }
-        u = l + partition_in_blocks(&mut tail[l..r], &*pivot, lt);
+        u = l + partition_in_blocks_classified(&mut tail[l..r], &*pivot, classify, block);
         v = u;
         // Scan the elements after the pivot until we find one that is greater than the pivot.
-        while v < tail.len() && unsafe { le!(&*ptr.add(v), &*pivot, lt) } {
+        while v < tail.len() && unsafe { !classify.lt(&*pivot, &*ptr.add(v)) } {
             v += 1;
         }
     }
@@ -313,13 +608,116 @@ where
 /// ```
 ///
 /// Panics if `index` is out of bounds.
-fn partition_equal<T, F>(data: &mut [T], index: usize, lt: &mut F) -> (usize, usize)
+fn partition_equal<T, F>(data: &mut [T], index: usize, lt: &mut F, block: usize) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    data.swap(0, index);
+    let (elem, tail) = data.split_first_mut().unwrap();
+    let (u, v) = {
+        // SAFETY: The pivot is the first element, which is not part of `tail`.
+        let pivot = unsafe { Elem::new(elem) };
+        let (u, v, done) = partition_three_way_in_blocks(tail, &pivot, lt, block);
+        if done < tail.len() {
+            // Too many elements are less than or equal to the pivot for the single pass to pay
+            // off. The rest is partitioned in two passes, and its parts are exchanged with the
+            // parts of the already partitioned prefix.
+            let rest = &mut tail[done..];
+            let less = partition_in_blocks(rest, &pivot, lt, block);
+            let le = &mut |a: &T, b: &T| !lt(b, a);
+            let equal = partition_in_blocks(&mut rest[less..], &pivot, le, block);
+            exchange(&mut tail[v..done + less], done - v);
+            exchange(&mut tail[u..v + less], v - u);
+            exchange(&mut tail[v + less..done + less + equal], done - v);
+            (u + less, v + less + equal)
+        } else {
+            (u, v)
+        }
+    };
+    data.swap(0, u);
+    (u, v)
+}
+
+/// Exchanges the elements of `data[..mid]` with the elements of `data[mid..]`, without keeping
+/// their order within either part. Makes as many swaps as there are elements in the shorter part.
+fn exchange<T>(data: &mut [T], mid: usize) {
+    let count = mid.min(data.len() - mid);
+    let (head, tail) = data.split_at_mut(data.len() - count);
+    head[..count].swap_with_slice(tail);
+}
+
+/// Partitions a prefix of `data` into elements less than `pivot`, elements equal to it and
+/// elements greater than it in a single pass. Returns `(u, v, done)`, where `done` is the length
+/// of the prefix, `u` is the number of elements less than the pivot in it and `v` the number of
+/// elements less than or equal to it.
+///
+/// The slice is scanned `block` elements at a time. Each element is classified without branching,
+/// storing the offsets of the elements less than or equal to the pivot and whether each of them is
+/// less than it. Then the stored elements are moved into place from left to right, like in
+/// Lomuto's partitioning: the slice is kept in the order `< pivot`, `== pivot`, `> pivot`, and each
+/// stored element is rotated into the end of its part, which takes the same three moves for both
+/// parts.
+///
+/// Since every element less than or equal to the pivot is moved, this is slower than partitioning
+/// in two passes when these are more than about a quarter of the slice. The scan stops before the
+/// first block after which they would be more than a quarter of the scanned elements and one more
+/// block. The extra quarter of a block keeps a first block with a few more of them than expected
+/// from stopping the scan.
+fn partition_three_way_in_blocks<T, F>(
+    data: &mut [T],
+    pivot: &T,
+    lt: &mut F,
+    block: usize,
+) -> (usize, usize, usize)
 where
     F: FnMut(&T, &T) -> bool,
 {
-    let (u, v) = partition_at(data, index, lt);
-    let dups = partition_equal_min(data[v..].as_mut(), 0, lt).1;
-    (u, v + dups)
+    debug_assert!(block > 0 && block <= MAX_PARTITION_BLOCK);
+
+    let len = data.len();
+    let base = data.as_mut_ptr();
+    // The ends of the elements less than and less than or equal to the pivot.
+    let (mut u, mut v) = (0, 0);
+    let mut offsets = [MaybeUninit::<u8>::uninit(); MAX_PARTITION_BLOCK];
+    let mut is_less = [MaybeUninit::<bool>::uninit(); MAX_PARTITION_BLOCK];
+
+    let mut start = 0;
+    while start < len {
+        let n = block.min(len - start);
+        let mut count = 0;
+        // SAFETY: `start + i < len`, and `count <= i < n <= MAX_PARTITION_BLOCK`.
+        unsafe {
+            for i in 0..n {
+                let elem = &*base.add(start + i);
+                let less = lt(elem, pivot);
+                let less_or_equal = less | le!(elem, pivot, lt);
+                offsets.get_unchecked_mut(count).write(i as u8);
+                is_less.get_unchecked_mut(count).write(less);
+                count += less_or_equal as usize;
+            }
+        }
+        if v + count > (start + n + block) / 4 {
+            return (u, v, start);
+        }
+        // SAFETY: `u <= v <= start + i` for every stored offset `i`, because `v` is incremented
+        // once per stored element and the offsets are increasing. Every element that is read into
+        // `tmp` is written back, and no user code runs while it is out of the slice.
+        unsafe {
+            for k in 0..count {
+                let elem = base.add(start + offsets.get_unchecked(k).assume_init() as usize);
+                let less = is_less.get_unchecked(k).assume_init();
+                let tmp = ManuallyDrop::new(ptr::read(elem));
+                ptr::copy(base.add(v), elem, 1);
+                ptr::copy(base.add(u), base.add(v), 1);
+                let dst = hint::select_unpredictable(less, u, v);
+                ptr::copy_nonoverlapping(&*tmp, base.add(dst), 1);
+                u += less as usize;
+                v += 1;
+            }
+        }
+        start += n;
+    }
+    (u, v, len)
 }
 
 /// Puts the minimum elements at the beginning of the slice and returns the indices of the first and
@@ -364,7 +762,7 @@ where
 
     while elem < r {
         // Scan the next block.
-        let block = cmp::min(BLOCK, width(elem, r));
+        let block = core::cmp::min(BLOCK, width(elem, r));
         unsafe {
             // Scan the block and store offsets to the elements that satisfy `elem <= min`.
             // SAFETY: The unsafety operations below involve the usage of the `offset`.
@@ -419,7 +817,8 @@ where
 }
 
 /// Partitions `data` into elements smaller than `pivot`, followed by elements greater than or equal
-/// to `pivot`. Returns the number of elements smaller than `pivot`.
+/// to `pivot`. Returns the number of elements smaller than `pivot`. The slice is scanned `block`
+/// elements at a time from each side, where `block` is at most `MAX_PARTITION_BLOCK`.
 ///
 /// This function is a slightly modified version of `core::slice::sort::partition_in_blocks`.
 ///
@@ -427,12 +826,93 @@ where
 /// This idea is presented in the [BlockQuicksort][pdf] paper.
 ///
 /// [pdf]: https://drops.dagstuhl.de/opus/volltexte/2016/6389/pdf/LIPIcs-ESA-2016-38.pdf
-fn partition_in_blocks<T, F>(data: &mut [T], pivot: &T, lt: &mut F) -> usize
+fn partition_in_blocks<T, F>(data: &mut [T], pivot: &T, lt: &mut F, block: usize) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    partition_in_blocks_classified(data, pivot, lt, block)
+}
+
+/// Finds the out-of-order elements of the blocks scanned by `partition_in_blocks_classified`.
+///
+/// Every "less than" function implements this by comparing the elements of a block one by one.
+/// The `simd` feature implements it for primitive integers by comparing several elements at once.
+trait Classify<T> {
+    /// Returns `true` if `a < b`.
+    fn lt(&mut self, a: &T, b: &T) -> bool;
+
+    /// Writes the offsets of the elements of `block[..len]` that are greater than or equal to
+    /// `pivot` to `offsets` in increasing order, and returns their number.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be valid for reads of `len` elements, and `offsets` for writes of `len` bytes,
+    /// where `len <= MAX_PARTITION_BLOCK`.
+    #[inline(always)]
+    unsafe fn offsets_ge(
+        &mut self,
+        block: *const T,
+        len: usize,
+        pivot: &T,
+        offsets: *mut u8,
+    ) -> usize {
+        let (mut elem, mut end) = (block, offsets);
+        for i in 0..len {
+            // Branchless comparison.
+            *end = i as u8;
+            end = end.add(!self.lt(&*elem, pivot) as usize);
+            elem = elem.add(1);
+        }
+        width(offsets, end)
+    }
+
+    /// Writes the offsets `i` of the elements `*end.sub(i + 1)` that are less than `pivot`, for
+    /// `i < len`, to `offsets` in increasing order, and returns their number.
+    ///
+    /// # Safety
+    ///
+    /// `end.sub(len)` must be valid for reads of `len` elements, and `offsets` for writes of `len`
+    /// bytes, where `len <= MAX_PARTITION_BLOCK`.
+    #[inline(always)]
+    unsafe fn offsets_lt_rev(
+        &mut self,
+        end: *const T,
+        len: usize,
+        pivot: &T,
+        offsets: *mut u8,
+    ) -> usize {
+        let (mut elem, mut last) = (end, offsets);
+        for i in 0..len {
+            // Branchless comparison.
+            elem = elem.sub(1);
+            *last = i as u8;
+            last = last.add(self.lt(&*elem, pivot) as usize);
+        }
+        width(offsets, last)
+    }
+}
+
+impl<T, F> Classify<T> for F
 where
     F: FnMut(&T, &T) -> bool,
 {
-    // Number of elements in a typical block.
-    const BLOCK: usize = 128;
+    #[inline(always)]
+    fn lt(&mut self, a: &T, b: &T) -> bool {
+        self(a, b)
+    }
+}
+
+/// Like `partition_in_blocks`, but finds the out-of-order elements of each block with `classify`.
+fn partition_in_blocks_classified<T, C>(
+    data: &mut [T],
+    pivot: &T,
+    classify: &mut C,
+    block: usize,
+) -> usize
+where
+    C: Classify<T>,
+{
+    debug_assert!(block > 0 && block <= MAX_PARTITION_BLOCK);
 
     // The partitioning algorithm repeats the following steps until completion:
     //
@@ -442,7 +922,7 @@ where
     //
     // We keep the following variables for a block of elements:
     //
-    // 1. `block` - Number of elements in the block.
+    // 1. `block_l`, `block_r` - Number of elements in the block.
     // 2. `start` - Start pointer into the `offsets` array.
     // 3. `end` - End pointer into the `offsets` array.
     // 4. `offsets` - Indices of out-of-order elements within the block.
@@ -453,32 +933,33 @@ where
     } = data.as_mut_ptr_range();
 
     // The current block on the left side (from `l` to `l.add(block_l)`).
-    let mut block_l = BLOCK;
-    let mut start_l = ptr::null_mut();
+    let mut block_l = block;
+    let mut start_l: *mut u8 = ptr::null_mut();
     let mut end_l = ptr::null_mut();
-    let mut offsets_l = [MaybeUninit::<u8>::uninit(); BLOCK];
+    let mut offsets_l = [MaybeUninit::<u8>::uninit(); MAX_PARTITION_BLOCK];
 
     // The current block on the right side (from `r.sub(block_r)` to `r`).
     // SAFETY: The documentation for .add() specifically mention that `vec.as_ptr().add(vec.len())`
     // is always safe
-    let mut block_r = BLOCK;
-    let mut start_r = ptr::null_mut();
+    let mut block_r = block;
+    let mut start_r: *mut u8 = ptr::null_mut();
     let mut end_r = ptr::null_mut();
-    let mut offsets_r = [MaybeUninit::<u8>::uninit(); BLOCK];
+    let mut offsets_r = [MaybeUninit::<u8>::uninit(); MAX_PARTITION_BLOCK];
 
-    // FIXME: When we get VLAs, try creating one array of length `min(v.len(), 2 * BLOCK)` rather
-    // than two fixed-size arrays of length `BLOCK`. VLAs might be more cache-efficient.
+    // FIXME: When we get VLAs, try creating one array of length `min(v.len(), 2 * block)` rather
+    // than two fixed-size arrays of length `MAX_PARTITION_BLOCK`. VLAs might be more
+    // cache-efficient.
 
     loop {
         // We are done with partitioning block-by-block when `l` and `r` get very close. Then we do
         // some patch-up work in order to partition the remaining elements in between.
-        let is_done = width(l, r) <= 2 * BLOCK;
+        let is_done = width(l, r) <= 2 * block;
 
         if is_done {
             // Number of remaining elements (still not compared to the pivot).
             let mut rem = width(l, r);
             if start_l < end_l || start_r < end_r {
-                rem -= BLOCK;
+                rem -= block;
             }
 
             // Adjust block sizes so that the left and right block don't overlap, but get perfectly
@@ -494,73 +975,31 @@ where
                 block_l = rem / 2;
                 block_r = rem - block_l;
             }
-            debug_assert!(block_l <= BLOCK && block_r <= BLOCK);
+            debug_assert!(block_l <= block && block_r <= block);
             debug_assert!(width(l, r) == block_l + block_r);
         }
 
         if start_l == end_l {
             // Trace `block_l` elements from the left side.
             start_l = offsets_l.as_mut_ptr().cast();
-            end_l = start_l;
-            let mut elem = l;
-
-            for i in 0..block_l {
-                // SAFETY: The unsafety operations below involve the usage of the `offset`.
-                // According to the conditions required by the function, we satisfy them
-                // because:
-                // 1. `offsets_l` is stack-allocated, and thus considered separate allocated object.
-                // 2. The comparison returns a `bool`. Casting a `bool` will never overflow `isize`.
-                // 3. We have guaranteed that `block_l` will be `<= BLOCK`. Plus, `end_l` was
-                //    initially set to the begin pointer of `offsets_l` which was declared on the
-                //    stack. Thus, we know that even in the worst case (all comparisons return true)
-                //    we will only be at most 1 byte pass the end
-                //
-                // Another unsafety operation here is dereferencing `elem`. However, `elem` was
-                // initially the begin pointer to the slice which is always valid.
-                unsafe {
-                    // Branchless comparison.
-                    *end_l = i as u8;
-                    end_l = end_l.add(ge!(&*elem, pivot, lt) as usize);
-                    elem = elem.add(1);
-                }
-            }
+            // SAFETY: `offsets_l` is stack-allocated and holds `MAX_PARTITION_BLOCK` offsets, and
+            // `block_l <= block <= MAX_PARTITION_BLOCK`. The block from `l` to `l.add(block_l)` is
+            // within the slice, since `width(l, r) >= block_l`.
+            end_l = unsafe { start_l.add(classify.offsets_ge(l, block_l, pivot, start_l)) };
         }
 
         if start_r == end_r {
             // Trace `block_r` elements from the right side.
             start_r = offsets_r.as_mut_ptr().cast();
-            end_r = start_r;
-            let mut elem = r;
-
-            for i in 0..block_r {
-                // SAFETY: The unsafety operations below involve the usage of the `offset`.
-                // According to the conditions required by the function, we satisfy them
-                // because:
-                // 1. `offsets_r` is stack-allocated, and thus considered separate allocated object.
-                // 2. The comparison returns a `bool`. Casting a `bool` will never overflow `isize`.
-                // 3. We have guaranteed that `block_r` will be `<= BLOCK`. Plus, `end_r` was
-                //    initially set to the begin pointer of `offsets_r` which was declared on the
-                //    stack. Thus, we know that even in the worst case (all all comparisons return
-                //    true) we will only be at most 1 byte pass the end.
-                //
-                // Another unsafety operation here is dereferencing `elem`. However, `elem` was
-                // initially `1 * sizeof(T)` past the end and we decrement it by `1 * sizeof(T)`
-                // before accessing it. Plus, `block_r` was asserted  to be less than `BLOCK` and
-                // `elem` will therefore at most be pointing to the  beginning of the slice.
-                unsafe {
-                    // Branchless comparison.
-                    elem = elem.sub(1);
-                    *end_r = i as u8;
-                    end_r = end_r.add(lt(&*elem, pivot) as usize);
-                }
-            }
+            // SAFETY: Same as above, with the block from `r.sub(block_r)` to `r`.
+            end_r = unsafe { start_r.add(classify.offsets_lt_rev(r, block_r, pivot, start_r)) };
         }
 
         // Number of out-of-order elements to swap between the left and right side.
-        let count = cmp::min(width(start_l, end_l), width(start_r, end_r));
+        let count = core::cmp::min(width(start_l, end_l), width(start_r, end_r));
 
         if count > 0 {
-            if count < BLOCK {
+            if count < block {
                 macro_rules! left {
                     () => {
                         l.add(usize::from(*start_l))
@@ -612,7 +1051,7 @@ where
             } else {
                 // If both blocks are full, we can swap them as a whole.
                 unsafe {
-                    ptr::swap_nonoverlapping(l, r.sub(BLOCK), BLOCK);
+                    ptr::swap_nonoverlapping(l, r.sub(block), block);
                     start_l = end_l;
                     start_r = end_r;
                 }
@@ -623,9 +1062,9 @@ where
             // All out-of-order elements in the left block were moved. Move to the next block.
 
             // block-width-guarantee
-            // SAFETY: if `!is_done` then the slice width is guaranteed to be at least `2*BLOCK`
-            // wide. There are at most `BLOCK` elements in `offsets_l` because of its
-            // size, so the `offset` operation is safe. Otherwise, the debug assertions
+            // SAFETY: if `!is_done` then the slice width is guaranteed to be at least `2*block`
+            // wide. There are at most `block` elements in `offsets_l` because of the
+            // block size, so the `offset` operation is safe. Otherwise, the debug assertions
             // in the `is_done` case guarantee that `width(l, r) == block_l + block_r`,
             // namely, that the block sizes have been adjusted to account
             // for the smaller number of remaining elements.
@@ -636,7 +1075,7 @@ where
             // All out-of-order elements in the right block were moved. Move to the previous block.
 
             // SAFETY: Same argument as [block-width-guarantee]. Either this is a full block
-            // `2*BLOCK`-wide, or `block_r` has been adjusted for the last handful of
+            // `2*block`-wide, or `block_r` has been adjusted for the last handful of
             // elements.
             r = unsafe { r.sub(block_r) };
         }
@@ -723,14 +1162,56 @@ fn sample<T>(data: &mut [T], count: usize) -> &mut [T] {
 }
 
 /// Partitions the slice so that elements in `data[..index]` are less than or equal to the pivot
-/// and elements in `data[index..]` are greater than or equal to the pivot.
+/// and elements in `data[index..]` are greater than or equal to the pivot. Uses the thresholds
+/// returned by `params`.
 ///
 /// Panics if `index >= data.len()`.
-fn select<T, F>(mut data: &mut [T], mut index: usize, lt: &mut F)
+fn select<T, F>(data: &mut [T], index: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    select_with(data, index, lt, &params::<T>())
+}
+
+/// Like `select`, but uses the thresholds in `params` instead of the ones compiled into the crate.
+fn select_with<T, F>(data: &mut [T], index: usize, lt: &mut F, params: &Params)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    select_using(data, index, lt, params, Strategy::Sampling)
+}
+
+/// Like `select_with`, but chooses the pivots with the given `strategy`.
+fn select_using<T, F>(data: &mut [T], index: usize, lt: &mut F, params: &Params, strategy: Strategy)
 where
     F: FnMut(&T, &T) -> bool,
+{
+    let partition = BlockPartition {
+        block: Some(params.partition_block),
+    };
+    select_partitioned(data, index, lt, params, strategy, &partition, &mut ())
+}
+
+/// Like `select_using`, but partitions the slice with `partition`. The samples that the pivots
+/// are chosen from are partitioned with the built-in block partitioning. Reports the progress of
+/// the selection to `observer`.
+fn select_partitioned<T, F, P, O>(
+    mut data: &mut [T],
+    mut index: usize,
+    lt: &mut F,
+    params: &Params,
+    strategy: Strategy,
+    partition: &P,
+    observer: &mut O,
+) where
+    F: FnMut(&T, &T) -> bool,
+    P: PartitionStrategy<T> + ?Sized,
+    O: Observer,
 {
     assert!(index < data.len());
+    if strategy == Strategy::FloydRivest {
+        return floyd_rivest(data, index, lt, params, partition, observer);
+    }
 
     fn descend<'a, T>(
         mut data: &'a mut [T],
@@ -760,39 +1241,95 @@ where
     // If the slice is likely to be sorted, try partitioning with the pivot at the index (ascending)
     // or slightly before the corresponding index from the back (descending).
     let mut previous_pivot = None;
-    match detect_order(data, lt) {
-        Some(Ordering::Greater) => return,
-        Some(Ordering::Less) => return data.reverse(),
-        _ => {}
+    match partition.sort_order(data, lt) {
+        SortOrder::Ascending => return observer.presorted(),
+        SortOrder::Descending => {
+            observer.presorted();
+            return data.reverse();
+        }
+        SortOrder::Unsorted => {}
     }
 
-    while data.len() > 24 {
-        let (u, v) = match index {
-            0 => select_min(data, lt),
-            i if i == data.len() - 1 => select_max(data, lt),
+    // If the slice is already partitioned at the index, e.g. because the same index was selected
+    // before, there is nothing to do.
+    if is_partitioned(data, index, lt) {
+        return observer.partitioned();
+    }
+
+    // After too many bad rounds, choose the rest of the pivots with the median of medians.
+    let mut bad_rounds = BadRounds::default();
+    // If the index is close to either end, try selecting it with a heap, but only once, since
+    // giving up suggests that the slice is in an order that defeats the heap. The median of
+    // medians keeps to its own steps, so that its bound is easy to reason about.
+    let mut try_heap = strategy != Strategy::MedianOfMedians;
+    while data.len() > params.tinysort_cutoff {
+        let strategy = match bad_rounds.exceeded(params) {
+            false => strategy,
+            true => Strategy::MedianOfMedians,
+        };
+        if try_heap && is_heap_index(data.len(), index) {
+            try_heap = false;
+            if heapselect(data, index, lt) {
+                return observer.round(Round {
+                    len: data.len(),
+                    index,
+                    kind: RoundKind::Heap,
+                    below: index,
+                    middle: 1,
+                    bracketed: true,
+                });
+            }
+        }
+        let (kind, (u, v)) = match index {
+            0 => (RoundKind::Minimum, select_min(data, lt)),
+            i if i == data.len() - 1 => (RoundKind::Maximum, select_max(data, lt)),
             _ => {
-                let (p, is_repeated) = choose_pivot(data, index, lt);
+                let (p, is_repeated) = match strategy {
+                    Strategy::Sampling => choose_pivot(data, index, lt, params),
+                    Strategy::SamplingWithReplacement => replacement_pivot(data, index, lt, params),
+                    Strategy::MedianOfNinthers => adaptive_pivot(data, index, lt, params),
+                    Strategy::MedianOfMedians => median_of_medians(data, lt, params),
+                    Strategy::FloydRivest => unreachable!(),
+                };
                 match previous_pivot {
                     // Test if the selected pivot is equal to a previous pivot from the left. In
                     // this case we know that the pivot is the minimum of the current slice.
-                    Some(was) if ge!(was, &data[p], lt) => partition_equal_min(data, p, lt),
-
-                    // If the selected pivot is equal to it's neighbor elements, use ternary
-                    // partitioning, which puts the elements equal to the pivot in the
-                    // middle. This is necessary to ensure that the algorithm terminates.
-                    _ if is_repeated => partition_equal(data, p, lt),
+                    Some(was) if ge!(was, &data[p], lt) => {
+                        (RoundKind::EqualToPrevious, partition_equal_min(data, p, lt))
+                    }
 
-                    // Otherwise, use the default binary partioning.
-                    _ => partition_at(data, p, lt),
+                    // Otherwise, let the partition strategy decide how to handle the elements
+                    // equal to the pivot.
+                    _ => {
+                        let (u, v) = partition.partition(data, p, is_repeated, lt);
+                        debug_assert!(u <= v && v < data.len());
+                        (
+                            RoundKind::Pivot {
+                                repeated: is_repeated,
+                            },
+                            (u, v),
+                        )
+                    }
                 }
             }
         };
+        observer.round(Round {
+            len: data.len(),
+            index,
+            kind,
+            below: u,
+            middle: v + 1 - u,
+            bracketed: u <= index && index <= v,
+        });
+        let len = data.len();
         match descend(data, index, u, v, previous_pivot) {
             ControlFlow::Continue(result) => (data, index, previous_pivot) = result,
             ControlFlow::Break(_) => return,
         }
+        bad_rounds.count(len, data.len());
     }
-    tinysort(data, lt);
+    observer.sorted(data.len());
+    tinyselect(data, index, lt);
 }
 
 /// Finds the minimum element and puts it at the beginning of the slice.
@@ -805,62 +1342,8 @@ where
         return (0, data.len() - 1);
     }
 
-    // Copy the initial minimum to the stack
-    let (head, tail) = data.split_first_mut().unwrap();
-    // SAFETY: `head` is not used after this point.
-    let mut min = unsafe { Elem::new(head) };
-
-    let Range { start: l, end: r } = tail.as_mut_ptr_range();
-    let mut elem = l;
-
-    // Setup the offsets array.
-    const BLOCK: usize = 64;
-    let mut offsets = [MaybeUninit::<u8>::uninit(); BLOCK];
-    let mut start = offsets.as_mut_ptr().cast();
-    let mut end: *mut u8 = start;
-
-    while elem < r {
-        // Scan the next block.
-        let block = cmp::min(BLOCK, width(elem, r));
-        unsafe {
-            // Scan the block and store offsets to the elements that satisfy `elem <= min`.
-            // SAFETY: The unsafety operations below involve the usage of the `offset`.
-            // According to the conditions required by the function, we satisfy them
-            // because:
-            // 1. `offsets` is stack-allocated, and thus considered separate allocated object.
-            // 2. The comparison returns a `bool`. Casting a `bool` will never overflow `isize`.
-            // 3. We have guaranteed that `block` will be `<= BLOCK`. Plus, `end` was initially set
-            //    to the begin pointer of `offsets` which was declared on the stack. Thus, we know
-            //    that even in the worst case (all comparisons return true) we will only be at most
-            //    1 byte pass the end
-            //
-            // Another unsafety operation here is dereferencing `elem`. However, `elem` was
-            // initially the begin pointer to the slice which is always valid.
-            for offset in 0..block {
-                end.write(offset as u8);
-                let is_lt = lt(&*elem.add(offset), &*min);
-                end = end.add(is_lt as usize);
-            }
-            // Scan the found elements
-            for _ in 0..width(start, end) {
-                // SAFETY: We know that the element is in bounds because we just scanned it.
-                let next = elem.add(*start as usize);
-                if lt(&*next, &*min) {
-                    // We found a new minimum.
-                    // SAFETY: `next` and `min` are both valid and they cannot overlap because
-                    // `min` is allocated on the stack, while `next` points to an element of the
-                    // slice.
-                    ptr::swap_nonoverlapping(next, &mut *min, 1);
-                }
-                // SAFETY: `start` is guaranteed to be in bounds, since `width(start, end) <=
-                // BLOCK`.
-                start = start.add(1);
-            }
-            elem = elem.add(block);
-            start = offsets.as_mut_ptr().cast();
-            end = start;
-        }
-    }
+    let min = position_of_extreme(data, 0, lt);
+    data.swap(0, min);
     (0, 0)
 }
 
@@ -874,63 +1357,78 @@ where
         return (0, data.len() - 1);
     }
 
-    // Copy the initial minimum to the stack
-    let (pivot, rest) = data.split_last_mut().unwrap();
-    // SAFETY: `pivot` is not used after this point.
-    let mut max = unsafe { Elem::new(pivot) };
-
-    let Range { start: l, end: r } = rest.as_mut_ptr_range();
-    let mut elem = l;
+    let last = data.len() - 1;
+    // Start from the last element, so that nothing needs to be moved if the slice is ascending.
+    let max = position_of_extreme(data, last, &mut |a: &T, b: &T| lt(b, a));
+    data.swap(max, last);
+    (last, last)
+}
 
-    // Setup the offsets array.
+/// Returns the position of an element `x` of `data` for which `better(y, x)` is `false` for all
+/// other elements `y`, i.e. a minimum if `better` is the "less than" function. The search starts
+/// from the element at `init`, which is returned if no element is better than it.
+///
+/// The elements are scanned in blocks. First, the offsets of the elements better than the current
+/// extreme are collected without branching, and then only those elements are compared again.
+/// Nothing is moved until the position is known, so the caller can swap the extreme into place
+/// once. If most elements of a block improve on the extreme, e.g. when searching for the minimum
+/// of a descending slice, filtering would scan the elements twice, so the next block is scanned
+/// directly instead.
+fn position_of_extreme<T, F>(data: &[T], init: usize, better: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+{
     const BLOCK: usize = 64;
-    let mut offsets = [MaybeUninit::<u8>::uninit(); BLOCK];
-    let mut start = offsets.as_mut_ptr().cast();
-    let mut end: *mut u8 = start;
 
-    while elem < r {
-        // Scan the next block.
-        let block = cmp::min(BLOCK, width(elem, r));
-        unsafe {
-            // Scan the block and store offsets to the elements that satisfy `elem <= min`.
-            // SAFETY: The unsafety operations below involve the usage of the `offset`.
-            // According to the conditions required by the function, we satisfy them
-            // because:
-            // 1. `offsets` is stack-allocated, and thus considered separate allocated object.
-            // 2. The comparison returns a `bool`. Casting a `bool` will never overflow `isize`.
-            // 3. We have guaranteed that `block` will be `<= BLOCK`. Plus, `end` was initially set
-            //    to the begin pointer of `offsets` which was declared on the stack. Thus, we know
-            //    that even in the worst case (all comparisons return true) we will only be at most
-            //    1 byte pass the end
-            //
-            // Another unsafety operation here is dereferencing `elem`. However, `elem` was
-            // initially the begin pointer to the slice which is always valid.
-            for offset in 0..block {
-                end.write(offset as u8);
-                let is_gt = lt(&*max, &*elem.add(offset));
-                end = end.add(is_gt as usize);
+    let (mut index, mut extreme) = (init, &data[init]);
+    let mut offsets = [0u8; BLOCK];
+    let mut dense = false;
+    for (block, start) in data.chunks(BLOCK).zip((0..).step_by(BLOCK)) {
+        let mut count = 0;
+        if dense {
+            // Select without branching, since runs of equal elements make the outcome hard to
+            // predict. The elements are compared in pairs first, so that only every other
+            // comparison depends on the previous one.
+            let mut pairs = block.chunks_exact(2);
+            for (pair, offset) in pairs.by_ref().zip((0..).step_by(2)) {
+                let second = better(&pair[1], &pair[0]);
+                let (i, elem) = if second {
+                    (offset + 1, &pair[1])
+                } else {
+                    (offset, &pair[0])
+                };
+                let is_better = better(elem, extreme);
+                count += is_better as usize;
+                index = if is_better { start + i } else { index };
+                extreme = if is_better { elem } else { extreme };
             }
-            // Scan the found elements
-            for _ in 0..width(start, end) {
-                // SAFETY: We know that the element is in bounds because we just scanned it.
-                let next = elem.add(*start as usize);
-                if lt(&*max, &*next) {
-                    // We found a new minimum.
-                    // SAFETY: `next` and `min` are both valid and they cannot overlap because
-                    // `min` is allocated on the stack, while `next` points to an element of the
-                    // slice.
-                    ptr::swap_nonoverlapping(next, &mut *max, 1);
+            if let [elem] = pairs.remainder() {
+                if better(elem, extreme) {
+                    (index, extreme) = (start + block.len() - 1, elem);
                 }
-                // SAFETY: `start` is guaranteed to be in bounds, since `width(start, end) <=
-                // BLOCK`.
-                start = start.add(1);
             }
-            elem = elem.add(block);
-            start = offsets.as_mut_ptr().cast();
-            end = start;
+            count *= 2;
+        } else {
+            for (offset, elem) in block.iter().enumerate() {
+                // SAFETY: `count <= offset < BLOCK`.
+                unsafe { *offsets.get_unchecked_mut(count) = offset as u8 };
+                count += better(elem, extreme) as usize;
+            }
+            for &offset in &offsets[..count] {
+                // SAFETY: The offsets were collected from `block`.
+                let elem = unsafe { block.get_unchecked(offset as usize) };
+                let is_better = better(elem, extreme);
+                index = if is_better {
+                    start + offset as usize
+                } else {
+                    index
+                };
+                extreme = if is_better { elem } else { extreme };
+            }
         }
+        dense = 2 * count > block.len();
     }
-    (data.len() - 1, data.len() - 1)
+    index
 }
 
 /// Reorder the slice such that the element at `index` is at its final sorted position.
@@ -938,8 +1436,9 @@ where
 /// This reordering has the additional property that any value at position `i < index` will be
 /// less than or equal to any value at a position `j > index`. Additionally, this reordering is
 /// unstable (i.e. any number of equal elements may end up at position `index`), in-place
-/// (i.e. does not allocate), and *O*(*n*) on average. The worst-case performance is *O*(*n* log
-/// *n*). This function is also known as "kth element" in other libraries.
+/// (i.e. does not allocate), and *O*(*n*) on average. The worst-case performance is *O*(*n*): once
+/// a few rounds have split the slice badly, the rest of the pivots are chosen with the median
+/// of medians. This function is also known as "kth element" in other libraries.
 ///
 /// Returns a triplet of the following from the reordered slice: the subslice prior to `index`, the
 /// element at `index`, and the subslice after `index`; accordingly, the values in those two
@@ -950,7 +1449,8 @@ where
 ///
 /// The implementation is similar to `core::slice::select_nth_unstable`, but it uses an adaptive
 /// pivot selection algorithm. This usually improves performance substantially, especially when
-/// `index` is far from the median.
+/// `index` is far from the median. If a strict bound on the number of comparisons is needed, use
+/// [`select_nth_unstable_with_options`] with [`Strategy::MedianOfMedians`].
 ///
 /// # Panics
 ///
@@ -1002,8 +1502,8 @@ where
 /// less than or equal to any value at a position `j > index` using the comparator function.
 /// Additionally, this reordering is unstable (i.e. any number of equal elements may end up at
 /// position `index`), in-place (i.e. does not allocate), and *O*(*n*) on average.
-/// The worst-case performance is *O*(*n* log *n*). This function is also known as
-/// "kth element" in other libraries.
+/// The worst-case performance is *O*(*n*). This function is also known as "kth element" in other
+/// libraries.
 ///
 /// It returns a triplet of the following from the slice reordered according to the provided
 /// comparator function: the subslice prior to `index`, the element at `index`, and the subslice
@@ -1065,6 +1565,53 @@ where
     split_partition(data, index)
 }
 
+/// Reorder the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position.
+///
+/// This is like [`select_nth_unstable_by`], but `lt` returns `true` if its first argument is less
+/// than the second, instead of an `Ordering`. This is the form the implementation uses internally,
+/// so it avoids the conversion. The [`cmp`] module contains combinators for building such
+/// functions.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_by_lt;
+/// let mut v = [-5i32, 4, 1, -3, 2];
+///
+/// // Find the median as if the slice were sorted in descending order.
+/// let (_, median, _) = select_nth_unstable_by_lt(&mut v, 2, |a, b| a > b);
+/// assert_eq!(*median, 1);
+/// ```
+#[inline]
+pub fn select_nth_unstable_by_lt<T, F>(
+    data: &mut [T],
+    index: usize,
+    mut lt: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    // If there are less than two elements, there is nothing to do. If `T` is a zero sized type, it
+    // cannot have any meaningful ordering, so we just return.
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, index);
+    }
+
+    if index == 0 {
+        select_min(data, &mut lt);
+    } else if index == data.len() - 1 {
+        select_max(data, &mut lt);
+    } else {
+        select(data, index, &mut lt);
+    }
+    split_partition(data, index)
+}
+
 /// Reorder the slice with a key extraction function such that the element at `index` is at its
 /// final sorted position.
 ///
@@ -1072,8 +1619,8 @@ where
 /// less than or equal to any value at a position `j > index` using the key extraction function.
 /// Additionally, this reordering is unstable (i.e. any number of equal elements may end up at
 /// position `index`), in-place (i.e. does not allocate), and *O*(*n*) on average.
-/// The worst-case performance is *O*(*n* log *n*). This function is also known as "kth element" in
-/// other libraries.
+/// The worst-case performance is *O*(*n*). This function is also known as "kth element" in other
+/// libraries.
 ///
 /// Returns a triplet of the following from the slice reordered according to the provided key
 /// extraction function: the subslice prior to `index`, the element at `index`, and the subslice
@@ -1136,6 +1683,19 @@ where
     split_partition(data, index)
 }
 
+/// Specifies how elements with equal keys are ordered by the selection functions that work on
+/// indices or cached keys.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Ties {
+    /// Equal elements are ordered by their original index. The partitioning and the element at
+    /// the selected index are then fully determined by the input.
+    #[default]
+    ByIndex,
+    /// Equal elements may end up in any order. This avoids comparing the indices when the keys
+    /// are equal.
+    Unordered,
+}
+
 #[cfg(feature = "std")]
 /// Reorder the slice with a key extraction function such that the element at `index` is at its
 /// final sorted position. During selection, the key function is called at most once per element, by
@@ -1144,7 +1704,7 @@ where
 /// This reordering has the additional property that any value at position `i < index` will be
 /// less than or equal to any value at a position `j > index` using the key extraction function.
 /// Additionally, this reordering is unstable (i.e. any number of equal elements may end up at
-/// position `index`) and *O*(*n*) on average. The worst-case performance is *O*(*n* log *n*).
+/// position `index`) and *O*(*n*) on average. The worst-case performance is *O*(*n*).
 /// This function is also known as "kth element" in other libraries.
 ///
 /// Returns a triplet of the following from the slice reordered according to the provided key
@@ -1185,21 +1745,143 @@ where
     F: FnMut(&T) -> K,
     K: Ord,
 {
-    use std::vec::Vec;
+    select_nth_unstable_by_cached_key_with_ties(data, index, f, Ties::ByIndex)
+}
+
+#[cfg(feature = "std")]
+/// Reorder the slice with a key extraction function such that the element at `index` is at its
+/// final sorted position, like [`select_nth_unstable_by_cached_key`], with `ties` specifying how
+/// elements with equal keys are ordered.
+///
+/// With [`Ties::ByIndex`], which is what [`select_nth_unstable_by_cached_key`] uses, elements
+/// with equal keys are ordered by their original position. This makes the result reproducible.
+/// With [`Ties::Unordered`], only the keys are compared.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_by_cached_key_with_ties, Ties};
+/// let mut v = [(1, 'a'), (0, 'b'), (1, 'c'), (1, 'd'), (0, 'e')];
+///
+/// // Among the elements with key 1, the one that came first is selected.
+/// select_nth_unstable_by_cached_key_with_ties(&mut v, 2, |x| x.0, Ties::ByIndex);
+/// assert_eq!(v[2], (1, 'a'));
+/// ```
+#[inline]
+pub fn select_nth_unstable_by_cached_key_with_ties<T, K, F>(
+    data: &mut [T],
+    index: usize,
+    f: F,
+    ties: Ties,
+) where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    select_by_cached_key(data, index, f, K::cmp, ties)
+}
 
+#[cfg(feature = "std")]
+/// Reorder the slice with a key extraction function and a comparator function over the keys such
+/// that the element at `index` is at its final sorted position. During selection, the key function
+/// is called at most once per element, by using temporary storage to remember the results of key
+/// evaluation.
+///
+/// This is like [`select_nth_unstable_by_cached_key`], but the keys don't need to implement
+/// `Ord`. This allows caching keys such as floats or composite keys with a custom ordering.
+/// Elements with equal keys are ordered by their original position.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_by_cached_key_by;
+/// let mut v = ["1.5", "-2.25", "10", "0.5", "3"];
+///
+/// // Find the median of the strings parsed as floats.
+/// select_nth_unstable_by_cached_key_by(
+///     &mut v,
+///     2,
+///     |s| s.parse::<f64>().unwrap(),
+///     |a, b| a.total_cmp(b),
+/// );
+/// assert_eq!(v[2], "1.5");
+/// ```
+#[inline]
+pub fn select_nth_unstable_by_cached_key_by<T, K, F, C>(
+    data: &mut [T],
+    index: usize,
+    f: F,
+    compare: C,
+) where
+    F: FnMut(&T) -> K,
+    C: FnMut(&K, &K) -> Ordering,
+{
+    select_by_cached_key(data, index, f, compare, Ties::ByIndex)
+}
+
+#[cfg(feature = "std")]
+/// Extracts the keys of the elements for the cached-key selection, paired with the positions of
+/// the elements converted with `position`.
+trait Decorate<T, K> {
+    fn decorate<I: Send>(&mut self, data: &[T], position: fn(usize) -> I) -> std::vec::Vec<(K, I)>;
+}
+
+#[cfg(feature = "std")]
+/// Extracts the keys one element at a time.
+struct Serial<F>(F);
+
+#[cfg(feature = "std")]
+impl<T, K, F> Decorate<T, K> for Serial<F>
+where
+    F: FnMut(&T) -> K,
+{
+    fn decorate<I: Send>(&mut self, data: &[T], position: fn(usize) -> I) -> std::vec::Vec<(K, I)> {
+        data.iter()
+            .map(&mut self.0)
+            .enumerate()
+            .map(|(i, k)| (k, position(i)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+/// Selects the element at `index` by the keys extracted with `f` and compared with `compare`,
+/// calling `f` once per element. See [`select_nth_unstable_by_cached_key_with_ties`].
+fn select_by_cached_key<T, K, F, C>(data: &mut [T], index: usize, f: F, compare: C, ties: Ties)
+where
+    F: FnMut(&T) -> K,
+    C: FnMut(&K, &K) -> Ordering,
+{
+    select_decorated(data, index, Serial(f), compare, ties)
+}
+
+#[cfg(feature = "std")]
+/// Selects the element at `index` by the keys extracted with `decorate` and compared with
+/// `compare`. The keys are paired with the positions of the elements, and the pairs are selected
+/// and then used to move the elements into place.
+fn select_decorated<T, K, D, C>(
+    data: &mut [T],
+    index: usize,
+    mut decorate: D,
+    mut compare: C,
+    ties: Ties,
+) where
+    D: Decorate<T, K>,
+    C: FnMut(&K, &K) -> Ordering,
+{
     // Helper macro for indexing our vector by the smallest possible type, to reduce allocation.
     macro_rules! select_nth_by_key {
-        ($t:ty, $slice:ident, $index:ident, $f:ident) => {{
-            let mut indices: Vec<_> = $slice
-                .iter()
-                .map($f)
-                .enumerate()
-                .map(|(i, k)| (k, i as $t))
-                .collect();
-            // The elements of `indices` are unique, as they are indexed, so any sort will be
-            // stable with respect to the original slice. We use `sort_unstable` here because
-            // it requires less memory allocation.
-            select_nth_unstable(&mut indices, index);
+        ($t:ty, $slice:ident, $index:ident) => {{
+            let mut indices = decorate.decorate($slice, |i| i as $t);
+            // The elements of `indices` are unique, as they are indexed, so comparing the indices
+            // of equal keys breaks ties by the original position.
+            match ties {
+                Ties::ByIndex => select_nth_unstable_by(&mut indices, $index, |a, b| {
+                    compare(&a.0, &b.0).then(a.1.cmp(&b.1))
+                }),
+                Ties::Unordered => {
+                    select_nth_unstable_by(&mut indices, $index, |a, b| compare(&a.0, &b.0))
+                }
+            };
             for i in 0..$slice.len() {
                 let mut index = indices[i].1;
                 while (index as usize) < i {
@@ -1221,15 +1903,15 @@ where
         return;
     }
     if sz_u8 < sz_u16 && len <= (u8::MAX as usize) {
-        return select_nth_by_key!(u8, data, index, f);
+        return select_nth_by_key!(u8, data, index);
     }
     if sz_u16 < sz_u32 && len <= (u16::MAX as usize) {
-        return select_nth_by_key!(u16, data, index, f);
+        return select_nth_by_key!(u16, data, index);
     }
     if sz_u32 < sz_usize && len <= (u32::MAX as usize) {
-        return select_nth_by_key!(u32, data, index, f);
+        return select_nth_by_key!(u32, data, index);
     }
-    select_nth_by_key!(usize, data, index, f)
+    select_nth_by_key!(usize, data, index)
 }
 
 fn split_partition<T>(data: &mut [T], index: usize) -> (&mut [T], &mut T, &mut [T]) {
@@ -1245,3 +1927,17 @@ fn width<T>(l: *const T, r: *const T) -> usize {
     // safety.
     unsafe { r.offset_from(l).max(0) as usize }
 }
+
+/// Returns the ordering of `a` and `b` given a "less than" function, for sorting with it.
+fn ordering<T, F>(a: &T, b: &T, lt: &mut F) -> Ordering
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if lt(a, b) {
+        Ordering::Less
+    } else if lt(b, a) {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
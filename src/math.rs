@@ -1,6 +1,24 @@
 #[cfg(not(feature = "std"))]
 extern crate libm;
 
+#[inline]
+/// Returns `a * b / c` rounded down, saturating to `usize::MAX`. The product is computed in 128
+/// bits if it doesn't fit in a `usize`, so that index calculations like `k * index / len` don't
+/// overflow on targets with a narrow `usize`.
+pub(crate) fn mul_div(a: usize, b: usize, c: usize) -> usize {
+    match a.checked_mul(b) {
+        Some(product) => product / c,
+        None => (a as u128 * b as u128 / c as u128).min(usize::MAX as u128) as usize,
+    }
+}
+
+#[cfg(feature = "std")]
+#[inline]
+/// Computes the arcsine of `x`, in radians.
+pub(crate) fn asin(x: f64) -> f64 {
+    x.asin()
+}
+
 #[inline]
 /// Returns the smallest integer greater than or equal to `x`.
 pub(crate) fn ceil(x: f64) -> f64 {
@@ -46,6 +64,19 @@ pub(crate) fn lerp(a: f64, b: f64, t: f64) -> f64 {
     a * (1.0 - t) + b * t
 }
 
+#[inline]
+/// Computes the natural logarithm of `x`.
+pub(crate) fn ln(x: f64) -> f64 {
+    #[cfg(feature = "std")]
+    {
+        x.ln()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        libm::log(x)
+    }
+}
+
 #[inline]
 /// Computes `log(x, base)`.
 pub(crate) fn log(x: f64, base: f64) -> f64 {
@@ -72,6 +103,13 @@ pub(crate) fn powf(x: f64, y: f64) -> f64 {
     }
 }
 
+#[cfg(feature = "std")]
+#[inline]
+/// Computes the sine of `x`, in radians.
+pub(crate) fn sin(x: f64) -> f64 {
+    x.sin()
+}
+
 #[inline]
 /// Computes the square root of `x`.
 pub(crate) fn sqrt(x: f64) -> f64 {
@@ -0,0 +1,302 @@
+use core::{cmp::Ordering, mem};
+
+use crate::{
+    params::params, partition_equal_min, select, select_max, select_min, sort::tinysort, BadRounds,
+    BlockPartition, PartitionStrategy,
+};
+
+/// Reorders the slice such that the elements at each of `indices` are at their final sorted
+/// positions, e.g. to find the quartiles and the 99th percentile at once. The indices are sorted in
+/// place, and may contain duplicates.
+///
+/// Each partitioning round chooses the pivot for the middle one of the indices in the part being
+/// partitioned, and the indices on either side of the pivot continue in the part on that side, so
+/// a round is a single pass over its part however many indices fall in it, and the parts that don't
+/// contain any of the indices are never visited again. Selecting `m` indices this way takes
+/// *O*(*n* log *m*) time, compared to *O*(*nm*) for `m` calls of
+/// [`select_nth_unstable`](crate::select_nth_unstable) on the whole slice.
+///
+/// Afterwards, the elements between any two consecutive selected indices are between the elements
+/// at these indices, the elements before the first one are less than or equal to it, and the
+/// elements after the last one are greater than or equal to it.
+///
+/// # Panics
+///
+/// Panics when any of the indices is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_many_nth_unstable;
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+/// let mut indices = [990, 250, 500, 750];
+/// select_many_nth_unstable(&mut v, &mut indices);
+/// assert_eq!(indices, [250, 500, 750, 990]);
+/// assert_eq!([v[250], v[500], v[750], v[990]], [250, 500, 750, 990]);
+/// assert!(v[251..500].iter().all(|&x| (250..500).contains(&x)));
+/// ```
+pub fn select_many_nth_unstable<T>(data: &mut [T], indices: &mut [usize])
+where
+    T: Ord,
+{
+    select_many_nth_unstable_by_lt(data, indices, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the elements at each of `indices` are
+/// at their final sorted positions. The indices are sorted in place.
+///
+/// See [`select_many_nth_unstable`] for details.
+///
+/// # Panics
+///
+/// Panics when any of the indices is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_many_nth_unstable_by;
+/// let mut v = [0.5, -1.0, 2.0, 0.25, 4.0, 1.5];
+/// select_many_nth_unstable_by(&mut v, &mut [0, 3, 5], f64::total_cmp);
+/// assert_eq!([v[0], v[3], v[5]], [-1.0, 1.5, 4.0]);
+/// ```
+pub fn select_many_nth_unstable_by<T, F>(data: &mut [T], indices: &mut [usize], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_many_nth_unstable_by_lt(data, indices, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the elements at each of `indices` are
+/// at their final sorted positions. The indices are sorted in place.
+///
+/// This is the "less than" form of [`select_many_nth_unstable`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when any of the indices is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_many_nth_unstable_by_lt;
+/// let mut v: Vec<u32> = (0..100).collect();
+///
+/// // The largest, the 10th largest and the 20th largest elements.
+/// select_many_nth_unstable_by_lt(&mut v, &mut [19, 0, 9], |a, b| a > b);
+/// assert_eq!([v[0], v[9], v[19]], [99, 90, 80]);
+/// ```
+pub fn select_many_nth_unstable_by_lt<T, F>(data: &mut [T], indices: &mut [usize], mut lt: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    indices.sort_unstable();
+    if let Some(&last) = indices.last() {
+        assert!(last < data.len(), "index out of bounds");
+    }
+    if data.len() > 1 && mem::size_of::<T>() > 0 {
+        select_ranks(data, indices, 0, &mut lt);
+    }
+}
+
+/// Reorders the slice such that the elements at `i` and `j` are both at their final sorted
+/// positions, e.g. to find the 1st and the 99th percentile at once.
+///
+/// Both ranks share the partitioning rounds as long as they fall on the same side of the pivot,
+/// which is chosen for the rank halfway between them. Once a pivot falls between them, the parts
+/// on its either side are selected from separately. When the ranks are close together, this takes
+/// about as long as selecting one of them, and when they are far apart, about as long as selecting
+/// one and then the other from the rest of the slice.
+///
+/// Afterwards, the elements before `min(i, j)` are less than or equal to it, the elements between
+/// the two ranks are between them, and the elements after `max(i, j)` are greater than or equal to
+/// it. Returns the elements at `i` and `j`, in that order. The ranks may be equal.
+///
+/// # Panics
+///
+/// Panics when `i >= len()` or `j >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_two_ranks;
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+/// let (p1, p99) = select_two_ranks(&mut v, 10, 990);
+/// assert_eq!((*p1, *p99), (10, 990));
+/// assert!(v[..10].iter().all(|&x| x < 10));
+/// assert!(v[11..990].iter().all(|&x| (10..990).contains(&x)));
+/// ```
+pub fn select_two_ranks<T>(data: &mut [T], i: usize, j: usize) -> (&T, &T)
+where
+    T: Ord,
+{
+    select_two_ranks_by_lt(data, i, j, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the elements at `i` and `j` are both
+/// at their final sorted positions.
+///
+/// See [`select_two_ranks`] for details.
+///
+/// # Panics
+///
+/// Panics when `i >= len()` or `j >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_two_ranks_by;
+/// let mut v = [5, 1, 4, 3, 2];
+/// let (second, fourth) = select_two_ranks_by(&mut v, 1, 3, |a, b| b.cmp(a));
+/// assert_eq!((*second, *fourth), (4, 2));
+/// ```
+pub fn select_two_ranks_by<T, F>(data: &mut [T], i: usize, j: usize, mut compare: F) -> (&T, &T)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_two_ranks_by_lt(data, i, j, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the elements at `i` and `j` are both
+/// at their final sorted positions.
+///
+/// This is the "less than" form of [`select_two_ranks`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `i >= len()` or `j >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_two_ranks_by_lt;
+/// let mut v = [-5, 1, -4, 3, 2];
+/// let (min, max) = select_two_ranks_by_lt(&mut v, 0, 4, |a: &i32, b: &i32| a.abs() < b.abs());
+/// assert_eq!((*min, *max), (1, -5));
+/// ```
+pub fn select_two_ranks_by_lt<T, F>(data: &mut [T], i: usize, j: usize, mut lt: F) -> (&T, &T)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = data.len();
+    assert!(i < len && j < len);
+    if len > 1 && mem::size_of::<T>() > 0 {
+        select_two(data, i.min(j), i.max(j), &mut lt);
+    }
+    (&data[i], &data[j])
+}
+
+/// Puts the elements at `i` and `j` at their sorted positions, where `i <= j`.
+fn select_two<T, F>(mut data: &mut [T], mut i: usize, mut j: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let params = params::<T>();
+    let partition = BlockPartition {
+        block: Some(params.partition_block),
+    };
+    let mut previous_pivot: Option<&T> = None;
+    let mut bad_rounds = BadRounds::default();
+
+    while i < j && data.len() > params.tinysort_cutoff {
+        let len = data.len();
+        if i == 0 && j == len - 1 {
+            // The extremes take one pass each, which is cheaper than any partition.
+            select_min(data, lt);
+            select_max(&mut data[1..], lt);
+            return;
+        }
+        let (p, is_repeated) = bad_rounds.choose_pivot(data, i + (j - i) / 2, lt, &params);
+        // The pivot is equal to a previous pivot from the left, so it is the minimum.
+        let is_minimum = previous_pivot.is_some_and(|was| !lt(was, &data[p]));
+        let (u, v) = match is_minimum {
+            true => partition_equal_min(data, p, lt),
+            false => partition.partition(data, p, is_repeated, lt),
+        };
+
+        if j < u {
+            data = data[..u].as_mut();
+        } else if i > v {
+            let (head, tail) = data.split_at_mut(v + 1);
+            (data, previous_pivot) = (tail, head.last());
+            i -= v + 1;
+            j -= v + 1;
+        } else {
+            // The pivot falls between the ranks, so they no longer share any elements.
+            let (head, tail) = data.split_at_mut(v + 1);
+            if i < u {
+                select(&mut head[..u], i, lt);
+            }
+            if j > v {
+                select(tail, j - v - 1, lt);
+            }
+            return;
+        }
+        bad_rounds.count(len, data.len());
+    }
+    match i == j {
+        true => select(data, i, lt),
+        false => tinysort(data, lt),
+    }
+}
+
+/// Puts the elements at each of the nondecreasing `ranks` at their sorted positions, where `offset`
+/// is the rank of `data[0]`. Each round partitions the slice around a pivot chosen for the
+/// middle rank, and the ranks on either side of the pivot continue in the part on that side, so
+/// every round is a single pass however many ranks there are.
+pub(crate) fn select_ranks<T, F>(data: &mut [T], ranks: &[usize], offset: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    select_ranks_after(data, ranks, offset, lt, BadRounds::default());
+}
+
+/// Like [`select_ranks`], but counts on from the `bad_rounds` of the rounds that led to `data`.
+fn select_ranks_after<T, F>(
+    data: &mut [T],
+    ranks: &[usize],
+    offset: usize,
+    lt: &mut F,
+    mut bad_rounds: BadRounds,
+) where
+    F: FnMut(&T, &T) -> bool,
+{
+    let params = params::<T>();
+    let partition = BlockPartition {
+        block: Some(params.partition_block),
+    };
+    let (mut data, mut ranks, mut offset) = (data, ranks, offset);
+    let mut previous_pivot: Option<&T> = None;
+
+    loop {
+        match *ranks {
+            [] => return,
+            [i] => return select(data, i - offset, lt),
+            [i, j] => return select_two(data, i - offset, j - offset, lt),
+            _ if data.len() <= params.tinysort_cutoff => return tinysort(data, lt),
+            _ => {}
+        }
+        let index = ranks[ranks.len() / 2] - offset;
+        let (p, is_repeated) = bad_rounds.choose_pivot(data, index, lt, &params);
+        // The pivot is equal to a previous pivot from the left, so it is the minimum.
+        let is_minimum = previous_pivot.is_some_and(|was| !lt(was, &data[p]));
+        let (u, v) = match is_minimum {
+            true => partition_equal_min(data, p, lt),
+            false => partition.partition(data, p, is_repeated, lt),
+        };
+
+        let below = ranks.partition_point(|&r| r < offset + u);
+        let above = ranks.partition_point(|&r| r <= offset + v);
+        // Both parts may still hold ranks, so each counts the round by its own length.
+        let len = data.len();
+        let mut left_rounds = bad_rounds;
+        left_rounds.count(len, u);
+        bad_rounds.count(len, len - v - 1);
+        let (head, tail) = data.split_at_mut(v + 1);
+        select_ranks_after(&mut head[..u], &ranks[..below], offset, lt, left_rounds);
+        (data, previous_pivot) = (tail, head.last());
+        ranks = &ranks[above..];
+        offset += v + 1;
+    }
+}
@@ -0,0 +1,262 @@
+use std::boxed::Box;
+
+use crate::wyrand::{thread_rng, WyRng};
+
+type Link<T> = Option<Box<Node<T>>>;
+
+/// A node of the treap.
+struct Node<T> {
+    /// The value stored in the node.
+    value: T,
+    /// The heap priority of the node. The priority of a node is greater than or equal to the
+    /// priorities of its children.
+    priority: u64,
+    /// The number of nodes in the subtree rooted at this node.
+    size: usize,
+    /// The subtree of values less than or equal to `value`.
+    left: Link<T>,
+    /// The subtree of values greater than or equal to `value`.
+    right: Link<T>,
+}
+
+impl<T> Node<T> {
+    /// Recomputes the size of the node from the sizes of its children.
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+/// Returns the number of nodes in the subtree.
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// Merges two treaps, where all values in `a` are less than or equal to the values in `b`.
+fn merge<T>(a: Link<T>, b: Link<T>) -> Link<T> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority >= b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+/// Splits the treap into the values `x` for which `goes_left(x)` is `true` and the rest. The
+/// predicate must be monotonic, i.e. `true` for a prefix of the values in ascending order.
+fn split<T, F>(link: Link<T>, goes_left: &mut F) -> (Link<T>, Link<T>)
+where
+    F: FnMut(&T) -> bool,
+{
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if goes_left(&node.value) {
+                let (l, r) = split(node.right.take(), goes_left);
+                node.right = l;
+                node.update();
+                (Some(node), r)
+            } else {
+                let (l, r) = split(node.left.take(), goes_left);
+                node.left = r;
+                node.update();
+                (l, Some(node))
+            }
+        }
+    }
+}
+
+/// A multiset that supports finding the `k`th smallest element and the rank of a value, in
+/// addition to insertion and removal, all in *O*(log *n*) expected time.
+///
+/// Repeatedly calling [`select_nth_unstable`](crate::select_nth_unstable) on a slice that changes
+/// between the calls costs *O*(*n*) per call. When the values change one at a time, this collection
+/// is usually a better fit. It is implemented as a treap, i.e. a binary search tree balanced by
+/// random heap priorities, where each node also stores the size of its subtree.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::OrderStatistics;
+/// let mut set = OrderStatistics::new();
+/// for x in [5, 1, 4, 1, 3] {
+///     set.insert(x);
+/// }
+/// assert_eq!(set.kth(2), Some(&3));
+/// assert_eq!(set.rank(&4), 3);
+///
+/// set.remove(&1);
+/// assert_eq!(set.kth(0), Some(&1));
+/// assert_eq!(set.len(), 4);
+/// ```
+pub struct OrderStatistics<T> {
+    /// The root of the treap.
+    root: Link<T>,
+    /// The source of node priorities.
+    rng: WyRng,
+}
+
+impl<T> Default for OrderStatistics<T> {
+    fn default() -> Self {
+        Self {
+            root: None,
+            rng: thread_rng(),
+        }
+    }
+}
+
+impl<T> OrderStatistics<T>
+where
+    T: Ord,
+{
+    /// Returns `true` if the collection contains a value equal to `value`.
+    pub fn contains(&self, value: &T) -> bool {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if value < &node.value {
+                link = &node.left;
+            } else if value > &node.value {
+                link = &node.right;
+            } else {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Inserts `value` into the collection. Equal values are kept as separate elements.
+    pub fn insert(&mut self, value: T) {
+        let node = Box::new(Node {
+            value,
+            priority: self.rng.u64(),
+            size: 1,
+            left: None,
+            right: None,
+        });
+        let (l, r) = split(self.root.take(), &mut |x| x < &node.value);
+        self.root = merge(merge(l, Some(node)), r);
+    }
+
+    /// Returns `true` if the collection contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the `k`th smallest element (zero-based), or `None` if `k >= len()`.
+    pub fn kth(&self, mut k: usize) -> Option<&T> {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let left = size(&node.left);
+            match k {
+                k if k < left => link = &node.left,
+                k if k == left => return Some(&node.value),
+                _ => {
+                    k -= left + 1;
+                    link = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the number of elements in the collection.
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Returns a new, empty collection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of elements strictly less than `value`.
+    pub fn rank(&self, value: &T) -> usize {
+        let mut rank = 0;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if &node.value < value {
+                rank += size(&node.left) + 1;
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
+        }
+        rank
+    }
+
+    /// Removes one element equal to `value` from the collection and returns it, or returns `None`
+    /// if there is no such element.
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let (l, r) = split(self.root.take(), &mut |x| x < value);
+        let (m, r) = split(r, &mut |x| x <= value);
+        let (removed, m) = match m {
+            Some(mut node) => {
+                let rest = merge(node.left.take(), node.right.take());
+                (Some(node.value), rest)
+            }
+            None => (None, None),
+        };
+        self.root = merge(merge(l, m), r);
+        removed
+    }
+}
+
+/// Calls `f` on the values of the subtree in ascending order, stopping at the first error.
+#[cfg(feature = "serde")]
+fn try_for_each<T, E, F>(link: &Link<T>, f: &mut F) -> Result<(), E>
+where
+    F: FnMut(&T) -> Result<(), E>,
+{
+    if let Some(node) = link {
+        try_for_each(&node.left, f)?;
+        f(&node.value)?;
+        try_for_each(&node.right, f)?;
+    }
+    Ok(())
+}
+
+/// Serializes the collection as a sequence of its elements in ascending order. The shape of the
+/// treap is not serialized, since it depends only on the random priorities.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for OrderStatistics<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(size(&self.root)))?;
+        try_for_each(&self.root, &mut |value| seq.serialize_element(value))?;
+        seq.end()
+    }
+}
+
+/// Deserializes the collection from a sequence of elements in any order.
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for OrderStatistics<T>
+where
+    T: Ord + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let values = std::vec::Vec::<T>::deserialize(deserializer)?;
+        let mut set = Self::new();
+        for value in values {
+            set.insert(value);
+        }
+        Ok(set)
+    }
+}
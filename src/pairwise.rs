@@ -0,0 +1,153 @@
+use core::ops::{Add, Sub};
+use std::vec::Vec;
+
+use crate::{select_nth_unstable, wyrand::thread_rng};
+
+/// Returns the `k`th smallest (zero-based) of the `a.len() * b.len()` sums `a[i] + b[j]`, without
+/// computing all of the sums.
+///
+/// The slices are copied and sorted, which makes the sums an implicit matrix with sorted rows and
+/// columns. The candidates for the result are kept as a range of columns in each row. In each
+/// round, a random candidate is used as a pivot, and the number of candidates less than and equal
+/// to it is counted by walking along the boundary of the pivot in the matrix, which takes
+/// *O*(*m* + *n*) time. The candidate ranges are then narrowed to the side that contains the
+/// result. When only a few candidates remain, they are selected from directly. The expected time
+/// is *O*(*m* log *m* + *n* log *n*), where *m* and *n* are the lengths of the slices.
+///
+/// The sums must not overflow.
+///
+/// # Panics
+///
+/// Panics if `k >= a.len() * b.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::kth_smallest_sum;
+/// let a = [1, 7, 11];
+/// let b = [2, 4, 6];
+/// // The sums in ascending order are 3, 5, 7, 9, 11, 13, 13, 15, 17.
+/// assert_eq!(kth_smallest_sum(&a, &b, 0), 3);
+/// assert_eq!(kth_smallest_sum(&a, &b, 5), 13);
+/// ```
+pub fn kth_smallest_sum<T>(a: &[T], b: &[T], k: usize) -> T
+where
+    T: Copy + Ord + Add<Output = T>,
+{
+    let count = a.len().checked_mul(b.len());
+    assert!(count.is_some_and(|count| k < count));
+
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_unstable();
+    b.sort_unstable();
+    kth_in_sorted_matrix(std::vec![0; a.len()], b.len(), k, |i, j| a[i] + b[j])
+}
+
+/// Returns the `k`th smallest element of the implicit matrix with `cols` columns whose element at
+/// `(i, j)` is `at(i, j)`. Row `i` consists of the columns `start[i]..cols`, where `start` is
+/// nonincreasing. The rows and the columns of the matrix must be sorted in ascending order.
+pub(crate) fn kth_in_sorted_matrix<T, F>(
+    start: Vec<usize>,
+    cols: usize,
+    mut k: usize,
+    mut at: F,
+) -> T
+where
+    T: Copy + Ord,
+    F: FnMut(usize, usize) -> T,
+{
+    // The candidates in row `i` are the elements in columns `lo[i]..hi[i]`. The bounds stay
+    // nonincreasing from row to row.
+    let rows = start.len();
+    let mut lo = start;
+    let mut hi = std::vec![cols; rows];
+    let mut less = std::vec![0; rows];
+    let mut less_or_equal = std::vec![0; rows];
+    let mut rng = thread_rng();
+    loop {
+        let total: usize = lo.iter().zip(&hi).map(|(l, h)| h - l).sum();
+        if total <= rows + cols {
+            let mut candidates: Vec<T> = (0..rows)
+                .flat_map(|i| (lo[i]..hi[i]).map(move |j| (i, j)))
+                .map(|(i, j)| at(i, j))
+                .collect();
+            return *select_nth_unstable(&mut candidates, k).1;
+        }
+
+        // Pick a uniformly random candidate as the pivot.
+        let mut r = rng.bounded_usize(0, total);
+        let mut i = 0;
+        while r >= hi[i] - lo[i] {
+            r -= hi[i] - lo[i];
+            i += 1;
+        }
+        let pivot = at(i, lo[i] + r);
+
+        // Find the number of elements less than, and less than or equal to the pivot in each row.
+        // Since the columns are sorted, these are nonincreasing from row to row. Only the columns
+        // from `lo[i]` onwards need to be looked at.
+        let (mut lt, mut le) = (cols, cols);
+        for i in 0..rows {
+            while lt > lo[i] && at(i, lt - 1) >= pivot {
+                lt -= 1;
+            }
+            while le > lo[i] && at(i, le - 1) > pivot {
+                le -= 1;
+            }
+            less[i] = lt.clamp(lo[i], hi[i]);
+            less_or_equal[i] = le.clamp(lo[i], hi[i]);
+        }
+
+        let below: usize = less.iter().zip(&lo).map(|(x, l)| x - l).sum();
+        let equal: usize = less_or_equal.iter().zip(&less).map(|(x, y)| x - y).sum();
+        if k < below {
+            hi.copy_from_slice(&less);
+        } else if k < below + equal {
+            return pivot;
+        } else {
+            lo.copy_from_slice(&less_or_equal);
+            k -= below + equal;
+        }
+    }
+}
+
+/// Returns the `k`th smallest (zero-based) of the `n * (n - 1) / 2` absolute differences
+/// `|data[i] - data[j]|` between the elements at positions `i < j`, without computing all of the
+/// differences. `data` doesn't need to be sorted.
+///
+/// This is the basic step of finding the `k`th closest pair of values, and of robust scale
+/// estimators such as the Qn estimator of Rousseeuw and Croux. A copy of the data is sorted, after
+/// which the number of differences less than or equal to a value can be counted in *O*(*n*) time
+/// with two pointers. The result is found by searching over the values of the differences: the
+/// candidates are narrowed around random pivots by counting their ranks, like in
+/// [`kth_smallest_sum`]. The expected time is *O*(*n* log *n*).
+///
+/// # Panics
+///
+/// Panics if `k >= n * (n - 1) / 2`, where `n = data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::kth_smallest_pairwise_diff;
+/// let data = [1u32, 6, 3, 10];
+/// // The differences in ascending order are 2, 3, 4, 5, 7, 9.
+/// assert_eq!(kth_smallest_pairwise_diff(&data, 0), 2);
+/// assert_eq!(kth_smallest_pairwise_diff(&data, 4), 7);
+/// ```
+pub fn kth_smallest_pairwise_diff<T>(data: &[T], k: usize) -> T
+where
+    T: Copy + Ord + Sub<Output = T>,
+{
+    let n = data.len();
+    assert!(k < n * n.saturating_sub(1) / 2);
+
+    let mut data = data.to_vec();
+    data.sort_unstable();
+
+    // Row `r` holds the differences from the element `i = n - 1 - r` to the elements after it, so
+    // that both the rows and the columns are sorted in ascending order.
+    let start = (0..n).map(|r| n - r).collect();
+    kth_in_sorted_matrix(start, n, k, |r, j| data[j] - data[n - 1 - r])
+}
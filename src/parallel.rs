@@ -0,0 +1,460 @@
+use core::{cmp::Ordering, mem, ptr};
+use std::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::{
+    params::params, partition_in_blocks, select, select_decorated, split_partition, BadRounds,
+    Decorate, Ties,
+};
+
+/// Extracts the keys on the threads of the rayon thread pool.
+pub(crate) struct Parallel<F>(pub(crate) F);
+
+impl<T, K, F> Decorate<T, K> for Parallel<F>
+where
+    T: Sync,
+    K: Send,
+    F: Fn(&T) -> K + Sync,
+{
+    fn decorate<I: Send>(&mut self, data: &[T], position: fn(usize) -> I) -> Vec<(K, I)> {
+        data.par_iter()
+            .map(&self.0)
+            .enumerate()
+            .map(|(i, k)| (k, position(i)))
+            .collect()
+    }
+}
+
+/// Reorder the slice with a key extraction function such that the element at `index` is at its
+/// final sorted position, like
+/// [`select_nth_unstable_by_cached_key`](crate::select_nth_unstable_by_cached_key), but extracts
+/// the keys in parallel.
+///
+/// When the keys are expensive to compute, e.g. because they are parsed from strings or matched
+/// with regular expressions, extracting them takes most of the time of the selection. Each key is
+/// independent of the others, so the keys are computed on the threads of the rayon thread pool,
+/// and only the selection of the cached keys runs on the calling thread. Elements with equal keys
+/// are ordered by their original position, so the result is the same as with the serial version.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_select_nth_unstable_by_cached_key;
+/// let mut v: Vec<String> = (0..1000).map(|x| ((x * 7919) % 1000).to_string()).collect();
+///
+/// // Find the median of the strings parsed as integers.
+/// par_select_nth_unstable_by_cached_key(&mut v, 500, |s| s.parse::<u32>().unwrap());
+/// assert_eq!(v[500], "500");
+/// ```
+pub fn par_select_nth_unstable_by_cached_key<T, K, F>(data: &mut [T], index: usize, f: F)
+where
+    T: Send + Sync,
+    F: Fn(&T) -> K + Sync,
+    K: Ord + Send,
+{
+    select_decorated(data, index, Parallel(f), K::cmp, Ties::ByIndex)
+}
+
+/// Slices shorter than this are selected from on the calling thread, since splitting them between
+/// threads costs more than it saves.
+///
+/// This and [`PAR_MIN_CHUNK`] were only measured on a single core, where the parallel rounds can't
+/// pay off, so they are estimates of where the overhead of a round is amortized. The
+/// `parallel_scaling_perf` benchmark reports the length from which the parallel selection beats
+/// the serial one for each thread count, and the time of the rounds by chunk length.
+const PAR_MIN_LEN: usize = 1 << 16;
+
+/// The smallest number of elements partitioned by a single task.
+const PAR_MIN_CHUNK: usize = 1 << 14;
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, like
+/// [`select_nth_unstable`](crate::select_nth_unstable), but partitions the slice on the threads of
+/// the rayon thread pool.
+///
+/// Each partitioning round splits the slice into chunks, which are partitioned around the pivot in
+/// parallel with the same block partitioning as the serial selection. Then the elements greater
+/// than or equal to the pivot before the final boundary are swapped with the elements less than
+/// the pivot after it, also in parallel, since the runs to swap are disjoint. The pivots are chosen
+/// from a sample like in the serial selection, and once the remaining part is shorter than about
+/// 65 000 elements, it is selected from on the calling thread.
+///
+/// The chunk partitions and the swaps read and write every element of the part once more than the
+/// serial partitioning, so this only pays off on slices of millions of elements with several
+/// threads available. When the thread pool has a single thread, the slice is selected from like
+/// with `select_nth_unstable`. The thresholds haven't been tuned on multi-core machines yet.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_select_nth_unstable;
+/// let mut v: Vec<u64> = (0..1_000_000).map(|x| (x * 7919) % 1_000_000).collect();
+/// let (_, median, _) = par_select_nth_unstable(&mut v, 500_000);
+/// assert_eq!(*median, 500_000);
+/// ```
+pub fn par_select_nth_unstable<T>(data: &mut [T], index: usize) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Ord + Send + Sync,
+{
+    par_select_nth_unstable_by_lt(data, index, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, partitioning the slice on the threads of the rayon thread pool.
+///
+/// See [`par_select_nth_unstable`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_select_nth_unstable_by;
+/// let mut v: Vec<f64> = (0..100_000).map(|x| ((x * 7919) % 100_000) as f64).collect();
+/// let (_, nth, _) = par_select_nth_unstable_by(&mut v, 10, |a, b| b.total_cmp(a));
+/// assert_eq!(*nth, 99_989.0);
+/// ```
+pub fn par_select_nth_unstable_by<T, F>(
+    data: &mut [T],
+    index: usize,
+    compare: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    par_select_nth_unstable_by_lt(data, index, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, partitioning the slice on the threads of the rayon thread pool.
+///
+/// This is the "less than" form of [`par_select_nth_unstable`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_select_nth_unstable_by_lt;
+/// let mut v: Vec<i64> = (0..100_000).map(|x| (x * 7919) % 100_000 - 50_000).collect();
+/// let (_, nth, _) = par_select_nth_unstable_by_lt(&mut v, 0, |a, b| a.abs() < b.abs());
+/// assert_eq!(*nth, 0);
+/// ```
+pub fn par_select_nth_unstable_by_lt<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Send + Sync,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    assert!(index < data.len());
+    if data.len() > 1 && mem::size_of::<T>() > 0 {
+        par_select(data, index, &lt);
+    }
+    split_partition(data, index)
+}
+
+/// Partitions the slice around the element at `index` on the threads of the rayon thread pool, and
+/// returns the elements less than it, the element itself and the elements greater than or equal to
+/// it, e.g. as the partitioning step of a parallel sort or quantile computation.
+///
+/// This is one round of [`par_select_nth_unstable`]: the slice is split into chunks, each chunk is
+/// block partitioned in parallel, and the misplaced runs of the chunks are swapped across the
+/// final boundary in parallel. If none of the other elements are less than the pivot, the elements
+/// equal to it are put right after it, so that the partition always makes progress on slices with
+/// many duplicates. Slices of up to 16 384 elements are partitioned as a single chunk, so they
+/// gain nothing from the threads, and so are all slices when the thread pool has a single thread.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_partition_at;
+/// let mut v: Vec<u64> = (0..100_000).map(|x| (x * 7919) % 100_000).collect();
+/// let (below, pivot, above) = par_partition_at(&mut v, 0);
+/// assert_eq!(below.len(), *pivot as usize);
+/// assert!(below.iter().all(|x| x < pivot) && above.iter().all(|x| x >= pivot));
+/// ```
+pub fn par_partition_at<T>(data: &mut [T], index: usize) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Ord + Send + Sync,
+{
+    par_partition_at_by_lt(data, index, T::lt)
+}
+
+/// Partitions the slice with a comparator function around the element at `index` on the threads of
+/// the rayon thread pool.
+///
+/// See [`par_partition_at`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_partition_at_by;
+/// let mut v = [0.5f64, -1.0, 2.5, 1.5, 0.0];
+/// let (below, pivot, _) = par_partition_at_by(&mut v, 3, |a, b| b.total_cmp(a));
+/// assert_eq!((below, *pivot), (&mut [2.5][..], 1.5));
+/// ```
+pub fn par_partition_at_by<T, F>(
+    data: &mut [T],
+    index: usize,
+    compare: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    par_partition_at_by_lt(data, index, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Partitions the slice with a "less than" function around the element at `index` on the threads
+/// of the rayon thread pool.
+///
+/// This is the "less than" form of [`par_partition_at`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::par_partition_at_by_lt;
+/// let mut v = [3, 1, 4, 1, 5, 9, 2, 6];
+/// let (below, pivot, above) = par_partition_at_by_lt(&mut v, 2, |a, b| a > b);
+/// assert_eq!(*pivot, 4);
+/// assert!(below.iter().all(|&x| x > 4) && above.iter().all(|&x| x <= 4));
+/// ```
+pub fn par_partition_at_by_lt<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Send + Sync,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    assert!(index < data.len());
+    let u = match mem::size_of::<T>() {
+        0 => index,
+        _ => par_partition_around(data, index, false, &lt, params::<T>().partition_block).0,
+    };
+    split_partition(data, u)
+}
+
+/// Puts the element at `index` at its sorted position, partitioning in parallel until the
+/// remaining part is shorter than [`PAR_MIN_LEN`], or not at all on a single thread.
+fn par_select<T, F>(mut data: &mut [T], mut index: usize, lt: &F)
+where
+    T: Send + Sync,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    let params = params::<T>();
+    let block = params.partition_block;
+    // On a single thread, the parallel rounds would only add the cost of the fix-ups.
+    let min_len = match rayon::current_num_threads() {
+        1 => usize::MAX,
+        _ => PAR_MIN_LEN,
+    };
+    let mut bad_rounds = BadRounds::default();
+    while data.len() >= min_len {
+        let len = data.len();
+        let (p, is_repeated) = bad_rounds.choose_pivot(data, index, &mut |a, b| lt(a, b), &params);
+        let (u, v) = par_partition_around(data, p, is_repeated, lt, block);
+        if index < u {
+            data = &mut data[..u];
+        } else if index > v {
+            data = &mut data[v + 1..];
+            index -= v + 1;
+        } else {
+            return;
+        }
+        bad_rounds.count(len, data.len());
+    }
+    select(data, index, &mut |a, b| lt(a, b));
+}
+
+/// Partitions `data` in parallel around the element at `pivot`, and returns `(u, v)` such that the
+/// elements of `data[..u]` are less than the pivot, `data[u..=v]` are equal to it, and the elements
+/// after `v` are greater than it. The elements equal to the pivot are grouped together only if
+/// `is_repeated` is `true` or none of the elements are less than the pivot, so otherwise `u == v`
+/// and the elements after `v` are greater than or equal to the pivot.
+fn par_partition_around<T, F>(
+    data: &mut [T],
+    pivot: usize,
+    is_repeated: bool,
+    lt: &F,
+    block: usize,
+) -> (usize, usize)
+where
+    T: Send + Sync,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    data.swap(0, pivot);
+    let (pivot, rest) = data.split_first_mut().unwrap();
+    let pivot = &*pivot;
+    let u = par_partition(rest, &|x: &T| lt(x, pivot), block);
+    // Like in the stable selection, the elements equal to the pivot are also grouped if none are
+    // less than it, since the round would otherwise only split off the pivot.
+    let equal = match is_repeated || u == 0 {
+        true => par_partition(&mut rest[u..], &|x: &T| !lt(pivot, x), block),
+        false => 0,
+    };
+    data.swap(0, u);
+    (u, u + equal)
+}
+
+/// Moves the elements for which `is_left` returns `true` to the front of `data` in parallel, and
+/// returns their count.
+///
+/// The slice is split into chunks that are partitioned separately on the threads of the rayon
+/// thread pool, after which the runs on the wrong side of the final boundary are swapped with each
+/// other. The swaps are split into disjoint pieces that are also done in parallel.
+pub(crate) fn par_partition<T, P>(data: &mut [T], is_left: &P, block: usize) -> usize
+where
+    T: Send,
+    P: Fn(&T) -> bool + Sync,
+{
+    par_partition_in_chunks(data, is_left, block, chunk_len(data.len()))
+}
+
+/// Like [`par_partition`], but with chunks of length `chunk`.
+pub(crate) fn par_partition_in_chunks<T, P>(
+    data: &mut [T],
+    is_left: &P,
+    block: usize,
+    chunk: usize,
+) -> usize
+where
+    T: Send,
+    P: Fn(&T) -> bool + Sync,
+{
+    let lefts = partition_chunks(data, is_left, block, chunk);
+    fix_up(data, chunk, &lefts)
+}
+
+/// Returns the number of elements partitioned by a single task.
+///
+/// Each thread gets about four chunks to balance the load. On a single thread, the slice is a
+/// single chunk, which leaves nothing to fix up.
+pub(crate) fn chunk_len(len: usize) -> usize {
+    match rayon::current_num_threads() {
+        1 => len.max(1),
+        threads => (len / (4 * threads)).max(PAR_MIN_CHUNK),
+    }
+}
+
+/// Partitions the chunks of `data` of length `chunk` in parallel, and returns the number of
+/// elements moved to the front of each.
+pub(crate) fn partition_chunks<T, P>(
+    data: &mut [T],
+    is_left: &P,
+    block: usize,
+    chunk: usize,
+) -> Vec<usize>
+where
+    T: Send,
+    P: Fn(&T) -> bool + Sync,
+{
+    data.par_chunks_mut(chunk)
+        .map(|chunk| partition_chunk(chunk, is_left, block))
+        .collect()
+}
+
+/// Swaps the elements of the partitioned chunks that are on the wrong side of the final boundary
+/// in parallel, and returns the boundary. `lefts` must be the result of [`partition_chunks`] with
+/// the same chunk length.
+fn fix_up<T: Send>(data: &mut [T], chunk: usize, lefts: &[usize]) -> usize {
+    let len = data.len();
+    let boundary: usize = lefts.iter().sum();
+
+    // Each chunk is laid out as `[left | right]`. The right runs before the boundary are swapped
+    // with the left runs after it, both taken in order, in pieces of at most `chunk` elements.
+    let starts = (0..lefts.len()).map(|i| i * chunk);
+    let holes = starts
+        .clone()
+        .zip(lefts)
+        .map(|(start, &left)| start + left..(start + chunk).min(len).min(boundary))
+        .filter(|run| run.start < run.end);
+    let mut strays = starts
+        .zip(lefts)
+        .map(|(start, &left)| start.max(boundary)..start + left)
+        .filter(|run| run.start < run.end);
+    let mut swaps = Vec::new();
+    let mut stray = strays.next();
+    for mut hole in holes {
+        while hole.start < hole.end {
+            let run = stray.as_mut().unwrap();
+            let count = (hole.end - hole.start).min(run.end - run.start);
+            swaps.push((hole.start, run.start, count));
+            hole.start += count;
+            run.start += count;
+            if run.start == run.end {
+                stray = strays.next();
+            }
+        }
+    }
+
+    let base = SendPtr(data.as_mut_ptr());
+    swaps.into_par_iter().for_each(|(a, b, count)| {
+        // SAFETY: `a..a + count` is before the boundary and `b..b + count` after it, so the runs
+        // don't overlap, and every position is in at most one run of all of the swaps, since the
+        // holes and the strays are disjoint and each of their positions is consumed once.
+        unsafe { ptr::swap_nonoverlapping(base.get().add(a), base.get().add(b), count) }
+    });
+    boundary
+}
+
+/// Moves the elements of `chunk` for which `is_left` returns `true` to its front, and returns
+/// their count.
+fn partition_chunk<T, P>(chunk: &mut [T], is_left: &P, block: usize) -> usize
+where
+    P: Fn(&T) -> bool,
+{
+    // The block partitioning passes the pivot as the second argument, but the predicate already
+    // holds it, so the first element stands in for it and is put in its place afterwards.
+    let (first, others) = chunk.split_first_mut().unwrap();
+    let left = partition_in_blocks(others, first, &mut |x, _| is_left(x), block);
+    match is_left(first) {
+        true => left + 1,
+        false => {
+            chunk.swap(0, left);
+            left
+        }
+    }
+}
+
+/// A pointer to the slice that is partitioned, shared between the tasks that swap disjoint runs of
+/// it.
+struct SendPtr<T>(*mut T);
+
+impl<T> SendPtr<T> {
+    fn get(&self) -> *mut T {
+        self.0
+    }
+}
+
+// SAFETY: The tasks only access disjoint elements through the pointer, and the elements are `Send`.
+unsafe impl<T: Send> Send for SendPtr<T> {}
+// SAFETY: As above, the pointer is only used for disjoint accesses.
+unsafe impl<T: Send> Sync for SendPtr<T> {}
@@ -0,0 +1,149 @@
+#[cfg(feature = "std")]
+use crate::{
+    cache::{self, CacheSizes},
+    math::mul_div,
+};
+
+/// The largest supported value of [`Params::partition_block`]. The offsets within a block are
+/// stored as `u8`s.
+pub(crate) const MAX_PARTITION_BLOCK: usize = 256;
+
+/// The smallest supported value of [`Params::tinysort_cutoff`]. Shorter slices are too short to
+/// draw the smallest pivot sample from.
+pub(crate) const MIN_TINYSORT_CUTOFF: usize = 8;
+
+/// The largest supported value of [`Params::tinysort_cutoff`]. Slices up to the cutoff are sorted
+/// or selected from by insertion, which takes quadratic time.
+pub(crate) const MAX_TINYSORT_CUTOFF: usize = 128;
+
+/// The thresholds that control the selection algorithm.
+///
+/// The defaults work well on common desktop hardware with small elements. With the `autotune`
+/// feature, [`tune`](crate::tune) measures the best values for the current machine and element
+/// type, and the build script can compile them into the crate in place of the defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// Slices of at most this length are sorted instead of partitioned.
+    pub tinysort_cutoff: usize,
+    /// The largest slice length for which the pivot is chosen from a 3 × 3 sample.
+    pub kth_of_3_max: usize,
+    /// The largest slice length for which the pivot is chosen from a 5 × 5 sample.
+    pub kth_of_5_max: usize,
+    /// The largest slice length for which the pivot is chosen from a 7 × 7 sample. Larger slices
+    /// choose the pivot by recursively selecting from a sample of about `sqrt(len)` elements.
+    pub kth_of_7_max: usize,
+    /// The number of elements scanned at a time on each side of the slice during partitioning.
+    pub partition_block: usize,
+    /// The number of bad rounds, which keep more than three quarters of the slice, after which the
+    /// remaining pivots are chosen with [`Strategy::MedianOfMedians`](crate::Strategy::MedianOfMedians).
+    /// This bounds the running time by a constant times the length of the slice, even for inputs that defeat
+    /// the sampling. `0` chooses every pivot with the median of medians, and `usize::MAX` never
+    /// does.
+    pub bad_round_limit: usize,
+}
+
+impl Params {
+    /// The thresholds used when the crate is not tuned.
+    pub const DEFAULT: Self = Self {
+        tinysort_cutoff: 24,
+        kth_of_3_max: 256,
+        kth_of_5_max: 1024,
+        kth_of_7_max: 4096,
+        partition_block: 128,
+        bad_round_limit: 4,
+    };
+
+    /// Returns `true` if the thresholds are usable. The sorting cutoff must be between 8 and 128
+    /// elements, since sorting short slices takes quadratic time. Each pivot selection strategy
+    /// needs a minimum number of elements to draw its sample from, and the strategies with larger
+    /// samples must be used for longer slices. The partition block must be between 1 and 256
+    /// elements.
+    pub const fn is_valid(&self) -> bool {
+        self.tinysort_cutoff >= MIN_TINYSORT_CUTOFF
+            && self.tinysort_cutoff <= MAX_TINYSORT_CUTOFF
+            && self.kth_of_3_max >= 24
+            && self.kth_of_5_max >= 48
+            && self.kth_of_7_max >= 96
+            && self.kth_of_3_max <= self.kth_of_5_max
+            && self.kth_of_5_max <= self.kth_of_7_max
+            && self.partition_block > 0
+            && self.partition_block <= MAX_PARTITION_BLOCK
+    }
+
+    /// Scales the thresholds from the reference element size to elements of type `T`. Sorting a
+    /// slice moves its elements more often than partitioning it, so slices of elements larger than
+    /// [`Self::REFERENCE_SIZE`] bytes are partitioned down to proportionally shorter lengths
+    /// before they are sorted.
+    pub(crate) fn sized<T>(&self) -> Self {
+        let size = core::mem::size_of::<T>().max(1);
+        let cutoff = (self.tinysort_cutoff * Self::REFERENCE_SIZE / size)
+            .clamp(MIN_TINYSORT_CUTOFF, self.tinysort_cutoff);
+        Self {
+            tinysort_cutoff: cutoff,
+            ..*self
+        }
+    }
+
+    /// The largest element size, in bytes, for which the sorting cutoff is used as it is.
+    const REFERENCE_SIZE: usize = 32;
+}
+
+#[cfg(feature = "std")]
+impl Params {
+    /// The size of the level 1 data cache the default thresholds were chosen for.
+    const REFERENCE_L1: usize = 32 << 10;
+    /// The size of the level 2 cache the default thresholds were chosen for.
+    const REFERENCE_L2: usize = 512 << 10;
+
+    /// Scales the thresholds from the reference machine to a machine with the given cache sizes
+    /// and elements of type `T`. The partition block grows and shrinks with the level 1 cache, but
+    /// the two blocks are kept within a quarter of it. The length from which the pivot is chosen
+    /// with a recursive selection from a large sample grows and shrinks with the level 2 cache.
+    pub(crate) fn scaled<T>(&self, cache: CacheSizes) -> Self {
+        let size = core::mem::size_of::<T>().max(1);
+        let block = mul_div(self.partition_block, cache.l1, Self::REFERENCE_L1)
+            .min(cache.l1 / (8 * size))
+            .clamp(16, MAX_PARTITION_BLOCK);
+        let kth_of_7_max = mul_div(self.kth_of_7_max, cache.l2, Self::REFERENCE_L2)
+            .min(16 * self.kth_of_7_max)
+            .max(self.kth_of_5_max);
+        Self {
+            kth_of_7_max,
+            partition_block: block,
+            ..*self
+        }
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The thresholds compiled into the crate. When the `autotune` feature is enabled and the
+/// `TURBOSELECT_PARAMS` environment variable points to a file generated with
+/// [`tune`](crate::tune), the build script makes the file available here.
+#[cfg(turboselect_tuned)]
+pub(crate) const PARAMS: Params = include!(concat!(env!("OUT_DIR"), "/params.rs"));
+#[cfg(not(turboselect_tuned))]
+pub(crate) const PARAMS: Params = Params::DEFAULT;
+
+const _: () = assert!(PARAMS.is_valid(), "invalid tuning parameters");
+
+/// Returns the thresholds to use for elements of type `T`. The thresholds compiled into the crate
+/// are scaled to the size of `T`, and under `std`, to the cache sizes of the processor, which are
+/// detected on the first call. Thresholds generated with [`tune`](crate::tune) are used as they
+/// are, since they were measured on the target machine.
+#[inline]
+pub(crate) fn params<T>() -> Params {
+    #[cfg(not(turboselect_tuned))]
+    let params = PARAMS.sized::<T>();
+    #[cfg(turboselect_tuned)]
+    let params = PARAMS;
+    #[cfg(all(feature = "std", not(turboselect_tuned)))]
+    if let Some(cache) = cache::sizes() {
+        return params.scaled::<T>(cache);
+    }
+    params
+}
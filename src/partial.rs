@@ -0,0 +1,471 @@
+use core::{cmp::Ordering, ops::Range};
+
+use crate::{ordering, select_nth_unstable_by_lt, select_two_ranks_by_lt};
+
+/// Moves the `k` largest elements of the slice to its back, and returns the rest of the slice and
+/// them, in that order. The order within each part is unspecified.
+///
+/// This is the counterpart of [`k_smallest`]. The largest elements go to the back rather than the
+/// front, so that the slice stays partitioned in ascending order, like after
+/// [`select_nth_unstable`](crate::select_nth_unstable) at `len() - k`. To get the largest elements
+/// at the front instead, use [`k_smallest_by`] with a reversed comparator.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_largest;
+/// let mut v = [5, 1, 8, 3, 9, 2, 7];
+/// let (rest, largest) = k_largest(&mut v, 3);
+/// largest.sort();
+/// assert_eq!(largest, [7, 8, 9]);
+/// assert_eq!(rest.len(), 4);
+/// assert_eq!(v[4..].len(), 3);
+/// ```
+pub fn k_largest<T>(data: &mut [T], k: usize) -> (&mut [T], &mut [T])
+where
+    T: Ord,
+{
+    k_largest_by_lt(data, k, T::lt)
+}
+
+/// Moves the `k` largest elements of the slice with respect to a comparator function to its back,
+/// and returns the rest of the slice and them. See [`k_largest`] for details.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_largest_by;
+/// let mut v = [0.5f64, -1.0, 2.5, 1.5, 0.0];
+/// let (_, largest) = k_largest_by(&mut v, 2, f64::total_cmp);
+/// assert!(largest.contains(&2.5) && largest.contains(&1.5));
+/// ```
+pub fn k_largest_by<T, F>(data: &mut [T], k: usize, mut compare: F) -> (&mut [T], &mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    k_largest_by_lt(data, k, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Moves the `k` elements of the slice with the largest keys to its back, and returns the rest of
+/// the slice and them. See [`k_largest`] for details.
+///
+/// The key is computed on every comparison, like in `slice::sort_unstable_by_key`.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_largest_by_key;
+/// let mut v = [-5i32, 4, 1, -3, 2];
+/// let (_, farthest) = k_largest_by_key(&mut v, 2, |a| a.abs());
+/// farthest.sort();
+/// assert_eq!(farthest, [-5, 4]);
+/// ```
+pub fn k_largest_by_key<T, K, F>(data: &mut [T], k: usize, mut f: F) -> (&mut [T], &mut [T])
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    k_largest_by_lt(data, k, |a, b| f(a).lt(&f(b)))
+}
+
+/// Moves the `k` largest elements of the slice with respect to a "less than" function to its back,
+/// and returns the rest of the slice and them.
+///
+/// This is the "less than" form of [`k_largest`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_largest_by_lt;
+/// let mut v = ["pear", "fig", "banana", "kiwi"];
+/// let (_, longest) = k_largest_by_lt(&mut v, 1, |a, b| a.len() < b.len());
+/// assert_eq!(longest, ["banana"]);
+/// ```
+pub fn k_largest_by_lt<T, F>(data: &mut [T], k: usize, lt: F) -> (&mut [T], &mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(
+        k <= data.len(),
+        "`k` must not exceed the length of the slice"
+    );
+    let split = data.len() - k;
+    if k > 0 && split > 0 {
+        select_nth_unstable_by_lt(data, split, lt);
+    }
+    data.split_at_mut(split)
+}
+
+/// Moves the `k` smallest elements of the slice to its front, and returns them and the rest of the
+/// slice, in that order. The order within each part is unspecified.
+///
+/// This is [`select_nth_unstable`](crate::select_nth_unstable) at `k - 1` with the result split
+/// into the `k` smallest elements and the others, without the index arithmetic or the special
+/// cases for `k == 0` and `k == len()`. Use [`partial_sort`] if the `k` smallest elements are
+/// needed in sorted order.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest;
+/// let mut v = [5, 1, 8, 3, 9, 2, 7];
+/// let (smallest, rest) = k_smallest(&mut v, 3);
+/// smallest.sort();
+/// assert_eq!(smallest, [1, 2, 3]);
+/// assert_eq!(rest.len(), 4);
+/// ```
+pub fn k_smallest<T>(data: &mut [T], k: usize) -> (&mut [T], &mut [T])
+where
+    T: Ord,
+{
+    k_smallest_by_lt(data, k, T::lt)
+}
+
+/// Moves the `k` smallest elements of the slice with respect to a comparator function to its front,
+/// and returns them and the rest of the slice. See [`k_smallest`] for details.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest_by;
+/// let mut v = [0.5f64, -1.0, 2.5, 1.5, 0.0];
+/// let (smallest, _) = k_smallest_by(&mut v, 2, f64::total_cmp);
+/// assert!(smallest.contains(&-1.0) && smallest.contains(&0.0));
+/// ```
+pub fn k_smallest_by<T, F>(data: &mut [T], k: usize, mut compare: F) -> (&mut [T], &mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    k_smallest_by_lt(data, k, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Moves the `k` elements of the slice with the smallest keys to its front, and returns them and
+/// the rest of the slice. See [`k_smallest`] for details.
+///
+/// The key is computed on every comparison, like in `slice::sort_unstable_by_key`.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest_by_key;
+/// let mut v = [-5i32, 4, 1, -3, 2];
+/// let (closest, _) = k_smallest_by_key(&mut v, 2, |a| a.abs());
+/// closest.sort();
+/// assert_eq!(closest, [1, 2]);
+/// ```
+pub fn k_smallest_by_key<T, K, F>(data: &mut [T], k: usize, mut f: F) -> (&mut [T], &mut [T])
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    k_smallest_by_lt(data, k, |a, b| f(a).lt(&f(b)))
+}
+
+/// Moves the `k` smallest elements of the slice with respect to a "less than" function to its
+/// front, and returns them and the rest of the slice.
+///
+/// This is the "less than" form of [`k_smallest`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest_by_lt;
+/// let mut v = ["pear", "fig", "banana", "kiwi"];
+/// let (shortest, _) = k_smallest_by_lt(&mut v, 1, |a, b| a.len() < b.len());
+/// assert_eq!(shortest, ["fig"]);
+/// ```
+pub fn k_smallest_by_lt<T, F>(data: &mut [T], k: usize, lt: F) -> (&mut [T], &mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(
+        k <= data.len(),
+        "`k` must not exceed the length of the slice"
+    );
+    if k > 0 && k < data.len() {
+        select_nth_unstable_by_lt(data, k - 1, lt);
+    }
+    data.split_at_mut(k)
+}
+
+/// Reorders the slice such that its `k` smallest elements are at the front in sorted order, and
+/// the other elements are after them in an unspecified order.
+///
+/// The `k`th smallest element is selected with
+/// [`select_nth_unstable`](crate::select_nth_unstable), after which only the elements before it
+/// are sorted, so this takes *O*(*n* + *k* log *k*) time instead of *O*(*n* log *n*) for sorting
+/// the whole slice. The sort is not stable.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partial_sort;
+/// let mut v = [5, 1, 8, 3, 9, 2, 7];
+/// partial_sort(&mut v, 3);
+/// assert_eq!(v[..3], [1, 2, 3]);
+/// assert!(v[3..].iter().all(|&x| x > 3));
+/// ```
+pub fn partial_sort<T>(data: &mut [T], k: usize)
+where
+    T: Ord,
+{
+    partial_sort_by_lt(data, k, T::lt);
+}
+
+/// Reorders the slice with a comparator function such that its `k` smallest elements are at the
+/// front in sorted order. See [`partial_sort`] for details.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partial_sort_by;
+/// let mut v = [0.5f64, -1.0, 2.5, 1.5, 0.0];
+/// partial_sort_by(&mut v, 2, |a, b| b.total_cmp(a));
+/// assert_eq!(v[..2], [2.5, 1.5]);
+/// ```
+pub fn partial_sort_by<T, F>(data: &mut [T], k: usize, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    partial_sort_by_lt(data, k, |a, b| compare(a, b) == Ordering::Less);
+}
+
+/// Reorders the slice with a key extraction function such that its `k` elements with the smallest
+/// keys are at the front, sorted by their keys. See [`partial_sort`] for details.
+///
+/// The key is computed on every comparison, like in `slice::sort_unstable_by_key`.
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partial_sort_by_key;
+/// let mut v = [-5i32, 4, 1, -3, 2];
+/// partial_sort_by_key(&mut v, 3, |a| a.abs());
+/// assert_eq!(v[..3], [1, 2, -3]);
+/// ```
+pub fn partial_sort_by_key<T, K, F>(data: &mut [T], k: usize, mut f: F)
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    partial_sort_by_lt(data, k, |a, b| f(a).lt(&f(b)));
+}
+
+/// Reorders the slice with a "less than" function such that its `k` smallest elements are at the
+/// front in sorted order.
+///
+/// This is the "less than" form of [`partial_sort`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `k > len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partial_sort_by_lt;
+/// let mut v = ["pear", "fig", "banana", "kiwi", "apple"];
+/// partial_sort_by_lt(&mut v, 2, |a, b| a.len() < b.len());
+/// assert_eq!(v[..2], ["fig", "pear"]);
+/// ```
+pub fn partial_sort_by_lt<T, F>(data: &mut [T], k: usize, mut lt: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(
+        k <= data.len(),
+        "`k` must not exceed the length of the slice"
+    );
+    if k == 0 {
+        return;
+    }
+    // The `k`th smallest element is already in place, so only the elements before it are sorted.
+    let head = match k < data.len() {
+        true => select_nth_unstable_by_lt(data, k - 1, &mut lt).0,
+        false => data,
+    };
+    head.sort_unstable_by(|a, b| ordering(a, b, &mut lt));
+}
+
+/// Reorders the slice such that the elements at the positions in `range` are at their final sorted
+/// positions, e.g. to get the middle decile in order without sorting the whole slice. Returns the
+/// elements before the range, the sorted elements in the range and the elements after it.
+///
+/// The first and the last position of the range are selected at once with
+/// [`select_two_ranks`](crate::select_two_ranks), after which only the elements between them are
+/// sorted, so this takes *O*(*n* + *m* log *m*) time, where *m* is the length of the range. The
+/// elements before the range are less than or equal to the elements in it, and the elements after
+/// it are greater than or equal to them. An empty range only partitions the slice at its start.
+///
+/// # Panics
+///
+/// Panics if `range.start > range.end` or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_range;
+/// let mut v: Vec<u32> = (0..100).map(|x| (x * 37) % 100).collect();
+/// let (below, window, above) = select_range(&mut v, 45..55);
+/// assert_eq!(window, (45..55).collect::<Vec<_>>());
+/// assert!(below.iter().all(|&x| x < 45));
+/// assert!(above.iter().all(|&x| x >= 55));
+/// ```
+pub fn select_range<T>(data: &mut [T], range: Range<usize>) -> (&mut [T], &mut [T], &mut [T])
+where
+    T: Ord,
+{
+    select_range_by_lt(data, range, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the elements at the positions in
+/// `range` are at their final sorted positions. See [`select_range`] for details.
+///
+/// # Panics
+///
+/// Panics if `range.start > range.end` or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_range_by;
+/// let mut v = [0.5f64, -1.0, 2.5, 1.5, 0.0, 3.0];
+/// let (_, window, _) = select_range_by(&mut v, 1..3, |a, b| b.total_cmp(a));
+/// assert_eq!(window, [2.5, 1.5]);
+/// ```
+pub fn select_range_by<T, F>(
+    data: &mut [T],
+    range: Range<usize>,
+    mut compare: F,
+) -> (&mut [T], &mut [T], &mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_range_by_lt(data, range, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a key extraction function such that the elements at the positions in
+/// `range` are at their final positions when sorted by their keys. See [`select_range`] for
+/// details.
+///
+/// The key is computed on every comparison, like in `slice::sort_unstable_by_key`.
+///
+/// # Panics
+///
+/// Panics if `range.start > range.end` or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_range_by_key;
+/// let mut v = [-5i32, 4, 1, -3, 2];
+/// let (_, window, _) = select_range_by_key(&mut v, 1..3, |a| a.abs());
+/// assert_eq!(window, [2, -3]);
+/// ```
+pub fn select_range_by_key<T, K, F>(
+    data: &mut [T],
+    range: Range<usize>,
+    mut f: F,
+) -> (&mut [T], &mut [T], &mut [T])
+where
+    F: FnMut(&T) -> K,
+    K: Ord,
+{
+    select_range_by_lt(data, range, |a, b| f(a).lt(&f(b)))
+}
+
+/// Reorders the slice with a "less than" function such that the elements at the positions in
+/// `range` are at their final sorted positions.
+///
+/// This is the "less than" form of [`select_range`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics if `range.start > range.end` or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_range_by_lt;
+/// let mut v: Vec<u32> = (0..1000).collect();
+///
+/// // The 6th to the 10th largest elements, from the largest.
+/// let (_, window, _) = select_range_by_lt(&mut v, 5..10, |a, b| a > b);
+/// assert_eq!(window, [994, 993, 992, 991, 990]);
+/// ```
+pub fn select_range_by_lt<T, F>(
+    data: &mut [T],
+    range: Range<usize>,
+    mut lt: F,
+) -> (&mut [T], &mut [T], &mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let Range { start, end } = range;
+    assert!(
+        start <= end && end <= data.len(),
+        "the range must be within the slice"
+    );
+    if start == end {
+        if start < data.len() {
+            select_nth_unstable_by_lt(data, start, &mut lt);
+        }
+    } else {
+        select_two_ranks_by_lt(data, start, end - 1, &mut lt);
+        // The first and the last element of the range are already in place.
+        if end - start > 2 {
+            data[start + 1..end - 1].sort_unstable_by(|a, b| ordering(a, b, &mut lt));
+        }
+    }
+    let (rest, above) = data.split_at_mut(end);
+    let (below, window) = rest.split_at_mut(start);
+    (below, window, above)
+}
@@ -0,0 +1,280 @@
+use core::{marker::PhantomData, mem};
+
+use crate::{params::params, partition_equal, select, split_partition};
+
+/// Slices of at most this length are selected by comparing the remaining bytes of the keys.
+const RADIX_CUTOFF: usize = 64;
+
+/// Slices longer than this are partitioned by a byte in blocks.
+const BLOCK_PARTITION_MIN: usize = 4096;
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, when the
+/// elements are ordered by the fixed-size byte-string keys that `f` returns, e.g. hashes, UUIDs or
+/// encoded sort keys. The keys are compared lexicographically, like `[u8; N]` is ordered.
+///
+/// Instead of comparing whole keys, the selection looks at one byte of the keys at a time, starting
+/// from the first byte. At each level, it counts the elements for each byte value, finds the byte
+/// value of the element at `index`, and partitions the slice into the elements with a smaller byte,
+/// an equal byte, and a larger byte. Only the middle part is processed further, at the next byte.
+/// When the middle part is small, the remaining bytes of the keys are compared directly.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_by_byte_key;
+/// let mut ids: Vec<[u8; 4]> = vec![*b"beta", *b"alfa", *b"echo", *b"alto", *b"delt"];
+/// let (_, nth, _) = select_nth_unstable_by_byte_key(&mut ids, 1, |id| id);
+/// assert_eq!(nth, b"alto");
+/// ```
+pub fn select_nth_unstable_by_byte_key<T, F, const N: usize>(
+    data: &mut [T],
+    index: usize,
+    mut f: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T) -> &[u8; N],
+{
+    assert!(index < data.len());
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, index);
+    }
+    radix_select(data, index, &mut Borrowed(&mut f));
+    split_partition(data, index)
+}
+
+/// Keys that can be converted to a byte string that is ordered like the key, for selecting with
+/// [`select_nth_unstable_by_radix_key`].
+///
+/// The bytes are always big-endian, regardless of the byte order of the target, so that the most
+/// significant byte comes first. Signed integers have their sign bit flipped, so that negative
+/// numbers come before positive ones. Floats are ordered like [`f64::total_cmp`]: the bits of
+/// negative floats are inverted and the sign bit of positive floats is set, so that `-NaN` comes
+/// first and `NaN` last, and `-0.0` comes before `0.0`.
+pub trait RadixKey<const N: usize> {
+    /// Returns the bytes of the key in an order-preserving form.
+    fn radix_bytes(&self) -> [u8; N];
+}
+
+macro_rules! impl_radix_key_unsigned {
+    ($($int:ty),*) => {
+        $(
+            impl RadixKey<{ mem::size_of::<$int>() }> for $int {
+                fn radix_bytes(&self) -> [u8; mem::size_of::<$int>()] {
+                    self.to_be_bytes()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_radix_key_signed {
+    ($($int:ty => $unsigned:ty),*) => {
+        $(
+            impl RadixKey<{ mem::size_of::<$int>() }> for $int {
+                fn radix_bytes(&self) -> [u8; mem::size_of::<$int>()] {
+                    ((*self as $unsigned) ^ (1 << (<$unsigned>::BITS - 1))).to_be_bytes()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_radix_key_float {
+    ($($float:ty => $unsigned:ty),*) => {
+        $(
+            impl RadixKey<{ mem::size_of::<$float>() }> for $float {
+                fn radix_bytes(&self) -> [u8; mem::size_of::<$float>()] {
+                    let bits = self.to_bits();
+                    let sign = 1 << (<$unsigned>::BITS - 1);
+                    let bits = if bits & sign != 0 { !bits } else { bits | sign };
+                    bits.to_be_bytes()
+                }
+            }
+        )*
+    };
+}
+
+impl_radix_key_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+impl_radix_key_float!(f32 => u32, f64 => u64);
+
+impl RadixKey<1> for bool {
+    fn radix_bytes(&self) -> [u8; 1] {
+        [*self as u8]
+    }
+}
+
+impl RadixKey<4> for char {
+    fn radix_bytes(&self) -> [u8; 4] {
+        (*self as u32).to_be_bytes()
+    }
+}
+
+impl<const N: usize> RadixKey<N> for [u8; N] {
+    fn radix_bytes(&self) -> [u8; N] {
+        *self
+    }
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, when the
+/// elements are ordered by the keys that `f` returns, selecting one byte of the keys at a time.
+///
+/// The keys are converted to byte strings with [`RadixKey`], which orders them like the keys on
+/// both little- and big-endian targets, and selected like with
+/// [`select_nth_unstable_by_byte_key`]. Floats are ordered like [`f64::total_cmp`].
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_by_radix_key;
+/// let mut v: Vec<(f64, &str)> = vec![(0.5, "a"), (-2.0, "b"), (3.25, "c"), (-0.0, "d")];
+/// let (_, nth, _) = select_nth_unstable_by_radix_key(&mut v, 1, |x| x.0);
+/// assert_eq!(nth.1, "d");
+/// ```
+pub fn select_nth_unstable_by_radix_key<T, K, F, const N: usize>(
+    data: &mut [T],
+    index: usize,
+    mut f: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    K: RadixKey<N>,
+    F: FnMut(&T) -> K,
+{
+    assert!(index < data.len());
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, index);
+    }
+    radix_select(data, index, &mut Computed(&mut f, PhantomData));
+    split_partition(data, index)
+}
+
+/// Reads the bytes of the keys of the elements, either from keys that the elements hold or from keys
+/// computed on the fly, which lets the keys that are held be read without copying them.
+trait ByteKeys<T, const N: usize> {
+    /// Returns the byte of the key of `elem` at `depth`.
+    fn byte(&mut self, elem: &T, depth: usize) -> u8;
+
+    /// Returns `true` if the key of `a` from `depth` on is less than the key of `b` from `depth` on.
+    fn lt_from(&mut self, a: &T, b: &T, depth: usize) -> bool;
+}
+
+/// The keys of [`select_nth_unstable_by_byte_key`], borrowed from the elements.
+struct Borrowed<'a, F>(&'a mut F);
+
+impl<T, F, const N: usize> ByteKeys<T, N> for Borrowed<'_, F>
+where
+    F: FnMut(&T) -> &[u8; N],
+{
+    #[inline]
+    fn byte(&mut self, elem: &T, depth: usize) -> u8 {
+        (self.0)(elem)[depth]
+    }
+
+    #[inline]
+    fn lt_from(&mut self, a: &T, b: &T, depth: usize) -> bool {
+        (self.0)(a)[depth..] < (self.0)(b)[depth..]
+    }
+}
+
+/// The keys of [`select_nth_unstable_by_radix_key`], converted to bytes when they are read.
+struct Computed<'a, F, K>(&'a mut F, PhantomData<fn() -> K>);
+
+impl<T, K, F, const N: usize> ByteKeys<T, N> for Computed<'_, F, K>
+where
+    K: RadixKey<N>,
+    F: FnMut(&T) -> K,
+{
+    #[inline]
+    fn byte(&mut self, elem: &T, depth: usize) -> u8 {
+        (self.0)(elem).radix_bytes()[depth]
+    }
+
+    #[inline]
+    fn lt_from(&mut self, a: &T, b: &T, depth: usize) -> bool {
+        (self.0)(a).radix_bytes()[depth..] < (self.0)(b).radix_bytes()[depth..]
+    }
+}
+
+/// Selects the element at `index` by the keys that `keys` reads, one byte at a time.
+fn radix_select<T, B, const N: usize>(data: &mut [T], index: usize, keys: &mut B)
+where
+    B: ByteKeys<T, N>,
+{
+    let (mut data, mut index, mut depth) = (data, index, 0);
+    while depth < N {
+        if data.len() <= RADIX_CUTOFF {
+            select(data, index, &mut |a, b| keys.lt_from(a, b, depth));
+            return;
+        }
+
+        // Count the elements for each byte value, and find the byte value of the element at
+        // `index`, along with the number of elements with a smaller byte.
+        let mut counts = [0; 256];
+        for elem in data.iter() {
+            counts[keys.byte(elem, depth) as usize] += 1;
+        }
+        let (mut byte, mut less) = (0, 0);
+        while less + counts[byte] <= index {
+            less += counts[byte];
+            byte += 1;
+        }
+        let equal = counts[byte];
+
+        // If all elements have the same byte, there is nothing to partition. Longer slices are
+        // partitioned in blocks like by the comparison-based selection, which avoids branching on
+        // the byte of every element but costs more to set up.
+        if equal < data.len() {
+            let byte = byte as u8;
+            if data.len() <= BLOCK_PARTITION_MIN {
+                partition_by_byte(data, depth, byte, keys);
+            } else {
+                let pivot = data
+                    .iter()
+                    .position(|elem| keys.byte(elem, depth) == byte)
+                    .unwrap();
+                let lt = &mut |a: &T, b: &T| keys.byte(a, depth) < keys.byte(b, depth);
+                partition_equal(data, pivot, lt, params::<T>().partition_block);
+            }
+        }
+        data = &mut data[less..less + equal];
+        index -= less;
+        depth += 1;
+    }
+}
+
+/// Partitions `data` into the elements whose key has a byte less than `byte` at `depth`, followed
+/// by the elements with a byte equal to it, followed by the elements with a greater byte.
+fn partition_by_byte<T, B, const N: usize>(data: &mut [T], depth: usize, byte: u8, keys: &mut B)
+where
+    B: ByteKeys<T, N>,
+{
+    let (mut l, mut m, mut r) = (0, 0, data.len());
+    while m < r {
+        match keys.byte(&data[m], depth) {
+            b if b < byte => {
+                data.swap(l, m);
+                l += 1;
+                m += 1;
+            }
+            b if b > byte => {
+                r -= 1;
+                data.swap(m, r);
+            }
+            _ => m += 1,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use core::cmp::Ordering;
+
+use crate::{params::params, partition_in_blocks};
+
+/// Partitions the slice into the elements less than `low`, the elements between `low` and `high`
+/// inclusive, and the elements greater than `high`, and returns the three parts.
+///
+/// This generalizes partitioning around a single pivot to a "fat" pivot covering a range of values,
+/// e.g. for splitting data into bands or filtering it by a range of values. The slice is
+/// partitioned in blocks, like around a single pivot, but the elements below the range are not
+/// scanned again. Within the parts, the elements are in no particular order. If `low > high`, the
+/// middle part is empty.
+///
+/// This takes *O*(*n*) time and makes at most 2*n* comparisons.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partition_by_range;
+/// let mut v = [9, 2, 7, 4, 5, 1, 8, 3, 6];
+/// let (below, within, above) = partition_by_range(&mut v, &3, &6);
+/// assert!(below.iter().all(|&x| x < 3));
+/// assert_eq!(within.len(), 4);
+/// assert!(within.iter().all(|&x| (3..=6).contains(&x)));
+/// assert!(above.iter().all(|&x| x > 6));
+/// ```
+pub fn partition_by_range<'a, T>(
+    data: &'a mut [T],
+    low: &T,
+    high: &T,
+) -> (&'a mut [T], &'a mut [T], &'a mut [T])
+where
+    T: Ord,
+{
+    partition_by_range_by(data, low, high, T::cmp)
+}
+
+/// Partitions the slice with a comparator function into the elements less than `low`, the
+/// elements between `low` and `high` inclusive, and the elements greater than `high`, and returns
+/// the three parts.
+///
+/// See [`partition_by_range`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partition_by_range_by;
+/// let mut v = [0.5, -1.0, 2.5, 1.5, f64::NAN];
+/// let (below, within, above) = partition_by_range_by(&mut v, &0.0, &2.0, f64::total_cmp);
+/// assert_eq!(below, [-1.0]);
+/// assert_eq!(within.len(), 2);
+/// assert_eq!(above.len(), 2);
+/// ```
+pub fn partition_by_range_by<'a, T, F>(
+    data: &'a mut [T],
+    low: &T,
+    high: &T,
+    mut compare: F,
+) -> (&'a mut [T], &'a mut [T], &'a mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    partition_by_range_by_lt(data, low, high, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Partitions the slice with a "less than" function into the elements less than `low`, the
+/// elements between `low` and `high` inclusive, and the elements greater than `high`, and returns
+/// the three parts.
+///
+/// This is the "less than" form of [`partition_by_range`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::partition_by_range_by_lt;
+/// let mut v = ["pear", "fig", "apple", "kiwi", "banana"];
+/// let (below, within, above) = partition_by_range_by_lt(&mut v, &"kiwi", &"pear", |a, b| {
+///     a.len() < b.len()
+/// });
+/// assert_eq!(below, ["fig"]);
+/// assert_eq!(within.len(), 2);
+/// assert_eq!(above.len(), 2);
+/// ```
+pub fn partition_by_range_by_lt<'a, T, F>(
+    data: &'a mut [T],
+    low: &T,
+    high: &T,
+    mut lt: F,
+) -> (&'a mut [T], &'a mut [T], &'a mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let block = params::<T>().partition_block;
+    let (u, v) = partition_range(data, low, high, &mut lt, block);
+    let (below, rest) = data.split_at_mut(u);
+    let (within, above) = rest.split_at_mut(v - u);
+    (below, within, above)
+}
+
+/// Partitions `data` into three parts: elements less than `low`, elements not less than `low` and
+/// not greater than `high`, and elements greater than `high`. Returns `(u, v)`, where `u` is the
+/// number of elements less than `low`, and `v` is `u` plus the number of elements in the middle.
+/// The slice is scanned `block` elements at a time from each side.
+///
+/// The resulting partitioning is:
+///
+/// ```text
+/// ┌─────────┬──────────────────┬──────────┐
+/// │ x < low │ low <= x <= high │ x > high │
+/// └─────────┴──────────────────┴──────────┘
+///            u                  v
+/// ```
+///
+/// Like `partition_equal`, this first splits off the elements less than `low` and then partitions
+/// the rest. Classifying the elements into all three parts in a single branchless pass was slower,
+/// since every element has to be moved, while the block partitioning only moves the elements on
+/// the wrong side.
+pub(crate) fn partition_range<T, F>(
+    data: &mut [T],
+    low: &T,
+    high: &T,
+    lt: &mut F,
+    block: usize,
+) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let u = partition_in_blocks(data, low, lt, block);
+    let v = u + partition_in_blocks(&mut data[u..], high, &mut |x, y| !lt(y, x), block);
+    (u, v)
+}
@@ -0,0 +1,95 @@
+use std::vec::Vec;
+
+use crate::select_nth_unstable;
+
+/// Reorders the fixed-width records in `data` such that the record at `index` is at its final
+/// sorted position, when the records are ordered by the keys that `f` extracts from them. Returns
+/// the bytes of the records before `index`, the record at `index`, and the bytes of the records
+/// after `index`.
+///
+/// `data` is treated as a sequence of `record_size`-byte records, e.g. the rows of a page in a
+/// storage engine. The keys are extracted once per record and cached together with the record
+/// positions, like in [`select_nth_unstable_by_cached_key`](crate::select_nth_unstable_by_cached_key).
+/// After the selection, the records are moved to their new positions by swapping them in place, so
+/// no record is copied out of the buffer. Ties between equal keys are broken by the original
+/// position of the records, so the record at `index` is the same as after a stable sort.
+///
+/// # Panics
+///
+/// Panics if `record_size == 0`, if `data.len()` is not a multiple of `record_size`, or if `index`
+/// is not less than the number of records.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_record_unstable;
+/// // Records of a one-byte tag followed by a little-endian `u16` key.
+/// let mut page = [
+///     b'a', 30, 0,
+///     b'b', 10, 0,
+///     b'c', 0, 1,
+///     b'd', 20, 0,
+/// ];
+/// let key = |record: &[u8]| u16::from_le_bytes([record[1], record[2]]);
+/// let (_, record, _) = select_nth_record_unstable(&mut page, 3, 1, key);
+/// assert_eq!(record, &[b'd', 20, 0]);
+/// ```
+pub fn select_nth_record_unstable<K, F>(
+    data: &mut [u8],
+    record_size: usize,
+    index: usize,
+    mut f: F,
+) -> (&mut [u8], &mut [u8], &mut [u8])
+where
+    K: Ord,
+    F: FnMut(&[u8]) -> K,
+{
+    assert!(record_size > 0, "the record size must be positive");
+    assert!(
+        data.len().is_multiple_of(record_size),
+        "the length of the data must be a multiple of the record size"
+    );
+    let count = data.len() / record_size;
+    assert!(index < count);
+
+    // Helper macro for indexing the records by the smallest possible type, to reduce allocation.
+    macro_rules! select_nth_record {
+        ($t:ty) => {{
+            let mut keys: Vec<_> = data
+                .chunks_exact(record_size)
+                .map(&mut f)
+                .enumerate()
+                .map(|(i, k)| (k, i as $t))
+                .collect();
+            select_nth_unstable(&mut keys, index);
+            for i in 0..count {
+                let mut j = keys[i].1;
+                while (j as usize) < i {
+                    j = keys[j as usize].1;
+                }
+                keys[i].1 = j;
+                swap_records(data, record_size, i, j as usize);
+            }
+        }};
+    }
+
+    if count > 1 {
+        if count <= u32::MAX as usize {
+            select_nth_record!(u32)
+        } else {
+            select_nth_record!(usize)
+        }
+    }
+
+    let (left, rest) = data.split_at_mut(index * record_size);
+    let (record, right) = rest.split_at_mut(record_size);
+    (left, record, right)
+}
+
+/// Swaps the `i`th and `j`th records of `data`, where `i <= j`.
+fn swap_records(data: &mut [u8], record_size: usize, i: usize, j: usize) {
+    if i < j {
+        let (head, tail) = data.split_at_mut(j * record_size);
+        head[i * record_size..(i + 1) * record_size].swap_with_slice(&mut tail[..record_size]);
+    }
+}
@@ -0,0 +1,285 @@
+use core::{cmp::Ordering, ops::ControlFlow};
+use std::vec::Vec;
+
+use crate::{
+    select_nth_unstable_by,
+    wyrand::{thread_rng, WyRng},
+};
+
+/// Returns the Theil–Sen estimate of the slope of a line fitted to `points`, i.e. the median of
+/// the slopes of the lines through all pairs of points with different `x` coordinates. If there
+/// is an even number of such pairs, returns the mean of the two middle slopes. Returns `None` if
+/// all points have the same `x` coordinate.
+///
+/// The *O*(*n*²) slopes are never computed all at once. Instead, the slope of a pair of points
+/// `i` and `j` with `x[i] < x[j]` is less than `t` exactly when the points are in the opposite
+/// order by `y - t * x` than by `x`. The number of slopes less than `t` is therefore the number of
+/// inversions in the order of the points by `y - t * x`, which is counted in *O*(*n* log *n*) time
+/// with a merge sort. The median is found with a randomized selection over the implicit set of
+/// slopes: in each round, a random sample of the slopes between the current bounds is drawn by
+/// sampling inversions, and the bounds are narrowed to two sampled slopes just below and above the
+/// relative rank of the median, like in the Floyd–Rivest algorithm. When few slopes
+/// remain between the bounds, they are computed and selected from directly. This is a simpler
+/// variant of the algorithm by Matoušek, and takes *O*(*n* log² *n*) expected time.
+///
+/// The intercept of the fitted line can be estimated with the
+/// median of `y - slope * x` over the points.
+///
+/// # Panics
+///
+/// Panics if any of the coordinates is not finite.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::theil_sen;
+/// // Points on the line y = 2x + 1, with one outlier.
+/// let points = [(0.0, 1.0), (1.0, 3.0), (2.0, 5.0), (3.0, 100.0), (4.0, 9.0)];
+/// assert_eq!(theil_sen(&points), Some(2.0));
+/// ```
+pub fn theil_sen(points: &[(f64, f64)]) -> Option<f64> {
+    assert!(points.iter().all(|p| p.0.is_finite() && p.1.is_finite()));
+    let mut points = points.to_vec();
+    points.sort_unstable_by(|a, b| cmp(a.0, b.0).then(cmp(a.1, b.1)));
+
+    // Count the pairs of points with different `x` coordinates.
+    let n = points.len();
+    let mut total = n * n.saturating_sub(1) / 2;
+    for group in points.chunk_by(|a, b| a.0 == b.0) {
+        total -= group.len() * (group.len() - 1) / 2;
+    }
+    if total == 0 {
+        return None;
+    }
+
+    let mut slopes = Slopes::new(&points, total);
+    let upper = slopes.kth(total / 2);
+    if total % 2 == 1 {
+        Some(upper)
+    } else {
+        Some((slopes.kth(total / 2 - 1) + upper) / 2.)
+    }
+}
+
+/// The number of slopes sampled to choose the pivots.
+const SAMPLE: usize = 256;
+
+/// The distance of the pivots from the relative rank of the selected slope in the sample.
+const GAP: usize = 16;
+
+/// The implicit set of slopes between pairs of points.
+struct Slopes<'a> {
+    /// The points, sorted by `x` and then by `y`.
+    points: &'a [(f64, f64)],
+    /// The number of pairs of points with different `x` coordinates.
+    total: usize,
+    /// A scratch buffer for the merge sort.
+    buf: Vec<u32>,
+    rng: WyRng,
+}
+
+impl<'a> Slopes<'a> {
+    fn new(points: &'a [(f64, f64)], total: usize) -> Self {
+        Self {
+            points,
+            total,
+            buf: Vec::new(),
+            rng: thread_rng(),
+        }
+    }
+
+    /// Returns the `k`th smallest slope.
+    fn kth(&mut self, k: usize) -> f64 {
+        let n = self.points.len();
+
+        // The slopes between the bounds are the pairs of points that are in a different order in
+        // `lo` than in `hi`. At first, `lo` is the order by `x`, which has no inversions, and `hi`
+        // is the order by descending `x`, where all pairs with different `x` are inverted.
+        let mut lo: Vec<u32> = (0..n as u32).collect();
+        let mut lo_count = 0;
+        let mut hi = lo.clone();
+        hi.sort_by(|&i, &j| {
+            let (a, b) = (self.points[i as usize], self.points[j as usize]);
+            cmp(b.0, a.0).then(i.cmp(&j))
+        });
+        let mut hi_count = self.total;
+        let mut t = f64::NAN;
+
+        loop {
+            // Rounding errors in the keys can make the bounds inconsistent with each other, in
+            // which case `hi_count - lo_count` is not exactly the number of slopes between them.
+            // If the bounds stop narrowing, the slopes between them are selected from directly.
+            let count = hi_count.saturating_sub(lo_count);
+            if count <= n {
+                return self.select_between(&lo, &hi, k - lo_count).unwrap_or(t);
+            }
+
+            // Draw a random sample of the slopes between the bounds, and use the sampled slopes a
+            // little below and above the relative rank of `k` as pivots, so that the result is
+            // likely to be between them.
+            let mut ranks: Vec<usize> = (0..SAMPLE)
+                .map(|_| self.rng.bounded_usize(0, count))
+                .collect();
+            ranks.sort_unstable();
+            let mut sample = Vec::with_capacity(SAMPLE);
+            let mut base = 0;
+            self.between(&lo, &hi, &mut |greater, x| {
+                while sample.len() < ranks.len() && ranks[sample.len()] < base + greater.len() {
+                    sample.push((greater[ranks[sample.len()] - base], x));
+                }
+                base += greater.len();
+                match sample.len() < ranks.len() {
+                    true => ControlFlow::Continue(()),
+                    false => ControlFlow::Break(()),
+                }
+            });
+            if sample.len() < SAMPLE {
+                return self.select_between(&lo, &hi, k - lo_count).unwrap_or(t);
+            }
+            let mut sample: Vec<f64> = sample
+                .into_iter()
+                .map(|(g, x)| self.slope(hi[g as usize], hi[x as usize]))
+                .collect();
+            sample.sort_unstable_by(f64::total_cmp);
+            let index = (k - lo_count) * SAMPLE / count;
+            let pivots = [
+                sample[index.saturating_sub(GAP)],
+                sample[(index + GAP).min(SAMPLE - 1)],
+            ];
+
+            // Narrow the bounds with the lower pivot first. If the result is less than the lower
+            // pivot, it is less than the upper pivot as well.
+            for pivot in pivots {
+                t = pivot;
+                let less = self.order(t, false);
+                let less_count = self.inversions(&less);
+                if k < less_count {
+                    (hi, hi_count) = (less, less_count);
+                    break;
+                }
+                let less_or_equal = self.order(t, true);
+                let less_or_equal_count = self.inversions(&less_or_equal);
+                if k < less_or_equal_count {
+                    return t;
+                }
+                (lo, lo_count) = (less_or_equal, less_or_equal_count);
+            }
+        }
+    }
+
+    /// Computes the slopes between the bounds `lo` and `hi`, and returns the one at `index`, or
+    /// the largest one if there are fewer slopes than expected. Returns `None` if there are no
+    /// slopes between the bounds.
+    fn select_between(&mut self, lo: &[u32], hi: &[u32], index: usize) -> Option<f64> {
+        let mut pairs = Vec::new();
+        self.between(lo, hi, &mut |greater, x| {
+            pairs.extend(greater.iter().map(|&g| (g, x)));
+            ControlFlow::Continue(())
+        });
+        let mut slopes: Vec<f64> = pairs
+            .into_iter()
+            .map(|(g, x)| self.slope(hi[g as usize], hi[x as usize]))
+            .collect();
+        let index = index.min(slopes.len().checked_sub(1)?);
+        Some(*select_nth_unstable_by(&mut slopes, index, f64::total_cmp).1)
+    }
+
+    /// Returns the slope of the line through the points `i` and `j`.
+    fn slope(&self, i: u32, j: u32) -> f64 {
+        let (a, b) = (self.points[i as usize], self.points[j as usize]);
+        (b.1 - a.1) / (b.0 - a.0)
+    }
+
+    /// Returns the indices of the points ordered by `y - t * x`. Ties are broken by the index, so
+    /// the pairs that are inverted in the order are those with a slope less than `t`. If
+    /// `or_equal` is `true`, ties between points with different `x` are broken by descending `x`
+    /// instead, so that the pairs with a slope equal to `t` are inverted as well.
+    fn order(&self, t: f64, or_equal: bool) -> Vec<u32> {
+        let mut keys: Vec<(f64, f64, u32)> = (self.points.iter().zip(0..))
+            .map(|(&(x, y), i)| (y - t * x, x, i))
+            .collect();
+        if or_equal {
+            keys.sort_unstable_by(|a, b| cmp(a.0, b.0).then(cmp(b.1, a.1)).then(a.2.cmp(&b.2)));
+        } else {
+            keys.sort_unstable_by(|a, b| cmp(a.0, b.0).then(a.2.cmp(&b.2)));
+        }
+        keys.into_iter().map(|(_, _, i)| i).collect()
+    }
+
+    /// Returns the number of inversions in `order`, i.e. the number of slopes that are less than
+    /// (or equal to) the value that `order` was created with.
+    fn inversions(&mut self, order: &[u32]) -> usize {
+        let mut values = order.to_vec();
+        let mut count = 0;
+        let _ = merge_inversions(&mut values, &mut self.buf, &mut |greater, _| {
+            count += greater.len();
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    /// Calls `f` for the pairs of points that are in a different order in `lo` than in `hi`, as
+    /// described in `merge_inversions`. The points are identified by their positions in `hi`.
+    fn between<F>(&mut self, lo: &[u32], hi: &[u32], f: &mut F)
+    where
+        F: FnMut(&[u32], u32) -> ControlFlow<()>,
+    {
+        let mut position = std::vec![0; hi.len()];
+        for (p, &i) in hi.iter().enumerate() {
+            position[i as usize] = p as u32;
+        }
+        let mut values: Vec<u32> = lo.iter().map(|&i| position[i as usize]).collect();
+        let _ = merge_inversions(&mut values, &mut self.buf, f);
+    }
+}
+
+/// Compares two numbers, treating `-0.0` as equal to `0.0`. Keys that overflowed to NaN compare
+/// equal to everything.
+fn cmp(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Sorts the distinct `values` with a merge sort. Whenever an element `x` of a right half is
+/// merged before some elements of the left half, or inserted before some elements in the insertion
+/// sort of a short run, calls `f(greater, x)`, where `greater` are those elements. Every inversion in `values` is reported exactly once. Stops early if `f` returns
+/// `ControlFlow::Break`.
+fn merge_inversions<F>(values: &mut [u32], buf: &mut Vec<u32>, f: &mut F) -> ControlFlow<()>
+where
+    F: FnMut(&[u32], u32) -> ControlFlow<()>,
+{
+    let len = values.len();
+    if len <= 16 {
+        // Insertion sort. The elements greater than the inserted one are at the end of the sorted
+        // prefix.
+        for i in 1..len {
+            let x = values[i];
+            let mut pos = i;
+            while pos > 0 && values[pos - 1] > x {
+                pos -= 1;
+            }
+            if pos < i {
+                f(&values[pos..i], x)?;
+                values[pos..=i].rotate_right(1);
+            }
+        }
+        return ControlFlow::Continue(());
+    }
+    let mid = len / 2;
+    merge_inversions(&mut values[..mid], buf, f)?;
+    merge_inversions(&mut values[mid..], buf, f)?;
+
+    buf.clear();
+    buf.extend_from_slice(&values[..mid]);
+    let (mut i, mut j) = (0, mid);
+    while i < buf.len() {
+        if j < len && values[j] < buf[i] {
+            f(&buf[i..], values[j])?;
+            values[i + j - mid] = values[j];
+            j += 1;
+        } else {
+            values[i + j - mid] = buf[i];
+            i += 1;
+        }
+    }
+    ControlFlow::Continue(())
+}
@@ -0,0 +1,165 @@
+use core::{cmp::Ordering, mem, ops::Range};
+
+use crate::{
+    params::params, partition_equal_min, select_max, select_min, sort::tinyselect, sort_order,
+    split_partition, BadRounds, BlockPartition, PartitionStrategy, SortOrder,
+};
+
+/// Reorders the slice such that some element whose rank is in `range` is at its final sorted
+/// position, and returns the partitioned slice around it.
+///
+/// Unlike [`select_nth_unstable`](crate::select_nth_unstable), which has to find the element at
+/// one exact index, this stops as soon as the pivot of a partitioning round lands inside `range`.
+/// The wider the range, the fewer rounds are needed, so e.g. an approximate median that may be
+/// anywhere between the 45th and 55th percentiles is usually found in a single round. The position
+/// of the returned element is the length of the first subslice. Of the valid positions, the one
+/// closest to the middle of `range` is chosen.
+///
+/// This takes *O*(*n*) time on average, like the exact selection.
+///
+/// # Panics
+///
+/// Panics if `range` is empty or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_rank_in;
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+/// let (left, median, right) = select_rank_in(&mut v, 450..550);
+/// assert!((450..550).contains(&left.len()));
+/// assert!(left.iter().all(|x| x <= median));
+/// assert!(right.iter().all(|x| x >= median));
+/// ```
+pub fn select_rank_in<T>(data: &mut [T], range: Range<usize>) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Ord,
+{
+    select_rank_in_by(data, range, T::cmp)
+}
+
+/// Reorders the slice with a comparator function such that some element whose rank is in `range`
+/// is at its final sorted position, and returns the partitioned slice around it.
+///
+/// See [`select_rank_in`] for details.
+///
+/// # Panics
+///
+/// Panics if `range` is empty or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_rank_in_by;
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// // Any of the ten largest elements.
+/// let (left, _, _) = select_rank_in_by(&mut v, 0..10, |a, b| b.cmp(a));
+/// assert!(left.len() < 10);
+/// ```
+pub fn select_rank_in_by<T, F>(
+    data: &mut [T],
+    range: Range<usize>,
+    mut compare: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_rank_in_by_lt(data, range, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that some element whose rank is in `range`
+/// is at its final sorted position, and returns the partitioned slice around it.
+///
+/// This is the "less than" form of [`select_rank_in`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics if `range` is empty or `range.end > data.len()`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_rank_in_by_lt;
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// // Any of the ten largest elements.
+/// let (left, _, _) = select_rank_in_by_lt(&mut v, 0..10, |a, b| a > b);
+/// assert!(left.len() < 10);
+/// ```
+pub fn select_rank_in_by_lt<T, F>(
+    data: &mut [T],
+    range: Range<usize>,
+    mut lt: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(range.start < range.end && range.end <= data.len());
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, range.start);
+    }
+    let index = rank_in(data, range, &mut lt);
+    split_partition(data, index)
+}
+
+/// Partitions `data` around an element whose final position is in `range` and returns that
+/// position. The pivots are chosen for the middle of the range, but the search stops at the first
+/// pivot that falls anywhere inside it.
+fn rank_in<T, F>(mut data: &mut [T], range: Range<usize>, lt: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let params = params::<T>();
+    let partition = BlockPartition {
+        block: Some(params.partition_block),
+    };
+    let (mut lo, mut hi) = (range.start, range.end);
+    let mut offset = 0;
+    let mut previous_pivot: Option<&T> = None;
+    let mut bad_rounds = BadRounds::default();
+
+    match sort_order(data, lt) {
+        SortOrder::Ascending => return lo + (hi - lo) / 2,
+        SortOrder::Descending => {
+            data.reverse();
+            return lo + (hi - lo) / 2;
+        }
+        SortOrder::Unsorted => {}
+    }
+
+    while data.len() > params.tinysort_cutoff {
+        let len = data.len();
+        let mid = lo + (hi - lo) / 2;
+        let (u, v) = if lo == 0 {
+            select_min(data, lt)
+        } else if hi == data.len() {
+            select_max(data, lt)
+        } else {
+            let (p, is_repeated) = bad_rounds.choose_pivot(data, mid, lt, &params);
+            match previous_pivot {
+                // The pivot is equal to a previous pivot from the left, so it is the minimum.
+                Some(was) if !lt(was, &data[p]) => partition_equal_min(data, p, lt),
+                _ => partition.partition(data, p, is_repeated, lt),
+            }
+        };
+
+        // Every position in `u..=v` holds an element equal to the pivot, so any of them that is
+        // inside the range will do.
+        let (first, last) = (u.max(lo), v.min(hi - 1));
+        if first <= last {
+            return offset + mid.clamp(first, last);
+        }
+        if hi <= u {
+            data = data[..u].as_mut();
+        } else {
+            let (head, tail) = data.split_at_mut(v + 1);
+            (data, previous_pivot) = (tail, head.last());
+            offset += v + 1;
+            (lo, hi) = (lo - (v + 1), hi - (v + 1));
+        }
+        bad_rounds.count(len, data.len());
+    }
+    let mid = lo + (hi - lo) / 2;
+    tinyselect(data, mid, lt);
+    offset + mid
+}
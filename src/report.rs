@@ -0,0 +1,387 @@
+#[cfg(feature = "std")]
+use core::{cmp::Ordering, mem};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::{
+    select_max, select_min, select_partitioned, split_partition, BlockPartition, SelectOptions,
+    Strategy,
+};
+
+/// How a round of the selection narrowed down the slice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundKind {
+    /// The index was the first one, so the minimum was moved to the front of the slice.
+    Minimum,
+    /// The index was the last one, so the maximum was moved to the back of the slice.
+    Maximum,
+    /// The index was among the first or the last few, so the elements up to it, or from it, were
+    /// collected in a heap in a single pass over the slice, which ended the selection.
+    Heap,
+    /// The pivot was equal to a previous pivot, which makes it the minimum of the slice. The
+    /// elements equal to it were moved to the front of the slice.
+    EqualToPrevious,
+    /// The slice was partitioned around a single pivot. `repeated` is `true` if the pivot was
+    /// likely to have many duplicates.
+    Pivot {
+        /// Whether the pivot was likely to have many duplicates.
+        repeated: bool,
+    },
+    /// The slice was partitioned in three parts around two pivots, like in the Floyd–Rivest
+    /// algorithm.
+    TwoPivots,
+}
+
+/// A single partitioning round of the selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Round {
+    /// The length of the subslice that was partitioned.
+    pub len: usize,
+    /// The index that was selected within the subslice.
+    pub index: usize,
+    /// How the subslice was partitioned.
+    pub kind: RoundKind,
+    /// The number of elements put before the middle part.
+    pub below: usize,
+    /// The number of elements in the middle part, i.e. the elements equal to the pivot, or the
+    /// elements between the two pivots.
+    pub middle: usize,
+    /// `true` if the index fell in the middle part. With one pivot, this ends the selection. With
+    /// two pivots, the selection continues in the middle part.
+    pub bracketed: bool,
+}
+
+/// Receives the events of a selection. The selection algorithm is generic over the observer, so
+/// that the `()` observer compiles to nothing.
+pub(crate) trait Observer {
+    /// The slice was found to be sorted in ascending or descending order.
+    fn presorted(&mut self) {}
+    /// The slice was found to be partitioned at the index already.
+    fn partitioned(&mut self) {}
+    /// A partitioning round was completed.
+    fn round(&mut self, _round: Round) {}
+    /// The remaining subslice of length `len` was sorted.
+    fn sorted(&mut self, _len: usize) {}
+}
+
+impl Observer for () {}
+
+/// A report of what happened during a single call to [`select_nth_unstable_with_report`].
+///
+/// The report is meant for finding out why the selection is slow on a particular input, e.g.
+/// because the pivots are often far from the selected element, which shows as rounds that remove
+/// only a small part of the slice. Only the rounds on the slice itself are reported, not the
+/// selections from the samples that the pivots are chosen from.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelectReport {
+    /// The strategy used to choose the pivots.
+    pub strategy: Strategy,
+    /// `true` if a slice was found to be sorted in ascending or descending order, which ended the
+    /// selection early.
+    pub presorted: bool,
+    /// `true` if a slice was found to be partitioned at the index already, which ended the
+    /// selection early.
+    pub already_partitioned: bool,
+    /// The partitioning rounds in the order they were run.
+    pub rounds: Vec<Round>,
+    /// The length of the short subslice that was sorted, or selected from by insertion, at the
+    /// end, or zero if the selection ended without it.
+    pub sorted_len: usize,
+    /// The number of comparisons made, including the ones made while choosing the pivots.
+    pub comparisons: usize,
+}
+
+#[cfg(feature = "std")]
+impl SelectReport {
+    /// Returns `true` if any round fell back to grouping the elements equal to the pivot, because
+    /// the pivot was likely to have many duplicates or was equal to a previous pivot. This is
+    /// typical for inputs with few distinct values.
+    pub fn fell_back(&self) -> bool {
+        self.rounds.iter().any(|round| {
+            matches!(
+                round.kind,
+                RoundKind::EqualToPrevious | RoundKind::Pivot { repeated: true }
+            )
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Observer for SelectReport {
+    fn presorted(&mut self) {
+        self.presorted = true;
+    }
+
+    fn partitioned(&mut self) {
+        self.already_partitioned = true;
+    }
+
+    fn round(&mut self, round: Round) {
+        self.rounds.push(round);
+    }
+
+    fn sorted(&mut self, len: usize) {
+        self.sorted_len = len;
+    }
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, like
+/// [`select_nth_unstable_with_options`](crate::select_nth_unstable_with_options), and returns a
+/// report of the partitioning rounds that were needed. Collecting the report doesn't change the
+/// steps of the selection, so the worst-case bound of [`Strategy::MedianOfMedians`] still holds.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_report, SelectOptions};
+/// let mut v: Vec<u64> = (0..100_000).map(|x| (x * 7919) % 100_003).collect();
+/// let report = select_nth_unstable_with_report(&mut v, 50_000, &SelectOptions::default());
+///
+/// // The rounds shrink the slice until the median is found.
+/// let first = report.rounds[0];
+/// assert_eq!(first.len, 100_000);
+/// for round in &report.rounds {
+///     println!("{:?}: {} elements, {} below", round.kind, round.len, round.below);
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub fn select_nth_unstable_with_report<T>(
+    data: &mut [T],
+    index: usize,
+    options: &SelectOptions,
+) -> SelectReport
+where
+    T: Ord,
+{
+    select_nth_unstable_with_report_by_lt(data, index, options, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, and returns a report of the partitioning rounds.
+///
+/// See [`select_nth_unstable_with_report`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_report_by, SelectOptions};
+/// let mut v: Vec<f64> = (0..1000).map(|x| ((x * 7919) % 1000) as f64).collect();
+/// let options = SelectOptions::default();
+/// let report = select_nth_unstable_with_report_by(&mut v, 10, &options, f64::total_cmp);
+/// assert_eq!(v[10], 10.0);
+/// assert!(report.comparisons > 0);
+/// ```
+#[cfg(feature = "std")]
+pub fn select_nth_unstable_with_report_by<T, F>(
+    data: &mut [T],
+    index: usize,
+    options: &SelectOptions,
+    mut compare: F,
+) -> SelectReport
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_report_by_lt(data, index, options, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, and returns a report of the partitioning rounds.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_report`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_report_by_lt, SelectOptions};
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// let options = SelectOptions::default();
+/// let report = select_nth_unstable_with_report_by_lt(&mut v, 10, &options, |a, b| a > b);
+/// assert_eq!(v[10], 989);
+/// assert!(report.comparisons > 0);
+/// ```
+#[cfg(feature = "std")]
+pub fn select_nth_unstable_with_report_by_lt<T, F>(
+    data: &mut [T],
+    index: usize,
+    options: &SelectOptions,
+    mut lt: F,
+) -> SelectReport
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(index < data.len());
+    let mut report = SelectReport {
+        strategy: options.strategy,
+        ..Default::default()
+    };
+    let mut comparisons = 0;
+    let mut lt = |a: &T, b: &T| {
+        comparisons += 1;
+        lt(a, b)
+    };
+    report_selection(data, index, options, &mut lt, &mut report);
+    report.comparisons = comparisons;
+    report
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, like
+/// [`select_nth_unstable`](crate::select_nth_unstable), and returns the reordered slice together
+/// with a [`SelectReport`] of the call.
+///
+/// The report is returned from the call rather than accumulated anywhere, so it can be used to
+/// check the cost of a selection in tests, or by callers that adapt to their input, e.g. by
+/// switching to a different strategy when the selections keep falling back.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_stats;
+/// let mut v: Vec<u32> = (0..10_000).map(|x| (x * 7919) % 10_000).collect();
+/// let ((_, median, _), report) = select_nth_unstable_with_stats(&mut v, 5000);
+/// assert_eq!(*median, 5000);
+/// assert!(report.comparisons < 3 * 10_000);
+/// assert!(!report.fell_back());
+/// ```
+#[cfg(feature = "std")]
+pub fn select_nth_unstable_with_stats<T>(
+    data: &mut [T],
+    index: usize,
+) -> ((&mut [T], &mut T, &mut [T]), SelectReport)
+where
+    T: Ord,
+{
+    select_nth_unstable_with_stats_by_lt(data, index, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, and returns the reordered slice together with a [`SelectReport`] of the call.
+///
+/// See [`select_nth_unstable_with_stats`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_stats_by;
+/// let mut v: Vec<u32> = (0..10_000).collect();
+/// let ((_, nth, _), report) = select_nth_unstable_with_stats_by(&mut v, 0, |a, b| b.cmp(a));
+/// assert_eq!(*nth, 9999);
+/// assert!(report.comparisons < 2 * 10_000);
+/// ```
+#[cfg(feature = "std")]
+pub fn select_nth_unstable_with_stats_by<T, F>(
+    data: &mut [T],
+    index: usize,
+    mut compare: F,
+) -> ((&mut [T], &mut T, &mut [T]), SelectReport)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_stats_by_lt(data, index, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, and returns the reordered slice together with a [`SelectReport`] of the call.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_stats`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_stats_by_lt;
+/// let mut v: Vec<u32> = (0..10_000).collect();
+/// let ((_, nth, _), report) = select_nth_unstable_with_stats_by_lt(&mut v, 0, |a, b| a > b);
+/// assert_eq!(*nth, 9999);
+/// assert!(!report.fell_back());
+/// ```
+#[cfg(feature = "std")]
+pub fn select_nth_unstable_with_stats_by_lt<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: F,
+) -> ((&mut [T], &mut T, &mut [T]), SelectReport)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let options = SelectOptions::default();
+    let report = select_nth_unstable_with_report_by_lt(data, index, &options, lt);
+    (split_partition(data, index), report)
+}
+
+/// Selects `index` with the options and records the rounds in `report`.
+#[cfg(feature = "std")]
+fn report_selection<T, F>(
+    data: &mut [T],
+    index: usize,
+    options: &SelectOptions,
+    lt: &mut F,
+    report: &mut SelectReport,
+) where
+    F: FnMut(&T, &T) -> bool,
+{
+    // If there are less than two elements, there is nothing to do. If `T` is a zero sized type, it
+    // cannot have any meaningful ordering, so we just return.
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let kind = if index == 0 {
+        select_min(data, lt);
+        RoundKind::Minimum
+    } else if index == data.len() - 1 {
+        select_max(data, lt);
+        RoundKind::Maximum
+    } else {
+        let params = options.params::<T>();
+        let partition = BlockPartition {
+            block: Some(params.partition_block),
+        };
+        select_partitioned(
+            data,
+            index,
+            lt,
+            &params,
+            options.strategy,
+            &partition,
+            report,
+        );
+        return;
+    };
+    report.round(Round {
+        len: data.len(),
+        index,
+        kind,
+        below: index,
+        middle: 1,
+        bracketed: true,
+    });
+}
@@ -0,0 +1,133 @@
+use core::cmp::Ordering;
+use std::vec::Vec;
+
+use crate::{ordering, select_nth_unstable_by};
+
+/// Shrinks `data` to its `k` smallest elements, dropping the rest. If `sorted` is `true`, the
+/// remaining elements are sorted in ascending order, otherwise their order is unspecified. If
+/// `k >= data.len()`, no elements are dropped.
+///
+/// The `k`th smallest element is selected with
+/// [`select_nth_unstable`](crate::select_nth_unstable), after which the vector is truncated, so
+/// this takes *O*(*n*) time, plus *O*(*k* log *k*) time for sorting the remaining elements. The
+/// capacity of the vector is not changed.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::retain_smallest_k;
+/// let mut v = vec![5, 1, 4, 1, 3];
+/// retain_smallest_k(&mut v, 3, true);
+/// assert_eq!(v, [1, 1, 3]);
+/// ```
+pub fn retain_smallest_k<T>(data: &mut Vec<T>, k: usize, sorted: bool)
+where
+    T: Ord,
+{
+    retain_smallest_k_by(data, k, sorted, T::cmp);
+}
+
+/// Shrinks `data` to its `k` smallest elements with respect to a comparator function, dropping the
+/// rest. See [`retain_smallest_k`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::retain_smallest_k_by;
+/// let mut v = vec![0.5, -1.0, 2.5, 1.5];
+/// retain_smallest_k_by(&mut v, 2, true, f64::total_cmp);
+/// assert_eq!(v, [-1.0, 0.5]);
+/// ```
+pub fn retain_smallest_k_by<T, F>(data: &mut Vec<T>, k: usize, sorted: bool, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if k == 0 {
+        data.clear();
+        return;
+    }
+    if k < data.len() {
+        select_nth_unstable_by(data, k - 1, &mut compare);
+        data.truncate(k);
+    }
+    if sorted {
+        data.sort_unstable_by(compare);
+    }
+}
+
+/// Shrinks `data` to its `k` smallest elements with respect to a "less than" function, dropping
+/// the rest. See [`retain_smallest_k`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::retain_smallest_k_by_lt;
+/// let mut v = vec!["pear", "fig", "banana", "kiwi"];
+/// retain_smallest_k_by_lt(&mut v, 2, true, |a, b| a.len() < b.len());
+/// assert_eq!(v, ["fig", "pear"]);
+/// ```
+pub fn retain_smallest_k_by_lt<T, F>(data: &mut Vec<T>, k: usize, sorted: bool, mut lt: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    retain_smallest_k_by(data, k, sorted, |a, b| ordering(a, b, &mut lt));
+}
+
+/// Shrinks `data` to its `k` largest elements, dropping the rest. If `sorted` is `true`, the
+/// remaining elements are sorted in descending order, otherwise their order is unspecified. If
+/// `k >= data.len()`, no elements are dropped.
+///
+/// This is the counterpart of [`retain_smallest_k`] for keeping the best candidates by score, and
+/// takes *O*(*n*) time, plus *O*(*k* log *k*) time for sorting the remaining elements. The
+/// capacity of the vector is not changed.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::truncate_to_top_k;
+/// let mut v = vec![5, 1, 4, 1, 3];
+/// truncate_to_top_k(&mut v, 2, true);
+/// assert_eq!(v, [5, 4]);
+/// ```
+pub fn truncate_to_top_k<T>(data: &mut Vec<T>, k: usize, sorted: bool)
+where
+    T: Ord,
+{
+    truncate_to_top_k_by(data, k, sorted, T::cmp);
+}
+
+/// Shrinks `data` to its `k` largest elements with respect to a comparator function, dropping the
+/// rest. See [`truncate_to_top_k`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::truncate_to_top_k_by;
+/// let mut v = vec![(3, 'a'), (9, 'b'), (5, 'c'), (7, 'd')];
+/// truncate_to_top_k_by(&mut v, 2, true, |a, b| a.0.cmp(&b.0));
+/// assert_eq!(v, [(9, 'b'), (7, 'd')]);
+/// ```
+pub fn truncate_to_top_k_by<T, F>(data: &mut Vec<T>, k: usize, sorted: bool, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    retain_smallest_k_by(data, k, sorted, |a, b| compare(b, a));
+}
+
+/// Shrinks `data` to its `k` largest elements with respect to a "less than" function, dropping the
+/// rest. See [`truncate_to_top_k`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::truncate_to_top_k_by_lt;
+/// let mut v = vec![0.5, -1.0, 2.5, 1.5];
+/// truncate_to_top_k_by_lt(&mut v, 1, false, |a, b| a < b);
+/// assert_eq!(v, [2.5]);
+/// ```
+pub fn truncate_to_top_k_by_lt<T, F>(data: &mut Vec<T>, k: usize, sorted: bool, mut lt: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    retain_smallest_k_by(data, k, sorted, |a, b| ordering(b, a, &mut lt));
+}
@@ -0,0 +1,265 @@
+use core::{
+    cmp::Ordering,
+    mem::{self, ManuallyDrop, MaybeUninit},
+    ptr, slice,
+};
+
+use crate::{
+    params::params,
+    sort::tinysort_stable_by_lt,
+    split_partition,
+    strategy::{median_of_medians, replacement_pivot},
+    BadRounds, Params,
+};
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, using
+/// `scratch` for partitioning out of place. Unlike
+/// [`select_nth_unstable`](crate::select_nth_unstable), this is stable: equal elements keep their
+/// relative order, so the element at `index` is the one that a stable sort would put there.
+///
+/// Each round reads the slice once from front to back, moving the elements less than the pivot to
+/// the front of the slice and the others to `scratch`, from which they are copied back after the
+/// elements less than the pivot. All of the writes are sequential, and the side that an element
+/// goes to is chosen without branching. The pivots are chosen from samples drawn with replacement,
+/// so that choosing them doesn't reorder the slice. This is several times faster than a stable
+/// sort, and about as fast as the unstable selection of `core` for small elements, but the copies
+/// make it slower than the in-place selection, especially for large elements.
+///
+/// `scratch` must hold at least as many elements as `data`. Its contents are ignored and left
+/// uninitialized, so the same buffer can be reused across calls without any allocation.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices, or when `scratch` is
+/// shorter than `data`.
+///
+/// # Examples
+///
+/// ```
+/// use std::mem::MaybeUninit;
+/// use turboselect::select_nth_with_scratch;
+///
+/// let mut scratch = [MaybeUninit::uninit(); 5];
+/// let mut v = [-5i32, 4, 2, -3, 1];
+/// let (_, median, _) = select_nth_with_scratch(&mut v, 2, &mut scratch);
+/// assert_eq!(*median, 1);
+/// ```
+pub fn select_nth_with_scratch<'a, T>(
+    data: &'a mut [T],
+    index: usize,
+    scratch: &mut [MaybeUninit<T>],
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    T: Ord,
+{
+    select_nth_with_scratch_by_lt(data, index, scratch, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, using `scratch` for partitioning out of place.
+///
+/// See [`select_nth_with_scratch`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices, or when `scratch` is
+/// shorter than `data`.
+///
+/// # Examples
+///
+/// ```
+/// use std::mem::MaybeUninit;
+/// use turboselect::select_nth_with_scratch_by;
+///
+/// // The records with equal keys stay in their original order.
+/// let mut scratch = [MaybeUninit::uninit(); 6];
+/// let mut v = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (2, 'e'), (3, 'f')];
+/// select_nth_with_scratch_by(&mut v, 3, &mut scratch, |a, b| a.0.cmp(&b.0));
+/// assert_eq!(v[..2], [(1, 'b'), (1, 'd')]);
+/// assert_eq!(v[2..5], [(2, 'a'), (2, 'c'), (2, 'e')]);
+/// ```
+pub fn select_nth_with_scratch_by<'a, T, F>(
+    data: &'a mut [T],
+    index: usize,
+    scratch: &mut [MaybeUninit<T>],
+    mut compare: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_with_scratch_by_lt(data, index, scratch, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, using `scratch` for partitioning out of place.
+///
+/// This is the "less than" form of [`select_nth_with_scratch`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices, or when `scratch` is
+/// shorter than `data`.
+///
+/// # Examples
+///
+/// ```
+/// use std::mem::MaybeUninit;
+/// use turboselect::select_nth_with_scratch_by_lt;
+///
+/// let mut scratch = Vec::with_capacity(1000);
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+/// let (_, nth, _) =
+///     select_nth_with_scratch_by_lt(&mut v, 10, scratch.spare_capacity_mut(), |a, b| a > b);
+/// assert_eq!(*nth, 989);
+/// ```
+pub fn select_nth_with_scratch_by_lt<'a, T, F>(
+    data: &'a mut [T],
+    index: usize,
+    scratch: &mut [MaybeUninit<T>],
+    mut lt: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(index < data.len());
+    assert!(
+        scratch.len() >= data.len(),
+        "the scratch space must be at least as long as the slice"
+    );
+    if data.len() > 1 && mem::size_of::<T>() > 0 {
+        select_stable(data, index, scratch, &mut lt);
+    }
+    split_partition(data, index)
+}
+
+/// Puts the element at `index` at its stably sorted position by partitioning out of place.
+fn select_stable<T, F>(
+    mut data: &mut [T],
+    mut index: usize,
+    scratch: &mut [MaybeUninit<T>],
+    lt: &mut F,
+) where
+    F: FnMut(&T, &T) -> bool,
+{
+    let params = params::<T>();
+    let mut bad_rounds = BadRounds::default();
+    while data.len() > params.tinysort_cutoff {
+        let len = data.len();
+        let (pivot, is_repeated) = match bad_rounds.exceeded(&params) {
+            false => {
+                let (p, is_repeated) = replacement_pivot(data, index, lt, &params);
+                // SAFETY: The pivot is read into the stack only for comparisons and is never
+                // dropped. The element itself is moved around like the others, but never dropped
+                // either.
+                (
+                    unsafe { ManuallyDrop::new(ptr::read(&data[p])) },
+                    is_repeated,
+                )
+            }
+            true => median_of_copies(data, scratch, lt, &params),
+        };
+        let u = partition_stable(data, scratch, |x| lt(x, &pivot));
+        if index < u {
+            bad_rounds.count(len, u);
+            data = &mut data[..u];
+            continue;
+        }
+        // The elements equal to the pivot are grouped only if there are likely to be many of
+        // them, or if none are less than the pivot, which would otherwise leave the slice as is.
+        let v = match is_repeated || u == 0 {
+            true => u + partition_stable(&mut data[u..], scratch, |x| !lt(&pivot, x)),
+            false => u,
+        };
+        if index < v {
+            return;
+        }
+        bad_rounds.count(len, len - v);
+        data = &mut data[v..];
+        index -= v;
+    }
+    tinysort_stable_by_lt(data, lt);
+}
+
+/// Chooses a pivot with the median of medians, like after too many bad rounds of the unstable
+/// selection, but from copies of the elements in `scratch`, so that `data` keeps its order.
+fn median_of_copies<T, F>(
+    data: &[T],
+    scratch: &mut [MaybeUninit<T>],
+    lt: &mut F,
+    params: &Params,
+) -> (ManuallyDrop<T>, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = data.len();
+    debug_assert!(scratch.len() >= len);
+    // SAFETY: The copies are only compared and moved around within `scratch`, which is never
+    // dropped, and the pivot is read from them into the stack like any other pivot. The elements
+    // in `data` are not touched, so they stay in place even if `lt` panics.
+    unsafe {
+        let copies = scratch.as_mut_ptr().cast::<T>();
+        ptr::copy_nonoverlapping(data.as_ptr(), copies, len);
+        let copies = slice::from_raw_parts_mut(copies, len);
+        let (p, is_repeated) = median_of_medians(copies, lt, params);
+        (ManuallyDrop::new(ptr::read(&copies[p])), is_repeated)
+    }
+}
+
+/// Partitions `data` stably such that the elements for which `pred` is `true` come first, and
+/// returns their number.
+///
+/// Each element is copied both to the end of the elements that satisfy `pred`, at the front of
+/// `data`, and to the end of the others, in `scratch`, and only the count of the matching side is
+/// incremented. This avoids a branch on the result of `pred`, which is mispredicted half of the
+/// time. Then the elements in `scratch` are copied back after the elements that satisfy `pred`.
+fn partition_stable<T, P>(data: &mut [T], scratch: &mut [MaybeUninit<T>], mut pred: P) -> usize
+where
+    P: FnMut(&T) -> bool,
+{
+    let len = data.len();
+    debug_assert!(scratch.len() >= len);
+    // SAFETY: The element at `data[i]` is written to `data[gap.front]` with `gap.front <= i`, which
+    // has been moved out or is `data[i]` itself, and to `scratch[gap.back]`, which is unused. Only
+    // one of the copies is counted, so `gap.front + gap.back == i` between the elements, and the
+    // uncounted copy is overwritten later. If `pred` panics, `gap` copies the elements in
+    // `scratch` back into the hole in `data`, so that every element is in `data` exactly once.
+    unsafe {
+        let mut gap = Gap {
+            data: data.as_mut_ptr(),
+            scratch: scratch.as_mut_ptr().cast::<T>(),
+            front: 0,
+            back: 0,
+        };
+        for i in 0..len {
+            let elem = gap.data.add(i);
+            let is_front = pred(&*elem);
+            ptr::copy_nonoverlapping(elem, gap.scratch.add(gap.back), 1);
+            ptr::copy(elem, gap.data.add(gap.front), 1);
+            gap.front += is_front as usize;
+            gap.back += !is_front as usize;
+        }
+        gap.front
+    }
+}
+
+/// The state of [`partition_stable`], which copies the elements in `scratch` back to `data` when
+/// dropped.
+struct Gap<T> {
+    data: *mut T,
+    scratch: *mut T,
+    /// The number of elements moved to the front of `data`.
+    front: usize,
+    /// The number of elements moved to `scratch`.
+    back: usize,
+}
+
+impl<T> Drop for Gap<T> {
+    fn drop(&mut self) {
+        // SAFETY: The slots `data[front..front + back]` were moved out, and the moved elements
+        // are at the front of `scratch`, which doesn't overlap `data`.
+        unsafe {
+            ptr::copy_nonoverlapping(self.scratch, self.data.add(self.front), self.back);
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use core::cmp::Reverse;
+
+#[cfg(feature = "std")]
+use crate::select_nth_unstable_by_cached_key;
+use crate::{
+    detect_order, median_each_row, partition_by_range, percentile, reselect_nth_unstable,
+    select_and_group, select_nth_unstable, select_nth_unstable_array, select_nth_unstable_by,
+    select_nth_unstable_by_key, select_nth_unstable_by_lt, select_nth_unstable_by_radix_key,
+    select_nth_unstable_with_budget, select_nth_unstable_with_options,
+    select_nth_unstable_with_partition, select_nth_unstable_with_swaps, select_rank_in,
+    select_two_ranks, tinysort_stable_by_lt, trim_percentiles, winsorize, wyrand::WyRng, RadixKey,
+    SelectOptions, SelectionState, SortOrder, Strategy, TernaryPartition,
+};
+
+/// The length of the longest `u16` slices tested, which is enough to reach every pivot strategy,
+/// including Floyd–Rivest, which starts at 600 elements.
+const MAX_LEN: usize = 640;
+
+/// The length of the longest `u64` slices tested.
+const MAX_LEN_WIDE: usize = 96;
+
+/// The pivot strategies, with the names of their checks.
+const STRATEGIES: [(&str, Strategy); 5] = [
+    ("Strategy::Sampling", Strategy::Sampling),
+    ("Strategy::MedianOfNinthers", Strategy::MedianOfNinthers),
+    ("Strategy::FloydRivest", Strategy::FloydRivest),
+    (
+        "Strategy::SamplingWithReplacement",
+        Strategy::SamplingWithReplacement,
+    ),
+    ("Strategy::MedianOfMedians", Strategy::MedianOfMedians),
+];
+
+/// The outcome of [`selftest`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// The number of checks that passed.
+    pub passed: usize,
+    /// The number of checks that failed.
+    pub failed: usize,
+    /// The name of the first check that failed, e.g. `"select_nth_unstable_by"`.
+    pub first_failure: Option<&'static str>,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if no check failed.
+    pub fn is_ok(&self) -> bool {
+        self.failed == 0
+    }
+
+    fn check(&mut self, name: &'static str, ok: bool) {
+        if ok {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+            self.first_failure.get_or_insert(name);
+        }
+    }
+}
+
+/// Runs a randomized correctness suite of the public functions of the crate and returns the number
+/// of checks that passed and failed.
+///
+/// The crate relies heavily on unsafe code, whose correctness can depend on the target and the
+/// compiler, e.g. on the alignment of the elements or the width of `usize`. This lets embedded
+/// users validate the crate on their exact target, from firmware or a small binary, without a
+/// test harness. Each round fills `u16` slices of up to 640 elements and `u64` slices of up to 96
+/// elements with random, sorted, reversed or repetitive data from a generator seeded with `seed`,
+/// and checks every selection and partitioning function against a slice sorted with
+/// `sort_unstable` from `core`. The same seed always runs the same checks.
+///
+/// The suite runs without allocating, in about 7 KB of stack. The functions that need `std` are
+/// only checked when it is enabled. A bug can also show up as a panic, which is not caught.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::selftest;
+/// let report = selftest(0x5eed, 4);
+/// assert!(report.is_ok(), "{:?} failed", report.first_failure);
+/// assert!(report.passed > 0);
+/// ```
+pub fn selftest(seed: u64, rounds: usize) -> SelfTestReport {
+    let mut rng = WyRng::new(seed);
+    let mut report = SelfTestReport::default();
+    for _ in 0..rounds {
+        Round::<u16, MAX_LEN>::new(&mut rng, WyRng::u16).run(&mut rng, &mut report);
+        Round::<u64, MAX_LEN_WIDE>::new(&mut rng, WyRng::u64).run(&mut rng, &mut report);
+    }
+    report
+}
+
+/// The buffers of one round of checks on slices of up to `L` elements.
+struct Round<T, const L: usize> {
+    len: usize,
+    /// The input of every check.
+    input: [T; L],
+    /// The input in sorted order.
+    sorted: [T; L],
+    /// The slice reordered by the function under check.
+    data: [T; L],
+    /// A buffer for sorting the output of a check.
+    scratch: [T; L],
+    /// The original positions of the elements.
+    positions: [u16; L],
+}
+
+impl<T, const L: usize> Round<T, L>
+where
+    T: Ord + Copy + Default + From<u16>,
+{
+    /// Fills the input with one of several patterns of random length.
+    fn new(rng: &mut WyRng, random: fn(&mut WyRng) -> T) -> Self {
+        let len = rng.bounded_usize(1, L + 1);
+        let mut input = [T::default(); L];
+        let pattern = rng.bounded_usize(0, 7);
+        for (i, x) in input[..len].iter_mut().enumerate() {
+            *x = match pattern {
+                0 => random(rng),
+                1 => T::from(rng.bounded_u16(0, 4)),
+                2 => T::from(i as u16),
+                3 => T::from((len - i) as u16),
+                4 => T::from((i % 17) as u16),
+                5 => T::from(7),
+                _ if rng.bounded_usize(0, 10) == 0 => random(rng),
+                _ => T::from(i as u16),
+            };
+        }
+        let mut sorted = input;
+        sorted[..len].sort_unstable();
+        Self {
+            len,
+            input,
+            sorted,
+            data: input,
+            scratch: input,
+            positions: [0; L],
+        }
+    }
+
+    /// Resets the slice under check to the input and returns it.
+    fn fresh(&mut self) -> &mut [T] {
+        self.data = self.input;
+        &mut self.data[..self.len]
+    }
+
+    /// Returns `true` if the slice under check has the same elements as the input.
+    fn is_permutation(&mut self) -> bool {
+        let len = self.len;
+        self.scratch[..len].copy_from_slice(&self.data[..len]);
+        self.scratch[..len].sort_unstable();
+        self.scratch[..len] == self.sorted[..len]
+    }
+
+    /// Returns `true` if every element before `k` is less than or equal to every element after it.
+    fn is_split(&self, k: usize) -> bool {
+        let (head, tail) = self.data[..self.len].split_at(k);
+        match (head.iter().max(), tail.iter().min()) {
+            (Some(max), Some(min)) => max <= min,
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if the element at `index` is at its sorted position.
+    fn is_selected(&mut self, index: usize) -> bool {
+        let nth = self.data[index];
+        nth == self.sorted[index]
+            && self.data[..index].iter().all(|x| *x <= nth)
+            && self.data[index..self.len].iter().all(|x| *x >= nth)
+            && self.is_permutation()
+    }
+
+    /// Returns `true` if the element at `index` is at its position in descending order.
+    fn is_selected_descending(&mut self, index: usize) -> bool {
+        let nth = self.data[index];
+        nth == self.sorted[self.len - 1 - index]
+            && self.data[..index].iter().all(|x| *x >= nth)
+            && self.data[index..self.len].iter().all(|x| *x <= nth)
+            && self.is_permutation()
+    }
+
+    fn run<const N: usize>(mut self, rng: &mut WyRng, report: &mut SelfTestReport)
+    where
+        T: RadixKey<N>,
+    {
+        let len = self.len;
+        let index = rng.bounded_usize(0, len);
+        let key = |x: &T| x.radix_bytes()[0];
+
+        select_nth_unstable(self.fresh(), index);
+        report.check("select_nth_unstable", self.is_selected(index));
+        select_nth_unstable_by(self.fresh(), index, |a, b| b.cmp(a));
+        let ok = self.is_selected_descending(index);
+        report.check("select_nth_unstable_by", ok);
+        select_nth_unstable_by_key(self.fresh(), index, |x| Reverse(*x));
+        let ok = self.is_selected_descending(index);
+        report.check("select_nth_unstable_by_key", ok);
+        select_nth_unstable_by_lt(self.fresh(), index, |a, b| a < b);
+        report.check("select_nth_unstable_by_lt", self.is_selected(index));
+        #[cfg(feature = "std")]
+        {
+            select_nth_unstable_by_cached_key(self.fresh(), index, |x| Reverse(*x));
+            let ok = self.is_selected_descending(index);
+            report.check("select_nth_unstable_by_cached_key", ok);
+        }
+        select_nth_unstable_by_radix_key(self.fresh(), index, |x| *x);
+        let ok = self.is_selected(index);
+        report.check("select_nth_unstable_by_radix_key", ok);
+
+        for (name, strategy) in STRATEGIES {
+            let options = SelectOptions {
+                strategy,
+                ..Default::default()
+            };
+            select_nth_unstable_with_options(self.fresh(), index, &options);
+            report.check(name, self.is_selected(index));
+        }
+        let options = SelectOptions::default();
+        select_nth_unstable_with_partition(self.fresh(), index, &options, &TernaryPartition);
+        report.check("TernaryPartition", self.is_selected(index));
+
+        let exact = select_nth_unstable_with_budget(self.fresh(), index, usize::MAX).is_exact();
+        let ok = exact && self.is_selected(index);
+        report.check("select_nth_unstable_with_budget", ok);
+        let budget = rng.bounded_usize(0, 4 * len);
+        let ranks = select_nth_unstable_with_budget(self.fresh(), index, budget).ranks();
+        let ok = ranks.contains(&index)
+            && self.is_split(ranks.start)
+            && self.is_split(ranks.end)
+            && self.is_permutation();
+        report.check("select_nth_unstable_with_budget (limited)", ok);
+
+        let (i, j) = (rng.bounded_usize(0, len), rng.bounded_usize(0, len));
+        select_two_ranks(self.fresh(), i, j);
+        let ok = self.is_selected(i) && self.is_selected(j);
+        report.check("select_two_ranks", ok);
+
+        let start = rng.bounded_usize(0, index + 1);
+        let end = rng.bounded_usize(index + 1, len + 1);
+        let (left, _, _) = select_rank_in(self.fresh(), start..end);
+        let rank = left.len();
+        let ok = (start..end).contains(&rank) && self.is_selected(rank);
+        report.check("select_rank_in", ok);
+
+        let (low, high) = (
+            self.input[i].min(self.input[j]),
+            self.input[i].max(self.input[j]),
+        );
+        let (below, within, _) = partition_by_range(self.fresh(), &low, &high);
+        let (b, w) = (below.len(), within.len());
+        let ok = self.data[..b].iter().all(|x| *x < low)
+            && self.data[b..b + w].iter().all(|x| low <= *x && *x <= high)
+            && self.data[b + w..len].iter().all(|x| *x > high)
+            && self.is_permutation();
+        report.check("partition_by_range", ok);
+
+        let (below, group, _) = select_and_group(self.fresh(), index, key);
+        let (b, g) = (below.len(), group.len());
+        let k = key(&self.sorted[index]);
+        let ok = (b..b + g).contains(&index)
+            && self.data[..b].iter().all(|x| key(x) < k)
+            && self.data[b..b + g].iter().all(|x| key(x) == k)
+            && self.data[b + g..len].iter().all(|x| key(x) > k)
+            && self.is_permutation();
+        report.check("select_and_group", ok);
+
+        self.data = self.input;
+        let positions = &mut self.positions[..len];
+        positions
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| *p = i as u16);
+        select_nth_unstable_with_swaps(&mut self.data[..len], index, |i, j| positions.swap(i, j));
+        let ok = (0..len).all(|i| self.input[self.positions[i] as usize] == self.data[i])
+            && self.is_selected(index);
+        report.check("select_nth_unstable_with_swaps", ok);
+
+        if len >= 16 {
+            let mut array = [T::default(); 16];
+            array.copy_from_slice(&self.input[..16]);
+            let mut sorted = array;
+            sorted.sort_unstable();
+            let k = index % 16;
+            let (left, nth, right) = select_nth_unstable_array(&mut array, k);
+            let ok = *nth == sorted[k]
+                && left.iter().all(|x| x <= nth)
+                && right.iter().all(|x| x >= nth);
+            report.check("select_nth_unstable_array", ok);
+        }
+
+        let k = len.min(64);
+        let positions = &mut self.positions[..k];
+        positions
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| *p = i as u16);
+        let input = &self.input;
+        let key_at = |i: u16| key(&input[i as usize]);
+        tinysort_stable_by_lt(positions, |&a, &b| key_at(a) < key_at(b));
+        let ok = self.positions[..k].windows(2).all(|w| {
+            let (a, b) = (key_at(w[0]), key_at(w[1]));
+            a < b || (a == b && w[0] < w[1])
+        });
+        report.check("tinysort_stable", ok);
+
+        for slice in [&self.input[..len], &self.sorted[..len]] {
+            let ascending = slice.windows(2).all(|w| w[0] <= w[1]);
+            let descending = slice.windows(2).all(|w| w[0] >= w[1]);
+            let ok = match detect_order(slice, T::cmp) {
+                SortOrder::Ascending => ascending,
+                SortOrder::Descending => descending,
+                SortOrder::Unsorted => !ascending && !descending,
+            };
+            report.check("detect_order", ok);
+        }
+
+        let p = rng.f64();
+        percentile(self.fresh(), p);
+        let ok = self.is_selected((p * (len - 1) as f64) as usize);
+        report.check("percentile", ok);
+
+        let (low, high) = (rng.f64() / 2.0, rng.f64() / 2.0);
+        let l = (low * len as f64) as usize;
+        let r = (len - (high * len as f64) as usize).max(l);
+        let middle = trim_percentiles(self.fresh(), low, high).len();
+        let ok = middle == r - l && self.is_split(l) && self.is_split(r) && self.is_permutation();
+        report.check("trim_percentiles", ok);
+
+        winsorize(self.fresh(), low, high);
+        self.scratch[..len].copy_from_slice(&self.data[..len]);
+        self.scratch[..len].sort_unstable();
+        let ok = (0..len).all(|k| match l < r {
+            true => self.scratch[k] == self.sorted[k.clamp(l, r - 1)],
+            false => self.scratch[k] == self.sorted[k],
+        });
+        report.check("winsorize", ok);
+
+        let n_cols = rng.bounded_usize(1, 9);
+        let rows = len / n_cols;
+        self.data = self.input;
+        let medians = median_each_row(&mut self.data[..rows * n_cols], n_cols);
+        let ok = medians.enumerate().all(|(r, median)| {
+            let row = &mut self.scratch[..n_cols];
+            row.copy_from_slice(&self.input[r * n_cols..(r + 1) * n_cols]);
+            row.sort_unstable();
+            *median == row[n_cols / 2]
+        });
+        report.check("median_each_row", ok);
+
+        // The warm selections modify the data, so they come last and sort it again.
+        select_nth_unstable(self.fresh(), index);
+        let mut modified = [0; 4];
+        for m in &mut modified {
+            *m = rng.bounded_usize(0, len);
+            self.data[*m] = self.input[rng.bounded_usize(0, len)];
+        }
+        self.sorted[..len].copy_from_slice(&self.data[..len]);
+        self.sorted[..len].sort_unstable();
+        reselect_nth_unstable(&mut self.data[..len], index, &mut modified);
+        report.check("reselect_nth_unstable", self.is_selected(index));
+
+        let mut state = SelectionState::default();
+        state.select_nth_unstable(&mut self.data[..len], index);
+        let at = rng.bounded_usize(0, len);
+        self.data[at] = self.input[rng.bounded_usize(0, len)];
+        self.sorted[..len].copy_from_slice(&self.data[..len]);
+        self.sorted[..len].sort_unstable();
+        state.select_nth_unstable(&mut self.data[..len], index);
+        report.check("SelectionState", self.is_selected(index));
+    }
+}
@@ -0,0 +1,275 @@
+#[cfg(turboselect_nightly)]
+use core::simd::{cmp::SimdPartialOrd, Simd};
+
+#[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+use crate::avx2;
+#[cfg(turboselect_nightly)]
+use crate::lanes::{offsets_ge_by, offsets_lt_rev_by, LANES};
+use crate::{
+    params::params, partition_at_classified, partition_equal, select_partitioned, sort_order,
+    split_partition, Classify, PartitionStrategy, SortOrder, Strategy,
+};
+
+mod sealed {
+    pub trait Sealed: Copy + Ord + Kernels {
+        /// Returns a mask whose bit `i` is set if `*lanes.add(i) < pivot`, for `i < LANES`.
+        ///
+        /// # Safety
+        ///
+        /// `lanes` must be valid for reads of `LANES` elements.
+        #[cfg(turboselect_nightly)]
+        unsafe fn lt_mask(lanes: *const Self, pivot: Self) -> u8;
+    }
+
+    /// The kernels for the architecture, if any.
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    pub use crate::avx2::Avx2Element as Kernels;
+
+    /// The kernels for the architecture, if any.
+    #[cfg(not(all(feature = "avx2", target_arch = "x86_64")))]
+    pub trait Kernels {}
+
+    #[cfg(not(all(feature = "avx2", target_arch = "x86_64")))]
+    impl<T> Kernels for T {}
+}
+
+/// A primitive integer type that [`select_nth_unstable_simd`] can partition with vectorized
+/// comparisons. This trait is sealed and implemented for `u32`, `u64`, `i32` and `i64`.
+pub trait SimdElement: sealed::Sealed {}
+
+macro_rules! simd_element {
+    ($($t:ty),*) => {$(
+        impl sealed::Sealed for $t {
+            #[cfg(turboselect_nightly)]
+            #[inline(always)]
+            unsafe fn lt_mask(lanes: *const Self, pivot: Self) -> u8 {
+                let lanes = Simd::<$t, LANES>::from_array(*lanes.cast::<[$t; LANES]>());
+                lanes.simd_lt(Simd::splat(pivot)).to_bitmask() as u8
+            }
+        }
+
+        impl SimdElement for $t {}
+    )*};
+}
+
+simd_element!(u32, u64, i32, i64);
+
+/// Reorders the slice of integers such that the element at `index` is at its final sorted
+/// position, like [`select_nth_unstable`](crate::select_nth_unstable), but compares eight elements
+/// at a time when partitioning.
+///
+/// Each block of the partitioning is compared against the pivot eight elements at a time, and the
+/// offsets of the out-of-order elements are looked up from the resulting bitmask, instead of
+/// comparing the elements one by one. The comparisons use `core::simd`, which requires a nightly
+/// compiler: with other compilers, the slice is partitioned like with `select_nth_unstable`.
+/// Rounds whose pivot is likely to have many duplicates are partitioned without vectorization.
+///
+/// The speedup depends on the instructions that the compiler may use. With AVX2 enabled, e.g.
+/// with `-C target-cpu=native`, selecting from a million random `u32`s takes about 40% less time,
+/// but with the baseline x86-64 instructions there is no speedup, and `u64`s are slower.
+///
+/// When `index` is the first or the last position, the minimum or the maximum is found without
+/// partitioning, by scanning the slice with lanewise comparisons that the compiler vectorizes on
+/// any compiler. With the baseline x86-64 instructions, this is about three times faster than
+/// `select_nth_unstable` for 32-bit integers and about 20% faster for 64-bit integers.
+///
+/// With the `avx2` feature on x86-64, the partitioning, the search for the minimum or the maximum
+/// and the check for a sorted slice before the first round use AVX2 kernels on stable compilers
+/// too, if the processor supports AVX2 when the function is called. Otherwise they fall back to
+/// the paths above.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_simd;
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+/// let (_, median, _) = select_nth_unstable_simd(&mut v, 500);
+/// assert_eq!(*median, 500);
+/// ```
+pub fn select_nth_unstable_simd<T>(data: &mut [T], index: usize) -> (&mut [T], &mut T, &mut [T])
+where
+    T: SimdElement,
+{
+    assert!(index < data.len());
+    if data.len() < 2 {
+        return split_partition(data, index);
+    }
+
+    if index == 0 {
+        select_min_simd(data);
+    } else if index == data.len() - 1 {
+        select_max_simd(data);
+    } else {
+        let params = params::<T>();
+        select_partitioned(
+            data,
+            index,
+            &mut T::lt,
+            &params,
+            Strategy::Sampling,
+            &VectorizedPartition,
+            &mut (),
+        );
+    }
+    split_partition(data, index)
+}
+
+/// Puts the minimum at the beginning of the slice, finding it with AVX2 if it is available.
+fn select_min_simd<T>(data: &mut [T])
+where
+    T: SimdElement,
+{
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if avx2::is_available() {
+        // SAFETY: AVX2 is available.
+        return data.swap(0, unsafe { avx2::position_of_min(data) });
+    }
+    data.swap(0, position_of_extreme::<T, false>(data));
+}
+
+/// Puts the maximum at the end of the slice, finding it with AVX2 if it is available.
+fn select_max_simd<T>(data: &mut [T])
+where
+    T: SimdElement,
+{
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if avx2::is_available() {
+        // SAFETY: AVX2 is available.
+        return data.swap(unsafe { avx2::position_of_max(data) }, data.len() - 1);
+    }
+    data.swap(position_of_extreme::<T, true>(data), data.len() - 1);
+}
+
+/// Returns the position of the first maximum of `data` if `MAX` is `true`, and of the first
+/// minimum otherwise. `data` must not be empty.
+///
+/// The slice is scanned in blocks like in the kernels of the architectures, but the lanewise
+/// extremes of a block are kept in an array, which the compiler vectorizes with whichever
+/// instructions the target has, instead of in a vector of intrinsics.
+fn position_of_extreme<T, const MAX: bool>(data: &[T]) -> usize
+where
+    T: SimdElement,
+{
+    const BLOCK: usize = 64;
+    const WIDTH: usize = 16;
+
+    let is_better = |a: T, b: T| match MAX {
+        true => a > b,
+        false => a < b,
+    };
+    let (mut extreme, mut start) = (data[0], 0);
+    let mut blocks = data.chunks_exact(BLOCK);
+    for (block, offset) in blocks.by_ref().zip((0..).step_by(BLOCK)) {
+        let (first, rest) = block.split_at(WIDTH);
+        let mut lanes: [T; WIDTH] = first.try_into().unwrap();
+        for chunk in rest.chunks_exact(WIDTH) {
+            for (lane, &x) in lanes.iter_mut().zip(chunk) {
+                *lane = if is_better(x, *lane) { x } else { *lane };
+            }
+        }
+        // Not short-circuiting, so that the lanes are compared at once.
+        if lanes
+            .iter()
+            .fold(false, |any, &x| any | is_better(x, extreme))
+        {
+            let best = block
+                .iter()
+                .fold(extreme, |a, &b| if is_better(b, a) { b } else { a });
+            (extreme, start) = (best, offset);
+        }
+    }
+    let rest = blocks.remainder();
+    let offset = data.len() - rest.len();
+    for (i, &x) in rest.iter().enumerate() {
+        if is_better(x, extreme) {
+            (extreme, start) = (x, offset + i);
+        }
+    }
+    start + data[start..].iter().position(|&x| x == extreme).unwrap()
+}
+
+/// The partitioning of [`select_nth_unstable_simd`], which always compares in the natural order of
+/// the elements.
+struct VectorizedPartition;
+
+impl<T> PartitionStrategy<T> for VectorizedPartition
+where
+    T: SimdElement,
+{
+    fn partition<F>(
+        &self,
+        data: &mut [T],
+        pivot: usize,
+        is_repeated: bool,
+        lt: &mut F,
+    ) -> (usize, usize)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let block = params::<T>().partition_block;
+        if is_repeated {
+            return partition_equal(data, pivot, lt, block);
+        }
+        #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+        if avx2::is_available() {
+            // SAFETY: AVX2 is available.
+            return unsafe { avx2::partition_at(data, pivot, block) };
+        }
+        partition_at_classified(data, pivot, &mut Vectorized, block)
+    }
+
+    fn sort_order<F>(&self, data: &[T], lt: &mut F) -> SortOrder
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+        if avx2::is_available() {
+            // SAFETY: AVX2 is available.
+            return unsafe { avx2::sort_order(data) };
+        }
+        sort_order(data, lt)
+    }
+}
+
+/// Finds the out-of-order elements of a block with the `lt_mask` of the element type.
+struct Vectorized;
+
+impl<T> Classify<T> for Vectorized
+where
+    T: SimdElement,
+{
+    #[inline(always)]
+    fn lt(&mut self, a: &T, b: &T) -> bool {
+        a < b
+    }
+
+    #[cfg(turboselect_nightly)]
+    #[inline(always)]
+    unsafe fn offsets_ge(
+        &mut self,
+        block: *const T,
+        len: usize,
+        pivot: &T,
+        offsets: *mut u8,
+    ) -> usize {
+        offsets_ge_by(block, len, *pivot, offsets, |lanes| {
+            T::lt_mask(lanes, *pivot)
+        })
+    }
+
+    #[cfg(turboselect_nightly)]
+    #[inline(always)]
+    unsafe fn offsets_lt_rev(
+        &mut self,
+        end: *const T,
+        len: usize,
+        pivot: &T,
+        offsets: *mut u8,
+    ) -> usize {
+        offsets_lt_rev_by(end, len, *pivot, offsets, |lanes| T::lt_mask(lanes, *pivot))
+    }
+}
@@ -1,3 +1,5 @@
+use core::cmp::Ordering;
+
 #[inline]
 /// Compares the elements at `a` and `b` and swaps them if `a` is greater than `b`. Returns `true`
 /// if the elements were swapped. Panics if `a` or `b` is out of bounds or `a == b`.
@@ -19,8 +21,10 @@ where
     }
 }
 
+/// Sorts the first `N` elements of `data` with a sorting network. Sizes without a network, i.e.
+/// those larger than 16, are sorted with an insertion sort instead.
 #[rustfmt::skip]
-fn network_sort<T, F, const N: usize>(data: &mut [T], lt: &mut F)
+pub(crate) fn network_sort<T, F, const N: usize>(data: &mut [T], lt: &mut F)
 where
     F: FnMut(&T, &T) -> bool,
 {
@@ -146,8 +150,85 @@ where
             sort2!(6, 8);   sort2!(7, 9);   sort2!(10, 12); sort2!(3, 4);   sort2!(5, 6); 
             sort2!(7, 8);   sort2!(9, 10);  sort2!(11, 12); sort2!(6, 7);   sort2!(8, 9); 
         }
-        n => unimplemented!("sorting network for size {n} not implemented"),
+        n => {
+            let len = core::cmp::min(n, data.len());
+            insertion_sort(&mut data[..len], lt);
+        }
+    }
+}
+
+/// Sorts `data` with an insertion sort.
+fn insertion_sort<T, F>(data: &mut [T], lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for i in 1..data.len() {
+        let mut j = i;
+        while j > 0 && lt(&data[j], &data[j - 1]) {
+            data.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+/// Moves the median of the 9 elements in `data` to `data[4]`, using an optimal median network of 19
+/// comparisons. The other elements end up in an unspecified order.
+#[cfg(any(test, feature = "filter"))]
+#[rustfmt::skip]
+pub(crate) fn median9<T, F>(data: &mut [T; 9], lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    macro_rules! sort2 {
+        ($a:expr, $b:expr) => {
+            if lt(&data[$b], &data[$a]) {
+                data.swap($a, $b);
+            }
+        };
+    }
+
+    sort2!(1, 2); sort2!(4, 5); sort2!(7, 8); sort2!(0, 1); sort2!(3, 4);
+    sort2!(6, 7); sort2!(1, 2); sort2!(4, 5); sort2!(7, 8); sort2!(0, 3);
+    sort2!(5, 8); sort2!(4, 7); sort2!(3, 6); sort2!(1, 4); sort2!(2, 5);
+    sort2!(4, 7); sort2!(4, 2); sort2!(6, 4); sort2!(4, 2);
+}
+
+/// Moves the median of the 25 elements in `data` to `data[12]`, using a median network of 99
+/// comparisons. The other elements end up in an unspecified order.
+#[cfg(any(test, feature = "filter"))]
+#[rustfmt::skip]
+pub(crate) fn median25<T, F>(data: &mut [T; 25], lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    macro_rules! sort2 {
+        ($a:expr, $b:expr) => {
+            if lt(&data[$b], &data[$a]) {
+                data.swap($a, $b);
+            }
+        };
     }
+
+    sort2!(0, 1);   sort2!(3, 4);   sort2!(2, 4);   sort2!(2, 3);   sort2!(6, 7);
+    sort2!(5, 7);   sort2!(5, 6);   sort2!(9, 10);  sort2!(8, 10);  sort2!(8, 9);
+    sort2!(12, 13); sort2!(11, 13); sort2!(11, 12); sort2!(15, 16); sort2!(14, 16);
+    sort2!(14, 15); sort2!(18, 19); sort2!(17, 19); sort2!(17, 18); sort2!(21, 22);
+    sort2!(20, 22); sort2!(20, 21); sort2!(23, 24); sort2!(2, 5);   sort2!(3, 6);
+    sort2!(0, 6);   sort2!(0, 3);   sort2!(4, 7);   sort2!(1, 7);   sort2!(1, 4);
+    sort2!(11, 14); sort2!(8, 14);  sort2!(8, 11);  sort2!(12, 15); sort2!(9, 15);
+    sort2!(9, 12);  sort2!(13, 16); sort2!(10, 16); sort2!(10, 13); sort2!(20, 23);
+    sort2!(17, 23); sort2!(17, 20); sort2!(21, 24); sort2!(18, 24); sort2!(18, 21);
+    sort2!(19, 22); sort2!(8, 17);  sort2!(9, 18);  sort2!(0, 18);  sort2!(0, 9);
+    sort2!(10, 19); sort2!(1, 19);  sort2!(1, 10);  sort2!(11, 20); sort2!(2, 20);
+    sort2!(2, 11);  sort2!(12, 21); sort2!(3, 21);  sort2!(3, 12);  sort2!(13, 22);
+    sort2!(4, 22);  sort2!(4, 13);  sort2!(14, 23); sort2!(5, 23);  sort2!(5, 14);
+    sort2!(15, 24); sort2!(6, 24);  sort2!(6, 15);  sort2!(7, 16);  sort2!(7, 19);
+    sort2!(13, 21); sort2!(15, 23); sort2!(7, 13);  sort2!(7, 15);  sort2!(1, 9);
+    sort2!(3, 11);  sort2!(5, 17);  sort2!(11, 17); sort2!(9, 17);  sort2!(4, 10);
+    sort2!(6, 12);  sort2!(7, 14);  sort2!(4, 6);   sort2!(4, 7);   sort2!(12, 14);
+    sort2!(10, 14); sort2!(6, 7);   sort2!(10, 12); sort2!(6, 10);  sort2!(6, 17);
+    sort2!(12, 17); sort2!(7, 17);  sort2!(7, 10);  sort2!(12, 18); sort2!(7, 12);
+    sort2!(10, 18); sort2!(12, 20); sort2!(10, 20); sort2!(10, 12);
 }
 
 /// Sorts the slice `data` using the given comparison function `lt`. For slice lengths of 16 or
@@ -178,7 +259,8 @@ where
             data.chunks_mut(size).for_each(|chunk| tinysort(chunk, lt));
             while size < len {
                 size *= 2;
-                for chunk in data.chunks_mut(size) {
+                // A trailing chunk that is not longer than the sorted runs is already sorted.
+                for chunk in data.chunks_mut(size).filter(|chunk| chunk.len() > size / 2) {
                     merge(chunk, lt);
                 }
             }
@@ -186,23 +268,150 @@ where
     }
 }
 
+/// Sorts the slice `data` in ascending order. The sort is stable, i.e. equal elements keep their
+/// relative order.
+///
+/// This is a binary insertion sort, which uses *O*(*n* log *n*) comparisons and *O*(*n*²) moves.
+/// It is meant for short slices, up to about 64 elements, such as the groups of a stable selection
+/// or small buffers in `no_std` code. It doesn't allocate.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::tinysort_stable;
+/// let mut v = [5, 1, 4, 1, 3];
+/// tinysort_stable(&mut v);
+/// assert_eq!(v, [1, 1, 3, 4, 5]);
+/// ```
+pub fn tinysort_stable<T: Ord>(data: &mut [T]) {
+    tinysort_stable_by_lt(data, T::lt);
+}
+
+/// Sorts the slice `data` stably with the comparator function `compare`. See [`tinysort_stable`].
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::tinysort_stable_by;
+/// let mut v = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+/// tinysort_stable_by(&mut v, |a, b| b.0.cmp(&a.0));
+/// assert_eq!(v, [(2, 'a'), (2, 'c'), (1, 'b'), (1, 'd')]);
+/// ```
+pub fn tinysort_stable_by<T, F>(data: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    tinysort_stable_by_lt(data, |a, b| compare(a, b) == Ordering::Less);
+}
+
+/// Sorts the slice `data` stably with the "less than" function `lt`. See [`tinysort_stable`].
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::tinysort_stable_by_lt;
+/// let mut v = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+/// tinysort_stable_by_lt(&mut v, |a, b| a.0 < b.0);
+/// assert_eq!(v, [(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+/// ```
+pub fn tinysort_stable_by_lt<T, F>(data: &mut [T], mut lt: F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for i in 1..data.len() {
+        if !lt(&data[i], &data[i - 1]) {
+            continue;
+        }
+        // Find the first element greater than `data[i]`, so that `data[i]` is inserted after the
+        // elements equal to it.
+        let (mut lo, mut hi) = (0, i - 1);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if lt(&data[i], &data[mid]) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        data[lo..=i].rotate_right(1);
+    }
+}
+
+/// Reorders the slice `data` such that the element at `index` is at its final sorted position,
+/// for slices short enough to be sorted with [`tinysort`].
+///
+/// If `index` is within the first or the last eighth of a slice longer than 16 elements, only the
+/// shorter side of `index` is sorted: the elements up to `index` are sorted, and each of the
+/// following elements that is less than `data[index]` is swapped with it and inserted into the
+/// sorted part, or symmetrically for the last eighth. This makes far fewer comparisons than a full
+/// sort. Closer to the middle, the insertions mispredict so many branches that sorting the whole
+/// slice with the branchless networks is faster, even though it makes more comparisons.
+pub(crate) fn tinyselect<T, F>(data: &mut [T], index: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = data.len();
+    if len <= 16 || 8 * index.min(len - 1 - index) >= len {
+        tinysort(data, lt);
+    } else if index < len / 2 {
+        tinysort(&mut data[..=index], lt);
+        for j in index + 1..len {
+            if lt(&data[j], &data[index]) {
+                data.swap(j, index);
+                let mut i = index;
+                while i > 0 && lt(&data[i], &data[i - 1]) {
+                    data.swap(i, i - 1);
+                    i -= 1;
+                }
+            }
+        }
+    } else {
+        tinysort(&mut data[index..], lt);
+        for j in 0..index {
+            if lt(&data[index], &data[j]) {
+                data.swap(j, index);
+                let mut i = index;
+                while i + 1 < len && lt(&data[i + 1], &data[i]) {
+                    data.swap(i, i + 1);
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Merges the two sorted runs of `chunk`, the first of which has a power-of-two length of at least
+/// 16, with a bitonic merger.
+///
+/// If the length of `chunk` is not a power of two, it is treated as if it were padded to the next
+/// power of two with elements greater than any other. The padding never moves, and comparisons
+/// with it never swap, so they are skipped instead of being issued to `sort2`.
 fn merge<T, F>(chunk: &mut [T], lt: &mut F)
 where
     F: FnMut(&T, &T) -> bool,
 {
-    let mut size = chunk.len().next_power_of_two();
-    for delta in 0..size / 2 {
+    let len = chunk.len();
+    let mut size = len.next_power_of_two();
+    for delta in (size - len)..size / 2 {
         sort2(chunk, delta, size - delta - 1, lt);
     }
     while size > 32 {
         size /= 2;
         for inner in chunk.chunks_mut(size) {
-            for index in 0..(size / 2) {
-                sort2(inner, index, index + (size / 2), lt);
-            }
+            half_clean(inner, size / 2, lt);
         }
     }
     for inner in chunk.chunks_mut(16) {
+        if inner.len() < 16 {
+            let mut half = 8;
+            while half > 0 {
+                for block in inner.chunks_mut(2 * half) {
+                    half_clean(block, half, lt);
+                }
+                half /= 2;
+            }
+            continue;
+        }
         sort2(inner, 0, 8, lt);
         sort2(inner, 1, 9, lt);
         sort2(inner, 2, 10, lt);
@@ -240,3 +449,14 @@ where
         sort2(inner, 14, 15, lt);
     }
 }
+
+/// Compares each element of `block` with the element `half` positions after it, skipping the
+/// elements that are past the end of the block.
+fn half_clean<T, F>(block: &mut [T], half: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    for index in 0..block.len().saturating_sub(half) {
+        sort2(block, index, index + half, lt);
+    }
+}
@@ -0,0 +1,699 @@
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(feature = "num-traits")]
+use crate::select_nth_unstable_by;
+use crate::{math::floor, select_many_nth_unstable_by_lt, select_nth_unstable_by_lt};
+
+/// Computes the median of every row of the row-major matrix `data` with `n_cols` columns.
+///
+/// Each row is reordered in place so that its median is at column `n_cols / 2`, i.e. the upper
+/// median is used for rows with an even number of columns. Returns an iterator over the medians
+/// of the rows, in row order.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0` or if `data.len()` is not a multiple of `n_cols`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_each_row;
+/// let mut m = [
+///     3, 1, 2,
+///     9, 7, 8,
+///     4, 6, 5,
+/// ];
+/// let medians: Vec<_> = median_each_row(&mut m, 3).map(|x| *x).collect();
+/// assert_eq!(medians, [2, 8, 5]);
+/// ```
+pub fn median_each_row<T>(data: &mut [T], n_cols: usize) -> impl Iterator<Item = &mut T>
+where
+    T: Ord,
+{
+    median_each_row_by_lt(data, n_cols, T::lt)
+}
+
+/// Computes the median of every row of the row-major matrix `data` with a comparator function.
+/// See [`median_each_row`] for details.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0` or if `data.len()` is not a multiple of `n_cols`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_each_row_by;
+/// let mut m = [0.3, 0.1, 0.2, 0.9, 0.7, 0.8];
+/// let medians: Vec<_> = median_each_row_by(&mut m, 3, f64::total_cmp).map(|x| *x).collect();
+/// assert_eq!(medians, [0.2, 0.8]);
+/// ```
+pub fn median_each_row_by<T, F>(
+    data: &mut [T],
+    n_cols: usize,
+    mut compare: F,
+) -> impl Iterator<Item = &mut T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    median_each_row_by_lt(data, n_cols, move |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Computes the median of every row of the row-major matrix `data` with a "less than" function.
+/// See [`median_each_row`] for details.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0` or if `data.len()` is not a multiple of `n_cols`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_each_row_by_lt;
+/// let mut m = [3, 1, 2, 9, 7, 8];
+/// let medians: Vec<_> = median_each_row_by_lt(&mut m, 3, |a, b| a > b).map(|x| *x).collect();
+/// assert_eq!(medians, [2, 8]);
+/// ```
+pub fn median_each_row_by_lt<T, F>(
+    data: &mut [T],
+    n_cols: usize,
+    mut lt: F,
+) -> impl Iterator<Item = &mut T>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(n_cols > 0, "the matrix must have at least one column");
+    assert!(
+        data.len().is_multiple_of(n_cols),
+        "the length of the slice must be a multiple of the number of columns"
+    );
+
+    let mid = n_cols / 2;
+    for row in data.chunks_exact_mut(n_cols) {
+        select_nth_unstable_by_lt(row, mid, &mut lt);
+    }
+    data.iter_mut().skip(mid).step_by(n_cols)
+}
+
+#[cfg(feature = "std")]
+/// Computes the median of every column of the row-major matrix `data` with `n_cols` columns.
+///
+/// The elements of each column are gathered with a stride of `n_cols` into a scratch buffer, which
+/// is allocated once and reused for every column. The matrix itself is left unchanged. For columns
+/// with an even number of elements, the upper median is returned. Returns the medians in column
+/// order.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0` or if `data.len()` is not a multiple of `n_cols`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_each_column;
+/// let m = [
+///     3, 1, 2,
+///     9, 7, 8,
+///     4, 6, 5,
+/// ];
+/// assert_eq!(median_each_column(&m, 3), [4, 6, 5]);
+/// ```
+pub fn median_each_column<T>(data: &[T], n_cols: usize) -> Vec<T>
+where
+    T: Ord + Clone,
+{
+    median_each_column_by_lt(data, n_cols, T::lt)
+}
+
+#[cfg(feature = "std")]
+/// Computes the median of every column of the row-major matrix `data` with a comparator function.
+/// See [`median_each_column`] for details.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0` or if `data.len()` is not a multiple of `n_cols`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_each_column_by;
+/// let m = [0.3, 0.1, 0.9, 0.7, 0.4, 0.6];
+/// assert_eq!(median_each_column_by(&m, 2, f64::total_cmp), [0.4, 0.6]);
+/// ```
+pub fn median_each_column_by<T, F>(data: &[T], n_cols: usize, mut compare: F) -> Vec<T>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    median_each_column_by_lt(data, n_cols, |a, b| compare(a, b) == Ordering::Less)
+}
+
+#[cfg(feature = "std")]
+/// Computes the median of every column of the row-major matrix `data` with a "less than"
+/// function. See [`median_each_column`] for details.
+///
+/// # Panics
+///
+/// Panics if `n_cols == 0` or if `data.len()` is not a multiple of `n_cols`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::median_each_column_by_lt;
+/// let m = [3, 1, 9, 7, 4, 6];
+/// assert_eq!(median_each_column_by_lt(&m, 2, |a, b| a > b), [4, 6]);
+/// ```
+pub fn median_each_column_by_lt<T, F>(data: &[T], n_cols: usize, mut lt: F) -> Vec<T>
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(n_cols > 0, "the matrix must have at least one column");
+    assert!(
+        data.len().is_multiple_of(n_cols),
+        "the length of the slice must be a multiple of the number of columns"
+    );
+
+    let n_rows = data.len() / n_cols;
+    if n_rows == 0 {
+        return Vec::new();
+    }
+
+    let mid = n_rows / 2;
+    let mut scratch = Vec::with_capacity(n_rows);
+    let mut medians = Vec::with_capacity(n_cols);
+    for col in 0..n_cols {
+        scratch.clear();
+        scratch.extend(data[col..].iter().step_by(n_cols).cloned());
+        let (_, median, _) = select_nth_unstable_by_lt(&mut scratch, mid, &mut lt);
+        medians.push(median.clone());
+    }
+    medians
+}
+
+/// Computes the `p`th quantile of the values of every group of `(key, value)` pairs with equal
+/// keys, e.g. the median price per category.
+///
+/// The pairs are sorted by key, which takes about *O*(*n* log *g*) time for *g* distinct keys, and
+/// then the values of each run of equal keys are reordered so that the quantile is at its final
+/// position, like with [`percentile`]. Returns an iterator over the key and the quantile of each
+/// group, in the order of the keys. The quantiles are selected as the iterator advances.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile_each_group;
+/// let mut sales = [("b", 5), ("a", 3), ("b", 1), ("a", 9), ("a", 4), ("b", 7)];
+/// let medians: Vec<_> = percentile_each_group(&mut sales, 0.5).collect();
+/// assert_eq!(medians, [(&"a", &4), (&"b", &5)]);
+/// ```
+pub fn percentile_each_group<K, V>(pairs: &mut [(K, V)], p: f64) -> impl Iterator<Item = (&K, &V)>
+where
+    K: Ord,
+    V: Ord,
+{
+    percentile_each_group_by_lt(pairs, p, V::lt)
+}
+
+/// Computes the `p`th quantile of the values of every group of `(key, value)` pairs with equal
+/// keys, comparing the values with a comparator function. See [`percentile_each_group`] for
+/// details.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile_each_group_by;
+/// let mut latencies = [(2, 0.5), (1, 0.25), (2, 1.5), (1, 0.75), (2, 1.0)];
+/// let p90: Vec<_> = percentile_each_group_by(&mut latencies, 0.9, f64::total_cmp).collect();
+/// assert_eq!(p90, [(&1, &0.25), (&2, &1.0)]);
+/// ```
+pub fn percentile_each_group_by<K, V, F>(
+    pairs: &mut [(K, V)],
+    p: f64,
+    mut compare: F,
+) -> impl Iterator<Item = (&K, &V)>
+where
+    K: Ord,
+    F: FnMut(&V, &V) -> Ordering,
+{
+    percentile_each_group_by_lt(pairs, p, move |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Computes the `p`th quantile of the values of every group of `(key, value)` pairs with equal
+/// keys, comparing the values with a "less than" function. See [`percentile_each_group`] for
+/// details.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile_each_group_by_lt;
+/// let mut scores = [('x', 10), ('y', 3), ('x', 30), ('x', 20)];
+/// let best: Vec<_> = percentile_each_group_by_lt(&mut scores, 0.0, |a, b| a > b).collect();
+/// assert_eq!(best, [(&'x', &30), (&'y', &3)]);
+/// ```
+pub fn percentile_each_group_by_lt<K, V, F>(
+    pairs: &mut [(K, V)],
+    p: f64,
+    mut lt: F,
+) -> impl Iterator<Item = (&K, &V)>
+where
+    K: Ord,
+    F: FnMut(&V, &V) -> bool,
+{
+    // The groups are selected lazily, so check `p` before the iterator is returned.
+    assert!(
+        (0.0..=1.0).contains(&p),
+        "`p` must be in the range [0.0, 1.0]"
+    );
+    pairs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    pairs.chunk_by_mut(|a, b| a.0 == b.0).map(move |group| {
+        let (index, _) = percentile_rank(group.len(), p);
+        let (_, pair, _) = select_nth_unstable_by_lt(group, index, |a, b| lt(&a.1, &b.1));
+        let pair: &(K, V) = pair;
+        (&pair.0, &pair.1)
+    })
+}
+
+/// Partitions out the lowest `low` and the highest `high` fraction of the elements of `data` and
+/// returns the remaining middle part of the slice.
+///
+/// After the call, `floor(low * len)` smallest elements are at the beginning of the slice and the
+/// `floor(high * len)` largest elements are at the end of the slice, each in an unspecified order.
+/// The returned subslice contains the elements between them, also in an unspecified order. This is
+/// the building block of trimmed means and other robust estimators.
+///
+/// # Panics
+///
+/// Panics if `low` or `high` is not in the range `[0.0, 1.0]`, or if `low + high > 1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::trim_percentiles;
+/// let mut v = [7, 100, 2, 5, -40, 3, 6, 1, 4, 8];
+///
+/// // Drop the lowest and the highest 10 % of the elements.
+/// let middle = trim_percentiles(&mut v, 0.1, 0.1);
+/// middle.sort();
+/// assert_eq!(middle, [1, 2, 3, 4, 5, 6, 7, 8]);
+/// ```
+pub fn trim_percentiles<T>(data: &mut [T], low: f64, high: f64) -> &mut [T]
+where
+    T: Ord,
+{
+    trim_percentiles_by_lt(data, low, high, T::lt)
+}
+
+/// Partitions out the lowest `low` and the highest `high` fraction of the elements of `data` with
+/// a comparator function and returns the remaining middle part of the slice. See
+/// [`trim_percentiles`] for details.
+///
+/// # Panics
+///
+/// Panics if `low` or `high` is not in the range `[0.0, 1.0]`, or if `low + high > 1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::trim_percentiles_by;
+/// let mut v = [0.7, 10.0, 0.2, 0.5, -4.0, 0.3, 0.6, 0.1, 0.4, 0.8];
+/// let middle = trim_percentiles_by(&mut v, 0.1, 0.1, f64::total_cmp);
+/// assert!(middle.iter().all(|x| (0.1..=0.8).contains(x)));
+/// ```
+pub fn trim_percentiles_by<T, F>(data: &mut [T], low: f64, high: f64, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    trim_percentiles_by_lt(data, low, high, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Partitions out the lowest `low` and the highest `high` fraction of the elements of `data` with
+/// a "less than" function and returns the remaining middle part of the slice. See
+/// [`trim_percentiles`] for details.
+///
+/// # Panics
+///
+/// Panics if `low` or `high` is not in the range `[0.0, 1.0]`, or if `low + high > 1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::trim_percentiles_by_lt;
+/// let mut v = [7, 100, 2, 5, -40, 3, 6, 1, 4, 8];
+/// let middle = trim_percentiles_by_lt(&mut v, 0.1, 0.1, |a: &i32, b| a.abs() < b.abs());
+/// assert!(middle.iter().all(|x| 1 < x.abs() && x.abs() < 100));
+/// ```
+pub fn trim_percentiles_by_lt<T, F>(data: &mut [T], low: f64, high: f64, mut lt: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let (l, r) = trim_ranks(data.len(), low, high);
+    if r < data.len() {
+        select_nth_unstable_by_lt(data, r, &mut lt);
+    }
+    if 0 < l && l < r {
+        select_nth_unstable_by_lt(&mut data[..r], l, &mut lt);
+    }
+    &mut data[l..r]
+}
+
+/// Clamps the lowest `low` and the highest `high` fraction of the elements of `data` to the
+/// nearest remaining values.
+///
+/// The two cut ranks are selected as in [`trim_percentiles`], after which the `floor(low * len)`
+/// smallest elements are replaced with the smallest remaining element and the `floor(high * len)`
+/// largest elements with the largest remaining element in a single pass over the tails. The order
+/// of the elements is unspecified after the call.
+///
+/// # Panics
+///
+/// Panics if `low` or `high` is not in the range `[0.0, 1.0]`, or if `low + high > 1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::winsorize;
+/// let mut v = [7, 100, 2, 5, -40, 3, 6, 1, 4, 8];
+///
+/// winsorize(&mut v, 0.1, 0.1);
+/// v.sort();
+/// assert_eq!(v, [1, 1, 2, 3, 4, 5, 6, 7, 8, 8]);
+/// ```
+pub fn winsorize<T>(data: &mut [T], low: f64, high: f64)
+where
+    T: Ord + Clone,
+{
+    winsorize_by_lt(data, low, high, T::lt);
+}
+
+/// Clamps the lowest `low` and the highest `high` fraction of the elements of `data` to the
+/// nearest remaining values with respect to a comparator function. See [`winsorize`] for details.
+///
+/// # Panics
+///
+/// Panics if `low` or `high` is not in the range `[0.0, 1.0]`, or if `low + high > 1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::winsorize_by;
+/// let mut v = [0.7, 10.0, 0.2, 0.5, -4.0, 0.3, 0.6, 0.1, 0.4, 0.8];
+/// winsorize_by(&mut v, 0.1, 0.1, f64::total_cmp);
+/// v.sort_by(f64::total_cmp);
+/// assert_eq!(v, [0.1, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.8]);
+/// ```
+pub fn winsorize_by<T, F>(data: &mut [T], low: f64, high: f64, mut compare: F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    winsorize_by_lt(data, low, high, |a, b| compare(a, b) == Ordering::Less);
+}
+
+/// Clamps the lowest `low` and the highest `high` fraction of the elements of `data` to the
+/// nearest remaining values with respect to a "less than" function. See [`winsorize`] for
+/// details.
+///
+/// # Panics
+///
+/// Panics if `low` or `high` is not in the range `[0.0, 1.0]`, or if `low + high > 1.0`.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::winsorize_by_lt;
+/// let mut v = [7, 100, 2, 5, -40, 3, 6, 1, 4, 8];
+/// winsorize_by_lt(&mut v, 0.0, 0.2, |a: &i32, b| a.abs() < b.abs());
+/// v.sort();
+/// assert_eq!(v, [1, 2, 3, 4, 5, 6, 7, 8, 8, 8]);
+/// ```
+pub fn winsorize_by_lt<T, F>(data: &mut [T], low: f64, high: f64, mut lt: F)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> bool,
+{
+    let (l, r) = trim_ranks(data.len(), low, high);
+    if l == r {
+        return;
+    }
+    if r < data.len() {
+        let (_, max, tail) = select_nth_unstable_by_lt(data, r - 1, &mut lt);
+        tail.fill(max.clone());
+    }
+    if l > 0 {
+        let (head, min, _) = select_nth_unstable_by_lt(&mut data[..r], l, &mut lt);
+        head.fill(min.clone());
+    }
+}
+
+/// Reorders the slice such that the element at the `p`th quantile is at its final sorted position,
+/// and returns it.
+///
+/// The element at position `floor(p * (len - 1))` of the sorted slice is selected, i.e. the
+/// quantile is rounded down to the nearest element. Use [`percentile_interpolated`] to interpolate
+/// between the two nearest elements instead.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]` or if the slice is empty.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile;
+/// let mut v = [7, 1, 9, 3, 5];
+/// assert_eq!(*percentile(&mut v, 0.5), 5);
+/// assert_eq!(*percentile(&mut v, 0.9), 7);
+/// ```
+pub fn percentile<T>(data: &mut [T], p: f64) -> &mut T
+where
+    T: Ord,
+{
+    percentile_by_lt(data, p, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at the `p`th quantile is
+/// at its final sorted position, and returns it. See [`percentile`] for details.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]` or if the slice is empty.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile_by;
+/// let mut v = [0.7, 0.1, 0.9, 0.3, 0.5];
+/// assert_eq!(*percentile_by(&mut v, 0.5, f64::total_cmp), 0.5);
+/// ```
+pub fn percentile_by<T, F>(data: &mut [T], p: f64, mut compare: F) -> &mut T
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    percentile_by_lt(data, p, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the element at the `p`th quantile is
+/// at its final sorted position, and returns it. See [`percentile`] for details.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]` or if the slice is empty.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile_by_lt;
+/// let mut v = [7, 1, 9, 3, 5];
+/// assert_eq!(*percentile_by_lt(&mut v, 0.25, |a, b| a > b), 7);
+/// ```
+pub fn percentile_by_lt<T, F>(data: &mut [T], p: f64, lt: F) -> &mut T
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let (index, _) = percentile_rank(data.len(), p);
+    select_nth_unstable_by_lt(data, index, lt).1
+}
+
+#[cfg(feature = "num-traits")]
+/// Computes the `p`th quantile of the slice by linear interpolation between the two nearest
+/// elements, and returns the interpolated value.
+///
+/// With `h = p * (len - 1)`, the result is `x + (y - x) * (h - floor(h))`, where `x` and `y` are
+/// the elements at positions `floor(h)` and `floor(h) + 1` of the sorted slice. This is the
+/// default method of NumPy and R. The elements only need to be numbers that can be converted from
+/// and to `f64`, such as `f32`, integers or fixed-point types implementing the traits of
+/// `num-traits`. The interpolation is computed in `f64`, so integers are rounded towards zero, and
+/// integers wider than 53 bits may be off by the rounding error of `f64`, but the result is always
+/// between `x` and `y`. After the call, the slice is partitioned at `floor(h)`.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `[0.0, 1.0]`, if the slice is empty, or if two elements
+/// are not comparable, e.g. because one of them is NaN.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::percentile_interpolated;
+/// let mut v = [4.0f32, 1.0, 3.0, 2.0];
+/// assert_eq!(percentile_interpolated(&mut v, 0.5), 2.5);
+/// let mut v = [10u8, 40, 20, 30];
+/// assert_eq!(percentile_interpolated(&mut v, 0.25), 17);
+/// ```
+pub fn percentile_interpolated<T>(data: &mut [T], p: f64) -> T
+where
+    T: Copy + PartialOrd + num_traits::Num + num_traits::NumCast,
+{
+    let (index, fraction) = percentile_rank(data.len(), p);
+    let compare = |a: &T, b: &T| a.partial_cmp(b).expect("the elements must be comparable");
+    let (_, &mut x, rest) = select_nth_unstable_by(data, index, compare);
+    match rest.iter().copied().min_by(compare) {
+        Some(y) if fraction > 0.0 && x != y => {
+            let to_f64 =
+                |v: T| num_traits::cast::<_, f64>(v).expect("the elements must fit in f64");
+            let (low, high) = (to_f64(x), to_f64(y));
+            // Rounding to `f64` can move the value of a wide integer past either element, or even
+            // out of the range of `T`.
+            match num_traits::cast::<_, T>(low + (high - low) * fraction) {
+                Some(value) if value < x => x,
+                Some(value) if value > y => y,
+                Some(value) => value,
+                None => y,
+            }
+        }
+        _ => x,
+    }
+}
+
+/// Reorders the slice such that the elements at each of the quantiles `ps` are at their final
+/// sorted positions, and returns them in the order of `ps`, e.g. to get the 1st percentile, the
+/// median and the 99th percentile at once.
+///
+/// Each quantile is rounded down to the nearest element like in [`percentile`], and the elements
+/// are selected with [`select_many_nth_unstable`](crate::select_many_nth_unstable), which shares
+/// the partitioning rounds between them. The quantiles don't need to be sorted, and since their
+/// number is fixed, no memory is allocated.
+///
+/// # Panics
+///
+/// Panics if any of `ps` is not in the range `[0.0, 1.0]`, or if the slice is empty and `ps` isn't.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_quantiles;
+/// let mut v: Vec<u32> = (0..=1000).rev().collect();
+/// let [p99, median, p1] = select_quantiles(&mut v, &[0.99, 0.5, 0.01]);
+/// assert_eq!((*p99, *median, *p1), (990, 500, 10));
+/// ```
+pub fn select_quantiles<'a, T, const N: usize>(data: &'a mut [T], ps: &[f64; N]) -> [&'a T; N]
+where
+    T: Ord,
+{
+    select_quantiles_by_lt(data, ps, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the elements at each of the quantiles
+/// `ps` are at their final sorted positions, and returns them. See [`select_quantiles`] for
+/// details.
+///
+/// # Panics
+///
+/// Panics if any of `ps` is not in the range `[0.0, 1.0]`, or if the slice is empty and `ps` isn't.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_quantiles_by;
+/// let mut v = [0.7, 0.1, 0.9, 0.3, 0.5];
+/// let [q1, q3] = select_quantiles_by(&mut v, &[0.25, 0.75], f64::total_cmp);
+/// assert_eq!((*q1, *q3), (0.3, 0.7));
+/// ```
+pub fn select_quantiles_by<'a, T, F, const N: usize>(
+    data: &'a mut [T],
+    ps: &[f64; N],
+    mut compare: F,
+) -> [&'a T; N]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_quantiles_by_lt(data, ps, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Reorders the slice with a "less than" function such that the elements at each of the quantiles
+/// `ps` are at their final sorted positions, and returns them. See [`select_quantiles`] for
+/// details.
+///
+/// # Panics
+///
+/// Panics if any of `ps` is not in the range `[0.0, 1.0]`, or if the slice is empty and `ps` isn't.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_quantiles_by_lt;
+/// let mut v = [7, 1, 9, 3, 5];
+/// let [max, min] = select_quantiles_by_lt(&mut v, &[0.0, 1.0], |a, b| a > b);
+/// assert_eq!((*max, *min), (9, 1));
+/// ```
+pub fn select_quantiles_by_lt<'a, T, F, const N: usize>(
+    data: &'a mut [T],
+    ps: &[f64; N],
+    lt: F,
+) -> [&'a T; N]
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let ranks = ps.map(|p| percentile_rank(data.len(), p).0);
+    let mut sorted = ranks;
+    select_many_nth_unstable_by_lt(data, &mut sorted, lt);
+    let data = &*data;
+    ranks.map(|r| &data[r])
+}
+
+/// Returns the position of the element below the `p`th quantile of a sorted slice of length `len`
+/// and the fraction of the way to the next element.
+fn percentile_rank(len: usize, p: f64) -> (usize, f64) {
+    assert!(
+        (0.0..=1.0).contains(&p),
+        "`p` must be in the range [0.0, 1.0]"
+    );
+    assert!(len > 0, "the slice must not be empty");
+    let h = p * (len - 1) as f64;
+    let index = floor(h) as usize;
+    (index, h - index as f64)
+}
+
+/// Returns `(l, r)`, where `l` is the number of elements to cut from the low end and `len - r` the
+/// number of elements to cut from the high end of a slice of length `len`.
+fn trim_ranks(len: usize, low: f64, high: f64) -> (usize, usize) {
+    assert!(
+        (0.0..=1.0).contains(&low),
+        "`low` must be in the range [0.0, 1.0]"
+    );
+    assert!(
+        (0.0..=1.0).contains(&high),
+        "`high` must be in the range [0.0, 1.0]"
+    );
+    assert!(low + high <= 1.0, "`low + high` must not exceed 1.0");
+
+    let n = len as f64;
+    let l = floor(low * n) as usize;
+    let r = len - floor(high * n) as usize;
+    (l, r.max(l))
+}
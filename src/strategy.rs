@@ -0,0 +1,785 @@
+use core::{cmp::Ordering, mem};
+
+use crate::{
+    choose_pivot,
+    math::{ceil, ln, mul_div, powf, sqrt},
+    nudge,
+    params::{params, MAX_TINYSORT_CUTOFF, MIN_TINYSORT_CUTOFF},
+    partition_at, partition_equal,
+    range::partition_range,
+    report::{Observer, Round, RoundKind},
+    sample, select_max, select_min, select_partitioned, select_using, select_with,
+    sort::tinysort,
+    sort_order, split_partition,
+    wyrand::WyRng,
+    BadRounds, Params, SortOrder,
+};
+
+/// The algorithm used to choose the pivots in [`select_nth_unstable_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Chooses the pivot from a sorted sample of up to 7 × 7 elements, or by recursively selecting
+    /// from a sample of about `sqrt(len)` elements, and moves it towards the median depending on
+    /// the length of the slice. This is the strategy used by
+    /// [`select_nth_unstable`](crate::select_nth_unstable).
+    #[default]
+    Sampling,
+    /// Alexandrescu's adaptive quickselect. If `index` is within the first or the last sixth of
+    /// the slice, the pivot is selected from the minima or the maxima of small groups of elements.
+    /// Otherwise, the pivot is selected from a sample of medians of ninthers from the middle of the
+    /// slice, at the relative position of `index`.
+    MedianOfNinthers,
+    /// The Floyd–Rivest algorithm. Two pivots are selected recursively from a sample of about
+    /// `len^(2/3) / 2` elements, so that the element at `index` falls between them with high
+    /// probability. The bounds are `sqrt(ln(len) * s * (len - s) / len) / 2` positions below and
+    /// above the relative position of `index` in the sample of `s` elements. The slice is then
+    /// partitioned in three parts around the pivots, and in most cases the selection continues
+    /// in the small middle part. Slices of at most 600 elements use the default strategy.
+    FloydRivest,
+    /// Like [`Strategy::Sampling`], but the sample is drawn with replacement from random
+    /// positions of the slice, and only the positions are stored, in a buffer on the stack. The
+    /// slice is left untouched until it is partitioned, and slices shorter than the sample can be
+    /// sampled too. The sample has about `sqrt(len)` elements, but at most 256, so the pivots of
+    /// very long slices are less accurate than with [`Strategy::Sampling`]. The positions are
+    /// drawn from a generator seeded with the length of the slice and the index, so the same input
+    /// always takes the same steps.
+    SamplingWithReplacement,
+    /// The median-of-medians algorithm by Blum, Floyd, Pratt, Rivest and Tarjan. The pivot is
+    /// the median of the medians of groups of 5 elements, selected recursively, and the slice is
+    /// always partitioned in three parts. This guarantees that each round removes at least about
+    /// 3/10 of the elements, so the number of comparisons and swaps is bounded by a constant
+    /// times the length of the slice, regardless of the input. The worst case is at most about 70
+    /// comparisons per element, and in practice it is closer to 12. The pivots don't depend on
+    /// any randomness, so the same input always takes the same steps.
+    ///
+    /// This is slower than the other strategies on average, and is meant for applications that
+    /// need a predictable worst case, e.g. real-time audio processing. The bound holds with the
+    /// built-in partitioning, but not necessarily with a custom [`PartitionStrategy`].
+    MedianOfMedians,
+}
+
+/// Options for [`select_nth_unstable_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SelectOptions {
+    /// The algorithm used to choose the pivots.
+    pub strategy: Strategy,
+    /// Slices of at most this length are sorted instead of partitioned. Values below 8 are
+    /// treated as 8, and values above 128 as 128. If `None`, the cutoff of the crate is used,
+    /// scaled down for elements larger than 32 bytes. The best value depends on the cost of
+    /// comparing and moving the elements, and can differ by an order of magnitude between e.g.
+    /// `u32`s and 64-byte structs.
+    pub tinysort_cutoff: Option<usize>,
+    /// If `true`, the built-in default thresholds are used instead of ones tuned with
+    /// [`tune`](crate::tune) or scaled to the cache sizes of the processor. The algorithm doesn't
+    /// use any randomness, so the same input is then reordered exactly the same way on every
+    /// machine, for a given version of the crate. This is useful for reproducible outputs, e.g. in
+    /// tests that compare whole slices. [`Strategy::FloydRivest`] is an exception, since its
+    /// sample bounds are computed with floating-point functions that may round differently
+    /// between platforms.
+    pub deterministic: bool,
+    /// The number of rounds that may keep more than three quarters of the slice before the rest of
+    /// the pivots are chosen with [`Strategy::MedianOfMedians`]. If `None`, the limit of the crate
+    /// is used. Lower limits bound the worst case more tightly, at the cost of falling back on
+    /// inputs that the sampling would have handled after all. `Some(usize::MAX)` disables the
+    /// fallback.
+    pub bad_round_limit: Option<usize>,
+}
+
+impl SelectOptions {
+    /// Returns the thresholds for elements of type `T` with the overrides of the options applied.
+    pub(crate) fn params<T>(&self) -> Params {
+        let params = match self.deterministic {
+            true => Params::DEFAULT.sized::<T>(),
+            false => params::<T>(),
+        };
+        let params = match self.tinysort_cutoff {
+            Some(cutoff) => Params {
+                tinysort_cutoff: cutoff.clamp(MIN_TINYSORT_CUTOFF, MAX_TINYSORT_CUTOFF),
+                ..params
+            },
+            None => params,
+        };
+        match self.bad_round_limit {
+            Some(limit) => Params {
+                bad_round_limit: limit,
+                ..params
+            },
+            None => params,
+        }
+    }
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, like
+/// [`select_nth_unstable`](crate::select_nth_unstable), but with the algorithm chosen by
+/// `options`. This is mostly useful for comparing the strategies on a particular workload.
+///
+/// With [`Strategy::MedianOfMedians`], the number of comparisons and swaps is bounded by a
+/// constant times the length of the slice, for any input. The other strategies are faster on
+/// average, but their running time depends on how well the pivots happen to split the input. They
+/// fall back to the median of medians after [`SelectOptions::bad_round_limit`] rounds that split
+/// the input badly, so their worst case is linear too, with a larger constant.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_options, SelectOptions, Strategy};
+/// let mut v: Vec<u32> = (0..1000).rev().collect();
+/// let options = SelectOptions {
+///     strategy: Strategy::MedianOfNinthers,
+///     ..Default::default()
+/// };
+/// let (_, nth, _) = select_nth_unstable_with_options(&mut v, 10, &options);
+/// assert_eq!(*nth, 10);
+/// ```
+pub fn select_nth_unstable_with_options<'a, T>(
+    data: &'a mut [T],
+    index: usize,
+    options: &SelectOptions,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    T: Ord,
+{
+    select_nth_unstable_with_options_by_lt(data, index, options, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, with the algorithm chosen by `options`.
+///
+/// See [`select_nth_unstable_with_options`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_options_by, SelectOptions, Strategy};
+/// let mut v: Vec<f64> = (0..1000).map(|x| x as f64).collect();
+/// let options = SelectOptions {
+///     strategy: Strategy::FloydRivest,
+///     ..Default::default()
+/// };
+/// let (_, nth, _) = select_nth_unstable_with_options_by(&mut v, 10, &options, f64::total_cmp);
+/// assert_eq!(*nth, 10.0);
+/// ```
+pub fn select_nth_unstable_with_options_by<'a, T, F>(
+    data: &'a mut [T],
+    index: usize,
+    options: &SelectOptions,
+    mut compare: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_options_by_lt(data, index, options, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, with the algorithm chosen by `options`.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_options`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_options_by_lt, SelectOptions, Strategy};
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// let options = SelectOptions {
+///     strategy: Strategy::MedianOfMedians,
+///     ..Default::default()
+/// };
+/// let (_, nth, _) = select_nth_unstable_with_options_by_lt(&mut v, 10, &options, |a, b| a > b);
+/// assert_eq!(*nth, 989);
+/// ```
+pub fn select_nth_unstable_with_options_by_lt<'a, T, F>(
+    data: &'a mut [T],
+    index: usize,
+    options: &SelectOptions,
+    mut lt: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    // If there are less than two elements, there is nothing to do. If `T` is a zero sized type, it
+    // cannot have any meaningful ordering, so we just return.
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, index);
+    }
+
+    if index == 0 {
+        select_min(data, &mut lt);
+    } else if index == data.len() - 1 {
+        select_max(data, &mut lt);
+    } else {
+        select_using(
+            data,
+            index,
+            &mut lt,
+            &options.params::<T>(),
+            options.strategy,
+        );
+    }
+    split_partition(data, index)
+}
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, like
+/// [`select_nth_unstable_with_options`], but partitions the slice with the given partition
+/// strategy. The pivot selection, the detection of sorted input and the fallbacks for repeated
+/// pivots are the same as with the built-in partitioning.
+///
+/// The worst-case bound of [`Strategy::MedianOfMedians`] holds if `partition` makes a bounded
+/// number of comparisons per element and groups the elements equal to the pivot when the pivot is
+/// marked as repeated.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_partition, SelectOptions, TernaryPartition};
+/// // Many duplicates, which the three-way partitioning groups together.
+/// let mut v: Vec<u32> = (0..1000).map(|x| x % 7).collect();
+/// let options = SelectOptions::default();
+/// let (_, nth, _) = select_nth_unstable_with_partition(&mut v, 500, &options, &TernaryPartition);
+/// assert_eq!(*nth, 3);
+/// ```
+pub fn select_nth_unstable_with_partition<'a, T, P>(
+    data: &'a mut [T],
+    index: usize,
+    options: &SelectOptions,
+    partition: &P,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    T: Ord,
+    P: PartitionStrategy<T> + ?Sized,
+{
+    select_nth_unstable_with_partition_by_lt(data, index, options, partition, T::lt)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, partitioning the slice with the given partition strategy.
+///
+/// See [`select_nth_unstable_with_partition`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_partition_by, SelectOptions, TernaryPartition};
+/// let mut v: Vec<(u32, char)> = (0..1000).map(|x| (x % 7, 'a')).collect();
+/// let options = SelectOptions::default();
+/// let (_, nth, _) = select_nth_unstable_with_partition_by(
+///     &mut v,
+///     500,
+///     &options,
+///     &TernaryPartition,
+///     |a, b| a.0.cmp(&b.0),
+/// );
+/// assert_eq!(nth.0, 3);
+/// ```
+pub fn select_nth_unstable_with_partition_by<'a, T, P, F>(
+    data: &'a mut [T],
+    index: usize,
+    options: &SelectOptions,
+    partition: &P,
+    mut compare: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    P: PartitionStrategy<T> + ?Sized,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_partition_by_lt(data, index, options, partition, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, partitioning the slice with the given partition strategy.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_partition`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable_with_partition_by_lt, BlockPartition, SelectOptions};
+/// let mut v: Vec<u32> = (0..1000).collect();
+/// let options = SelectOptions::default();
+/// let partition = BlockPartition::default();
+/// let (_, nth, _) =
+///     select_nth_unstable_with_partition_by_lt(&mut v, 0, &options, &partition, |a, b| a > b);
+/// assert_eq!(*nth, 999);
+/// ```
+pub fn select_nth_unstable_with_partition_by_lt<'a, T, P, F>(
+    data: &'a mut [T],
+    index: usize,
+    options: &SelectOptions,
+    partition: &P,
+    mut lt: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    P: PartitionStrategy<T> + ?Sized,
+    F: FnMut(&T, &T) -> bool,
+{
+    // If there are less than two elements, there is nothing to do. If `T` is a zero sized type, it
+    // cannot have any meaningful ordering, so we just return.
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, index);
+    }
+
+    if index == 0 {
+        select_min(data, &mut lt);
+    } else if index == data.len() - 1 {
+        select_max(data, &mut lt);
+    } else {
+        let params = options.params::<T>();
+        select_partitioned(
+            data,
+            index,
+            &mut lt,
+            &params,
+            options.strategy,
+            partition,
+            &mut (),
+        );
+    }
+    split_partition(data, index)
+}
+
+/// A partitioning algorithm that [`select_nth_unstable_with_partition`] uses to split the slice
+/// around a pivot. Implement this to supply a custom partitioning kernel, e.g. one specialized
+/// for an element type or for particular hardware, while reusing the rest of the selection
+/// algorithm.
+///
+/// The partitioning must be correct for the selection to be correct. Incorrect results from
+/// `partition` can't cause undefined behavior, but the selection may then panic or produce an
+/// arbitrary order.
+pub trait PartitionStrategy<T> {
+    /// Partitions `data` around the element at `pivot`, using `lt` to compare the elements.
+    /// `is_repeated` is `true` if the pivot is likely to have many duplicates in the slice.
+    ///
+    /// Returns `(u, v)`, where `u` is the number of elements less than the pivot and `u <= v`.
+    /// After the call, the pivot must be at `data[u]`, the elements in `data[..u]` must be less
+    /// than the pivot, the elements in `data[u..=v]` must be equal to it, and the elements in
+    /// `data[v + 1..]` must not be less than it.
+    ///
+    /// If `is_repeated` is `true`, at least some of the elements equal to the pivot should be put
+    /// in `data[u..=v]`. Otherwise, the selection might not terminate on slices with many
+    /// duplicates.
+    fn partition<F>(
+        &self,
+        data: &mut [T],
+        pivot: usize,
+        is_repeated: bool,
+        lt: &mut F,
+    ) -> (usize, usize)
+    where
+        F: FnMut(&T, &T) -> bool;
+
+    /// Returns the order of `data` with respect to `lt`. The selection checks this before the
+    /// first round, and ends early if the slice is sorted. The default compares the adjacent
+    /// elements with `lt`, like [`detect_order`](crate::detect_order). Override it together with
+    /// `partition` to check the order with comparisons specialized for the element type.
+    fn sort_order<F>(&self, data: &[T], lt: &mut F) -> SortOrder
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        sort_order(data, lt)
+    }
+}
+
+/// The default partitioning. The slice is split in two parts by swapping blocks of out-of-order
+/// elements from both ends. If the pivot is likely to have many duplicates, the slice is split in
+/// three parts instead, grouping the elements equal to the pivot together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockPartition {
+    /// The number of elements scanned at a time on each side of the slice, or `None` to use the
+    /// size chosen for the element type.
+    pub(crate) block: Option<usize>,
+}
+
+impl<T> PartitionStrategy<T> for BlockPartition {
+    fn partition<F>(
+        &self,
+        data: &mut [T],
+        pivot: usize,
+        is_repeated: bool,
+        lt: &mut F,
+    ) -> (usize, usize)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        let block = self.block.unwrap_or_else(|| params::<T>().partition_block);
+        match is_repeated {
+            // Ternary partitioning puts the elements equal to the pivot in the middle, which is
+            // necessary to ensure that the algorithm terminates.
+            true => partition_equal(data, pivot, lt, block),
+            false => partition_at(data, pivot, lt, block),
+        }
+    }
+}
+
+/// Three-way partitioning, which always groups the elements equal to the pivot together. If only a
+/// few elements are less than or equal to the pivot, this takes a single pass that moves just
+/// these elements. Otherwise, it makes an additional pass over the elements greater than or equal
+/// to the pivot. This can be faster than [`BlockPartition`] if the slice has only a few distinct
+/// values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TernaryPartition;
+
+impl<T> PartitionStrategy<T> for TernaryPartition {
+    fn partition<F>(&self, data: &mut [T], pivot: usize, _: bool, lt: &mut F) -> (usize, usize)
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        partition_equal(data, pivot, lt, params::<T>().partition_block)
+    }
+}
+
+/// Chooses the pivot for `Strategy::MedianOfNinthers`. Returns `(p, is_repeated)` like
+/// `choose_pivot`.
+pub(crate) fn adaptive_pivot<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: &mut F,
+    params: &Params,
+) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = data.len();
+    if len < 12 {
+        choose_pivot(data, index, lt, params)
+    } else if index * 6 <= len {
+        median_of_minima(data, index, lt, params)
+    } else if index * 6 >= len * 5 {
+        median_of_maxima(data, index, lt, params)
+    } else {
+        median_of_ninthers(data, index, lt, params)
+    }
+}
+
+/// The largest sample drawn by `Strategy::SamplingWithReplacement`.
+const MAX_REPLACEMENT_SAMPLE: usize = 256;
+
+/// Chooses the pivot for `Strategy::SamplingWithReplacement`. Returns `(p, is_repeated)` like
+/// `choose_pivot`, but doesn't move any elements of `data`.
+pub(crate) fn replacement_pivot<T, F>(
+    data: &[T],
+    index: usize,
+    lt: &mut F,
+    params: &Params,
+) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    const ALPHA: f64 = 0.75;
+    let len = data.len();
+    let count = (ceil(ALPHA * sqrt(len as f64)) as usize).clamp(3, MAX_REPLACEMENT_SAMPLE);
+    let index = nudge(index, len);
+    let k = mul_div(count, index, len);
+
+    let mut rng = WyRng::new(((len as u64) << 32) ^ index as u64 ^ 0x9e37_79b9_7f4a_7c15);
+    let mut positions = [0; MAX_REPLACEMENT_SAMPLE];
+    let positions = &mut positions[..count];
+    positions
+        .iter_mut()
+        .for_each(|p| *p = rng.bounded_usize(0, len));
+
+    // The positions are compared through a trait object, so that selecting them doesn't
+    // instantiate the selection for yet another comparison function.
+    let mut by_position = |a: &usize, b: &usize| lt(&data[*a], &data[*b]);
+    let mut by_position: &mut dyn FnMut(&usize, &usize) -> bool = &mut by_position;
+    select_with(positions, k, &mut by_position, params);
+    let is_repeated = match k {
+        0 => !by_position(&positions[k], &positions[k + 1]),
+        k if k == count - 1 => !by_position(&positions[k - 1], &positions[k]),
+        _ => !by_position(&positions[k - 1], &positions[k + 1]),
+    };
+    (positions[k], is_repeated)
+}
+
+/// Chooses the pivot for `Strategy::MedianOfMedians`. Moves the medians of groups of 5 elements
+/// to the beginning of the slice and selects their median. The pivot is always treated as
+/// repeated, so that the elements equal to it are grouped together.
+pub(crate) fn median_of_medians<T, F>(data: &mut [T], lt: &mut F, params: &Params) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let groups = medians_of_fives(data, lt);
+    let medians = &mut data[..groups];
+    select_using(medians, groups / 2, lt, params, Strategy::MedianOfMedians);
+    (groups / 2, true)
+}
+
+/// Sorts the groups of 5 elements of the slice, moves their medians to the beginning of the slice
+/// and returns their count. Each group is sorted with 9 comparisons.
+pub(crate) fn medians_of_fives<T, F>(data: &mut [T], lt: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    // The medians are written to positions that belong to groups that are already processed.
+    let groups = data.len() / 5;
+    for g in 0..groups {
+        tinysort(&mut data[5 * g..5 * g + 5], lt);
+        data.swap(g, 5 * g + 2);
+    }
+    groups
+}
+
+/// Moves the minima of `2 * index` groups of elements to the beginning of the slice, and selects
+/// the pivot at `index` from them. Requires that `0 < index <= len / 6`.
+fn median_of_minima<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: &mut F,
+    params: &Params,
+) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let subset = 2 * index;
+    let (sample, rest) = data.split_at_mut(subset);
+    let group = rest.len() / subset;
+    for (elem, chunk) in sample.iter_mut().zip(rest.chunks_exact_mut(group)) {
+        for other in chunk {
+            if lt(other, elem) {
+                mem::swap(other, elem);
+            }
+        }
+    }
+    select_using(sample, index, lt, params, Strategy::MedianOfNinthers);
+    (index, is_repeated(sample, index, lt))
+}
+
+/// Moves the maxima of `2 * (len - 1 - index)` groups of elements to the end of the slice, and
+/// selects the pivot at `index` from them. Requires that `len * 5 / 6 <= index < len - 1`.
+fn median_of_maxima<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: &mut F,
+    params: &Params,
+) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let from_end = data.len() - 1 - index;
+    let subset = 2 * from_end;
+    let start = data.len() - subset;
+    let (rest, sample) = data.split_at_mut(start);
+    let group = rest.len() / subset;
+    for (elem, chunk) in sample.iter_mut().zip(rest.chunks_exact_mut(group)) {
+        for other in chunk {
+            if lt(elem, other) {
+                mem::swap(other, elem);
+            }
+        }
+    }
+    let k = subset - 1 - from_end;
+    select_using(sample, k, lt, params, Strategy::MedianOfNinthers);
+    (start + k, is_repeated(sample, k, lt))
+}
+
+/// Computes the medians of ninthers of `9 * frac` elements around the middle of the slice into
+/// `frac` elements in the middle, and selects the pivot from them at the relative position of
+/// `index`. Requires that `len >= 12`.
+fn median_of_ninthers<T, F>(
+    data: &mut [T],
+    index: usize,
+    lt: &mut F,
+    params: &Params,
+) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let len = data.len();
+    let frac = match len {
+        len if len <= 1024 => len / 12,
+        // Compared as a `u64`, so that the constant fits on targets with a 16-bit `usize`.
+        len if len as u64 <= 128 * 1024 => len / 64,
+        len => len / 1024,
+    };
+
+    // The ninthers are taken from three runs of `3 * frac` elements: one to the left of the middle,
+    // one in the middle and one to the right of it, separated by gaps of equal length.
+    let lo = len / 2 - frac / 2;
+    let hi = lo + frac;
+    let gap = (len - 9 * frac) / 4;
+    let (mut a, mut b) = (lo - 4 * frac - gap, hi + frac + gap);
+    for i in lo..hi {
+        ninther(
+            data,
+            [a, i - frac, b, a + 1, i, b + 1, a + 2, i + frac, b + 2],
+            lt,
+        );
+        a += 3;
+        b += 3;
+    }
+
+    let sample = &mut data[lo..hi];
+    let k = mul_div(frac, index, len);
+    select_using(sample, k, lt, params, Strategy::MedianOfNinthers);
+    (lo + k, is_repeated(sample, k, lt))
+}
+
+/// Moves the median of the medians of the triplets in `at` to `at[4]`.
+fn ninther<T, F>(data: &mut [T], at: [usize; 9], lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    median_of_3(data, at[0], at[1], at[2], lt);
+    median_of_3(data, at[3], at[4], at[5], lt);
+    median_of_3(data, at[6], at[7], at[8], lt);
+    median_of_3(data, at[1], at[4], at[7], lt);
+}
+
+/// Sorts the elements at `a`, `b` and `c`, which moves their median to `b`.
+fn median_of_3<T, F>(data: &mut [T], a: usize, b: usize, c: usize, lt: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    if lt(&data[b], &data[a]) {
+        data.swap(a, b);
+    }
+    if lt(&data[c], &data[b]) {
+        data.swap(b, c);
+        if lt(&data[b], &data[a]) {
+            data.swap(a, b);
+        }
+    }
+}
+
+/// Returns `true` if the selected element at `k` in `sample` is equal to one of its neighbors,
+/// which means that the pivot is likely to have many duplicates.
+fn is_repeated<T, F>(sample: &[T], k: usize, lt: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    (k > 0 && !lt(&sample[k - 1], &sample[k]))
+        || (k + 1 < sample.len() && !lt(&sample[k], &sample[k + 1]))
+}
+
+/// The length of the slices from which `Strategy::FloydRivest` draws a sample.
+const FLOYD_RIVEST_MIN: usize = 600;
+
+/// Partitions the slice like `select`, using the Floyd–Rivest algorithm.
+pub(crate) fn floyd_rivest<T, F, P, O>(
+    mut data: &mut [T],
+    mut index: usize,
+    lt: &mut F,
+    params: &Params,
+    partition: &P,
+    observer: &mut O,
+) where
+    F: FnMut(&T, &T) -> bool,
+    P: PartitionStrategy<T> + ?Sized,
+    O: Observer,
+{
+    let mut bad_rounds = BadRounds::default();
+    while data.len() > FLOYD_RIVEST_MIN && !bad_rounds.exceeded(params) {
+        // Choose the bounds of the sample so that the selected element is between the pivots with
+        // high probability.
+        let len = data.len();
+        let n = len as f64;
+        let z = ln(n);
+        let s = 0.5 * powf(n, 2. / 3.);
+        let gap = 0.5 * sqrt(z * s * (n - s) / n);
+        let count = s as usize;
+        let k = (index as f64 * s / n) as usize;
+        let lo = (k as f64 - gap).max(0.) as usize;
+        let hi = ((k as f64 + gap) as usize).clamp(lo + 1, count - 1);
+
+        // Select the pivots from the sample and move them to the ends of the slice.
+        let sample = sample(data, count);
+        select_using(sample, lo, lt, params, Strategy::FloydRivest);
+        select_using(
+            &mut sample[lo + 1..],
+            hi - lo - 1,
+            lt,
+            params,
+            Strategy::FloydRivest,
+        );
+        data.swap(0, lo);
+        data.swap(len - 1, hi);
+
+        let (u, v) = partition_between(data, lt, params.partition_block);
+        observer.round(Round {
+            len,
+            index,
+            kind: RoundKind::TwoPivots,
+            below: u,
+            middle: v - u,
+            bracketed: u <= index && index < v,
+        });
+        if index < u {
+            data = &mut data[..u];
+        } else if index >= v {
+            data = &mut data[v..];
+            index -= v;
+        } else if !lt(&data[u], &data[v - 1]) {
+            // The pivots are equal, so all elements in the middle part are equal to them.
+            return;
+        } else if v - u == len {
+            // All elements are between the pivots, e.g. because there are many duplicates.
+            break;
+        } else {
+            data = &mut data[u..v];
+            index -= u;
+        }
+        bad_rounds.count(len, data.len());
+    }
+    // After too many bad rounds, the rest of the pivots are chosen with the median of medians.
+    let strategy = match bad_rounds.exceeded(params) {
+        false => Strategy::Sampling,
+        true => Strategy::MedianOfMedians,
+    };
+    select_partitioned(data, index, lt, params, strategy, partition, observer);
+}
+
+/// Partitions `data` in three parts around the pivots at the first and the last position of the
+/// slice. The first pivot must not be greater than the last one. Returns `(u, v)`, where `u` is
+/// the number of elements less than the first pivot, and `v` is the number of elements less than
+/// or equal to the last pivot.
+///
+/// The resulting partitioning is:
+///
+/// ```text
+/// ┌─────────────┬─────────────────────────────┬─────────────────┐
+/// │ x < data[u] │ data[u] <= x <= data[v - 1] │ x > data[v - 1] │
+/// └─────────────┴─────────────────────────────┴─────────────────┘
+///                u                             v
+/// ```
+fn partition_between<T, F>(data: &mut [T], lt: &mut F, block: usize) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let (first, rest) = data.split_first_mut().unwrap();
+    let (last, middle) = rest.split_last_mut().unwrap();
+    let (l, r) = partition_range(middle, first, last, lt, block);
+
+    // Move the pivots between the parts.
+    data.swap(0, l);
+    data.swap(data.len() - 1, r + 1);
+    (l, r + 2)
+}
@@ -0,0 +1,942 @@
+//! Approximate quantiles of streams that are too long to keep in memory.
+//!
+//! The selection functions of the crate find exact quantiles, but need all of the elements in a
+//! slice. The estimators in this module see each value once and keep a summary of a fixed size
+//! instead, so they can track a quantile of an unbounded stream, e.g. of request latencies, at the
+//! cost of an approximate result.
+//!
+//! [`OnlineQuantile`] tracks a single quantile in constant memory. [`TDigest`] and [`GkSummary`]
+//! keep summaries from which any quantile can be estimated, and summaries of different streams,
+//! e.g. from different machines, can be merged. `TDigest` is usually more accurate near the
+//! extremes, while `GkSummary` guarantees the error in the rank of every quantile and works with
+//! any ordered values. They require the `std` feature.
+
+#[cfg(feature = "std")]
+use core::f64::consts::PI;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::math::floor;
+#[cfg(feature = "std")]
+use crate::math::{asin, ceil, lerp, sin};
+
+/// Estimates a quantile of a stream of numbers in constant memory with the P² algorithm of Jain and
+/// Chlamtac.
+///
+/// The estimator keeps five markers: the minimum, the maximum, the estimated `p`th quantile, and
+/// the estimated `p / 2`th and `(1 + p) / 2`th quantiles between them. Each marker has a height,
+/// which is the estimate, and a position, which is the number of values seen so far that are below
+/// it. When a value is inserted, the positions of the markers above it are incremented, and any
+/// middle marker that has drifted at least one position away from where its quantile should be is
+/// moved by one position, with its height adjusted by fitting a parabola through it and its
+/// neighbours. Inserting a value takes constant time, and nothing is allocated.
+///
+/// The first five values are kept exactly, so until then the estimate is exact. After that, the
+/// estimate is usually within a fraction of a percentile of the true quantile for smooth
+/// distributions, but there is no guaranteed bound on the error, and it can be larger for
+/// distributions with gaps or heavy tails. Use [`percentile`](crate::percentile) when the values
+/// fit in memory and the exact quantile is needed.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::streaming::OnlineQuantile;
+/// let mut p90 = OnlineQuantile::new(0.9);
+/// for i in 0..10_000u32 {
+///     // A permutation of 0..10_000.
+///     p90.insert(f64::from(i * 7919 % 10_000));
+/// }
+/// let estimate = p90.estimate().unwrap();
+/// assert!((estimate - 9000.0).abs() < 100.0);
+/// assert_eq!(p90.min(), Some(0.0));
+/// assert_eq!(p90.max(), Some(9999.0));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OnlineQuantile {
+    /// The quantile to track, in the range `[0.0, 1.0]`.
+    p: f64,
+    /// The number of values inserted, not counting NaNs.
+    count: u64,
+    /// The heights of the markers in ascending order, or the first values in sorted order while
+    /// fewer than five values have been inserted.
+    heights: [f64; 5],
+    /// The positions of the markers, i.e. the number of values below each of them. The first is
+    /// always 0 and the last `count - 1`.
+    positions: [u64; 5],
+}
+
+impl OnlineQuantile {
+    /// Returns an estimator of the `p`th quantile, e.g. `0.5` for the median or `0.99` for the
+    /// 99th percentile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 1.0]`.
+    pub fn new(p: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "`p` must be in the range [0.0, 1.0]"
+        );
+        Self {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [0, 1, 2, 3, 4],
+        }
+    }
+
+    /// Returns the quantile that is tracked.
+    pub fn quantile(&self) -> f64 {
+        self.p
+    }
+
+    /// Returns `true` if no values have been inserted.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the number of values inserted, not counting NaNs.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Inserts `x` into the stream. NaNs are ignored.
+    pub fn insert(&mut self, x: f64) {
+        if x.is_nan() {
+            return;
+        }
+        if self.count < 5 {
+            // Insertion sort, so that the estimate is exact and the markers are ready at five.
+            let mut i = self.count as usize;
+            while i > 0 && x < self.heights[i - 1] {
+                self.heights[i] = self.heights[i - 1];
+                i -= 1;
+            }
+            self.heights[i] = x;
+            self.count += 1;
+            return;
+        }
+
+        // The markers above `x` move up by one position. The extreme markers follow `x` if it is
+        // a new minimum or maximum.
+        let heights = &mut self.heights;
+        if x < heights[0] {
+            heights[0] = x;
+        }
+        if x > heights[4] {
+            heights[4] = x;
+        }
+        let cell = heights[1..4].iter().filter(|&&h| h <= x).count();
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1;
+        }
+        self.count += 1;
+
+        // The quantile that each marker tracks, which is also how far its desired position moves
+        // with each value.
+        let p = self.p;
+        let increments = [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0];
+        for i in 1..4 {
+            let positions = &self.positions;
+            let desired = increments[i] * (self.count - 1) as f64;
+            let drift = desired - positions[i] as f64;
+            let step = if drift >= 1.0 && positions[i + 1] - positions[i] > 1 {
+                1.0
+            } else if drift <= -1.0 && positions[i] - positions[i - 1] > 1 {
+                -1.0
+            } else {
+                continue;
+            };
+            let [n0, n1, n2] = [positions[i - 1], positions[i], positions[i + 1]].map(|n| n as f64);
+            let [h0, h1, h2] = [heights[i - 1], heights[i], heights[i + 1]];
+            let parabolic = h1
+                + step / (n2 - n0)
+                    * ((n1 - n0 + step) * (h2 - h1) / (n2 - n1)
+                        + (n2 - n1 - step) * (h1 - h0) / (n1 - n0));
+            heights[i] = if h0 < parabolic && parabolic < h2 {
+                parabolic
+            } else if step > 0.0 {
+                h1 + (h2 - h1) / (n2 - n1)
+            } else {
+                h1 - (h1 - h0) / (n1 - n0)
+            };
+            match step > 0.0 {
+                true => self.positions[i] += 1,
+                false => self.positions[i] -= 1,
+            }
+        }
+    }
+
+    /// Returns the estimated `p`th quantile of the values inserted so far, or `None` if no values
+    /// have been inserted. While at most five values have been inserted, this is the value at
+    /// position `floor(p * (len - 1))` of the sorted values, like [`percentile`](crate::percentile).
+    pub fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count @ 1..=5 => {
+                let rank = floor(self.p * (count - 1) as f64) as usize;
+                Some(self.heights[rank])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+
+    /// Returns the smallest value inserted, or `None` if no values have been inserted.
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.heights[0])
+    }
+
+    /// Returns the largest value inserted, or `None` if no values have been inserted.
+    pub fn max(&self) -> Option<f64> {
+        let last = self.count.min(5).checked_sub(1)?;
+        Some(self.heights[last as usize])
+    }
+}
+
+/// Checks that the quantile is in range and that the heights and positions of the markers are in
+/// order, so that a restored estimator stays consistent as more values are inserted.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for OnlineQuantile {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "OnlineQuantile")]
+        struct State {
+            p: f64,
+            count: u64,
+            heights: [f64; 5],
+            positions: [u64; 5],
+        }
+
+        let state = State::deserialize(deserializer)?;
+        if !(0.0..=1.0).contains(&state.p) {
+            return Err(D::Error::custom("`p` must be in the range [0.0, 1.0]"));
+        }
+        let heights = &state.heights[..state.count.min(5) as usize];
+        if heights.iter().any(|h| h.is_nan()) || !heights.windows(2).all(|w| w[0] <= w[1]) {
+            return Err(D::Error::custom("the heights must be sorted"));
+        }
+        let positions = &state.positions;
+        if positions[0] != 0
+            || positions[4] != state.count.max(5) - 1
+            || !positions.windows(2).all(|w| w[0] < w[1])
+        {
+            return Err(D::Error::custom(
+                "the positions must increase from 0 to the count",
+            ));
+        }
+        Ok(Self {
+            p: state.p,
+            count: state.count,
+            heights: state.heights,
+            positions: state.positions,
+        })
+    }
+}
+
+/// Estimates quantiles of a stream of numbers from a mergeable summary, the t-digest of Dunning and
+/// Ertl.
+///
+/// The digest clusters the values into centroids, each of which is summarized by the mean and the
+/// number of its values. The centroids near the extremes are kept small and those near the median
+/// are allowed to grow, so that the error in the rank of an estimate shrinks towards the extremes,
+/// roughly in proportion to `sqrt(p * (1 - p)) / compression`. The exception are the first and the
+/// last `(PI / compression)^2` of the values, which may share a centroid each. The number of
+/// centroids is bounded by about the `compression` parameter, regardless of how many values are
+/// inserted. Since the estimates interpolate between the centroids, a quantile of values with many
+/// duplicates may fall between two adjacent distinct values.
+///
+/// Inserted values are buffered and merged into the centroids in batches, so that inserting a value
+/// takes amortized *O*(log *compression*) time. Digests of different streams can be combined with
+/// [`merge`](Self::merge), which gives about the same summary as inserting all of the values into
+/// one digest, e.g. to compute the percentiles of a metric over a cluster from per-machine digests.
+/// The minimum and the maximum are kept exactly.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::streaming::TDigest;
+/// let mut shards = [TDigest::new(100.0), TDigest::new(100.0)];
+/// for i in 0..100_000u32 {
+///     // A permutation of 0..100_000, split between the shards.
+///     shards[i as usize % 2].insert(f64::from(i * 7919 % 100_000));
+/// }
+/// let [mut digest, other] = shards;
+/// digest.merge(&other);
+/// assert_eq!(digest.len(), 100_000);
+///
+/// let p99 = digest.quantile(0.99).unwrap();
+/// assert!((p99 - 99_000.0).abs() < 100.0);
+/// let median = digest.quantile(0.5).unwrap();
+/// assert!((median - 50_000.0).abs() < 1000.0);
+/// assert_eq!(digest.quantile(1.0), Some(99_999.0));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TDigest {
+    /// Bounds the number of centroids. Larger values give more accurate estimates.
+    compression: f64,
+    /// The number of values inserted, not counting NaNs.
+    count: u64,
+    /// The smallest value inserted, or 0.0 if the digest is empty.
+    min: f64,
+    /// The largest value inserted, or 0.0 if the digest is empty.
+    max: f64,
+    /// The merged centroids, sorted by their means.
+    centroids: Vec<Centroid>,
+    /// The values and the centroids of other digests that are not merged yet.
+    buffer: Vec<Centroid>,
+}
+
+/// A cluster of the values in a [`TDigest`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Centroid {
+    /// The mean of the values.
+    mean: f64,
+    /// The number of values.
+    weight: u64,
+}
+
+#[cfg(feature = "std")]
+impl Default for TDigest {
+    /// Returns a digest with a compression of 100.
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TDigest {
+    /// Returns an empty digest that keeps about `compression` centroids at most. Values between 50
+    /// and 1000 are typical, and the default is 100.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `compression` is less than 1 or not finite.
+    pub fn new(compression: f64) -> Self {
+        assert!(
+            (1.0..f64::INFINITY).contains(&compression),
+            "the compression must be at least 1"
+        );
+        Self {
+            compression,
+            count: 0,
+            min: 0.0,
+            max: 0.0,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the compression of the digest.
+    pub fn compression(&self) -> f64 {
+        self.compression
+    }
+
+    /// Returns `true` if no values have been inserted.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the number of values inserted, including those of merged digests, not counting
+    /// NaNs.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the smallest value inserted, or `None` if no values have been inserted.
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Returns the largest value inserted, or `None` if no values have been inserted.
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Inserts `x` into the digest. NaNs are ignored.
+    pub fn insert(&mut self, x: f64) {
+        if x.is_nan() {
+            return;
+        }
+        self.extend_range(x, x);
+        self.count += 1;
+        self.buffer.push(Centroid { mean: x, weight: 1 });
+        if self.buffer.len() >= self.buffer_capacity() {
+            self.compress();
+        }
+    }
+
+    /// Adds the values summarized by `other` to the digest. The result doesn't depend much on the
+    /// order in which digests are merged, but it isn't exactly the same as inserting the values
+    /// into one digest.
+    pub fn merge(&mut self, other: &TDigest) {
+        if other.is_empty() {
+            return;
+        }
+        self.extend_range(other.min, other.max);
+        self.count += other.count;
+        self.buffer.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        if self.buffer.len() >= self.buffer_capacity() {
+            self.compress();
+        }
+    }
+
+    /// Returns the estimated `p`th quantile of the values inserted, or `None` if no values have
+    /// been inserted. The buffered values are merged into the centroids first.
+    ///
+    /// The estimate interpolates linearly between the means of adjacent centroids, and between the
+    /// extreme centroids and the minimum and the maximum. While a centroid holds a single value,
+    /// that value is returned exactly, so the quantiles of a few values are exact, and, like
+    /// [`percentile`](crate::percentile), `p = 0.0` gives the minimum and `p = 1.0` the maximum.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 1.0]`.
+    pub fn quantile(&mut self, p: f64) -> Option<f64> {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "`p` must be in the range [0.0, 1.0]"
+        );
+        if self.count == 0 {
+            return None;
+        }
+        self.compress();
+        let centroids = &self.centroids;
+        let (first, last) = (centroids[0], centroids[centroids.len() - 1]);
+        let total = self.count as f64;
+        let index = p * total;
+        if index < 1.0 {
+            return Some(self.min);
+        }
+        if index > total - 1.0 {
+            return Some(self.max);
+        }
+        // The values of the extreme centroids are assumed to be spread evenly between their means
+        // and the minimum or the maximum, which are single values.
+        let half = first.weight as f64 / 2.0;
+        if first.weight > 2 && index < half {
+            return Some(lerp(self.min, first.mean, (index - 1.0) / (half - 1.0)));
+        }
+        let half = last.weight as f64 / 2.0;
+        if last.weight > 2 && total - index <= half {
+            return Some(lerp(
+                self.max,
+                last.mean,
+                (total - index - 1.0) / (half - 1.0),
+            ));
+        }
+
+        // The number of values before the mean of the left centroid.
+        let mut before = first.weight as f64 / 2.0;
+        for pair in centroids.windows(2) {
+            let (left, right) = (pair[0], pair[1]);
+            let gap = (left.weight + right.weight) as f64 / 2.0;
+            if before + gap > index {
+                // A single value is exact for half a value on either side of it.
+                let (mut skip_left, mut skip_right) = (0.0, 0.0);
+                if left.weight == 1 {
+                    if index - before < 0.5 {
+                        return Some(left.mean);
+                    }
+                    skip_left = 0.5;
+                }
+                if right.weight == 1 {
+                    if before + gap - index <= 0.5 {
+                        return Some(right.mean);
+                    }
+                    skip_right = 0.5;
+                }
+                let t = (index - before - skip_left) / (gap - skip_left - skip_right);
+                return Some(lerp(left.mean, right.mean, t));
+            }
+            before += gap;
+        }
+        let t = (index - before) / (last.weight as f64 / 2.0);
+        Some(lerp(last.mean, self.max, t.clamp(0.0, 1.0)))
+    }
+
+    /// Returns the number of centroids to buffer before merging them.
+    fn buffer_capacity(&self) -> usize {
+        ceil(5.0 * self.compression) as usize
+    }
+
+    /// Extends the range of the values inserted to include `min` and `max`.
+    fn extend_range(&mut self, min: f64, max: f64) {
+        if self.count == 0 {
+            (self.min, self.max) = (min, max);
+        } else {
+            self.min = self.min.min(min);
+            self.max = self.max.max(max);
+        }
+    }
+
+    /// Merges the buffered centroids into the sorted ones.
+    ///
+    /// The centroids are merged in order of their means as long as the merged centroid spans at
+    /// most one unit of the scale function `k(q) = compression / (2 * PI) * asin(2 * q - 1)`,
+    /// where `q` is the fraction of the values below a centroid. The scale function is steep near
+    /// 0 and 1, which keeps the centroids at the extremes small.
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let scale = self.compression / (2.0 * PI);
+        let k = |q: f64| scale * asin(2.0 * q - 1.0);
+        let q = |k: f64| (1.0 + sin((k / scale).min(PI / 2.0))) / 2.0;
+
+        let mut centroids = core::mem::take(&mut self.centroids);
+        centroids.append(&mut self.buffer);
+        centroids.sort_unstable_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total = self.count as f64;
+        // The number of values in the centroids before the current one, and the largest number of
+        // values that can be before the next one.
+        let mut before = 0;
+        let mut limit = total * q(k(0.0) + 1.0);
+        let mut current = 0;
+        for i in 1..centroids.len() {
+            let next = centroids[i];
+            let merged = &mut centroids[current];
+            if (before + merged.weight + next.weight) as f64 <= limit {
+                merged.weight += next.weight;
+                let mean = merged.mean
+                    + (next.mean - merged.mean) * next.weight as f64 / merged.weight as f64;
+                // Rounding must not move the mean past the next one.
+                merged.mean = mean.min(next.mean);
+            } else {
+                before += merged.weight;
+                limit = total * q(k(before as f64 / total) + 1.0);
+                current += 1;
+                centroids[current] = next;
+            }
+        }
+        centroids.truncate(current + 1);
+        self.centroids = centroids;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Extend<f64> for TDigest {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        for x in iter {
+            self.insert(x);
+        }
+    }
+}
+
+/// Checks that the centroids are sorted, that their weights add up to the count, and that their
+/// means are within the range of the values, so that a restored digest gives consistent estimates.
+#[cfg(all(feature = "std", feature = "serde"))]
+impl<'de> serde::Deserialize<'de> for TDigest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "TDigest")]
+        struct State {
+            compression: f64,
+            count: u64,
+            min: f64,
+            max: f64,
+            centroids: Vec<Centroid>,
+            buffer: Vec<Centroid>,
+        }
+
+        let state = State::deserialize(deserializer)?;
+        if !(1.0..f64::INFINITY).contains(&state.compression) {
+            return Err(D::Error::custom("the compression must be at least 1"));
+        }
+        if !state.centroids.windows(2).all(|w| w[0].mean <= w[1].mean) {
+            return Err(D::Error::custom("the centroids must be sorted"));
+        }
+        let all = state.centroids.iter().chain(&state.buffer);
+        if all.clone().any(|c| c.weight == 0) {
+            return Err(D::Error::custom("the weights must be positive"));
+        }
+        if all.clone().map(|c| c.weight).sum::<u64>() != state.count {
+            return Err(D::Error::custom("the weights must add up to the count"));
+        }
+        if all
+            .clone()
+            .any(|c| !(state.min..=state.max).contains(&c.mean))
+        {
+            return Err(D::Error::custom(
+                "the means must be between the minimum and the maximum",
+            ));
+        }
+        Ok(Self {
+            compression: state.compression,
+            count: state.count,
+            min: state.min,
+            max: state.max,
+            centroids: state.centroids,
+            buffer: state.buffer,
+        })
+    }
+}
+
+/// An ε-approximate quantile summary of a stream of ordered values, with the algorithm of Greenwald
+/// and Khanna.
+///
+/// The summary keeps a sorted subset of the values, together with bounds on their ranks among all
+/// of the values inserted. [`query`](Self::query) returns a value whose rank is within `epsilon *
+/// len` of the rank of the requested quantile, for any distribution of the values. In practice,
+/// the summary keeps on the order of (1 / `epsilon`) log(`epsilon` * `len`) values, e.g. about 1300
+/// for an `epsilon` of 0.001 and 20 000 values. Unlike [`TDigest`], the values don't need
+/// to be numbers, and the error is guaranteed rather than typical. The summaries of different
+/// streams can be merged, and the merged summary is as accurate as the less accurate of the two.
+///
+/// When the values are still available, e.g. on the shards they were summarized from, the exact
+/// quantile can be found from [`bounds`](Self::bounds): only the values between the bounds, about
+/// `4 * epsilon * len` of them, need to be gathered and selected from.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{select_nth_unstable, streaming::GkSummary};
+/// let shards: Vec<Vec<u32>> = (0..4)
+///     .map(|shard| (0..25_000).map(|i| (i * 4 + shard) * 7919 % 100_000).collect())
+///     .collect();
+///
+/// // Each shard summarizes its values, and the summaries are merged.
+/// let mut summary = GkSummary::new(0.001);
+/// for shard in &shards {
+///     let mut local = GkSummary::new(0.001);
+///     local.extend(shard.iter().copied());
+///     summary.merge(&local);
+/// }
+/// let median = *summary.query(0.5).unwrap();
+/// assert!(median.abs_diff(50_000) <= 100);
+///
+/// // The exact median is between the bounds, at a known position among the values between them.
+/// let (&low, &high) = summary.bounds(0.5).unwrap();
+/// let below = shards.iter().flatten().filter(|&&x| x < low).count();
+/// let mut candidates: Vec<u32> = shards
+///     .iter()
+///     .flatten()
+///     .copied()
+///     .filter(|x| (low..=high).contains(x))
+///     .collect();
+/// let rank = (100_000 - 1) / 2;
+/// let (_, exact, _) = select_nth_unstable(&mut candidates, rank - below);
+/// assert_eq!(*exact, 49_999);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GkSummary<T> {
+    /// The allowed error in the rank of a query, relative to the number of values.
+    epsilon: f64,
+    /// The number of values inserted.
+    count: u64,
+    /// The values kept, in sorted order.
+    tuples: Vec<GkTuple<T>>,
+}
+
+/// A value kept by a [`GkSummary`], with bounds on its rank.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct GkTuple<T> {
+    value: T,
+    /// The smallest rank of the value minus the smallest rank of the previous value.
+    g: u64,
+    /// The largest rank of the value minus its smallest rank.
+    delta: u64,
+}
+
+#[cfg(feature = "std")]
+impl<T> GkSummary<T>
+where
+    T: Ord,
+{
+    /// Returns an empty summary whose queries are accurate to within `epsilon * len` ranks, e.g.
+    /// `0.001` for a tenth of a percentile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is not in the range `(0.0, 1.0)`.
+    pub fn new(epsilon: f64) -> Self {
+        assert!(
+            epsilon > 0.0 && epsilon < 1.0,
+            "`epsilon` must be in the range (0.0, 1.0)"
+        );
+        Self {
+            epsilon,
+            count: 0,
+            tuples: Vec::new(),
+        }
+    }
+
+    /// Returns the allowed error of the summary, relative to the number of values.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Returns `true` if no values have been inserted.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the number of values inserted, including those of merged summaries.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Inserts `value` into the summary.
+    pub fn insert(&mut self, value: T) {
+        let tuples = &mut self.tuples;
+        let position = tuples.partition_point(|t| t.value <= value);
+        // A new minimum or maximum has an exact rank. Otherwise, the rank of the value is at most
+        // the largest rank of the next value.
+        let delta = match tuples.get(position) {
+            Some(next) if position > 0 => next.g + next.delta - 1,
+            _ => 0,
+        };
+        tuples.insert(position, GkTuple { value, g: 1, delta });
+        self.count += 1;
+        if self.count.is_multiple_of(self.compress_period()) {
+            self.compress();
+        }
+    }
+
+    /// Adds the values summarized by `other` to the summary. The error of the merged summary is the
+    /// larger of the errors of the two.
+    pub fn merge(&mut self, other: &GkSummary<T>)
+    where
+        T: Clone,
+    {
+        let epsilon = self.epsilon.max(other.epsilon);
+        let (a, b) = (core::mem::take(&mut self.tuples), &other.tuples);
+        let (ranks_a, ranks_b) = (rank_bounds(&a), rank_bounds(b));
+        let (count_a, count_b) = (self.count, other.count);
+
+        // The rank of a value in the union is at least its smallest rank in its own summary plus
+        // the smallest rank of the previous value of the other summary, and at most its largest
+        // rank plus the largest rank of the next value of the other summary, minus one. Equal
+        // values are ordered with those of `self` first.
+        let mut merged = Vec::with_capacity(a.len() + b.len());
+        let mut a = a.into_iter().zip(ranks_a.iter().copied()).peekable();
+        let mut j = 0;
+        let mut previous_a = (0, 0);
+        let mut previous_rmin = 0;
+        let mut push = |value, rmin: u64, rmax: u64| {
+            merged.push(GkTuple {
+                value,
+                g: rmin - previous_rmin,
+                delta: rmax - rmin,
+            });
+            previous_rmin = rmin;
+        };
+        loop {
+            let from_a = match (a.peek(), b.get(j)) {
+                (Some((x, _)), Some(y)) => x.value <= y.value,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            if from_a {
+                let (x, (rmin, rmax)) = a.next().unwrap();
+                let before = j.checked_sub(1).map_or(0, |j| ranks_b[j].0);
+                let after = ranks_b.get(j).map_or(count_b, |&(_, rmax)| rmax - 1);
+                push(x.value, rmin + before, rmax + after);
+                previous_a = (rmin, rmax);
+            } else {
+                let (rmin, rmax) = ranks_b[j];
+                let after = a.peek().map_or(count_a, |&(_, (_, rmax))| rmax - 1);
+                push(b[j].value.clone(), rmin + previous_a.0, rmax + after);
+                j += 1;
+            }
+        }
+
+        self.epsilon = epsilon;
+        self.count += other.count;
+        self.tuples = merged;
+        self.compress();
+    }
+
+    /// Returns a value whose rank is within `epsilon * len` of the rank of the `p`th quantile, or
+    /// `None` if no values have been inserted. The rank of the quantile is `floor(p * (len - 1))`,
+    /// like in [`percentile`](crate::percentile), so `p = 0.0` gives the minimum and `p = 1.0` the
+    /// maximum exactly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 1.0]`.
+    pub fn query(&self, p: f64) -> Option<&T> {
+        let rank = self.rank(p)?;
+        let mut rmin = 0;
+        let mut best = (u64::MAX, None);
+        for tuple in &self.tuples {
+            rmin += tuple.g;
+            let rmax = rmin + tuple.delta;
+            let error = rank.saturating_sub(rmin).max(rmax.saturating_sub(rank));
+            if error < best.0 {
+                best = (error, Some(&tuple.value));
+            }
+            if rmin > rank {
+                break;
+            }
+        }
+        best.1
+    }
+
+    /// Returns two values between which the value at the rank of the `p`th quantile is, or `None`
+    /// if no values have been inserted. The rank of the quantile is `floor(p * (len - 1))`, like in
+    /// [`query`](Self::query).
+    ///
+    /// The values are those of the summary closest to the quantile whose ranks are known to be at
+    /// most and at least the rank of the quantile. Both ranks are within `2 * epsilon * len` of it.
+    /// To find the exact quantile, select the element at the rank of the quantile, minus the
+    /// number of values smaller than the first bound, from the values between the bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in the range `[0.0, 1.0]`.
+    pub fn bounds(&self, p: f64) -> Option<(&T, &T)> {
+        let rank = self.rank(p)?;
+        let mut rmin = 0;
+        let mut low = &self.tuples[0].value;
+        for tuple in &self.tuples {
+            rmin += tuple.g;
+            if rmin + tuple.delta <= rank {
+                low = &tuple.value;
+            }
+            if rmin >= rank {
+                return Some((low, &tuple.value));
+            }
+        }
+        unreachable!("the smallest rank of the maximum is the number of values")
+    }
+
+    /// Returns the 1-based rank of the `p`th quantile, or `None` if the summary is empty.
+    fn rank(&self, p: f64) -> Option<u64> {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "`p` must be in the range [0.0, 1.0]"
+        );
+        let last = self.count.checked_sub(1)?;
+        Some(floor(p * last as f64) as u64 + 1)
+    }
+
+    /// Returns how many values are inserted between compressions.
+    fn compress_period(&self) -> u64 {
+        (floor(1.0 / (2.0 * self.epsilon)) as u64).max(1)
+    }
+
+    /// Merges each value into the next one, from the largest down, as long as the rank bounds of
+    /// the merged value span at most `2 * epsilon * len` ranks. The minimum and the maximum are
+    /// kept, so that their ranks stay exact.
+    fn compress(&mut self) {
+        let tuples = &mut self.tuples;
+        if tuples.len() < 3 {
+            return;
+        }
+        let limit = floor(2.0 * self.epsilon * self.count as f64) as u64;
+        // The kept values are moved to the end, starting with the maximum, and `kept` is the
+        // position of the smallest of them.
+        let mut kept = tuples.len() - 1;
+        for i in (1..tuples.len() - 1).rev() {
+            let (g, next) = (tuples[i].g, &tuples[kept]);
+            if g + next.g + next.delta <= limit {
+                tuples[kept].g += g;
+            } else {
+                kept -= 1;
+                tuples.swap(i, kept);
+            }
+        }
+        kept -= 1;
+        tuples.swap(0, kept);
+        tuples.drain(..kept);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Extend<T> for GkSummary<T>
+where
+    T: Ord,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// Returns the smallest and the largest rank of each value of a summary.
+#[cfg(feature = "std")]
+fn rank_bounds<T>(tuples: &[GkTuple<T>]) -> Vec<(u64, u64)> {
+    let mut rmin = 0;
+    tuples
+        .iter()
+        .map(|tuple| {
+            rmin += tuple.g;
+            (rmin, rmin + tuple.delta)
+        })
+        .collect()
+}
+
+/// Checks that the values are sorted and that their rank bounds are consistent with the number of
+/// values, so that a restored summary keeps its guarantees.
+#[cfg(all(feature = "std", feature = "serde"))]
+impl<'de, T> serde::Deserialize<'de> for GkSummary<T>
+where
+    T: Ord + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "GkSummary")]
+        struct State<T> {
+            epsilon: f64,
+            count: u64,
+            tuples: Vec<GkTuple<T>>,
+        }
+
+        let state = State::<T>::deserialize(deserializer)?;
+        if !(state.epsilon > 0.0 && state.epsilon < 1.0) {
+            return Err(D::Error::custom(
+                "`epsilon` must be in the range (0.0, 1.0)",
+            ));
+        }
+        if !state.tuples.windows(2).all(|w| w[0].value <= w[1].value) {
+            return Err(D::Error::custom("the values must be sorted"));
+        }
+        let ranks = rank_bounds(&state.tuples);
+        if state.tuples.iter().any(|t| t.g == 0)
+            || ranks.last().map_or(0, |&(rmin, _)| rmin) != state.count
+            || ranks.iter().any(|&(_, rmax)| rmax > state.count)
+        {
+            return Err(D::Error::custom(
+                "the ranks of the values must be within the count",
+            ));
+        }
+        Ok(Self {
+            epsilon: state.epsilon,
+            count: state.count,
+            tuples: state.tuples,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::vec::Vec;
+
+use crate::{select, split_partition};
+
+/// Reorders a slice of strings such that the element at `index` is at its final sorted position,
+/// comparing the strings by their bytes. For `String` and `&str`, this is the same order as their
+/// `Ord` implementations.
+///
+/// The first 8 bytes of each string are cached as a `u64` in a temporary buffer, together with the
+/// position of the string, like in
+/// [`select_nth_unstable_by_cached_key`](crate::select_nth_unstable_by_cached_key). Most
+/// comparisons are decided by the cached prefixes, so the string data only needs to be read when
+/// two prefixes are equal. This avoids following a pointer to the string data on every comparison.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_by_prefix;
+/// let mut v = ["pear", "apple", "peach", "plum", "apricot"];
+/// let (_, nth, _) = select_nth_unstable_by_prefix(&mut v, 3);
+/// assert_eq!(*nth, "pear");
+/// ```
+pub fn select_nth_unstable_by_prefix<T>(
+    data: &mut [T],
+    index: usize,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: AsRef<[u8]>,
+{
+    assert!(index < data.len());
+
+    // Helper macro for indexing the strings by the smallest possible type, to reduce allocation.
+    macro_rules! select_nth_by_prefix {
+        ($t:ty) => {{
+            let mut keys: Vec<_> = data
+                .iter()
+                .enumerate()
+                .map(|(i, s)| (prefix(s.as_ref()), i as $t))
+                .collect();
+            let strings = &*data;
+            select(&mut keys, index, &mut |a, b| {
+                a.0 < b.0
+                    || a.0 == b.0 && strings[a.1 as usize].as_ref() < strings[b.1 as usize].as_ref()
+            });
+            for i in 0..data.len() {
+                let mut j = keys[i].1;
+                while (j as usize) < i {
+                    j = keys[j as usize].1;
+                }
+                keys[i].1 = j;
+                data.swap(i, j as usize);
+            }
+        }};
+    }
+
+    if data.len() > 1 {
+        if data.len() <= u32::MAX as usize {
+            select_nth_by_prefix!(u32)
+        } else {
+            select_nth_by_prefix!(usize)
+        }
+    }
+    split_partition(data, index)
+}
+
+/// Returns the first 8 bytes of `s` as a big-endian `u64`, padded with zeros. If the prefixes of
+/// two byte strings differ, they are ordered like the byte strings. Equal prefixes don't imply
+/// equal strings, e.g. `"a"` and `"a\0"` have the same prefix.
+fn prefix(s: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let len = s.len().min(8);
+    bytes[..len].copy_from_slice(&s[..len]);
+    u64::from_be_bytes(bytes)
+}
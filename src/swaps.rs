@@ -0,0 +1,269 @@
+use core::{cmp::Ordering, mem};
+
+use crate::{params::params, replacement_pivot, split_partition, BadRounds};
+
+/// Slices of at most this length are finished with a selection sort up to the index.
+const SWAP_CUTOFF: usize = 16;
+
+/// Reorders the slice such that the element at `index` is at its final sorted position, calling
+/// `on_swap(i, j)` whenever the elements at `i` and `j` are swapped.
+///
+/// Every element is moved by swapping it with another element, so applying the same swaps to
+/// another structure, e.g. a parallel array, a map from elements to their positions or a copy of
+/// the slice on another device, keeps it in sync with the slice. The indices are always distinct
+/// and relative to the start of `data`. Selecting indices instead, e.g. with
+/// [`arg_partial_sort`](crate::arg_partial_sort), needs an extra allocation and an indirection on
+/// every comparison.
+///
+/// The pivots are chosen from samples drawn with replacement, which doesn't move any elements, and
+/// the slice is partitioned by swapping pairs of misplaced elements. This is slower than
+/// [`select_nth_unstable`](crate::select_nth_unstable), which moves the elements in cycles instead
+/// of in pairs, but faster than selecting indices and applying the permutation afterwards.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_swaps;
+/// let mut v = [50, 10, 40, 30, 20];
+/// let mut names = ["e", "a", "d", "c", "b"];
+/// select_nth_unstable_with_swaps(&mut v, 2, |i, j| names.swap(i, j));
+/// assert_eq!(v[2], 30);
+/// assert_eq!(names[2], "c");
+/// ```
+pub fn select_nth_unstable_with_swaps<T, S>(
+    data: &mut [T],
+    index: usize,
+    on_swap: S,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    T: Ord,
+    S: FnMut(usize, usize),
+{
+    select_nth_unstable_with_swaps_by(data, index, on_swap, T::cmp)
+}
+
+/// Reorders the slice with a comparator function such that the element at `index` is at its final
+/// sorted position, calling `on_swap(i, j)` whenever the elements at `i` and `j` are swapped.
+///
+/// See [`select_nth_unstable_with_swaps`] for details.
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_swaps_by;
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+/// // Track where each element is.
+/// let mut position: Vec<usize> = vec![0; 1000];
+/// v.iter().enumerate().for_each(|(i, &x)| position[x as usize] = i);
+/// let mut at = v.clone();
+/// select_nth_unstable_with_swaps_by(&mut v, 10, |i, j| {
+///     at.swap(i, j);
+///     position[at[i] as usize] = i;
+///     position[at[j] as usize] = j;
+/// }, |a, b| b.cmp(a));
+/// assert_eq!(v[10], 989);
+/// assert_eq!(position[989], 10);
+/// ```
+pub fn select_nth_unstable_with_swaps_by<T, S, F>(
+    data: &mut [T],
+    index: usize,
+    on_swap: S,
+    mut compare: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    S: FnMut(usize, usize),
+    F: FnMut(&T, &T) -> Ordering,
+{
+    select_nth_unstable_with_swaps_by_lt(data, index, on_swap, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Reorders the slice with a "less than" function such that the element at `index` is at its final
+/// sorted position, calling `on_swap(i, j)` whenever the elements at `i` and `j` are swapped.
+///
+/// This is the "less than" form of [`select_nth_unstable_with_swaps`], like
+/// [`select_nth_unstable_by_lt`](crate::select_nth_unstable_by_lt).
+///
+/// # Panics
+///
+/// Panics when `index >= len()`, meaning it always panics on empty slices.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::select_nth_unstable_with_swaps_by_lt;
+/// let mut v = [50, 10, 40, 30, 20];
+/// let mut swaps = 0;
+/// select_nth_unstable_with_swaps_by_lt(&mut v, 0, |_, _| swaps += 1, |a, b| a > b);
+/// assert_eq!(v[0], 50);
+/// assert_eq!(swaps, 0);
+/// ```
+pub fn select_nth_unstable_with_swaps_by_lt<T, S, F>(
+    data: &mut [T],
+    index: usize,
+    mut on_swap: S,
+    mut lt: F,
+) -> (&mut [T], &mut T, &mut [T])
+where
+    S: FnMut(usize, usize),
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(index < data.len());
+    if data.len() > 1 && mem::size_of::<T>() > 0 {
+        select_swapping(data, index, &mut lt, &mut on_swap);
+    }
+    split_partition(data, index)
+}
+
+/// The selection loop of [`select_nth_unstable_with_swaps_by`]. Like `select_partitioned`, the
+/// elements equal to the pivot are grouped together if the pivot is likely to be repeated, or if it
+/// is not greater than the previous pivot, so that many duplicates don't slow it down.
+fn select_swapping<T, F, S>(data: &mut [T], index: usize, lt: &mut F, on_swap: &mut S)
+where
+    F: FnMut(&T, &T) -> bool,
+    S: FnMut(usize, usize),
+{
+    let mut swap = |data: &mut [T], i: usize, j: usize| {
+        if i != j {
+            data.swap(i, j);
+            on_swap(i, j);
+        }
+    };
+    let len = data.len();
+    select_between(data, 0, len, index, lt, &mut swap, BadRounds::default());
+}
+
+/// Puts the element at `index` at its sorted position within `data[lo..hi]`, moving the elements
+/// only with `swap`. After too many `bad_rounds`, the pivots are chosen with the median of medians.
+fn select_between<T, F, S>(
+    data: &mut [T],
+    mut lo: usize,
+    mut hi: usize,
+    index: usize,
+    lt: &mut F,
+    swap: &mut S,
+    mut bad_rounds: BadRounds,
+) where
+    F: FnMut(&T, &T) -> bool,
+    S: FnMut(&mut [T], usize, usize),
+{
+    let params = params::<T>();
+    // Whether `data[lo - 1]` is the pivot of the previous round.
+    let mut after_pivot = false;
+    while hi - lo > SWAP_CUTOFF {
+        let len = hi - lo;
+        let (p, mut is_repeated) = match bad_rounds.exceeded(&params) {
+            false => {
+                let (p, is_repeated) = replacement_pivot(&data[lo..hi], index - lo, lt, &params);
+                (lo + p, is_repeated)
+            }
+            true => (median_of_medians_swapping(data, lo, hi, lt, swap), true),
+        };
+        // The previous pivot is not greater than any element of the subslice, so if this pivot is
+        // not greater than it, this pivot is the minimum.
+        if after_pivot && !lt(&data[lo - 1], &data[p]) {
+            is_repeated = true;
+        }
+
+        swap(data, lo, p);
+        let u = partition_swapping(data, lo + 1, hi, lo, &mut |x, pivot| lt(x, pivot), swap);
+        let mid = u - 1;
+        swap(data, lo, mid);
+        let end = match is_repeated {
+            true => partition_swapping(data, u, hi, mid, &mut |x, pivot| !lt(pivot, x), swap),
+            false => u,
+        };
+
+        if index < mid {
+            hi = mid;
+            after_pivot = false;
+        } else if index < end {
+            return;
+        } else {
+            lo = end;
+            after_pivot = true;
+        }
+        bad_rounds.count(len, hi - lo);
+    }
+    for i in lo..=index {
+        let min = (i + 1..hi).fold(i, |min, j| if lt(&data[j], &data[min]) { j } else { min });
+        swap(data, i, min);
+    }
+}
+
+/// Chooses a pivot for `data[lo..hi]` like the median of medians of the other selections, but
+/// moves the elements only with `swap`, and returns the position of the pivot. The groups of 5
+/// elements are sorted by insertion, their medians are swapped to the front of the range, and the
+/// median of the medians is selected with the median of medians too.
+fn median_of_medians_swapping<T, F, S>(
+    data: &mut [T],
+    lo: usize,
+    hi: usize,
+    lt: &mut F,
+    swap: &mut S,
+) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+    S: FnMut(&mut [T], usize, usize),
+{
+    let groups = (hi - lo) / 5;
+    for g in 0..groups {
+        let start = lo + 5 * g;
+        for i in start + 1..start + 5 {
+            let mut j = i;
+            while j > start && lt(&data[j], &data[j - 1]) {
+                swap(data, j - 1, j);
+                j -= 1;
+            }
+        }
+        swap(data, lo + g, start + 2);
+    }
+    let pivot = lo + groups / 2;
+    select_between(data, lo, lo + groups, pivot, lt, swap, BadRounds::EXCEEDED);
+    pivot
+}
+
+/// Moves the elements of `data[start..end]` for which `is_left(elem, &data[pivot])` returns `true`
+/// before the others by swapping pairs of misplaced elements, and returns the end of the left part.
+/// The pivot must be outside of `start..end`.
+fn partition_swapping<T, P, S>(
+    data: &mut [T],
+    start: usize,
+    end: usize,
+    pivot: usize,
+    is_left: &mut P,
+    swap: &mut S,
+) -> usize
+where
+    P: FnMut(&T, &T) -> bool,
+    S: FnMut(&mut [T], usize, usize),
+{
+    let (mut l, mut r) = (start, end);
+    loop {
+        while l < r && is_left(&data[l], &data[pivot]) {
+            l += 1;
+        }
+        while l < r && !is_left(&data[r - 1], &data[pivot]) {
+            r -= 1;
+        }
+        if l == r {
+            return l;
+        }
+        // `data[l]` belongs to the right and `data[r - 1]` to the left, so they are distinct.
+        swap(data, l, r - 1);
+        l += 1;
+        r -= 1;
+    }
+}
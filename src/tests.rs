@@ -1,13 +1,65 @@
 #[cfg(feature = "std")]
 extern crate std;
 
+use core::cell::Cell;
 use std::{io::Write, println, vec::Vec};
 
 use crate::{
-    choose_pivot, partition_at, partition_equal_min, sample, select, select_nth_unstable,
-    sort::tinysort, wyrand::WyRng,
+    arg_partial_sort,
+    cache::{self, CacheSizes},
+    choose_pivot, detect_order, is_partitioned, k_largest, k_largest_by, k_largest_by_key,
+    k_largest_by_lt, k_smallest, k_smallest_by, k_smallest_by_key, k_smallest_by_lt,
+    kth_smallest_pairwise_diff, kth_smallest_sum,
+    math::mul_div,
+    median_each_column, median_each_row,
+    params::{Params, PARAMS},
+    partial_sort, partial_sort_by, partial_sort_by_key, partial_sort_by_lt, partition_at,
+    partition_by_range, partition_equal, partition_equal_min, percentile, percentile_each_group,
+    quantile_bins, quantile_bins_by, reselect_nth_unstable, retain_smallest_k, sample, select,
+    select_and_group, select_many_nth_unstable, select_many_nth_unstable_by,
+    select_many_nth_unstable_by_lt, select_nth_record_unstable, select_nth_unstable,
+    select_nth_unstable_array, select_nth_unstable_array_by, select_nth_unstable_by_byte_key,
+    select_nth_unstable_by_cached_key_by, select_nth_unstable_by_cached_key_with_ties,
+    select_nth_unstable_by_domain_key, select_nth_unstable_by_prefix,
+    select_nth_unstable_by_radix_key, select_nth_unstable_in_domain,
+    select_nth_unstable_with_budget, select_nth_unstable_with_fold,
+    select_nth_unstable_with_fold_by, select_nth_unstable_with_options,
+    select_nth_unstable_with_partition, select_nth_unstable_with_report,
+    select_nth_unstable_with_stats, select_nth_unstable_with_swaps,
+    select_nth_unstable_with_swaps_by, select_nth_with_scratch, select_nth_with_scratch_by,
+    select_quantiles, select_quantiles_by, select_quantiles_by_lt, select_range, select_range_by,
+    select_range_by_key, select_range_by_lt, select_rank_in, select_rank_in_by, select_two_ranks,
+    select_two_ranks_by, select_two_ranks_by_lt, select_using, select_with,
+    sort::{median25, median9, network_sort, tinyselect, tinysort},
+    strategy::replacement_pivot,
+    tinysort_stable_by_lt, top_k_with_indices, top_k_with_indices_by_row, trim_percentiles,
+    truncate_to_top_k, winsorize,
+    wyrand::{thread_rng, WyRng},
+    BoundedTopK, Histogram, OrderStatistics, PartitionStrategy, RadixKey, RoundKind, SelectOptions,
+    SelectionState, SortOrder, Strategy, TernaryPartition, Ties, TurboSelect, WindowQuantile,
 };
 
+#[test]
+fn arg_partial_sorts() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(0, 500);
+        let data: Vec<_> = iter_rng(&mut rng, count, count / 2 + 1).collect();
+        let k = rng.bounded_usize(0, count + 2);
+
+        let mut expected: Vec<_> = (0..count as u32).collect();
+        expected.sort_by_key(|&i| (data[i as usize], i));
+        expected.truncate(k);
+        assert_eq!(arg_partial_sort(&data, k), expected);
+    }
+}
+
 #[test]
 fn bool_median() {
     #[cfg(not(miri))]
@@ -32,6 +84,378 @@ fn bool_median() {
     }
 }
 
+#[test]
+fn cached_key_ties() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(1, 500);
+        let data: Vec<_> = (0..count).map(|i| (rng.bounded_usize(0, 10), i)).collect();
+        let index = rng.bounded_usize(0, count);
+        let mut stable = data.clone();
+        stable.sort_by_key(|x| x.0);
+
+        let mut selected = data.clone();
+        select_nth_unstable_by_cached_key_with_ties(&mut selected, index, |x| x.0, Ties::ByIndex);
+        assert_eq!(selected[index], stable[index]);
+        selected[..index].sort();
+        let mut expected = stable[..index].to_vec();
+        expected.sort();
+        assert_eq!(selected[..index], expected);
+
+        let mut selected = data.clone();
+        select_nth_unstable_by_cached_key_with_ties(&mut selected, index, |x| x.0, Ties::Unordered);
+        assert_eq!(selected[index].0, stable[index].0);
+        assert!(selected[..index].iter().all(|x| x.0 <= stable[index].0));
+        assert!(selected[index..].iter().all(|x| x.0 >= stable[index].0));
+    }
+}
+
+#[test]
+fn cached_key_by() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(1, 500);
+        let mut data: Vec<_> = (0..count).map(|_| rng.bounded_f64(-1.0, 1.0)).collect();
+        let index = rng.bounded_usize(0, count);
+        let mut sorted = data.clone();
+        sorted.sort_by(f64::total_cmp);
+
+        select_nth_unstable_by_cached_key_by(&mut data, index, |x| -x, f64::total_cmp);
+        let nth = data[index];
+        assert_eq!(nth, sorted[count - index - 1]);
+        assert!(data[..index].iter().all(|x| x >= &nth));
+        assert!(data[index..].iter().all(|x| x <= &nth));
+    }
+}
+
+#[test]
+fn byte_keys() {
+    // Slices longer than 4096 elements are partitioned in blocks.
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (100, 10_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 5000);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        // Use few distinct values for the leading bytes, so that several levels are needed.
+        let count = rng.bounded_usize(1, max_len);
+        let mut data: Vec<[u8; 16]> = (0..count)
+            .map(|_| core::array::from_fn(|i| rng.bounded_u8(0, if i < 4 { 2 } else { 255 })))
+            .collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let index = rng.bounded_usize(0, count);
+        let (left, nth, right) = select_nth_unstable_by_byte_key(&mut data, index, |k| k);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+    }
+}
+
+#[test]
+fn radix_keys() {
+    // Slices longer than 4096 elements are partitioned in blocks.
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (100, 10_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 5000);
+
+    // The bytes don't depend on the byte order of the target.
+    assert_eq!(0x0102_0304_u32.radix_bytes(), [1, 2, 3, 4]);
+    assert_eq!((-1_i16).radix_bytes(), [0x7f, 0xff]);
+    assert_eq!(0_i8.radix_bytes(), [0x80]);
+    assert_eq!(1.0_f32.radix_bytes(), [0xbf, 0x80, 0, 0]);
+    assert_eq!((-1.0_f32).radix_bytes(), [0x40, 0x7f, 0xff, 0xff]);
+    assert_eq!('A'.radix_bytes(), [0, 0, 0, 0x41]);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..10_000 {
+        let (a, b) = (rng.u64(), rng.u64());
+        assert_eq!(a.cmp(&b), a.radix_bytes().cmp(&b.radix_bytes()));
+        let (a, b) = (a as i64, b as i64);
+        assert_eq!(a.cmp(&b), a.radix_bytes().cmp(&b.radix_bytes()));
+        let (a, b) = (a as i16, b as i16);
+        assert_eq!(a.cmp(&b), a.radix_bytes().cmp(&b.radix_bytes()));
+        let (a, b) = (rng.u128() as i128, rng.u128() as i128);
+        assert_eq!(a.cmp(&b), a.radix_bytes().cmp(&b.radix_bytes()));
+        // Random bits include infinities, NaNs and subnormals.
+        let (a, b) = (f64::from_bits(rng.u64()), f64::from_bits(rng.u64()));
+        assert_eq!(a.total_cmp(&b), a.radix_bytes().cmp(&b.radix_bytes()));
+        let (a, b) = (a as f32, b as f32);
+        assert_eq!(a.total_cmp(&b), a.radix_bytes().cmp(&b.radix_bytes()));
+    }
+
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(1, max_len);
+        let mut data: Vec<(f64, i32)> = (0..count)
+            .map(|_| match rng.bounded_usize(0, 3) {
+                0 => (rng.f64() - 0.5, rng.u32() as i32),
+                1 => (f64::from_bits(rng.u64()), rng.bounded_u32(0, 4) as i32 - 2),
+                _ => (-0.0, -(rng.bounded_u32(0, 100) as i32)),
+            })
+            .collect();
+        let index = rng.bounded_usize(0, count);
+
+        let mut sorted: Vec<f64> = data.iter().map(|x| x.0).collect();
+        sorted.sort_by(f64::total_cmp);
+        let (left, nth, right) = select_nth_unstable_by_radix_key(&mut data, index, |x| x.0);
+        let nth = nth.0;
+        assert_eq!(nth.to_bits(), sorted[index].to_bits());
+        assert!(left.iter().all(|x| x.0.total_cmp(&nth).is_le()));
+        assert!(right.iter().all(|x| x.0.total_cmp(&nth).is_ge()));
+
+        let mut sorted: Vec<i32> = data.iter().map(|x| x.1).collect();
+        sorted.sort();
+        let (_, nth, _) = select_nth_unstable_by_radix_key(&mut data, index, |x| x.1);
+        assert_eq!(nth.1, sorted[index]);
+    }
+}
+
+#[test]
+fn domain_keys() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        // Both domains that are counted and domains too wide for the slice.
+        let count = rng.bounded_usize(1, 5000);
+        let high = if rng.bool() { 100 } else { 1_000_000 };
+        let width = rng.bounded_i64(0, high);
+        let min = rng.bounded_i64(-1000, 1000);
+        let mut data: Vec<i64> = (0..count)
+            .map(|_| rng.bounded_i64(min, min + width + 1))
+            .collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let index = rng.bounded_usize(0, count);
+        let (left, nth, right) = select_nth_unstable_in_domain(&mut data, index, min..=min + width);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+        data.sort();
+        assert_eq!(data, sorted);
+
+        // Records with keys, which are counted into buckets.
+        let mut data: Vec<(i64, usize)> = (0..count)
+            .map(|i| (rng.bounded_i64(min, min + width + 1), i))
+            .collect();
+        let mut sorted = data.clone();
+        sorted.sort_by_key(|x| x.0);
+        let (left, nth, right) =
+            select_nth_unstable_by_domain_key(&mut data, index, min..=min + width, |x| x.0);
+        assert_eq!(nth.0, sorted[index].0);
+        assert!(left.iter().all(|x| x.0 <= nth.0));
+        assert!(right.iter().all(|x| x.0 >= nth.0));
+        data.sort_by_key(|x| x.1);
+        assert!(data.iter().enumerate().all(|(i, x)| x.1 == i));
+    }
+
+    // The whole range of the type.
+    let mut data: Vec<u64> = (0..1000).map(|_| rng.u64()).collect();
+    let mut sorted = data.clone();
+    sorted.sort();
+    select_nth_unstable_in_domain(&mut data, 123, 0..=u64::MAX);
+    assert_eq!(data[123], sorted[123]);
+    let mut data: Vec<i128> = (0..1000).map(|_| rng.u64() as i128 - (1 << 70)).collect();
+    let mut sorted = data.clone();
+    sorted.sort();
+    select_nth_unstable_in_domain(&mut data, 456, i128::MIN..=i128::MAX);
+    assert_eq!(data[456], sorted[456]);
+
+    // Elements outside the bounds can't be counted.
+    let result = std::panic::catch_unwind(|| {
+        let mut data: Vec<u8> = (0..100_000).map(|x| x as u8).collect();
+        select_nth_unstable_in_domain(&mut data, 50, 10..=200);
+    });
+    assert!(result.is_err());
+    let result = std::panic::catch_unwind(|| {
+        let mut data = std::vec![-1i8; 100_000];
+        data[1000] = -3;
+        select_nth_unstable_in_domain(&mut data, 50, -2..=5);
+    });
+    assert!(result.is_err());
+    // Keys outside the bounds are rejected whether they are bucketed or compared.
+    for len in [10, 100_000] {
+        let result = std::panic::catch_unwind(|| {
+            let mut data: Vec<(u32, u32)> = (0..len).map(|x| (x % 1000, x)).collect();
+            select_nth_unstable_by_domain_key(&mut data, 5, 0..=998, |x| x.0);
+        });
+        assert!(result.is_err() == (len > 999));
+        let result = std::panic::catch_unwind(|| {
+            let mut data: Vec<u32> = (0..len).map(|x| x + 1_000_000).collect();
+            data[len as usize / 2] = 3;
+            select_nth_unstable_in_domain(&mut data, 5, 1_000_000..=u32::MAX);
+        });
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn cache_params() {
+    let reference = CacheSizes {
+        l1: 32 << 10,
+        l2: 512 << 10,
+    };
+    assert_eq!(PARAMS.scaled::<u32>(reference), PARAMS);
+
+    // Larger caches allow larger blocks, unless the elements are large.
+    let large = CacheSizes {
+        l1: 64 << 10,
+        l2: 2 << 20,
+    };
+    let params = PARAMS.scaled::<u32>(large);
+    assert_eq!(params.partition_block, 2 * PARAMS.partition_block);
+    assert_eq!(params.kth_of_7_max, 4 * PARAMS.kth_of_7_max);
+    assert!(PARAMS.scaled::<[u8; 256]>(large).partition_block < PARAMS.partition_block);
+    assert!(PARAMS.scaled::<()>(large).is_valid());
+
+    if let Some(sizes) = cache::sizes() {
+        assert!(sizes.l1 > 0 && sizes.l2 > 0);
+        assert!(PARAMS.scaled::<u64>(sizes).is_valid());
+    }
+}
+
+#[test]
+fn comparators() {
+    use crate::cmp::{by_key, float_total, reversed, then_by};
+
+    let mut rng = WyRng::new(123);
+    let mut lt = then_by(
+        reversed(by_key(|x: &(usize, f64)| x.0)),
+        |a: &(usize, f64), b: &(usize, f64)| float_total()(&a.1, &b.1),
+    );
+    for _iter in 0..1000 {
+        let a = (rng.bounded_usize(0, 3), rng.bounded_f64(-1.0, 1.0));
+        let b = (rng.bounded_usize(0, 3), rng.bounded_f64(-1.0, 1.0));
+        let expected = b.0.cmp(&a.0).then(a.1.total_cmp(&b.1)) == core::cmp::Ordering::Less;
+        assert_eq!(lt(&a, &b), expected);
+    }
+}
+
+#[cfg(feature = "ordered-float")]
+#[test]
+fn ordered_floats() {
+    use ordered_float::{NotNan, OrderedFloat};
+
+    use crate::{cmp::float_total, select_nth_unstable_by_lt};
+
+    let mut rng = WyRng::new(123);
+    for _iter in 0..100 {
+        let count = rng.bounded_usize(1, 500);
+        let floats: Vec<f64> = (0..count)
+            .map(|_| match rng.bounded_usize(0, 20) {
+                0 => f64::NAN,
+                1 => -0.0,
+                _ => rng.bounded_f64(-1.0, 1.0),
+            })
+            .collect();
+        let index = rng.bounded_usize(0, count);
+
+        let mut data: Vec<_> = floats.iter().copied().map(OrderedFloat).collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+        select_nth_unstable(&mut data, index);
+        assert_eq!(data[index], sorted[index]);
+
+        let mut data: Vec<_> = floats.iter().copied().map(OrderedFloat).collect();
+        let mut sorted = data.clone();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        select_nth_unstable_by_lt(&mut data, index, float_total());
+        assert_eq!(data[index].0.to_bits(), sorted[index].0.to_bits());
+
+        let mut data: Vec<_> = floats.iter().filter_map(|&x| NotNan::new(x).ok()).collect();
+        if data.is_empty() {
+            continue;
+        }
+        let index = index % data.len();
+        let mut sorted = data.clone();
+        sorted.sort_by(|a, b| a.into_inner().total_cmp(&b.into_inner()));
+        select_nth_unstable_by_lt(&mut data, index, float_total());
+        assert_eq!(
+            data[index].into_inner().to_bits(),
+            sorted[index].into_inner().to_bits()
+        );
+    }
+}
+
+#[test]
+fn distributed_select() {
+    use crate::distributed::{narrow, next_step, partition_by_value, rank_of, sample_rank, Step};
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 5;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let mut shards: Vec<Vec<usize>> = (0..rng.bounded_usize(1, 10))
+            .map(|_| {
+                let count = rng.bounded_usize(0, 1000);
+                iter_rng(&mut rng, count, 500).collect()
+            })
+            .collect();
+        let mut sorted: Vec<_> = shards.iter().flatten().copied().collect();
+        if sorted.is_empty() {
+            continue;
+        }
+        sorted.sort();
+        let mut index = rng.bounded_usize(0, sorted.len());
+        let expected = sorted[index];
+
+        let mut active: Vec<&mut [usize]> = shards.iter_mut().map(|s| s.as_mut_slice()).collect();
+        let nth = loop {
+            // Gather a sample of every 10th active element and pick the pivot from it.
+            let mut sample: Vec<_> = active
+                .iter()
+                .flat_map(|shard| shard.iter().step_by(10).copied())
+                .collect();
+            let total = active.iter().map(|shard| shard.len()).sum();
+            let rank = sample_rank(index, total, sample.len());
+            let pivot = *select_nth_unstable(&mut sample, rank).1;
+
+            let counts: Vec<_> = active
+                .iter_mut()
+                .map(|shard| {
+                    let counts = rank_of(shard, &pivot);
+                    assert_eq!(partition_by_value(shard, &pivot), counts);
+                    counts
+                })
+                .collect();
+            let step = next_step(counts.iter().copied().sum(), index);
+            if step == Step::Found {
+                break pivot;
+            }
+            active = active
+                .into_iter()
+                .zip(&counts)
+                .map(|(shard, &counts)| narrow(shard, counts, step))
+                .collect();
+            index = step.index();
+        };
+        assert_eq!(nth, expected);
+    }
+}
+
 #[test]
 fn extreme_index() {
     let mut rng = WyRng::new(123);
@@ -59,6 +483,163 @@ fn extreme_index() {
     assert_eq!(nth, data[index]);
 }
 
+/// Returns the 64-bit FNV-1a hash of the elements of `data`.
+fn fnv1a(data: &[u32]) -> u64 {
+    data.iter()
+        .flat_map(|x| x.to_le_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[test]
+fn golden() {
+    // The exact output of the deterministic mode for fixed inputs. If this fails, the pivot
+    // choice or the partitioning order has changed, which changes the output for every user of
+    // the deterministic mode. Update the expected values only if that is intended.
+    fn reorder(data: &[u32], index: usize, strategy: Strategy) -> Vec<u32> {
+        let options = SelectOptions {
+            strategy,
+            deterministic: true,
+            ..Default::default()
+        };
+        let mut data = data.to_vec();
+        select_nth_unstable_with_options(&mut data, index, &options);
+        data
+    }
+
+    let mut rng = WyRng::new(123);
+    let short: Vec<_> = (0..40).map(|_| rng.bounded_u32(0, 100)).collect();
+    assert_eq!(
+        reorder(&short, 17, Strategy::Sampling),
+        [
+            3, 7, 9, 12, 14, 15, 16, 17, 19, 20, 21, 22, 26, 27, 27, 34, 35, 35, 38, 38, 40, 44,
+            78, 73, 96, 51, 98, 89, 89, 83, 68, 66, 96, 97, 72, 88, 85, 79, 48, 98
+        ]
+    );
+
+    type Pattern = fn(&mut WyRng, u32) -> u32;
+    let patterns: [(&str, Pattern); 4] = [
+        ("random", |rng, _| rng.u32()),
+        ("few_distinct", |rng, _| rng.bounded_u32(0, 8)),
+        ("sawtooth", |_, i| i % 97),
+        ("organ_pipe", |_, i| i.min(9_999 - i)),
+    ];
+    let expected = [
+        ("random", Strategy::Sampling, 1000, 0x38d3b9d3d245b692),
+        ("random", Strategy::Sampling, 5000, 0x46b74a31de454bc6),
+        (
+            "random",
+            Strategy::MedianOfNinthers,
+            1000,
+            0xf6ee590de28f6b9e,
+        ),
+        (
+            "random",
+            Strategy::MedianOfNinthers,
+            5000,
+            0xc2de914f106dbcde,
+        ),
+        (
+            "random",
+            Strategy::MedianOfMedians,
+            1000,
+            0xacfbca1ccca2ea92,
+        ),
+        (
+            "random",
+            Strategy::MedianOfMedians,
+            5000,
+            0xe2fdd485d31a0752,
+        ),
+        ("few_distinct", Strategy::Sampling, 1000, 0x70b714f06e678fc1),
+        ("few_distinct", Strategy::Sampling, 5000, 0x12af14f07ca42601),
+        (
+            "few_distinct",
+            Strategy::MedianOfNinthers,
+            1000,
+            0xe1c130e0f65934c1,
+        ),
+        (
+            "few_distinct",
+            Strategy::MedianOfNinthers,
+            5000,
+            0x0e4f414cc5f57db1,
+        ),
+        (
+            "few_distinct",
+            Strategy::MedianOfMedians,
+            1000,
+            0x494e2af9d28824b1,
+        ),
+        (
+            "few_distinct",
+            Strategy::MedianOfMedians,
+            5000,
+            0x6e938671a9a319d1,
+        ),
+        ("sawtooth", Strategy::Sampling, 1000, 0x9b8f0c0657f6950d),
+        ("sawtooth", Strategy::Sampling, 5000, 0xe206506955e1cc2d),
+        (
+            "sawtooth",
+            Strategy::MedianOfNinthers,
+            1000,
+            0x7468e0462f068a1d,
+        ),
+        (
+            "sawtooth",
+            Strategy::MedianOfNinthers,
+            5000,
+            0x24bf1181a93c1f9d,
+        ),
+        (
+            "sawtooth",
+            Strategy::MedianOfMedians,
+            1000,
+            0xb5f3d7f7bedca42d,
+        ),
+        (
+            "sawtooth",
+            Strategy::MedianOfMedians,
+            5000,
+            0x8a736a921cd1735d,
+        ),
+        ("organ_pipe", Strategy::Sampling, 1000, 0x422e8d3751dd9261),
+        ("organ_pipe", Strategy::Sampling, 5000, 0x2c10f1b9894a2531),
+        (
+            "organ_pipe",
+            Strategy::MedianOfNinthers,
+            1000,
+            0xac9a7385ac6c6515,
+        ),
+        (
+            "organ_pipe",
+            Strategy::MedianOfNinthers,
+            5000,
+            0x33165f6fca183f7d,
+        ),
+        (
+            "organ_pipe",
+            Strategy::MedianOfMedians,
+            1000,
+            0x3e9edfdb18bd6a79,
+        ),
+        (
+            "organ_pipe",
+            Strategy::MedianOfMedians,
+            5000,
+            0x15c4f9eba4171cd5,
+        ),
+    ];
+    for (name, strategy, index, hash) in expected {
+        let (_, pattern) = patterns.iter().find(|(label, _)| *label == name).unwrap();
+        let mut rng = WyRng::new(123);
+        let data: Vec<_> = (0..10_000).map(|i| pattern(&mut rng, i)).collect();
+        let hash_of_output = fnv1a(&reorder(&data, index, strategy));
+        assert_eq!(hash_of_output, hash, "{name}, {strategy:?}, {index}");
+    }
+}
+
 #[test]
 fn hidden_shuffle() {
     let count = 100;
@@ -74,6 +655,94 @@ fn iter_rng(rng: &mut WyRng, count: usize, high: usize) -> impl Iterator<Item =
     core::iter::from_fn(move || Some(rng.bounded_usize(0, high))).take(count)
 }
 
+/// Returns an exclusive upper bound for the values of `len` random elements, chosen so that the
+/// elements are either mostly two values, repeated about ten times each, or mostly distinct.
+fn random_high(rng: &mut WyRng, len: usize) -> usize {
+    [2, len / 10 + 1, usize::MAX][rng.bounded_usize(0, 3)]
+}
+
+#[test]
+fn sort_orders() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, 200);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, 10).collect();
+        let expected = if data.windows(2).all(|w| w[0] <= w[1]) {
+            SortOrder::Ascending
+        } else if data.windows(2).all(|w| w[0] >= w[1]) {
+            SortOrder::Descending
+        } else {
+            SortOrder::Unsorted
+        };
+        assert_eq!(detect_order(&data, usize::cmp), expected);
+
+        data.sort();
+        assert_eq!(detect_order(&data, usize::cmp), SortOrder::Ascending);
+        data.reverse();
+        let expected = if data.first() == data.last() {
+            SortOrder::Ascending
+        } else {
+            SortOrder::Descending
+        };
+        assert_eq!(detect_order(&data, usize::cmp), expected);
+    }
+}
+
+#[test]
+fn kth_diffs() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let n = rng.bounded_usize(2, 100);
+        let high = rng.bounded_usize(1, 1000);
+        let data: Vec<_> = iter_rng(&mut rng, n, high).collect();
+        let mut diffs = Vec::new();
+        for (i, x) in data.iter().enumerate() {
+            diffs.extend(data[i + 1..].iter().map(|y| x.abs_diff(*y)));
+        }
+        diffs.sort();
+        for _ in 0..10 {
+            let k = rng.bounded_usize(0, diffs.len());
+            assert_eq!(kth_smallest_pairwise_diff(&data, k), diffs[k]);
+        }
+    }
+}
+
+#[test]
+fn kth_sums() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let m = rng.bounded_usize(1, 60);
+        let n = rng.bounded_usize(1, 60);
+        let high = rng.bounded_usize(1, 1000);
+        let a: Vec<_> = iter_rng(&mut rng, m, high).collect();
+        let b: Vec<_> = iter_rng(&mut rng, n, high).collect();
+        let mut sums: Vec<_> = a
+            .iter()
+            .flat_map(|x| b.iter().map(move |y| x + y))
+            .collect();
+        sums.sort();
+        for _ in 0..10 {
+            let k = rng.bounded_usize(0, m * n);
+            assert_eq!(kth_smallest_sum(&a, &b, k), sums[k]);
+        }
+    }
+}
+
 #[test]
 fn large_median() {
     let mut rng = WyRng::new(123);
@@ -93,6 +762,123 @@ fn large_median() {
     assert!(right.iter().all(|elem| elem > &mid));
 }
 
+#[test]
+fn median_each() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 1;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let n_rows = rng.bounded_usize(1, 50);
+        let n_cols = rng.bounded_usize(1, 50);
+        let data: Vec<_> = iter_rng(&mut rng, n_rows * n_cols, 100).collect();
+
+        let columns = median_each_column(&data, n_cols);
+        for (col, median) in columns.iter().enumerate() {
+            let mut column: Vec<_> = data[col..].iter().step_by(n_cols).copied().collect();
+            column.sort();
+            assert_eq!(*median, column[n_rows / 2]);
+        }
+
+        let mut rows = data.clone();
+        let medians: Vec<_> = median_each_row(&mut rows, n_cols).map(|x| *x).collect();
+        assert_eq!(medians.len(), n_rows);
+        for (row, median) in data.chunks(n_cols).zip(medians) {
+            let mut row = row.to_vec();
+            row.sort();
+            assert_eq!(median, row[n_cols / 2]);
+        }
+    }
+}
+
+#[test]
+fn median_networks() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let mut data: [usize; 9] = core::array::from_fn(|_| rng.bounded_usize(0, 9));
+        let mut sorted = data;
+        sorted.sort();
+        median9(&mut data, &mut usize::lt);
+        assert_eq!(data[4], sorted[4]);
+
+        let mut data: [usize; 25] = core::array::from_fn(|_| rng.bounded_usize(0, 25));
+        let mut sorted = data;
+        sorted.sort();
+        median25(&mut data, &mut usize::lt);
+        assert_eq!(data[12], sorted[12]);
+    }
+}
+
+#[cfg(feature = "filter")]
+#[test]
+fn median_filters() {
+    use crate::median_filter;
+
+    let mut rng = WyRng::new(123);
+
+    for size in [1, 3, 5, 7] {
+        let (width, height) = (rng.bounded_usize(1, 20), rng.bounded_usize(1, 20));
+        let src: Vec<_> = iter_rng(&mut rng, width * height, 10).collect();
+        let mut dst = std::vec![0; src.len()];
+        median_filter(&src, &mut dst, width, size);
+
+        let r = (size / 2) as isize;
+        for (i, out) in dst.iter().enumerate() {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            let mut window = Vec::new();
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let xx = (x + dx).clamp(0, width as isize - 1) as usize;
+                    let yy = (y + dy).clamp(0, height as isize - 1) as usize;
+                    window.push(src[yy * width + xx]);
+                }
+            }
+            window.sort();
+            assert_eq!(*out, window[window.len() / 2]);
+        }
+    }
+}
+
+#[test]
+fn min_max() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(1, 1000);
+        let high = rng.bounded_usize(1, count + 1);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, high).collect();
+        // Ascending and descending runs of blocks make some blocks dense with new extremes.
+        match rng.bounded_usize(0, 3) {
+            0 => data.sort(),
+            1 => data.sort_by(|a, b| b.cmp(a)),
+            _ => {}
+        }
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        for index in [0, count - 1] {
+            let mut data = data.clone();
+            select_nth_unstable(&mut data, index);
+            assert_eq!(data[index], sorted[index]);
+            data.sort();
+            assert_eq!(data, sorted);
+        }
+    }
+}
+
 #[test]
 fn min_10() {
     let len = 10;
@@ -120,7 +906,7 @@ fn min_10() {
         assert_eq!(data, cloned);
     }
 
-    let mut data: Vec<_> = core::iter::repeat(1).take(10).collect();
+    let mut data: Vec<_> = core::iter::repeat_n(1, 10).collect();
     let (u, v) = partition_equal_min(&mut data, 0, &mut usize::lt);
     assert_eq!(u, 0);
     assert_eq!(v, 9);
@@ -188,8 +974,8 @@ fn pivots() {
     let repeat = 10_000;
 
     fn record(mut data: Vec<usize>, index: usize, total_cost: &mut f64, results: &mut Vec<u8>) {
-        let (p, _) = choose_pivot(&mut data, index, &mut usize::lt);
-        let (u, v) = partition_at(&mut data, p, &mut usize::lt);
+        let (p, _) = choose_pivot(&mut data, index, &mut usize::lt, &PARAMS);
+        let (u, v) = partition_at(&mut data, p, &mut usize::lt, PARAMS.partition_block);
         let count = data.len();
         let cost = if index < u {
             u
@@ -250,44 +1036,2305 @@ fn pivots() {
 }
 
 #[test]
-fn patterns() {
-    let mut rng = WyRng::new(123);
-
-    #[cfg(not(miri))]
-    let count = 10_000;
-    #[cfg(miri)]
-    let count = 1000;
-
+fn order_statistics() {
     #[cfg(not(miri))]
-    let repeat = 1000;
-
+    let repeat = 10_000;
     #[cfg(miri)]
-    let repeat = 10;
+    let repeat = 100;
 
-    fn run(mut data: Vec<usize>, index: usize) {
-        let (left, nth, right) = select_nth_unstable(data.as_mut_slice(), index);
-        left.iter().enumerate().for_each(|(i, elem)| match i {
-            i if elem > nth => panic!("left[{i}] = {elem} > nth = {nth}"),
-            _ => (),
-        });
-        right.iter().enumerate().for_each(|(i, elem)| match i {
-            i if elem < nth => panic!("left[{i}] = {elem} < nth = {nth}"),
-            _ => (),
-        });
-    }
+    let mut rng = WyRng::new(123);
+    let mut set = OrderStatistics::new();
+    let mut sorted = Vec::new();
 
-    for iter in 0..repeat {
-        let index = (iter * count) / repeat;
-        run(ptn_sorted(count, rng.as_mut()), index);
-        run(ptn_mostlysorted(count, rng.as_mut()), index);
-        run(ptn_reversed(count, rng.as_mut()), index);
-        run(ptn_sawtooth(count, rng.as_mut()), index);
-        run(ptn_zeroone(count, rng.as_mut()), index);
+    for _iter in 0..repeat {
+        let value = rng.bounded_usize(0, 100);
+        if rng.bounded_usize(0, 3) > 0 {
+            set.insert(value);
+            let i = sorted.partition_point(|x| x < &value);
+            sorted.insert(i, value);
+        } else {
+            let removed = set.remove(&value);
+            match sorted.binary_search(&value) {
+                Ok(i) => assert_eq!(removed, Some(sorted.remove(i))),
+                Err(_) => assert_eq!(removed, None),
+            }
+        }
+        assert_eq!(set.len(), sorted.len());
+        assert_eq!(set.rank(&value), sorted.partition_point(|x| x < &value));
+        assert_eq!(set.contains(&value), sorted.binary_search(&value).is_ok());
+        let k = rng.bounded_usize(0, sorted.len() + 1);
+        assert_eq!(set.kth(k), sorted.get(k));
     }
 }
 
 #[test]
-fn sample_n() {
+fn params() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let kth_of_3_max = rng.bounded_usize(24, 512);
+        let kth_of_5_max = rng.bounded_usize(kth_of_3_max.max(48), 2048);
+        let params = Params {
+            tinysort_cutoff: rng.bounded_usize(8, 129),
+            kth_of_3_max,
+            kth_of_5_max,
+            kth_of_7_max: rng.bounded_usize(kth_of_5_max.max(96), 8192),
+            partition_block: rng.bounded_usize(1, 257),
+            bad_round_limit: rng.bounded_usize(0, 8),
+        };
+        assert!(params.is_valid());
+        let count = rng.bounded_usize(2, 20_000);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, count / 4 + 1).collect();
+        let index = rng.bounded_usize(0, count);
+        select_with(&mut data, index, &mut usize::lt, &params);
+        let nth = data[index];
+        assert!(data[..index].iter().all(|x| *x <= nth));
+        assert!(data[index..].iter().all(|x| *x >= nth));
+    }
+}
+
+#[test]
+fn invalid_params() {
+    let invalid = [
+        Params {
+            tinysort_cutoff: 7,
+            ..PARAMS
+        },
+        Params {
+            tinysort_cutoff: 129,
+            ..PARAMS
+        },
+        Params {
+            kth_of_3_max: 23,
+            ..PARAMS
+        },
+        Params {
+            kth_of_5_max: 47,
+            ..PARAMS
+        },
+        Params {
+            kth_of_7_max: 95,
+            ..PARAMS
+        },
+        Params {
+            kth_of_3_max: 600,
+            kth_of_5_max: 500,
+            ..PARAMS
+        },
+        Params {
+            kth_of_5_max: 2000,
+            kth_of_7_max: 1000,
+            ..PARAMS
+        },
+        Params {
+            partition_block: 0,
+            ..PARAMS
+        },
+        Params {
+            partition_block: 257,
+            ..PARAMS
+        },
+    ];
+    for params in invalid {
+        assert!(!params.is_valid(), "{params:?}");
+    }
+    // The bounds themselves are valid.
+    let bounds = Params {
+        tinysort_cutoff: 128,
+        kth_of_3_max: 1024,
+        kth_of_5_max: 1024,
+        kth_of_7_max: 1024,
+        partition_block: 256,
+        ..PARAMS
+    };
+    assert!(bounds.is_valid());
+}
+
+#[test]
+fn narrow_products() {
+    // The sample indices are computed as `count * index / len`, which must not overflow even if
+    // the product doesn't fit in a `usize`.
+    assert_eq!(mul_div(3, 4, 5), 2);
+    assert_eq!(
+        mul_div(usize::MAX, usize::MAX - 1, usize::MAX),
+        usize::MAX - 1
+    );
+    assert_eq!(mul_div(usize::MAX, 4, 2), usize::MAX);
+    assert_eq!(mul_div(1 << 20, usize::MAX / 3, usize::MAX), (1 << 20) / 3);
+    let mut rng = WyRng::new(123);
+    for _ in 0..1000 {
+        let len = rng.bounded_usize(1, usize::MAX);
+        let index = rng.bounded_usize(0, len);
+        let count = rng.bounded_usize(1, 1 << 16);
+        let k = mul_div(count, index, len);
+        assert!(k < count);
+        assert_eq!(k as u128, count as u128 * index as u128 / len as u128);
+    }
+}
+
+#[test]
+fn strategies() {
+    #[cfg(not(miri))]
+    let repeat = 200;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for strategy in [
+        Strategy::Sampling,
+        Strategy::SamplingWithReplacement,
+        Strategy::MedianOfNinthers,
+        Strategy::FloydRivest,
+        Strategy::MedianOfMedians,
+    ] {
+        let options = SelectOptions {
+            strategy,
+            ..Default::default()
+        };
+        for _ in 0..repeat {
+            let count = match rng.bool() {
+                true => rng.bounded_usize(1, 300),
+                false => rng.bounded_usize(1, 50_000),
+            };
+            let high = rng.bounded_usize(1, count + 1);
+            let mut data: Vec<_> = iter_rng(&mut rng, count, high).collect();
+            // Skew the indices towards the ends of the slice.
+            let index = match rng.bounded_usize(0, 3) {
+                0 => rng.bounded_usize(0, count / 6 + 1),
+                1 => count - 1 - rng.bounded_usize(0, count / 6 + 1),
+                _ => rng.bounded_usize(0, count),
+            };
+            let mut sorted = data.clone();
+            sorted.sort();
+            let (left, nth, right) = select_nth_unstable_with_options(&mut data, index, &options);
+            assert_eq!(*nth, sorted[index]);
+            assert!(left.iter().all(|x| x <= nth));
+            assert!(right.iter().all(|x| x >= nth));
+        }
+    }
+}
+
+#[test]
+fn replacement_pivots() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 20;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        // The slices may be shorter than the sample.
+        let len = rng.bounded_usize(1, 2000);
+        let data: Vec<_> = iter_rng(&mut rng, len, len).collect();
+        let index = rng.bounded_usize(0, len);
+        let copy = data.clone();
+        let (p, _) = replacement_pivot(&data, index, &mut usize::lt, &PARAMS);
+        assert!(p < len);
+        assert_eq!(data, copy);
+    }
+}
+
+#[test]
+fn smallest_k() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let k = rng.bounded_usize(0, len + 1);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let (smallest, rest) = k_smallest(&mut data, k);
+        assert_eq!((smallest.len(), rest.len()), (k, len - k));
+        smallest.sort();
+        assert_eq!(smallest, &sorted[..k]);
+        assert!(rest.iter().all(|&x| k == 0 || x >= sorted[k - 1]));
+        data.sort();
+        assert_eq!(data, sorted);
+    }
+
+    let mut data: Vec<i32> = (-500..500).collect();
+    let (largest, _) = k_smallest_by(&mut data, 3, |a, b| b.cmp(a));
+    largest.sort();
+    assert_eq!(largest, [497, 498, 499]);
+    let (closest, _) = k_smallest_by_key(&mut data, 1, |x| x.abs());
+    assert_eq!(closest, [0]);
+    let (all, none) = k_smallest_by_lt(&mut data, 1000, |a, b| a < b);
+    assert_eq!((all.len(), none.len()), (1000, 0));
+}
+
+#[test]
+fn largest_k() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let k = rng.bounded_usize(0, len + 1);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let (rest, largest) = k_largest(&mut data, k);
+        assert_eq!((rest.len(), largest.len()), (len - k, k));
+        largest.sort();
+        assert_eq!(largest, &sorted[len - k..]);
+        assert!(rest.iter().all(|&x| k == 0 || x <= sorted[len - k]));
+        data.sort();
+        assert_eq!(data, sorted);
+    }
+
+    let mut data: Vec<i32> = (-500..500).collect();
+    let (_, smallest) = k_largest_by(&mut data, 3, |a, b| b.cmp(a));
+    smallest.sort();
+    assert_eq!(smallest, [-500, -499, -498]);
+    let (_, farthest) = k_largest_by_key(&mut data, 1, |x| x.abs());
+    assert_eq!(farthest, [-500]);
+    let (none, all) = k_largest_by_lt(&mut data, 1000, |a, b| a < b);
+    assert_eq!((none.len(), all.len()), (0, 1000));
+}
+
+#[test]
+fn partial_sorts() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let k = match rng.bounded_usize(0, 4) {
+            0 => len,
+            1 => rng.bounded_usize(0, 30).min(len),
+            _ => rng.bounded_usize(0, len + 1),
+        };
+        let mut sorted = data.clone();
+        sorted.sort();
+        partial_sort(&mut data, k);
+        assert_eq!(data[..k], sorted[..k]);
+        if k > 0 {
+            assert!(data[k..].iter().all(|x| *x >= data[k - 1]));
+        }
+        data.sort();
+        assert_eq!(data, sorted);
+    }
+
+    let mut data: Vec<i32> = (-500..500).rev().collect();
+    partial_sort_by(&mut data, 3, |a, b| b.cmp(a));
+    assert_eq!(data[..3], [499, 498, 497]);
+    partial_sort_by_key(&mut data, 5, |x| x.abs());
+    assert_eq!(data[0], 0);
+    assert!(data[1..5].iter().all(|x| (1..=2).contains(&x.abs())));
+    partial_sort_by_lt(&mut data, 1000, |a, b| a < b);
+    assert!(data.is_sorted());
+}
+
+#[test]
+fn select_ranges() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let (a, b) = (rng.bounded_usize(0, len + 1), rng.bounded_usize(0, len + 1));
+        let (start, end) = (a.min(b), a.max(b));
+        let mut sorted = data.clone();
+        sorted.sort();
+        let (below, window, above) = select_range(&mut data, start..end);
+        assert_eq!((below.len(), window.len()), (start, end - start));
+        assert_eq!(window, &sorted[start..end]);
+        let split = sorted.get(start).copied().unwrap_or(usize::MAX);
+        assert!(below.iter().all(|&x| x <= split));
+        assert!(above.iter().all(|&x| end == start || x >= sorted[end - 1]));
+        assert!(above.iter().all(|&x| end > start || x >= split));
+        data.sort();
+        assert_eq!(data, sorted);
+    }
+
+    let mut data: Vec<i32> = (-500..500).collect();
+    let (_, window, _) = select_range_by(&mut data, 0..3, |a, b| b.cmp(a));
+    assert_eq!(window, [499, 498, 497]);
+    let (below, window, _) = select_range_by_key(&mut data, 1..5, |x| x.abs());
+    assert_eq!(below, [0]);
+    assert!(window.iter().all(|x| (1..=2).contains(&x.abs())));
+    let (below, window, above) = select_range_by_lt(&mut data, 1000..1000, |a, b| a < b);
+    assert_eq!((below.len(), window.len(), above.len()), (1000, 0, 0));
+}
+
+#[test]
+fn partition_equal_ternary() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 2;
+
+    let mut rng = WyRng::new(123);
+    for len in [1, 10, 300, 2000] {
+        for high in [2, 16, len] {
+            for _ in 0..repeat {
+                let block = rng.bounded_usize(1, crate::MAX_PARTITION_BLOCK + 1);
+                let mut data: Vec<_> = iter_rng(rng.as_mut(), len, high).collect();
+                // Large elements at the front make the single pass stop partway through.
+                let front = rng.bounded_usize(0, len + 1);
+                data[..front].sort_by(|a, b| b.cmp(a));
+                let mut sorted = data.clone();
+                sorted.sort();
+                // Pivots of low ranks are partitioned in a single pass, and the others in two.
+                let rank = match rng.bounded_usize(0, 2) {
+                    0 => rng.bounded_usize(0, len.div_ceil(10)),
+                    _ => rng.bounded_usize(0, len),
+                };
+                let pivot = sorted[rank];
+                let index = data.iter().position(|&x| x == pivot).unwrap();
+                let (u, v) = partition_equal(&mut data, index, &mut usize::lt, block);
+                assert_eq!(u, sorted.partition_point(|&x| x < pivot));
+                assert_eq!(v + 1, sorted.partition_point(|&x| x <= pivot));
+                assert!(data[..u].iter().all(|&x| x < pivot));
+                assert!(data[u..=v].iter().all(|&x| x == pivot));
+                assert!(data[v + 1..].iter().all(|&x| x > pivot));
+                data.sort();
+                assert_eq!(data, sorted);
+            }
+        }
+    }
+}
+
+#[test]
+fn partition_strategies() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    // Lomuto partitioning, which groups all elements equal to the pivot together.
+    struct Lomuto(Cell<usize>);
+
+    impl<T> PartitionStrategy<T> for Lomuto {
+        fn partition<F>(&self, data: &mut [T], pivot: usize, _: bool, lt: &mut F) -> (usize, usize)
+        where
+            F: FnMut(&T, &T) -> bool,
+        {
+            self.0.set(self.0.get() + 1);
+            data.swap(0, pivot);
+            let mut u = 0;
+            for i in 1..data.len() {
+                if lt(&data[i], &data[0]) {
+                    u += 1;
+                    data.swap(i, u);
+                }
+            }
+            data.swap(0, u);
+            let mut v = u;
+            for i in u + 1..data.len() {
+                if !lt(&data[u], &data[i]) {
+                    v += 1;
+                    data.swap(i, v);
+                }
+            }
+            (u, v)
+        }
+    }
+
+    let mut rng = WyRng::new(123);
+    let lomuto = Lomuto(Cell::new(0));
+    for strategy in [
+        Strategy::Sampling,
+        Strategy::SamplingWithReplacement,
+        Strategy::MedianOfNinthers,
+        Strategy::FloydRivest,
+        Strategy::MedianOfMedians,
+    ] {
+        let options = SelectOptions {
+            strategy,
+            ..Default::default()
+        };
+        for _ in 0..repeat {
+            let count = rng.bounded_usize(1, 5000);
+            let high = rng.bounded_usize(1, count + 1);
+            let data: Vec<_> = iter_rng(&mut rng, count, high).collect();
+            let index = rng.bounded_usize(0, count);
+            let mut sorted = data.clone();
+            sorted.sort();
+            let mut copy = data.clone();
+            let nth = *select_nth_unstable_with_partition(&mut copy, index, &options, &lomuto).1;
+            assert_eq!(nth, sorted[index]);
+            assert!(is_partitioned(&copy, index, &mut usize::lt));
+            let mut copy = data;
+            let nth =
+                *select_nth_unstable_with_partition(&mut copy, index, &options, &TernaryPartition)
+                    .1;
+            assert_eq!(nth, sorted[index]);
+            assert!(is_partitioned(&copy, index, &mut usize::lt));
+        }
+    }
+    assert!(lomuto.0.get() > 0);
+}
+
+#[test]
+fn bounded_comparisons() {
+    #[cfg(not(miri))]
+    let lens = [10, 100, 1000, 10_000, 100_000];
+    #[cfg(miri)]
+    let lens = [10, 100, 1000];
+
+    let mut rng = WyRng::new(123);
+    let mut worst = 0.0f64;
+    for len in lens {
+        let patterns: [Vec<usize>; 7] = [
+            iter_rng(&mut rng, len, len).collect(),
+            iter_rng(&mut rng, len, 4).collect(),
+            (0..len).collect(),
+            (0..len).rev().collect(),
+            (0..len).map(|x| x.min(len - x)).collect(),
+            (0..len).map(|x| x % 17).collect(),
+            std::vec![7; len],
+        ];
+        for mut pattern in patterns {
+            // Break the order, so that the selection can't return early.
+            pattern.swap(0, len / 2);
+            for index in [1, len / 4, len / 2, len * 3 / 4, len - 2] {
+                let mut data = pattern.clone();
+                let mut comparisons = 0;
+                let mut lt = |a: &usize, b: &usize| {
+                    comparisons += 1;
+                    a < b
+                };
+                select_using(
+                    &mut data,
+                    index,
+                    &mut lt,
+                    &PARAMS,
+                    Strategy::MedianOfMedians,
+                );
+                assert!(is_partitioned(&data, index, &mut usize::lt));
+                worst = worst.max(comparisons as f64 / len as f64);
+            }
+        }
+    }
+    // The theoretical bound is about 70 comparisons per element.
+    assert!(worst <= 20.);
+}
+
+/// McIlroy's adversary: the values are decided lazily, as the elements are compared. All undecided
+/// elements are equal and larger than the decided ones. When two undecided elements are compared,
+/// the one that was last compared with an undecided element, which is likely the pivot, is
+/// decided first, so that the pivot ends up as small as possible.
+struct Adversary {
+    values: Vec<Cell<usize>>,
+    decided: Cell<usize>,
+    candidate: Cell<usize>,
+    comparisons: Cell<usize>,
+}
+
+impl Adversary {
+    fn new(len: usize) -> Self {
+        let values: Vec<_> = (0..len).map(|_| Cell::new(len)).collect();
+        // Decide the first two elements out of order, so that the slice isn't sorted.
+        values[0].set(1);
+        values[1].set(0);
+        Self {
+            values,
+            decided: Cell::new(2),
+            candidate: Cell::new(0),
+            comparisons: Cell::new(0),
+        }
+    }
+
+    fn lt(&self, &a: &usize, &b: &usize) -> bool {
+        let (values, len) = (&self.values, self.values.len());
+        self.comparisons.set(self.comparisons.get() + 1);
+        if values[a].get() == len && values[b].get() == len {
+            let x = if a == self.candidate.get() { a } else { b };
+            values[x].set(self.decided.replace(self.decided.get() + 1));
+        }
+        if values[a].get() == len {
+            self.candidate.set(a);
+        } else if values[b].get() == len {
+            self.candidate.set(b);
+        }
+        values[a].get() < values[b].get()
+    }
+
+    /// Returns the number of comparisons per element, after checking that `data` is partitioned
+    /// at each of `indices` by the decided values.
+    fn comparisons(&self, data: &[usize], indices: &[usize]) -> f64 {
+        let mut value_lt = |&a: &usize, &b: &usize| self.values[a].get() < self.values[b].get();
+        for &index in indices {
+            assert!(is_partitioned(data, index, &mut value_lt));
+        }
+        self.comparisons.get() as f64 / data.len() as f64
+    }
+}
+
+#[test]
+fn adversarial_comparisons() {
+    fn comparisons(len: usize, bad_round_limit: Option<usize>) -> f64 {
+        let adversary = Adversary::new(len);
+        let mut data: Vec<_> = (0..len).collect();
+        let index = len / 2;
+        let options = SelectOptions {
+            bad_round_limit,
+            deterministic: true,
+            ..Default::default()
+        };
+        let params = options.params::<usize>();
+        select_using(
+            &mut data,
+            index,
+            &mut |a, b| adversary.lt(a, b),
+            &params,
+            Strategy::Sampling,
+        );
+        adversary.comparisons(&data, &[index])
+    }
+
+    #[cfg(not(miri))]
+    let lens = [1000, 10_000, 100_000];
+    #[cfg(miri)]
+    let lens = [1000];
+
+    for len in lens {
+        let bounded = comparisons(len, None);
+        assert!(bounded <= 20.);
+        // Without the fallback, the adversary makes the selection superlinear.
+        assert!(comparisons(len, Some(usize::MAX)) > bounded);
+    }
+}
+
+#[test]
+fn adversarial_entry_points() {
+    // The functions that partition in their own loops fall back to the median of medians too.
+    use crate::{
+        select_nth_unstable_with_budget_by_lt, select_nth_unstable_with_fold_by_lt,
+        select_nth_unstable_with_options_by_lt, select_nth_unstable_with_swaps_by_lt,
+        select_nth_with_scratch_by_lt, select_rank_in_by_lt,
+    };
+
+    #[cfg(not(miri))]
+    let len = 100_000;
+    #[cfg(miri)]
+    let len = 1000;
+    let (i, j) = (len / 2, 3 * len / 4);
+    let fresh = || (Adversary::new(len), (0..len).collect::<Vec<_>>());
+
+    let (adversary, mut data) = fresh();
+    select_two_ranks_by_lt(&mut data, i, j, |a, b| adversary.lt(a, b));
+    assert!(adversary.comparisons(&data, &[i, j]) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    // Adjacent ranks stay in the same part, so that every round partitions for all of them.
+    let mut indices: Vec<_> = (i..i + 16).collect();
+    select_many_nth_unstable_by_lt(&mut data, &mut indices, |a, b| adversary.lt(a, b));
+    assert!(adversary.comparisons(&data, &indices) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    let lt = |a: &usize, b: &usize| adversary.lt(a, b);
+    select_nth_unstable_with_fold_by_lt(&mut data, i, 0, |n, _| n + 1, lt);
+    assert!(adversary.comparisons(&data, &[i]) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    let lt = |a: &usize, b: &usize| adversary.lt(a, b);
+    let result = select_nth_unstable_with_budget_by_lt(&mut data, i, 20 * len, lt);
+    assert!(result.is_exact());
+    assert!(adversary.comparisons(&data, &[i]) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    let mut mirror = data.clone();
+    let on_swap = |a, b| mirror.swap(a, b);
+    select_nth_unstable_with_swaps_by_lt(&mut data, i, on_swap, |a, b| adversary.lt(a, b));
+    assert_eq!(mirror, data);
+    assert!(adversary.comparisons(&data, &[i]) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    select_rank_in_by_lt(&mut data, i..i + 1, |a, b| adversary.lt(a, b));
+    assert!(adversary.comparisons(&data, &[i]) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    let options = SelectOptions {
+        strategy: Strategy::FloydRivest,
+        ..Default::default()
+    };
+    let lt = |a: &usize, b: &usize| adversary.lt(a, b);
+    select_nth_unstable_with_options_by_lt(&mut data, i, &options, lt);
+    assert!(adversary.comparisons(&data, &[i]) <= 20.);
+
+    let (adversary, mut data) = fresh();
+    let mut scratch = Vec::with_capacity(len);
+    let lt = |a: &usize, b: &usize| adversary.lt(a, b);
+    select_nth_with_scratch_by_lt(&mut data, i, scratch.spare_capacity_mut(), lt);
+    assert!(adversary.comparisons(&data, &[i]) <= 20.);
+
+    #[cfg(feature = "rayon")]
+    {
+        use std::sync::Mutex;
+
+        let (adversary, mut data) = fresh();
+        let adversary = Mutex::new(adversary);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            crate::par_select_nth_unstable_by_lt(&mut data, i, |a, b| {
+                adversary.lock().unwrap().lt(a, b)
+            })
+        });
+        let adversary = adversary.into_inner().unwrap();
+        assert!(adversary.comparisons(&data, &[i]) <= 20.);
+    }
+}
+
+#[test]
+fn retained() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(0, 500);
+        let data: Vec<_> = iter_rng(&mut rng, count, count / 4 + 1).collect();
+        let k = rng.bounded_usize(0, count + 10);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let smallest = &sorted[..k.min(count)];
+        let largest: Vec<_> = sorted.iter().rev().take(k).copied().collect();
+
+        let mut v = data.clone();
+        retain_smallest_k(&mut v, k, true);
+        assert_eq!(v, smallest);
+        let mut v = data.clone();
+        retain_smallest_k(&mut v, k, false);
+        v.sort();
+        assert_eq!(v, smallest);
+
+        let mut v = data.clone();
+        truncate_to_top_k(&mut v, k, true);
+        assert_eq!(v, largest);
+        let mut v = data.clone();
+        truncate_to_top_k(&mut v, k, false);
+        v.sort_by(|a, b| b.cmp(a));
+        assert_eq!(v, largest);
+    }
+}
+
+#[test]
+fn reports() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for strategy in [
+        Strategy::Sampling,
+        Strategy::SamplingWithReplacement,
+        Strategy::MedianOfNinthers,
+        Strategy::FloydRivest,
+        Strategy::MedianOfMedians,
+    ] {
+        let options = SelectOptions {
+            strategy,
+            ..Default::default()
+        };
+        for _ in 0..repeat {
+            let count = rng.bounded_usize(2, 20_000);
+            let high = rng.bounded_usize(1, count + 1);
+            let mut data: Vec<_> = iter_rng(&mut rng, count, high).collect();
+            let index = rng.bounded_usize(0, count);
+            let report = select_nth_unstable_with_report(&mut data, index, &options);
+            assert_eq!(report.strategy, strategy);
+            assert!(is_partitioned(&data, index, &mut usize::lt));
+
+            // Each round continues in the part of the previous round that contains the index.
+            let (mut len, mut offset) = (count, 0);
+            for round in &report.rounds {
+                assert_eq!((round.len, round.index), (len, index - offset));
+                assert!(round.below + round.middle <= round.len);
+                if round.index < round.below {
+                    len = round.below;
+                } else if round.bracketed {
+                    len = round.middle;
+                    offset += round.below;
+                } else {
+                    len -= round.below + round.middle;
+                    offset += round.below + round.middle;
+                }
+            }
+            let done = report.presorted || report.already_partitioned || report.sorted_len > 0;
+            assert!(done || report.rounds.last().is_some_and(|r| r.bracketed));
+            if strategy == Strategy::FloydRivest && count > 600 && index > 0 && index < count - 1 {
+                assert_eq!(report.rounds[0].kind, RoundKind::TwoPivots);
+            }
+        }
+    }
+
+    // Sorted slices are detected before any rounds.
+    let mut data: Vec<_> = (0..1000).collect();
+    let report = select_nth_unstable_with_report(&mut data, 500, &SelectOptions::default());
+    assert!(report.presorted && report.rounds.is_empty());
+}
+
+#[test]
+fn stats() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(1, 20_000);
+        let high = rng.bounded_usize(1, count + 1);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, high).collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+        let index = rng.bounded_usize(0, count);
+
+        let ((left, nth, right), report) = select_nth_unstable_with_stats(&mut data, index);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+        assert_eq!(report.strategy, Strategy::Sampling);
+        assert!(count < 2 || report.comparisons >= count - 1);
+        assert!(report.comparisons <= 10 * count);
+    }
+
+    // Few distinct values make the pivots repeat.
+    let mut data: Vec<_> = iter_rng(&mut rng, 10_000, 3).collect();
+    let (_, report) = select_nth_unstable_with_stats(&mut data, 5000);
+    assert!(report.fell_back());
+    let mut data: Vec<_> = iter_rng(&mut rng, 10_000, 10_000).collect();
+    let (_, report) = select_nth_unstable_with_stats(&mut data, 5000);
+    assert!(!report.fell_back());
+}
+
+#[test]
+fn signed_ranges() {
+    #[cfg(not(miri))]
+    let repeat = 10_000;
+    #[cfg(miri)]
+    let repeat = 100;
+
+    let mut rng = WyRng::new(123);
+    let (mut min, mut max) = (i8::MAX, i8::MIN);
+    for _ in 0..repeat {
+        let x = rng.bounded_i8(-3, 4);
+        (min, max) = (min.min(x), max.max(x));
+        assert!((-3..4).contains(&rng.bounded_i16(-3, 4)));
+        assert!((i32::MIN..0).contains(&rng.bounded_i32(i32::MIN, 0)));
+        assert!((-1..i64::MAX).contains(&rng.bounded_i64(-1, i64::MAX)));
+        assert!((-10..-5).contains(&rng.bounded_i128(-10, -5)));
+        assert!((-1..1).contains(&rng.bounded_isize(-1, 1)));
+        assert!((-0.5..0.5).contains(&rng.bounded_f32(-0.5, 0.5)));
+    }
+    assert_eq!((min, max), (-3, 3));
+}
+
+#[test]
+fn random_collections() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let ascii = rng.ascii_string(2, 10);
+        assert!((2..10).contains(&ascii.len()));
+        assert!(ascii.bytes().all(|b| b.is_ascii_graphic() || b == b' '));
+
+        let utf8 = rng.utf8_string(0, 5);
+        assert!(utf8.chars().count() < 5);
+
+        let vec = rng.vec(3, 4, |rng| rng.bounded_i8(-1, 1));
+        assert_eq!(vec.len(), 3);
+        assert!(vec.iter().all(|x| (-1..1).contains(x)));
+    }
+}
+
+#[test]
+fn split_rngs() {
+    let mut rng = WyRng::new(123);
+    let (mut a, mut b) = (rng.split(), rng.split());
+    let mut again = WyRng::new(123);
+    assert_eq!(again.split().u64(), a.u64());
+    assert_eq!(again.split().u64(), b.u64());
+    assert_ne!(a.u64(), b.u64());
+}
+
+#[test]
+fn thread_rngs() {
+    // The generators differ between calls and between threads.
+    let a = thread_rng().u64();
+    let b = thread_rng().u64();
+    let c = std::thread::spawn(|| thread_rng().u64()).join().unwrap();
+    assert!(a != b && a != c && b != c);
+}
+
+#[test]
+fn arrays() {
+    fn check<const N: usize>(rng: &mut WyRng) {
+        let mut data = [0u16; N];
+        data.iter_mut()
+            .for_each(|x| *x = rng.bounded_u32(0, 20) as u16);
+        let mut sorted = data;
+        sorted.sort();
+        let index = rng.bounded_usize(0, N);
+        let (left, nth, right) = select_nth_unstable_array(&mut data, index);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+        let (_, nth, _) = select_nth_unstable_array_by(&mut data, index, |a, b| b.cmp(a));
+        assert_eq!(*nth, sorted[N - 1 - index]);
+    }
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        check::<1>(&mut rng);
+        check::<7>(&mut rng);
+        check::<16>(&mut rng);
+        // Longer arrays are selected like slices.
+        check::<32>(&mut rng);
+        check::<100>(&mut rng);
+    }
+}
+
+#[test]
+fn rank_windows() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let lo = rng.bounded_usize(0, len);
+        let hi = rng.bounded_usize(lo + 1, len + 1);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let (left, nth, right) = select_rank_in(&mut data, lo..hi);
+        let index = left.len();
+        assert!((lo..hi).contains(&index));
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+
+        let (left, nth, _) = select_rank_in_by(&mut data, lo..hi, |a, b| b.cmp(a));
+        let index = left.len();
+        assert!((lo..hi).contains(&index));
+        assert_eq!(*nth, sorted[len - 1 - index]);
+    }
+}
+
+#[test]
+fn comparator_forms() {
+    use crate::distributed::{
+        partition_by_value_by, partition_by_value_by_lt, rank_of, rank_of_by, rank_of_by_lt,
+    };
+    use crate::{
+        median_each_column_by, median_each_column_by_lt, median_each_row_by, median_each_row_by_lt,
+        partition_by_range_by_lt, percentile_by, percentile_by_lt, reselect_nth_unstable_by,
+        reselect_nth_unstable_by_lt, retain_smallest_k_by, retain_smallest_k_by_lt,
+        select_nth_unstable_array_by_lt, select_nth_unstable_by,
+        select_nth_unstable_with_budget_by_lt, select_nth_unstable_with_options_by,
+        select_nth_unstable_with_options_by_lt, select_nth_unstable_with_partition_by,
+        select_nth_unstable_with_partition_by_lt, select_nth_unstable_with_report_by,
+        select_nth_unstable_with_report_by_lt, select_nth_unstable_with_stats_by,
+        select_nth_unstable_with_stats_by_lt, select_nth_unstable_with_swaps_by_lt,
+        select_rank_in_by_lt, tinysort_stable_by, trim_percentiles_by, trim_percentiles_by_lt,
+        truncate_to_top_k_by, truncate_to_top_k_by_lt, winsorize_by, winsorize_by_lt,
+        BlockPartition,
+    };
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (200, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 100);
+
+    // Select in descending order, so that the comparator is not accidentally ignored.
+    let desc = |a: &usize, b: &usize| b.cmp(a);
+    let gt = |a: &usize, b: &usize| a > b;
+    let options = SelectOptions::default();
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let index = rng.bounded_usize(0, len);
+        let mut sorted = data.clone();
+        sorted.sort_by(desc);
+        let nth = sorted[index];
+
+        let mut v = data.clone();
+        assert_eq!(
+            *select_nth_unstable_with_options_by(&mut v, index, &options, desc).1,
+            nth
+        );
+        let mut v = data.clone();
+        assert_eq!(
+            *select_nth_unstable_with_options_by_lt(&mut v, index, &options, gt).1,
+            nth
+        );
+        let partition = BlockPartition::default();
+        let mut v = data.clone();
+        let (_, x, _) =
+            select_nth_unstable_with_partition_by(&mut v, index, &options, &partition, desc);
+        assert_eq!(*x, nth);
+        let mut v = data.clone();
+        let (_, x, _) =
+            select_nth_unstable_with_partition_by_lt(&mut v, index, &options, &partition, gt);
+        assert_eq!(*x, nth);
+        let mut v = data.clone();
+        select_nth_unstable_with_report_by(&mut v, index, &options, desc);
+        assert_eq!(v[index], nth);
+        let mut v = data.clone();
+        select_nth_unstable_with_report_by_lt(&mut v, index, &options, gt);
+        assert_eq!(v[index], nth);
+        let mut v = data.clone();
+        assert_eq!(
+            *select_nth_unstable_with_stats_by(&mut v, index, desc).0 .1,
+            nth
+        );
+        let mut v = data.clone();
+        assert_eq!(
+            *select_nth_unstable_with_stats_by_lt(&mut v, index, gt).0 .1,
+            nth
+        );
+        let mut v = data.clone();
+        assert_eq!(
+            *select_nth_unstable_with_swaps_by_lt(&mut v, index, |_, _| {}, gt).1,
+            nth
+        );
+        let mut v = data.clone();
+        let result = select_nth_unstable_with_budget_by_lt(&mut v, index, usize::MAX, gt);
+        assert_eq!(result.candidates, [nth]);
+        let mut v = data.clone();
+        let (left, x, _) = select_rank_in_by_lt(&mut v, index..len, gt);
+        assert!((index..len).contains(&left.len()));
+        assert!(left.iter().all(|y| y >= x));
+
+        let mut v = data.clone();
+        let (below, within, above) = partition_by_range_by_lt(&mut v, &nth, &nth, gt);
+        assert!(below.iter().all(|&x| x > nth));
+        assert!(within.iter().all(|&x| x == nth));
+        assert!(above.iter().all(|&x| x < nth));
+        let counts = rank_of(&data, &nth);
+        let mut v = data.clone();
+        assert_eq!(partition_by_value_by(&mut v, &nth, usize::cmp), counts);
+        let mut v = data.clone();
+        assert_eq!(partition_by_value_by_lt(&mut v, &nth, usize::lt), counts);
+        assert!(v[..counts.less].iter().all(|&x| x < nth));
+        assert!(v[counts.less..counts.less + counts.equal]
+            .iter()
+            .all(|&x| x == nth));
+        assert_eq!(rank_of_by(&data, &nth, usize::cmp), counts);
+        assert_eq!(rank_of_by_lt(&data, &nth, usize::lt), counts);
+
+        // Reselecting after changing an element on each side.
+        let mut v = data.clone();
+        select_nth_unstable_by(&mut v, index, desc);
+        let mut modified = [rng.bounded_usize(0, len), rng.bounded_usize(0, len)];
+        for &i in &modified {
+            v[i] = rng.bounded_usize(0, max);
+        }
+        let mut expected = v.clone();
+        expected.sort_by(desc);
+        let mut w = v.clone();
+        let mut m = modified;
+        assert_eq!(
+            *reselect_nth_unstable_by(&mut v, index, &mut modified, desc).1,
+            expected[index]
+        );
+        assert_eq!(
+            *reselect_nth_unstable_by_lt(&mut w, index, &mut m, gt).1,
+            expected[index]
+        );
+
+        // The convenience functions built on the selection.
+        let p = rng.f64();
+        let mut v = data.clone();
+        let mut w = data.clone();
+        assert_eq!(
+            percentile_by(&mut v, p, desc),
+            percentile_by_lt(&mut w, p, gt)
+        );
+        let (low, high) = (rng.f64() / 2.0, rng.f64() / 2.0);
+        let mut v = data.clone();
+        let mut w = data.clone();
+        let mut a = trim_percentiles_by(&mut v, low, high, desc).to_vec();
+        let mut b = trim_percentiles_by_lt(&mut w, low, high, gt).to_vec();
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        let mut v = data.clone();
+        let mut w = data.clone();
+        winsorize_by(&mut v, low, high, desc);
+        winsorize_by_lt(&mut w, low, high, gt);
+        v.sort();
+        w.sort();
+        assert_eq!(v, w);
+        let k = rng.bounded_usize(0, len + 1);
+        let mut v = data.clone();
+        let mut w = data.clone();
+        retain_smallest_k_by(&mut v, k, true, desc);
+        retain_smallest_k_by_lt(&mut w, k, true, gt);
+        assert_eq!(v, sorted[..k]);
+        assert_eq!(w, sorted[..k]);
+        let mut v = data.clone();
+        let mut w = data.clone();
+        truncate_to_top_k_by(&mut v, k, true, desc);
+        truncate_to_top_k_by_lt(&mut w, k, true, gt);
+        let ascending: Vec<_> = sorted.iter().rev().copied().collect();
+        assert_eq!(v, ascending[..k]);
+        assert_eq!(w, ascending[..k]);
+
+        let n_cols = rng.bounded_usize(1, 8);
+        let mut m = data[..len - len % n_cols].to_vec();
+        let columns = median_each_column_by(&m, n_cols, desc);
+        assert_eq!(columns, median_each_column_by_lt(&m, n_cols, gt));
+        let rows: Vec<_> = median_each_row_by(&mut m.clone(), n_cols, desc)
+            .map(|x| *x)
+            .collect();
+        let rows_lt: Vec<_> = median_each_row_by_lt(&mut m, n_cols, gt)
+            .map(|x| *x)
+            .collect();
+        assert_eq!(rows, rows_lt);
+    }
+
+    let mut v = [9, 3, 7, 1, 5, 8, 2, 6];
+    assert_eq!(
+        *select_nth_unstable_array_by_lt(&mut v, 1, |a, b| a > b).1,
+        8
+    );
+    let mut v = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+    tinysort_stable_by(&mut v, |a, b| b.0.cmp(&a.0));
+    assert_eq!(v, [(2, 'a'), (2, 'c'), (1, 'b'), (1, 'd')]);
+}
+
+#[test]
+fn budgets() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let index = rng.bounded_usize(0, len);
+        let budget = rng.bounded_usize(0, 4 * len);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let result = select_nth_unstable_with_budget(&mut data, index, budget);
+        let ranks = result.ranks();
+        assert!(ranks.contains(&index));
+        assert!(result.comparisons <= budget);
+        let (lowest, highest) = (sorted[ranks.start], sorted[ranks.end - 1]);
+        assert!(result.below.iter().all(|&x| x <= lowest));
+        assert!(result
+            .candidates
+            .iter()
+            .all(|&x| lowest <= x && x <= highest));
+        assert!(result.above.iter().all(|&x| x >= highest));
+        if result.is_exact() {
+            assert_eq!(result.candidates[0], sorted[index]);
+        }
+    }
+
+    // A generous budget always gives the exact result.
+    let mut data: Vec<_> = iter_rng(&mut rng, 10_000, usize::MAX).collect();
+    let result = select_nth_unstable_with_budget(&mut data, 1234, usize::MAX);
+    assert!(result.is_exact());
+}
+
+#[test]
+fn swap_callbacks() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let index = rng.bounded_usize(0, len);
+        let original = data.clone();
+        let mut sorted = data.clone();
+        sorted.sort();
+        // Replaying the swaps on the original positions must give the same order as the slice.
+        let mut positions: Vec<usize> = (0..len).collect();
+        let (left, nth, right) = select_nth_unstable_with_swaps(&mut data, index, |i, j| {
+            assert_ne!(i, j);
+            positions.swap(i, j);
+        });
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+        assert!(positions.iter().zip(&data).all(|(&p, x)| original[p] == *x));
+    }
+
+    // Already sorted or reversed slices, and a descending order.
+    let mut data: Vec<usize> = (0..10_000).rev().collect();
+    let mut count = 0;
+    select_nth_unstable_with_swaps(&mut data, 5000, |_, _| count += 1);
+    assert_eq!(data[5000], 5000);
+    assert!(count > 0);
+    let mut count = 0;
+    select_nth_unstable_with_swaps_by(&mut data, 100, |_, _| count += 1, |a, b| b.cmp(a));
+    assert_eq!(data[100], 9899);
+}
+
+#[test]
+fn two_ranks() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let (i, j) = match rng.bounded_usize(0, 4) {
+            0 => (len / 100, len - 1 - len / 100),
+            1 => (0, len - 1),
+            _ => (rng.bounded_usize(0, len), rng.bounded_usize(0, len)),
+        };
+        let mut sorted = data.clone();
+        sorted.sort();
+        let (x, y) = select_two_ranks(&mut data, i, j);
+        assert_eq!((*x, *y), (sorted[i], sorted[j]));
+        let (lo, hi) = (i.min(j), i.max(j));
+        assert!(data[..lo].iter().all(|x| *x <= data[lo]));
+        assert!(data[lo..=hi]
+            .iter()
+            .all(|x| data[lo] <= *x && *x <= data[hi]));
+        assert!(data[hi..].iter().all(|x| *x >= data[hi]));
+    }
+
+    let mut data: Vec<usize> = (0..10_000).collect();
+    let (x, y) = select_two_ranks_by(&mut data, 9000, 10, |a, b| b.cmp(a));
+    assert_eq!((*x, *y), (999, 9989));
+    let (x, y) = select_two_ranks_by_lt(&mut data, 5000, 5001, |a, b| a < b);
+    assert_eq!((*x, *y), (5000, 5001));
+}
+
+#[test]
+fn many_ranks() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let count = rng.bounded_usize(0, 20);
+        let mut indices: Vec<_> = iter_rng(&mut rng, count, len).collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+        select_many_nth_unstable(&mut data, &mut indices);
+        assert!(indices.is_sorted());
+        for &i in &indices {
+            assert_eq!(data[i], sorted[i]);
+            assert!(data[..i].iter().all(|x| *x <= data[i]));
+            assert!(data[i..].iter().all(|x| *x >= data[i]));
+        }
+        data.sort();
+        assert_eq!(data, sorted);
+    }
+
+    let mut data: Vec<usize> = (0..10_000).collect();
+    select_many_nth_unstable_by(&mut data, &mut [9000, 10, 10, 5000], |a, b| b.cmp(a));
+    assert_eq!([data[10], data[5000], data[9000]], [9989, 4999, 999]);
+    select_many_nth_unstable_by_lt(&mut data, &mut [0, 9999, 1], |a, b| a < b);
+    assert_eq!([data[0], data[1], data[9999]], [0, 1, 9999]);
+    select_many_nth_unstable(&mut [] as &mut [u32], &mut []);
+}
+
+#[test]
+fn extension_methods() {
+    use crate::select_nth_unstable_array_by_lt;
+
+    let mut rng = WyRng::new(123);
+    let mut data: Vec<_> = iter_rng(&mut rng, 1000, 100).collect();
+    let mut sorted = data.clone();
+    sorted.sort();
+
+    let (_, nth, _) = data.turbo_select_nth_unstable(500);
+    assert_eq!(*nth, sorted[500]);
+    let (_, nth, _) = data.turbo_select_nth_unstable_by(10, |a, b| b.cmp(a));
+    assert_eq!(*nth, sorted[989]);
+    let (_, nth, _) = data.turbo_select_nth_unstable_by_key(0, |&x| usize::MAX - x);
+    assert_eq!(*nth, sorted[999]);
+    let (_, nth, _) = data[..].turbo_select_nth_unstable_by_lt(999, |a, b| a < b);
+    assert_eq!(*nth, sorted[999]);
+
+    let mut array = [3, 1, 2];
+    assert_eq!(*array.turbo_select_nth_unstable(0).1, 1);
+
+    // Arrays are sorted with the network for their length, like `select_nth_unstable_array`.
+    let array: [u64; 12] = core::array::from_fn(|_| rng.u64());
+    let (mut a, mut b) = (array, array);
+    let (mut by_method, mut by_function) = (0, 0);
+    a.turbo_select_nth_unstable_by_lt(5, |x, y| {
+        by_method += 1;
+        x < y
+    });
+    select_nth_unstable_array_by_lt(&mut b, 5, |x, y| {
+        by_function += 1;
+        x < y
+    });
+    assert_eq!(a, b);
+    assert_eq!(by_method, by_function);
+    let mut sorted = array;
+    sorted.sort();
+    assert_eq!(a, sorted);
+    assert_eq!(
+        *array.clone().turbo_select_nth_unstable_by_key(3, |&x| !x).1,
+        sorted[8]
+    );
+    assert_eq!(
+        *array
+            .clone()
+            .turbo_select_nth_unstable_by(3, |x, y| y.cmp(x))
+            .1,
+        sorted[8]
+    );
+}
+
+#[test]
+fn folds() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let index = match rng.bounded_usize(0, 4) {
+            0 => 0,
+            1 => len - 1,
+            _ => rng.bounded_usize(0, len),
+        };
+        let mut sorted = data.clone();
+        sorted.sort();
+        let count = |(n, sum): (usize, u128), x: &usize| (n + 1, sum + *x as u128);
+        let result = select_nth_unstable_with_fold(&mut data, index, (0, 0), count);
+        assert_eq!(*result.nth, sorted[index]);
+        let expected_below = sorted[..index].iter().fold((0, 0), count);
+        let expected_above = sorted[index + 1..].iter().fold((0, 0), count);
+        assert_eq!(result.fold_below, expected_below);
+        assert_eq!(result.fold_above, expected_above);
+        assert!(result.below.iter().all(|x| x <= result.nth));
+        assert!(result.above.iter().all(|x| x >= result.nth));
+    }
+
+    // Every element is folded once, including zero-sized ones.
+    let mut data = [(); 100];
+    let result = select_nth_unstable_with_fold(&mut data, 30, 0, |n, _| n + 1);
+    assert_eq!((result.fold_below, result.fold_above), (30, 69));
+    let mut data: Vec<usize> = (0..10_000).collect();
+    let result = select_nth_unstable_with_fold_by(&mut data, 10, 0, |n, _| n + 1, |a, b| b.cmp(a));
+    assert_eq!(*result.nth, 9989);
+    assert_eq!((result.fold_below, result.fold_above), (10, 9989));
+}
+
+#[test]
+fn scratch_selection() {
+    use core::mem::MaybeUninit;
+    use std::{boxed::Box, string::ToString};
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    let mut scratch = Vec::with_capacity(max_len);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).zip(0..).collect();
+        let index = rng.bounded_usize(0, len);
+        let mut sorted = data.clone();
+        sorted.sort_by_key(|x| x.0);
+        let scratch = scratch.spare_capacity_mut();
+        let (_, nth, _) =
+            select_nth_with_scratch_by(&mut data, index, scratch, |a, b| a.0.cmp(&b.0));
+        // The element at `index` is the one that a stable sort puts there.
+        assert_eq!(*nth, sorted[index]);
+        assert!(data[..index].iter().all(|x| x.0 <= sorted[index].0));
+        assert!(data[index..].iter().all(|x| x.0 >= sorted[index].0));
+        // Equal elements stay in their original order.
+        let mut equal: Vec<_> = data.clone();
+        equal.sort_by_key(|x| x.0);
+        assert_eq!(equal, sorted);
+    }
+
+    // Elements with destructors, and a comparison function that panics midway.
+    let mut scratch = [const { MaybeUninit::uninit() }; 500];
+    let mut data: Vec<_> = (0..500).map(|x| ((x * 7919) % 500).to_string()).collect();
+    let mut sorted = data.clone();
+    sorted.sort();
+    let (_, nth, _) = select_nth_with_scratch(&mut data, 250, &mut scratch);
+    assert_eq!(*nth, sorted[250]);
+    let mut data: Vec<_> = (0..500).map(|x| Box::new((x * 7919) % 500)).collect();
+    let mut scratch = [const { MaybeUninit::uninit() }; 500];
+    let calls = Cell::new(0);
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        select_nth_with_scratch_by(&mut data, 100, &mut scratch, |a, b| {
+            calls.set(calls.get() + 1);
+            assert!(calls.get() < 300);
+            a.cmp(b)
+        });
+    }));
+    assert!(result.is_err());
+    data.sort();
+    assert!(data.iter().map(|x| **x).eq(0..500));
+
+    let result = std::panic::catch_unwind(|| {
+        let mut data = [1, 2, 3];
+        select_nth_with_scratch(&mut data, 0, &mut [MaybeUninit::uninit(); 2]);
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn equi_depth_bins() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (500, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let max = random_high(&mut rng, len);
+        let data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let n_bins = rng.bounded_usize(1, 257);
+        let labels = quantile_bins(&data, n_bins);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let splitters: Vec<_> = (1..n_bins).map(|k| sorted[k * len / n_bins]).collect();
+        for (x, &label) in data.iter().zip(&labels) {
+            assert_eq!(label as usize, splitters.iter().filter(|&s| s <= x).count());
+        }
+    }
+
+    // Distinct elements fill every bin evenly.
+    let data: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+    let labels = quantile_bins_by(&data, 10, |a, b| b.cmp(a));
+    for (x, &label) in data.iter().zip(&labels) {
+        assert_eq!(label as u32, (999 - x) / 100);
+    }
+}
+
+#[test]
+fn histograms() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (500, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let n_buckets = rng.bounded_usize(1, 257);
+        // The buckets of an equi-depth histogram are the bins of `quantile_bins`.
+        let mut histogram = Histogram::equi_depth(&data, n_buckets);
+        let mut counts = std::vec![0; n_buckets];
+        quantile_bins(&data, n_buckets)
+            .iter()
+            .for_each(|&b| counts[b as usize] += 1);
+        assert_eq!(histogram.counts(), counts);
+
+        // Adding batches counts each element in the bucket of its value.
+        let batch: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        histogram.add(&batch);
+        for x in &batch {
+            let bucket = histogram.edges().iter().filter(|&e| e <= x).count();
+            assert_eq!(histogram.bucket_of(x), bucket);
+            counts[bucket] += 1;
+        }
+        assert_eq!(histogram.counts(), counts);
+        assert_eq!(histogram.total(), 2 * len);
+    }
+
+    // Equally spaced edges.
+    let mut histogram = Histogram::with_edges(std::vec![10, 20, 30]);
+    histogram.add(&[0, 9, 10, 15, 25, 30, 45, 100]);
+    assert_eq!(histogram.counts(), [2, 2, 1, 3]);
+}
+
+#[cfg(feature = "selftest")]
+#[test]
+fn self_test() {
+    #[cfg(not(miri))]
+    let rounds = 50;
+    #[cfg(miri)]
+    let rounds = 1;
+
+    let report = crate::selftest(123, rounds);
+    assert!(report.is_ok(), "{:?} failed", report.first_failure);
+    assert!(report.passed >= rounds * 2 * 20);
+}
+
+#[test]
+fn grouped_ties() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = random_high(&mut rng, len);
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).map(|x| (x, 0u8)).collect();
+        let index = rng.bounded_usize(0, len);
+        let mut sorted: Vec<_> = data.iter().map(|x| x.0).collect();
+        sorted.sort();
+        let key = sorted[index];
+        let count = sorted.iter().filter(|&&x| x == key).count();
+        let (below, tied, above) = select_and_group(&mut data, index, |x| x.0);
+        assert_eq!(tied.len(), count);
+        assert!((below.len()..below.len() + tied.len()).contains(&index));
+        assert!(below.iter().all(|x| x.0 < key));
+        assert!(tied.iter().all(|x| x.0 == key));
+        assert!(above.iter().all(|x| x.0 > key));
+    }
+}
+
+#[test]
+fn ranges() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (1000, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let max = [2, len / 10 + 1, 1000][rng.bounded_usize(0, 3)];
+        let mut data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let (low, high) = (rng.bounded_usize(0, max + 1), rng.bounded_usize(0, max + 1));
+        let mut expected = data.clone();
+        expected.sort();
+        let (below, within, above) = partition_by_range(&mut data, &low, &high);
+        assert!(below.iter().all(|&x| x < low));
+        assert!(within.iter().all(|&x| low <= x && x <= high));
+        assert!(above.iter().all(|&x| x > high && x >= low));
+        data.sort();
+        assert_eq!(data, expected);
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_cached_keys() {
+    use crate::par_select_nth_unstable_by_cached_key;
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, 5000);
+        let keys: Vec<_> = iter_rng(&mut rng, len, len / 4 + 1).collect();
+        let data: Vec<_> = keys.into_iter().zip(0..).collect();
+        let index = rng.bounded_usize(0, len);
+        let mut serial = data.clone();
+        select_nth_unstable_by_cached_key_with_ties(&mut serial, index, |x| x.0, Ties::ByIndex);
+        let mut parallel = data.clone();
+        par_select_nth_unstable_by_cached_key(&mut parallel, index, |x| x.0);
+        assert_eq!(parallel, serial);
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_selection() {
+    use crate::{
+        par_partition_at, par_partition_at_by, par_partition_at_by_lt, par_select_nth_unstable,
+        par_select_nth_unstable_by, par_select_nth_unstable_by_lt, parallel::par_partition,
+    };
+
+    // A single thread selects serially and partitions the slice as a single chunk.
+    for threads in [1, 4] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(check);
+    }
+
+    fn check() {
+        #[cfg(not(miri))]
+        let (repeat, max_len) = (20, 300_000);
+        #[cfg(miri)]
+        let (repeat, max_len) = (2, 100);
+
+        let mut rng = WyRng::new(123);
+        for _ in 0..repeat {
+            let len = rng.bounded_usize(1, max_len);
+            let max = random_high(&mut rng, len);
+            let data: Vec<_> = iter_rng(&mut rng, len, max).collect();
+            let mut sorted = data.clone();
+            sorted.sort_unstable();
+
+            let index = rng.bounded_usize(0, len);
+            let mut selected = data.clone();
+            let (below, nth, above) = par_select_nth_unstable(&mut selected, index);
+            assert_eq!(*nth, sorted[index]);
+            assert!(below.iter().all(|x| x <= nth) && above.iter().all(|x| x >= nth));
+            selected.sort_unstable();
+            assert_eq!(selected, sorted);
+
+            let split = sorted[index];
+            let mut partitioned = data.clone();
+            let left = par_partition(
+                &mut partitioned,
+                &|&x: &usize| x < split,
+                PARAMS.partition_block,
+            );
+            assert_eq!(left, sorted.partition_point(|&x| x < split));
+            assert!(partitioned[..left].iter().all(|&x| x < split));
+            assert!(partitioned[left..].iter().all(|&x| x >= split));
+
+            let mut partitioned = data.clone();
+            let (below, pivot, above) = par_partition_at(&mut partitioned, index);
+            let pivot = *pivot;
+            assert!(below.iter().all(|&x| x < pivot));
+            assert!(above.iter().all(|&x| x >= pivot));
+            partitioned.sort_unstable();
+            assert_eq!(partitioned, sorted);
+        }
+
+        let mut data: Vec<usize> = (0..200_000).rev().collect();
+        assert_eq!(
+            *par_select_nth_unstable_by(&mut data, 10, |a, b| b.cmp(a)).1,
+            199_989
+        );
+        assert_eq!(
+            *par_select_nth_unstable_by_lt(&mut data, 199_999, |a, b| a < b).1,
+            199_999
+        );
+        let mut data = std::vec![7u8; 100_000];
+        assert_eq!(*par_select_nth_unstable(&mut data, 50_000).1, 7);
+
+        let mut data: Vec<usize> = (0..100_000).collect();
+        let (below, pivot, _) = par_partition_at_by(&mut data, 60_000, |a, b| b.cmp(a));
+        assert_eq!((below.len(), *pivot), (39_999, 60_000));
+        let (below, pivot, _) = par_partition_at_by_lt(&mut data, 5, |a, b| a < b);
+        assert_eq!(below.len(), *pivot);
+    }
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn simd_selection() {
+    use crate::select_nth_unstable_simd;
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (100, 10_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 100);
+
+    macro_rules! check {
+        ($rng:expr, $t:ty) => {{
+            let len = $rng.bounded_usize(1, max_len);
+            let max = random_high(&mut $rng, len);
+            // Shifting the values down makes some of them wrap around, e.g. to negative numbers.
+            let data: Vec<$t> = iter_rng(&mut $rng, len, max)
+                .map(|x| x.wrapping_sub(max / 2) as $t)
+                .collect();
+            let mut sorted = data.clone();
+            sorted.sort_unstable();
+            // The order of these is checked by separate kernels too.
+            let reversed: Vec<$t> = sorted.iter().rev().copied().collect();
+            let mut perturbed = sorted.clone();
+            perturbed.swap($rng.bounded_usize(0, len), $rng.bounded_usize(0, len));
+
+            for data in [&data, &sorted, &reversed, &perturbed] {
+                // The extremes are found by separate kernels.
+                for index in [$rng.bounded_usize(0, len), 0, len - 1] {
+                    let mut selected = data.clone();
+                    let (below, nth, above) = select_nth_unstable_simd(&mut selected, index);
+                    assert_eq!(*nth, sorted[index]);
+                    assert!(below.iter().all(|x| x <= nth) && above.iter().all(|x| x >= nth));
+                    selected.sort_unstable();
+                    assert_eq!(selected, sorted);
+                }
+            }
+        }};
+    }
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        check!(rng, u32);
+        check!(rng, u64);
+        check!(rng, i32);
+        check!(rng, i64);
+    }
+}
+
+#[test]
+fn counting_selection() {
+    use crate::select_nth_unstable_u8;
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (100, 10_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 500);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let max = [2, 100, 256][rng.bounded_usize(0, 3)];
+        let data: Vec<u8> = iter_rng(&mut rng, len, max).map(|x| x as u8).collect();
+        let mut sorted = data.clone();
+        sorted.sort_unstable();
+
+        for index in [rng.bounded_usize(0, len), 0, len - 1] {
+            let mut selected = data.clone();
+            let (below, nth, above) = select_nth_unstable_u8(&mut selected, index);
+            assert_eq!(*nth, sorted[index]);
+            assert!(below.iter().all(|x| x <= nth) && above.iter().all(|x| x >= nth));
+            selected.sort_unstable();
+            assert_eq!(selected, sorted);
+        }
+    }
+}
+
+#[test]
+fn float_selection() {
+    use crate::{select_nth_unstable_float, NanError, NanPolicy};
+    use core::cmp::Ordering;
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (100, 10_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 100);
+
+    macro_rules! check {
+        ($rng:expr, $t:ty) => {{
+            let len = $rng.bounded_usize(1, max_len);
+            let nan_rate = [0, 1, 100][$rng.bounded_usize(0, 3)];
+            let data: Vec<$t> = (0..len)
+                .map(|_| match $rng.bounded_usize(0, 100) {
+                    x if x < nan_rate => [<$t>::NAN, -<$t>::NAN][x % 2],
+                    x @ 0..=9 => [0.0, -0.0, <$t>::INFINITY, <$t>::NEG_INFINITY][x % 4],
+                    _ => ($rng.f64() - 0.5) as $t * 1e3,
+                })
+                .collect();
+
+            for nans in [NanPolicy::First, NanPolicy::Last] {
+                // The NaNs are equal to each other, and the other values are in the total order.
+                let class = |x: &$t| match (x.is_nan(), nans) {
+                    (false, _) => 1,
+                    (true, NanPolicy::First) => 0,
+                    (true, _) => 2,
+                };
+                let compare = |a: &$t, b: &$t| match class(a).cmp(&class(b)) {
+                    Ordering::Equal if class(a) == 1 => a.total_cmp(b),
+                    ordering => ordering,
+                };
+                let mut sorted = data.clone();
+                sorted.sort_by(compare);
+
+                let index = $rng.bounded_usize(0, len);
+                let mut selected = data.clone();
+                let (below, nth, above) =
+                    select_nth_unstable_float(&mut selected, index, nans).unwrap();
+                assert_eq!(compare(nth, &sorted[index]), Ordering::Equal);
+                assert!(below.iter().all(|x| compare(x, nth).is_le()));
+                assert!(above.iter().all(|x| compare(x, nth).is_ge()));
+            }
+
+            let mut selected = data.clone();
+            let index = $rng.bounded_usize(0, len);
+            match data.iter().position(|x| x.is_nan()) {
+                Some(first) => {
+                    let result = select_nth_unstable_float(&mut selected, index, NanPolicy::Error);
+                    assert_eq!(result.unwrap_err(), NanError { index: first });
+                }
+                None => {
+                    assert!(
+                        select_nth_unstable_float(&mut selected, index, NanPolicy::Error).is_ok()
+                    );
+                }
+            }
+        }};
+    }
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        check!(rng, f32);
+        check!(rng, f64);
+    }
+}
+
+#[test]
+fn heap_selection() {
+    use crate::heapselect::{heapselect, is_heap_index};
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (200, 10_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (8, 1000);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(512, max_len);
+        let mut data: Vec<_> = match rng.bounded_usize(0, 4) {
+            0 => iter_rng(&mut rng, len, len).collect(),
+            1 => iter_rng(&mut rng, len, 4).collect(),
+            2 => (0..len).collect(),
+            _ => (0..len).rev().collect(),
+        };
+        let mut sorted = data.clone();
+        sorted.sort_unstable();
+        let offset = rng.bounded_usize(1, 8);
+        for index in [offset, len - 1 - offset] {
+            assert!(is_heap_index(len, index));
+            let mut selected = data.clone();
+            if heapselect(&mut selected, index, &mut usize::lt) {
+                assert_eq!(selected[index], sorted[index]);
+                assert!(is_partitioned(&selected, index, &mut usize::lt));
+            }
+            selected.sort_unstable();
+            assert_eq!(selected, sorted);
+        }
+
+        // Through the selection, which falls back to partitioning if the heap gives up.
+        let index = [offset, len - 1 - offset][rng.bounded_usize(0, 2)];
+        data.swap(0, len / 2);
+        let report = select_nth_unstable_with_report(&mut data, index, &SelectOptions::default());
+        assert_eq!(data[index], sorted[index]);
+        assert!(is_partitioned(&data, index, &mut usize::lt));
+        assert!(
+            report
+                .rounds
+                .iter()
+                .filter(|round| round.kind == RoundKind::Heap)
+                .count()
+                <= 1
+        );
+    }
+    assert!(!is_heap_index(10_000, 0));
+    assert!(!is_heap_index(10_000, 100));
+    assert!(!is_heap_index(100, 4));
+}
+
+#[test]
+fn bounded_top_k() {
+    use crate::heapselect::minmax;
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (200, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (8, 200);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let k = rng.bounded_usize(0, 50);
+        let max = [4, len + 1][rng.bounded_usize(0, 2)];
+        let values: Vec<_> = iter_rng(&mut rng, len, max).collect();
+        let mut top = BoundedTopK::new(k);
+        let mut pushed = Vec::new();
+        for &value in &values {
+            let rejected = top.push(value);
+            pushed.push(value);
+            pushed.sort_unstable_by(|a, b| b.cmp(a));
+            assert_eq!(rejected, pushed.get(k).copied());
+            pushed.truncate(k);
+            assert_eq!(top.len(), pushed.len());
+            assert_eq!(top.min(), pushed.last());
+            assert_eq!(top.max(), pushed.first());
+        }
+
+        // Pushing and replacing the elements keeps a valid min-max heap.
+        let heap = top.clone().into_vec();
+        for (i, x) in heap.iter().enumerate() {
+            // Each ancestor on a level of minima is at most `x`, and on a level of maxima at
+            // least `x`.
+            let mut j = i;
+            while j > 0 {
+                j = (j - 1) / 2;
+                match (j + 1).ilog2() & 1 == 0 {
+                    true => assert!(heap[j] <= *x),
+                    false => assert!(heap[j] >= *x),
+                }
+            }
+        }
+        if !heap.is_empty() {
+            assert_eq!(
+                heap[minmax::position_of_max(&heap, &mut usize::lt)],
+                *heap.iter().max().unwrap()
+            );
+        }
+
+        let mut sorted = top.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        assert_eq!(top.into_sorted_vec(), sorted);
+    }
+}
+
+#[test]
+fn streaming_k_smallest() {
+    use crate::{k_smallest_iter, k_smallest_iter_by, k_smallest_iter_by_lt};
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (200, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (8, 200);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let k = rng.bounded_usize(0, 100);
+        let data: Vec<_> = match rng.bounded_usize(0, 3) {
+            0 => iter_rng(&mut rng, len, len + 1).collect(),
+            1 => iter_rng(&mut rng, len, 4).collect(),
+            _ => (0..len).rev().collect(),
+        };
+        let mut sorted = data.clone();
+        sorted.sort_unstable();
+        sorted.truncate(k);
+        assert_eq!(k_smallest_iter(data.iter().copied(), k), sorted);
+        assert_eq!(k_smallest_iter_by(data.clone(), k, usize::cmp), sorted);
+        let largest = k_smallest_iter_by_lt(data.iter(), k, |a, b| a > b);
+        let mut expected = data.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert!(largest.into_iter().eq(expected.iter().take(k)));
+    }
+
+    // An iterator that isn't fused and resumes after returning `None`.
+    let mut calls = 0;
+    let resuming = core::iter::from_fn(|| {
+        calls += 1;
+        (calls % 3 != 1).then_some(calls)
+    });
+    assert!(k_smallest_iter(resuming.take(10), 2).is_empty());
+}
+
+#[test]
+fn iterator_selection() {
+    use crate::{select_nth_from_iter, select_nth_from_iter_by_lt, IterSelector};
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (100, 5000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (8, 200);
+
+    let mut rng = WyRng::new(123);
+    let mut selector = IterSelector::new();
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(0, max_len);
+        let data: Vec<_> = iter_rng(&mut rng, len, len / 4 + 1).collect();
+        let mut sorted = data.clone();
+        sorted.sort_unstable();
+        let k = rng.bounded_usize(0, len + 2);
+
+        assert_eq!(
+            select_nth_from_iter(data.iter().copied(), k),
+            sorted.get(k).copied()
+        );
+        let largest = select_nth_from_iter_by_lt(data.iter(), k, |a, b| a > b);
+        assert_eq!(largest, sorted.iter().rev().nth(k));
+        assert_eq!(selector.select_nth(data.clone(), k), sorted.get(k).copied());
+
+        match selector.partition_nth_by(data.clone(), k, usize::cmp) {
+            Some((below, nth, above)) => {
+                assert_eq!(*nth, sorted[k]);
+                assert!(below.iter().all(|x| x <= nth) && above.iter().all(|x| x >= nth));
+            }
+            None => assert!(k >= len),
+        }
+        let mut partitioned = selector.take();
+        partitioned.sort_unstable();
+        assert_eq!(partitioned, sorted);
+    }
+}
+
+#[test]
+fn online_quantile() {
+    use crate::{math::ln, percentile_by, streaming::OnlineQuantile};
+
+    #[cfg(not(miri))]
+    let (repeat, len, tolerance) = (40, 20_000, 0.005);
+    #[cfg(miri)]
+    let (repeat, len, tolerance) = (4, 500, 0.05);
+
+    let mut rng = WyRng::new(123);
+    for iter in 0..repeat {
+        let p = [0.0, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 1.0][iter % 9];
+        // Uniform and exponentially distributed values.
+        let data: Vec<f64> = match iter % 2 {
+            0 => (0..len).map(|_| rng.f64()).collect(),
+            _ => (0..len).map(|_| -ln(1.0 - rng.f64())).collect(),
+        };
+        let mut sorted = data.clone();
+        sorted.sort_unstable_by(f64::total_cmp);
+
+        let mut estimator = OnlineQuantile::new(p);
+        assert_eq!(estimator.estimate(), None);
+        for (i, &x) in data.iter().enumerate() {
+            estimator.insert(x);
+            if i < 5 {
+                let mut first = data[..=i].to_vec();
+                assert_eq!(
+                    estimator.estimate(),
+                    Some(*percentile_by(&mut first, p, f64::total_cmp))
+                );
+            }
+        }
+        estimator.insert(f64::NAN);
+        assert_eq!(estimator.len(), len as u64);
+        assert_eq!(estimator.min(), Some(sorted[0]));
+        assert_eq!(estimator.max(), Some(sorted[len - 1]));
+
+        // The rank of the estimate is close to the rank of the quantile.
+        let estimate = estimator.estimate().unwrap();
+        let rank = sorted.partition_point(|&x| x < estimate) as f64 / len as f64;
+        assert!((rank - p).abs() < tolerance, "p = {p}, rank = {rank}");
+    }
+}
+
+#[test]
+fn t_digest() {
+    use core::f64::consts::PI;
+
+    use crate::{math::ln, streaming::TDigest};
+
+    #[cfg(not(miri))]
+    let (repeat, len) = (12, 50_000);
+    #[cfg(miri)]
+    let (repeat, len) = (3, 500);
+
+    let mut rng = WyRng::new(123);
+    for iter in 0..repeat {
+        // Uniform, exponentially and Pareto distributed values.
+        let len = rng.bounded_usize(1, len);
+        let data: Vec<f64> = match iter % 3 {
+            0 => (0..len).map(|_| rng.f64()).collect(),
+            1 => (0..len).map(|_| -ln(1.0 - rng.f64())).collect(),
+            _ => (0..len).map(|_| 1.0 / (1.0 - rng.f64()).powi(2)).collect(),
+        };
+        let mut sorted = data.clone();
+        sorted.sort_unstable_by(f64::total_cmp);
+
+        let compression = [50.0, 100.0, 500.0][iter % 3];
+        let mut digest = TDigest::new(compression);
+        digest.extend(data.iter().copied());
+        digest.insert(f64::NAN);
+        // The same values split between shards, whose digests are merged.
+        let mut shards: Vec<_> = (0..4).map(|_| TDigest::new(compression)).collect();
+        for (i, &x) in data.iter().enumerate() {
+            shards[i % 4].insert(x);
+        }
+        let mut merged = TDigest::new(compression);
+        shards.iter().for_each(|shard| merged.merge(shard));
+
+        for digest in [&mut digest, &mut merged] {
+            assert_eq!(digest.len(), len as u64);
+            assert_eq!(digest.min(), Some(sorted[0]));
+            assert_eq!(digest.max(), Some(sorted[len - 1]));
+            assert_eq!(digest.quantile(0.0), Some(sorted[0]));
+            assert_eq!(digest.quantile(1.0), Some(sorted[len - 1]));
+            for p in [0.001, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 0.999] {
+                // The estimate is between the values whose ranks are close to `p`, about as close
+                // as the centroids around `p` are small. The extreme centroids can hold about
+                // `(PI / compression)^2` of the values.
+                let estimate = digest.quantile(p).unwrap();
+                let error = 3.0 / compression * (p * (1.0 - p)).sqrt() + (PI / compression).powi(2);
+                let error = error * len as f64 + 2.0;
+                let low = (p * len as f64 - error).max(0.0) as usize;
+                let high = ((p * len as f64 + error) as usize).min(len - 1);
+                assert!(
+                    (sorted[low]..=sorted[high]).contains(&estimate),
+                    "p = {p}, estimate = {estimate}"
+                );
+            }
+        }
+    }
+
+    // Equal values are estimated exactly.
+    let mut digest = TDigest::default();
+    digest.extend([2.5; 1000]);
+    assert_eq!(digest.quantile(0.3), Some(2.5));
+}
+
+#[test]
+fn gk_summary() {
+    use crate::streaming::GkSummary;
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (30, 20_000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (4, 300);
+
+    let mut rng = WyRng::new(123);
+    for iter in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let data: Vec<_> = match iter % 3 {
+            0 => iter_rng(&mut rng, len, usize::MAX).collect(),
+            1 => iter_rng(&mut rng, len, len / 50 + 1).collect(),
+            _ => (0..len).rev().collect(),
+        };
+        let mut sorted = data.clone();
+        sorted.sort_unstable();
+
+        let epsilon = [0.001, 0.01, 0.05][iter % 3];
+        let mut summary = GkSummary::new(epsilon);
+        summary.extend(data.iter().copied());
+        // The same values split unevenly between shards, whose summaries are merged in turn.
+        let mut merged = GkSummary::new(epsilon);
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            let (shard, tail) = rest.split_at(rng.bounded_usize(1, rest.len() + 1));
+            let mut local = GkSummary::new(epsilon);
+            local.extend(shard.iter().copied());
+            merged.merge(&local);
+            rest = tail;
+        }
+
+        for summary in [&summary, &merged] {
+            assert_eq!(summary.len(), len as u64);
+            assert_eq!(summary.query(0.0), Some(&sorted[0]));
+            assert_eq!(summary.query(1.0), Some(&sorted[len - 1]));
+            let error = epsilon * len as f64;
+            for _ in 0..20 {
+                let p = rng.f64();
+                let rank = (p * (len - 1) as f64) as usize;
+                // Some position of the value is within the allowed error of the rank.
+                let value = summary.query(p).unwrap();
+                let first = sorted.partition_point(|x| x < value);
+                let last = sorted.partition_point(|x| x <= value) - 1;
+                assert!(first as f64 <= rank as f64 + error && rank as f64 <= last as f64 + error);
+
+                // The bounds are close to the rank and give its exact value.
+                let (low, high) = summary.bounds(p).unwrap();
+                assert!(low <= &sorted[rank] && &sorted[rank] <= high);
+                let below = sorted.partition_point(|x| x < low);
+                let last_low = sorted.partition_point(|x| x <= low) - 1;
+                let first_high = sorted.partition_point(|x| x < high);
+                assert!(rank as f64 <= last_low as f64 + 2.0 * error);
+                assert!(first_high as f64 <= rank as f64 + 2.0 * error);
+                let mut candidates: Vec<_> =
+                    data.iter().filter(|&x| (low..=high).contains(&x)).collect();
+                assert_eq!(
+                    *select_nth_unstable(&mut candidates, rank - below).1,
+                    &sorted[rank]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn network_sorts() {
+    fn check<const N: usize>(rng: &mut WyRng) {
+        let mut data: Vec<_> = iter_rng(rng, N + 2, 10).collect();
+        let mut sorted = data.clone();
+        sorted[..N].sort();
+        network_sort::<_, _, N>(&mut data, &mut usize::lt);
+        assert_eq!(data, sorted);
+    }
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        check::<4>(&mut rng);
+        check::<16>(&mut rng);
+        // Sizes without a sorting network fall back to an insertion sort.
+        check::<17>(&mut rng);
+        check::<33>(&mut rng);
+    }
+}
+
+#[test]
+fn partitioned() {
+    #[cfg(not(miri))]
+    let count = 100_000;
+    #[cfg(miri)]
+    let count = 1000;
+
+    let mut rng = WyRng::new(123);
+    let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+    let index = rng.bounded_usize(1, count - 1);
+    select(&mut data, index, &mut usize::lt);
+    assert!(is_partitioned(&data, index, &mut usize::lt));
+
+    // Selecting the same index again should only scan the slice.
+    let mut comparisons = 0;
+    let before = data.clone();
+    select(&mut data, index, &mut |a: &usize, b: &usize| {
+        comparisons += 1;
+        a < b
+    });
+    assert_eq!(data, before);
+    assert!(comparisons < 2 * count);
+}
+
+#[test]
+fn patterns() {
+    let mut rng = WyRng::new(123);
+
+    #[cfg(not(miri))]
+    let count = 10_000;
+    #[cfg(miri)]
+    let count = 1000;
+
+    #[cfg(not(miri))]
+    let repeat = 1000;
+
+    #[cfg(miri)]
+    let repeat = 10;
+
+    fn run(mut data: Vec<usize>, index: usize) {
+        let (left, nth, right) = select_nth_unstable(data.as_mut_slice(), index);
+        left.iter().enumerate().for_each(|(i, elem)| match i {
+            i if elem > nth => panic!("left[{i}] = {elem} > nth = {nth}"),
+            _ => (),
+        });
+        right.iter().enumerate().for_each(|(i, elem)| match i {
+            i if elem < nth => panic!("left[{i}] = {elem} < nth = {nth}"),
+            _ => (),
+        });
+    }
+
+    for iter in 0..repeat {
+        let index = (iter * count) / repeat;
+        run(ptn_sorted(count, rng.as_mut()), index);
+        run(ptn_mostlysorted(count, rng.as_mut()), index);
+        run(ptn_reversed(count, rng.as_mut()), index);
+        run(ptn_sawtooth(count, rng.as_mut()), index);
+        run(ptn_zeroone(count, rng.as_mut()), index);
+    }
+}
+
+#[test]
+fn records() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    const SIZE: usize = 12;
+    let key = |record: &[u8]| u32::from_le_bytes(record[4..8].try_into().unwrap());
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(1, 1000);
+        let mut data = Vec::with_capacity(count * SIZE);
+        for i in 0..count {
+            data.extend_from_slice(&(i as u32).to_le_bytes());
+            data.extend_from_slice(&rng.bounded_u32(0, count as u32 / 4 + 1).to_le_bytes());
+            data.extend_from_slice(&[0xab; 4]);
+        }
+        let mut sorted = data.clone();
+        let mut chunks: Vec<_> = sorted.chunks_exact(SIZE).map(|c| c.to_vec()).collect();
+        chunks.sort_by_key(|c| key(c));
+        sorted = chunks.concat();
+
+        let index = rng.bounded_usize(0, count);
+        let (left, record, right) = select_nth_record_unstable(&mut data, SIZE, index, key);
+        assert_eq!(left.len(), index * SIZE);
+        assert_eq!(record, &sorted[index * SIZE..(index + 1) * SIZE]);
+        let nth = key(record);
+        assert!(left.chunks_exact(SIZE).all(|c| key(c) <= nth));
+        assert!(right.chunks_exact(SIZE).all(|c| key(c) >= nth));
+
+        // The records are moved as a whole.
+        let mut ids: Vec<_> = data.chunks_exact(SIZE).map(|c| c[..4].to_vec()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+        assert!(data.chunks_exact(SIZE).all(|c| c[8..] == [0xab; 4]));
+    }
+}
+
+#[test]
+fn reselect() {
+    #[cfg(not(miri))]
+    let (count, repeat) = (10_000, 100);
+    #[cfg(miri)]
+    let (count, repeat) = (100, 10);
+
+    let mut rng = WyRng::new(123);
+    let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+    let index = rng.bounded_usize(0, count);
+    select_nth_unstable(&mut data, index);
+
+    for _iter in 0..repeat {
+        let m = rng.bounded_usize(0, 20);
+        let mut modified: Vec<_> = iter_rng(&mut rng, m, count).collect();
+        for &i in &modified {
+            data[i] = rng.bounded_usize(0, count);
+        }
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let (left, nth, right) = reselect_nth_unstable(&mut data, index, &mut modified);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|elem| elem <= nth));
+        assert!(right.iter().all(|elem| elem >= nth));
+    }
+}
+
+#[test]
+fn sample_n() {
     #[cfg(not(miri))]
     let repeat = 1000;
     #[cfg(miri)]
@@ -336,6 +3383,128 @@ fn sorts() {
     sort_indexed::<7>();
 }
 
+#[test]
+fn string_prefixes() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        // Use a small alphabet that includes zero bytes, and strings both shorter and longer than
+        // the cached prefix.
+        let count = rng.bounded_usize(1, 2000);
+        let mut data: Vec<std::string::String> = (0..count)
+            .map(|_| {
+                let chars = rng.vec(0, 16, |rng| ['\0', 'a', 'b'][rng.bounded_usize(0, 3)]);
+                chars.into_iter().collect()
+            })
+            .collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let index = rng.bounded_usize(0, count);
+        let (left, nth, right) = select_nth_unstable_by_prefix(&mut data, index);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|x| x <= nth));
+        assert!(right.iter().all(|x| x >= nth));
+    }
+}
+
+#[test]
+#[cfg(feature = "regression")]
+fn theil_sen_slopes() {
+    use crate::theil_sen;
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        // Use few distinct coordinates, so that there are repeated points, points with the same
+        // `x`, and equal slopes. Zeros are of both signs.
+        let count = rng.bounded_usize(2, 200);
+        let high = rng.bounded_usize(2, 50);
+        let mut coordinate = || {
+            let sign = if rng.bool() { 1. } else { -1. };
+            sign * rng.bounded_usize(0, high) as f64
+        };
+        let points: Vec<_> = (0..count).map(|_| (coordinate(), coordinate())).collect();
+        let mut slopes = Vec::new();
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                if a.0 != b.0 {
+                    slopes.push((b.1 - a.1) / (b.0 - a.0));
+                }
+            }
+        }
+        slopes.sort_by(f64::total_cmp);
+        let expected = match slopes.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(slopes[n / 2]),
+            n => Some((slopes[n / 2 - 1] + slopes[n / 2]) / 2.),
+        };
+        assert_eq!(theil_sen(&points), expected);
+    }
+}
+
+#[test]
+fn tinyselects() {
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (20, 64);
+    #[cfg(miri)]
+    let (repeat, max_len) = (1, 20);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        for len in 1..=max_len {
+            for index in 0..len {
+                let mut data: Vec<_> = iter_rng(&mut rng, len, len / 2 + 1).collect();
+                let mut sorted = data.clone();
+                sorted.sort();
+                tinyselect(&mut data, index, &mut usize::lt);
+                assert_eq!(data[index], sorted[index]);
+                assert!(data[..index].iter().all(|x| *x <= data[index]));
+                assert!(data[index..].iter().all(|x| *x >= data[index]));
+            }
+        }
+    }
+}
+
+#[test]
+fn tinysort_cutoffs() {
+    // Large elements are sorted in shorter slices.
+    assert_eq!(Params::DEFAULT.sized::<u32>(), Params::DEFAULT);
+    assert_eq!(Params::DEFAULT.sized::<[u8; 64]>().tinysort_cutoff, 12);
+    assert_eq!(Params::DEFAULT.sized::<[u8; 1024]>().tinysort_cutoff, 8);
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for cutoff in [None, Some(0), Some(8), Some(100), Some(usize::MAX)] {
+        let options = SelectOptions {
+            tinysort_cutoff: cutoff,
+            ..Default::default()
+        };
+        assert!(options.params::<[u64; 8]>().is_valid());
+        for _ in 0..repeat {
+            let count = rng.bounded_usize(1, 2000);
+            let mut data: Vec<_> = (0..count).map(|_| [rng.u64() % 100; 8]).collect();
+            let index = rng.bounded_usize(0, count);
+            let mut sorted = data.clone();
+            sorted.sort();
+            select_nth_unstable_with_options(&mut data, index, &options);
+            assert_eq!(data[index], sorted[index]);
+        }
+    }
+}
+
 #[test]
 fn tinysorts() {
     #[cfg(not(miri))]
@@ -356,6 +3525,480 @@ fn tinysorts() {
     }
 }
 
+#[test]
+fn stable_tinysorts() {
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 4;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        for len in 0..=64 {
+            // Tag the elements with their original positions to check the stability.
+            let mut data: Vec<_> = iter_rng(&mut rng, len, 8).enumerate().collect();
+            let mut sorted = data.clone();
+            sorted.sort_by_key(|&(_, key)| key);
+            tinysort_stable_by_lt(&mut data, |a, b| a.1 < b.1);
+            assert_eq!(data, sorted);
+        }
+    }
+}
+
+#[test]
+fn top_k() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(0, 500);
+        let data: Vec<_> = (0..count)
+            .map(|_| match rng.bounded_u32(0, 50) {
+                0 => f32::NAN,
+                x => x as f32,
+            })
+            .collect();
+        let k = rng.bounded_usize(0, 50);
+
+        let mut expected: Vec<_> = (0..count as u32)
+            .map(|i| (i, data[i as usize]))
+            .filter(|(_, x)| !x.is_nan())
+            .collect();
+        expected.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        expected.truncate(k);
+        assert_eq!(top_k_with_indices(&data, k), expected);
+    }
+
+    let data: Vec<_> = iter_rng(&mut rng, 1000, 100).collect();
+    for (row, top) in data
+        .chunks(100)
+        .zip(top_k_with_indices_by_row(&data, 100, 5))
+    {
+        assert_eq!(top, top_k_with_indices(row, 5));
+    }
+}
+
+#[test]
+fn trim() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(1, 500);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let low = rng.bounded_f64(0.0, 0.5);
+        let high = rng.bounded_f64(0.0, 0.5);
+        let l = (low * count as f64) as usize;
+        let r = count - (high * count as f64) as usize;
+
+        let middle = trim_percentiles(&mut data, low, high);
+        middle.sort();
+        assert_eq!(middle, &sorted[l..r]);
+        assert!(data[..l].iter().all(|elem| elem <= &sorted[l]));
+        assert!(data[r..].iter().all(|elem| elem >= &sorted[r - 1]));
+    }
+}
+
+#[test]
+fn warm_start() {
+    #[cfg(not(miri))]
+    let (count, repeat) = (10_000, 100);
+    #[cfg(miri)]
+    let (count, repeat) = (100, 10);
+
+    let mut rng = WyRng::new(123);
+    let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+    let mut state = SelectionState::default();
+    let index = rng.bounded_usize(0, count);
+
+    for _iter in 0..repeat {
+        // Modify a few elements between the calls.
+        for _ in 0..rng.bounded_usize(0, 10) {
+            let i = rng.bounded_usize(0, count);
+            data[i] = rng.bounded_usize(0, count);
+        }
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let (left, nth, right) = state.select_nth_unstable(&mut data, index);
+        assert_eq!(*nth, sorted[index]);
+        assert!(left.iter().all(|elem| elem <= nth));
+        assert!(right.iter().all(|elem| elem >= nth));
+    }
+}
+
+#[test]
+fn window_quantile() {
+    #[cfg(not(miri))]
+    let repeat = 10_000;
+    #[cfg(miri)]
+    let repeat = 100;
+
+    let mut rng = WyRng::new(123);
+    let window = rng.bounded_usize(1, 100);
+    let q = rng.f64();
+    let mut tracker = WindowQuantile::new(window, q);
+    let mut values = Vec::new();
+
+    for _iter in 0..repeat {
+        let value = rng.bounded_usize(0, 1000);
+        tracker.push(value);
+        values.push(value);
+
+        let mut sorted = values[values.len().saturating_sub(window)..].to_vec();
+        sorted.sort();
+        let rank = (q * (sorted.len() - 1) as f64) as usize;
+        assert_eq!(tracker.quantile(), Some(&sorted[rank]));
+    }
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derived_keys() {
+    use crate::{select_nth_unstable_by, select_nth_unstable_by_cached_key, SelectKey};
+
+    #[derive(Clone, Debug, PartialEq, SelectKey)]
+    struct Record {
+        #[select_key]
+        group: usize,
+        #[select_key(reverse)]
+        score: usize,
+        id: usize,
+    }
+
+    #[derive(SelectKey)]
+    struct Wrapper<T>(#[select_key] T);
+
+    #[cfg(not(miri))]
+    let (repeat, max_len) = (500, 2000);
+    #[cfg(miri)]
+    let (repeat, max_len) = (20, 100);
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let len = rng.bounded_usize(1, max_len);
+        let mut data: Vec<_> = (0..len)
+            .map(|id| Record {
+                group: rng.bounded_usize(0, 4),
+                score: rng.bounded_usize(0, len),
+                id,
+            })
+            .collect();
+        let index = rng.bounded_usize(0, len);
+        let mut sorted = data.clone();
+        sorted.sort_by_key(|r| (r.group, core::cmp::Reverse(r.score)));
+        let key = |r: &Record| (r.group, core::cmp::Reverse(r.score));
+
+        let mut copy = data.clone();
+        select_nth_unstable_by_cached_key(&mut copy, index, Record::select_key);
+        assert_eq!(key(&copy[index]), key(&sorted[index]));
+        let (below, nth, above) = select_nth_unstable_by(&mut data, index, Record::cmp_key);
+        assert_eq!(key(nth), key(&sorted[index]));
+        assert!(below
+            .iter()
+            .all(|r| r.lt_key(nth) || r.cmp_key(nth).is_eq()));
+        assert!(above.iter().all(|r| !r.lt_key(nth)));
+    }
+
+    let mut data = [3, 1, 2].map(Wrapper);
+    let (_, median, _) = select_nth_unstable_by(&mut data, 1, Wrapper::cmp_key);
+    assert_eq!(median.select_key(), 2);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized_state() {
+    use crate::streaming::{GkSummary, OnlineQuantile, TDigest};
+
+    #[cfg(not(miri))]
+    let repeat = 100;
+    #[cfg(miri)]
+    let repeat = 5;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(0, 200);
+        let values: Vec<_> = iter_rng(&mut rng, count, 100).collect();
+
+        let mut set = OrderStatistics::new();
+        values.iter().for_each(|&x| set.insert(x));
+        let json = serde_json::to_string(&set).unwrap();
+        let restored: OrderStatistics<usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.len(), set.len());
+        for k in 0..set.len() {
+            assert_eq!(restored.kth(k), set.kth(k));
+        }
+
+        // A restored tracker continues from where the original left off.
+        let window = rng.bounded_usize(1, 50);
+        let mut tracker = WindowQuantile::new(window, rng.f64());
+        values.iter().for_each(|&x| _ = tracker.push(x));
+        let json = serde_json::to_string(&tracker).unwrap();
+        let mut restored: WindowQuantile<usize> = serde_json::from_str(&json).unwrap();
+        for _ in 0..window {
+            let value = rng.bounded_usize(0, 100);
+            assert_eq!(restored.push(value), tracker.push(value));
+            assert_eq!(restored.quantile(), tracker.quantile());
+        }
+
+        let mut top = BoundedTopK::new(rng.bounded_usize(0, 20));
+        top.extend(values.iter().copied());
+        let json = serde_json::to_string(&top).unwrap();
+        let mut restored: BoundedTopK<usize> = serde_json::from_str(&json).unwrap();
+        for _ in 0..10 {
+            let value = rng.bounded_usize(0, 100);
+            assert_eq!(restored.push(value), top.push(value));
+            assert_eq!(restored.min(), top.min());
+        }
+
+        let mut estimator = OnlineQuantile::new(rng.f64());
+        values.iter().for_each(|&x| estimator.insert(x as f64));
+        let json = serde_json::to_string(&estimator).unwrap();
+        let mut restored: OnlineQuantile = serde_json::from_str(&json).unwrap();
+        for _ in 0..10 {
+            let value = rng.f64();
+            estimator.insert(value);
+            restored.insert(value);
+            // JSON may round the heights in the last digit.
+            let (a, b) = (restored.estimate().unwrap(), estimator.estimate().unwrap());
+            assert!((a - b).abs() <= 1e-9 * b.abs());
+        }
+
+        let mut summary = GkSummary::new(rng.bounded_usize(1, 20) as f64 / 100.0);
+        summary.extend(values.iter().copied());
+        let json = serde_json::to_string(&summary).unwrap();
+        let mut restored: GkSummary<usize> = serde_json::from_str(&json).unwrap();
+        for _ in 0..10 {
+            let (value, p) = (rng.bounded_usize(0, 100), rng.f64());
+            summary.insert(value);
+            restored.insert(value);
+            assert_eq!(restored.query(p), summary.query(p));
+            assert_eq!(restored.bounds(p), summary.bounds(p));
+        }
+
+        let mut digest = TDigest::new(rng.bounded_usize(1, 20) as f64);
+        digest.extend(values.iter().map(|&x| x as f64));
+        let json = serde_json::to_string(&digest).unwrap();
+        let mut restored: TDigest = serde_json::from_str(&json).unwrap();
+        for _ in 0..10 {
+            let (value, p) = (rng.f64(), rng.f64());
+            digest.insert(value);
+            restored.insert(value);
+            let (a, b) = (restored.quantile(p).unwrap(), digest.quantile(p).unwrap());
+            assert!((a - b).abs() <= 1e-9 * b.abs());
+        }
+
+        if count > 0 {
+            let mut data = values.clone();
+            let index = rng.bounded_usize(0, count);
+            let mut state = SelectionState::default();
+            state.select_nth_unstable(&mut data, index);
+            let json = serde_json::to_string(&state).unwrap();
+            let mut restored: SelectionState<usize> = serde_json::from_str(&json).unwrap();
+            let mut sorted = data.clone();
+            sorted.sort();
+            assert_eq!(
+                *restored.select_nth_unstable(&mut data, index).1,
+                sorted[index]
+            );
+        }
+    }
+
+    let mut histogram = Histogram::equi_depth(&[5, 1, 4, 2, 3], 3);
+    let json = serde_json::to_string(&histogram).unwrap();
+    let mut restored: Histogram<usize> = serde_json::from_str(&json).unwrap();
+    histogram.add(&[0, 6]);
+    restored.add(&[0, 6]);
+    assert_eq!(restored.counts(), histogram.counts());
+    let invalid = r#"{"edges":[2,1],"counts":[0,0,0]}"#;
+    assert!(serde_json::from_str::<Histogram<usize>>(invalid).is_err());
+    let invalid = r#"{"edges":[1,2],"counts":[0,0]}"#;
+    assert!(serde_json::from_str::<Histogram<usize>>(invalid).is_err());
+
+    let invalid = r#"{"capacity":2,"values":[1,2,3]}"#;
+    assert!(serde_json::from_str::<BoundedTopK<usize>>(invalid).is_err());
+    let invalid = r#"{"p":0.5,"count":6,"heights":[0,1,2,3,4],"positions":[0,1,2,3,4]}"#;
+    assert!(serde_json::from_str::<OnlineQuantile>(invalid).is_err());
+    let invalid = r#"{"p":0.5,"count":6,"heights":[0,3,2,3,4],"positions":[0,1,2,3,5]}"#;
+    assert!(serde_json::from_str::<OnlineQuantile>(invalid).is_err());
+    let centroids = r#"[{"mean":2,"weight":2},{"mean":1,"weight":1}]"#;
+    let invalid = std::format!(
+        r#"{{"compression":10,"count":3,"min":0,"max":3,"centroids":{centroids},"buffer":[]}}"#
+    );
+    assert!(serde_json::from_str::<TDigest>(&invalid).is_err());
+    let centroids = r#"[{"mean":1,"weight":1},{"mean":2,"weight":2}]"#;
+    let invalid = std::format!(
+        r#"{{"compression":10,"count":4,"min":0,"max":3,"centroids":{centroids},"buffer":[]}}"#
+    );
+    assert!(serde_json::from_str::<TDigest>(&invalid).is_err());
+    let tuples = r#"[{"value":2,"g":1,"delta":0},{"value":1,"g":1,"delta":0}]"#;
+    let invalid = std::format!(r#"{{"epsilon":0.1,"count":2,"tuples":{tuples}}}"#);
+    assert!(serde_json::from_str::<GkSummary<usize>>(&invalid).is_err());
+    let tuples = r#"[{"value":1,"g":1,"delta":0},{"value":2,"g":1,"delta":2}]"#;
+    let invalid = std::format!(r#"{{"epsilon":0.1,"count":2,"tuples":{tuples}}}"#);
+    assert!(serde_json::from_str::<GkSummary<usize>>(&invalid).is_err());
+    let invalid = r#"{"window":2,"q":0.5,"values":[1,2,3]}"#;
+    assert!(serde_json::from_str::<WindowQuantile<usize>>(invalid).is_err());
+    let invalid = r#"{"window":2,"q":1.5,"values":[]}"#;
+    assert!(serde_json::from_str::<WindowQuantile<usize>>(invalid).is_err());
+}
+
+#[test]
+fn winsorized() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+
+    for _iter in 0..repeat {
+        let count = rng.bounded_usize(1, 500);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+        let mut expected = data.clone();
+        expected.sort();
+
+        let low = rng.bounded_f64(0.0, 0.5);
+        let high = rng.bounded_f64(0.0, 0.5);
+        let l = (low * count as f64) as usize;
+        let r = count - (high * count as f64) as usize;
+        let (min, max) = (expected[l], expected[r - 1]);
+        expected[..l].fill(min);
+        expected[r..].fill(max);
+
+        winsorize(&mut data, low, high);
+        data.sort();
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+fn group_percentiles() {
+    use std::collections::BTreeMap;
+
+    #[cfg(not(miri))]
+    let repeat = 500;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(0, 2000);
+        let n_keys = rng.bounded_usize(1, 50);
+        let mut pairs: Vec<_> = (0..count)
+            .map(|_| {
+                (
+                    rng.bounded_usize(0, n_keys),
+                    rng.bounded_usize(0, count + 1),
+                )
+            })
+            .collect();
+        let mut groups = BTreeMap::<usize, Vec<usize>>::new();
+        for &(k, v) in &pairs {
+            groups.entry(k).or_default().push(v);
+        }
+        let p = rng.bounded_f64(0.0, 1.0);
+        let expected: Vec<_> = groups
+            .iter_mut()
+            .map(|(&k, values)| {
+                values.sort();
+                (k, values[(p * (values.len() - 1) as f64) as usize])
+            })
+            .collect();
+        let quantiles: Vec<_> = percentile_each_group(&mut pairs, p)
+            .map(|(&k, &v)| (k, v))
+            .collect();
+        assert_eq!(quantiles, expected);
+    }
+}
+
+#[test]
+fn percentiles() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(1, 500);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+        let p = rng.bounded_f64(0.0, 1.0);
+        let h = p * (count - 1) as f64;
+        assert_eq!(*percentile(&mut data, p), sorted[h as usize]);
+
+        #[cfg(feature = "num-traits")]
+        {
+            use crate::percentile_interpolated;
+            let mut floats: Vec<_> = data.iter().map(|&x| x as f64).collect();
+            let lower = sorted[h as usize] as f64;
+            let upper = sorted[(h as usize + 1).min(count - 1)] as f64;
+            let expected = lower + (upper - lower) * (h - h.floor());
+            assert_eq!(percentile_interpolated(&mut floats, p), expected);
+            assert_eq!(
+                percentile_interpolated(&mut data, p),
+                sorted[h as usize] + ((upper - lower) * (h - h.floor())) as usize
+            );
+        }
+    }
+
+    #[cfg(feature = "num-traits")]
+    {
+        use crate::percentile_interpolated;
+        // The difference of the elements doesn't fit in the type.
+        assert_eq!(percentile_interpolated(&mut [100i8, -100], 0.5), 0);
+        assert_eq!(percentile_interpolated(&mut [i8::MAX, i8::MIN], 0.75), 63);
+        assert_eq!(percentile_interpolated(&mut [-3i8, -2], 0.5), -2);
+        assert_eq!(
+            percentile_interpolated(&mut [i64::MIN, i64::MAX], 1.0),
+            i64::MAX
+        );
+        // The elements are rounded to the same `f64`, which is out of the range of the type.
+        assert_eq!(
+            percentile_interpolated(&mut [u64::MAX, u64::MAX - 1], 0.5),
+            u64::MAX
+        );
+    }
+}
+
+#[test]
+fn quantile_lists() {
+    #[cfg(not(miri))]
+    let repeat = 1000;
+    #[cfg(miri)]
+    let repeat = 10;
+
+    let mut rng = WyRng::new(123);
+    for _ in 0..repeat {
+        let count = rng.bounded_usize(1, 2000);
+        let mut data: Vec<_> = iter_rng(&mut rng, count, count).collect();
+        let mut sorted = data.clone();
+        sorted.sort();
+        let ps: [f64; 5] = core::array::from_fn(|_| rng.bounded_f64(0.0, 1.0));
+        let expected = ps.map(|p| sorted[(p * (count - 1) as f64) as usize]);
+        assert_eq!(select_quantiles(&mut data, &ps).map(|x| *x), expected);
+    }
+
+    let mut data: Vec<usize> = (0..=100).collect();
+    let [a, b, c] = select_quantiles_by(&mut data, &[0.5, 0.0, 0.5], |a, b| b.cmp(a));
+    assert_eq!([*a, *b, *c], [50, 100, 50]);
+    let [] = select_quantiles_by_lt(&mut [] as &mut [u32], &[], |a, b| a < b);
+}
+
 /// Returns a vector of integers where most elements are in sorted order. The maximum is randomized
 /// and in the range `0..count`. The ratio of randomized (unsorted) elements is randomized and in
 /// the range 1% to 50%.
@@ -0,0 +1,253 @@
+use core::cmp::Ordering;
+use std::vec::Vec;
+
+use crate::{
+    heapselect::{heapify, minmax, sift_down},
+    ordering,
+};
+
+/// Keeps the `k` largest values of a stream.
+///
+/// The values are kept in a min-max heap of at most `k` elements, so the smallest of them, which
+/// the next value has to beat to get in, and the largest of them are both available in constant
+/// time. Pushing a value takes *O*(log *k*) time, or a single comparison if the value doesn't make
+/// it into the top `k`, which is the common case once the stream has run for a while.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::BoundedTopK;
+/// let mut top = BoundedTopK::new(3);
+/// for score in [12, 15, 11, 90, 14, 13, 12, 16, 11] {
+///     top.push(score);
+/// }
+/// assert_eq!(top.min(), Some(&15));
+/// assert_eq!(top.max(), Some(&90));
+/// assert_eq!(top.into_sorted_vec(), [15, 16, 90]);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BoundedTopK<T> {
+    /// The maximum number of values kept.
+    capacity: usize,
+    /// The values kept, in the order of a min-max heap.
+    values: Vec<T>,
+}
+
+impl<T> BoundedTopK<T>
+where
+    T: Ord,
+{
+    /// Returns an empty collection that keeps the `k` largest values pushed into it. With `k == 0`,
+    /// no values are kept.
+    pub fn new(k: usize) -> Self {
+        Self {
+            capacity: k,
+            values: Vec::with_capacity(k),
+        }
+    }
+
+    /// Returns the number of values that are kept at most.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns `true` if no values are kept.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the number of values kept, which is less than the capacity only until that many
+    /// values have been pushed.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Pushes `value`. If the collection was full, returns the value that didn't make it into the
+    /// top `k`, which is either the smallest value kept so far, or `value` itself if it is not
+    /// larger than that.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        if self.values.len() < self.capacity {
+            let last = self.values.len();
+            self.values.push(value);
+            minmax::sift_up(&mut self.values, last, &mut T::lt);
+            return None;
+        }
+        match self.values.first_mut() {
+            Some(min) if *min < value => {
+                let evicted = core::mem::replace(min, value);
+                minmax::sift_down(&mut self.values, 0, &mut T::lt);
+                Some(evicted)
+            }
+            _ => Some(value),
+        }
+    }
+
+    /// Returns the smallest value kept, i.e. the `k`th largest value pushed so far once the
+    /// collection is full, or `None` if it is empty.
+    pub fn min(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    /// Returns the largest value kept, or `None` if the collection is empty.
+    pub fn max(&self) -> Option<&T> {
+        let values = &self.values;
+        (!values.is_empty()).then(|| &values[minmax::position_of_max(values, &mut T::lt)])
+    }
+
+    /// Returns an iterator over the values kept, in an unspecified order.
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    /// Returns the values kept, in an unspecified order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    /// Returns the values kept, sorted in ascending order.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut values = self.values;
+        values.sort_unstable();
+        values
+    }
+}
+
+impl<T> Extend<T> for BoundedTopK<T>
+where
+    T: Ord,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BoundedTopK<T> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.iter()
+    }
+}
+
+impl<T> IntoIterator for BoundedTopK<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+/// Deserializes the capacity and the values, and rebuilds the heap from the values, so that a
+/// collection serialized by a different version of the crate can be restored too.
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for BoundedTopK<T>
+where
+    T: Ord + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "BoundedTopK")]
+        struct State<T> {
+            capacity: usize,
+            values: Vec<T>,
+        }
+
+        let state = State::<T>::deserialize(deserializer)?;
+        if state.values.len() > state.capacity {
+            return Err(D::Error::custom("more values than the capacity"));
+        }
+        let mut top = Self::new(state.capacity);
+        top.extend(state.values);
+        Ok(top)
+    }
+}
+
+/// Returns the `k` smallest elements of `iter` in ascending order, or all of them if there are
+/// fewer than `k`.
+///
+/// Unlike [`k_smallest`](crate::k_smallest), this doesn't need the elements in a slice: they are
+/// consumed one at a time, and only the `k` smallest so far are kept, in a max-heap. This takes
+/// *O*(*k*) memory, and *O*(*n* log *k*) time in the worst case, but an element that doesn't make
+/// it into the `k` smallest costs a single comparison, so on random input the time is closer to
+/// *O*(*n*). This suits data that doesn't fit in memory or arrives incrementally.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest_iter;
+/// let lines = "12\n7\n31\n3\n18\n5";
+/// let smallest = k_smallest_iter(lines.lines().map(|line| line.parse::<u32>().unwrap()), 3);
+/// assert_eq!(smallest, [3, 5, 7]);
+/// ```
+pub fn k_smallest_iter<I>(iter: I, k: usize) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    k_smallest_iter_by_lt(iter, k, I::Item::lt)
+}
+
+/// Returns the `k` smallest elements of `iter` with respect to a comparator function, in
+/// ascending order. See [`k_smallest_iter`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest_iter_by;
+/// let smallest = k_smallest_iter_by((0..10).map(|x| x as f64 * 0.5 - 2.0), 2, f64::total_cmp);
+/// assert_eq!(smallest, [-2.0, -1.5]);
+/// ```
+pub fn k_smallest_iter_by<I, F>(iter: I, k: usize, mut compare: F) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    k_smallest_iter_by_lt(iter, k, |a, b| compare(a, b) == Ordering::Less)
+}
+
+/// Returns the `k` smallest elements of `iter` with respect to a "less than" function, in
+/// ascending order. See [`k_smallest_iter`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::k_smallest_iter_by_lt;
+/// let words = ["select", "a", "partition", "the", "pivot"];
+/// let shortest = k_smallest_iter_by_lt(words, 2, |a, b| a.len() < b.len());
+/// assert_eq!(shortest, ["a", "the"]);
+/// ```
+pub fn k_smallest_iter_by_lt<I, F>(iter: I, k: usize, mut lt: F) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    F: FnMut(&I::Item, &I::Item) -> bool,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+    // The iterator is fused, so if there were fewer than `k` elements, it stays exhausted and the
+    // heap isn't used, even if the iterator would otherwise resume after returning `None`.
+    let mut iter = iter.into_iter().fuse();
+    let mut heap: Vec<_> = iter.by_ref().take(k).collect();
+    heapify(&mut heap, &mut lt);
+    for x in iter {
+        if lt(&x, &heap[0]) {
+            heap[0] = x;
+            sift_down(&mut heap, 0, &mut lt);
+        }
+    }
+    heap.sort_unstable_by(|a, b| ordering(a, b, &mut lt));
+    heap
+}
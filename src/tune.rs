@@ -0,0 +1,143 @@
+use core::fmt;
+use std::{time::Instant, vec::Vec};
+
+use crate::{params::Params, select_with};
+
+/// The number of times each candidate is measured. The fastest measurement is used.
+const REPEAT: usize = 5;
+
+/// The number of elements selected from in each measurement, split into slices of the measured
+/// length.
+const WORK: usize = 1 << 20;
+
+impl fmt::Display for Params {
+    /// Formats the thresholds as the contents of the file that the build script reads from
+    /// `TURBOSELECT_PARAMS`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "// Generated with `turboselect::tune`.")?;
+        writeln!(f, "Params {{")?;
+        writeln!(f, "    tinysort_cutoff: {},", self.tinysort_cutoff)?;
+        writeln!(f, "    kth_of_3_max: {},", self.kth_of_3_max)?;
+        writeln!(f, "    kth_of_5_max: {},", self.kth_of_5_max)?;
+        writeln!(f, "    kth_of_7_max: {},", self.kth_of_7_max)?;
+        writeln!(f, "    partition_block: {},", self.partition_block)?;
+        writeln!(f, "    bad_round_limit: {},", self.bad_round_limit)?;
+        writeln!(f, "}}")
+    }
+}
+
+/// Measures the thresholds of the selection algorithm that are fastest on this machine for
+/// elements produced by `generate`, and returns them.
+///
+/// The thresholds are tuned one at a time, starting from [`Params::DEFAULT`], by timing selections
+/// from slices of the lengths that each threshold affects. This takes a few seconds. The result
+/// formats as the contents of a constants file. To use it, write the file and rebuild the crate
+/// with the `autotune` feature and the `TURBOSELECT_PARAMS` environment variable set to the path of
+/// the file, relative to the root of this crate or absolute.
+///
+/// # Examples
+///
+/// ```no_run
+/// let mut state = 0x_853c_49e6_748f_ea9b_u64;
+/// let params = turboselect::tune(|| {
+///     state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+///     (state >> 32) as u32
+/// });
+/// std::fs::write("/tmp/turboselect_params.rs", params.to_string()).unwrap();
+/// // TURBOSELECT_PARAMS=/tmp/turboselect_params.rs cargo build --features autotune
+/// ```
+pub fn tune<T, G>(mut generate: G) -> Params
+where
+    T: Ord + Clone,
+    G: FnMut() -> T,
+{
+    let mut params = Params::DEFAULT;
+    tune_one(
+        &mut params,
+        &mut generate,
+        &[32, 64, 96, 128, 192, 256],
+        &[100_000],
+        |p, x| p.partition_block = x,
+    );
+    tune_one(
+        &mut params,
+        &mut generate,
+        &[8, 12, 16, 20, 24, 32, 40, 48, 64],
+        &[100, 300, 1_000],
+        |p, x| p.tinysort_cutoff = x,
+    );
+    tune_one(
+        &mut params,
+        &mut generate,
+        &[64, 128, 192, 256, 384, 512],
+        &[200, 500, 1_000],
+        |p, x| p.kth_of_3_max = x,
+    );
+    tune_one(
+        &mut params,
+        &mut generate,
+        &[256, 512, 768, 1024, 1536, 2048],
+        &[500, 1_500, 3_000],
+        |p, x| p.kth_of_5_max = x,
+    );
+    tune_one(
+        &mut params,
+        &mut generate,
+        &[1024, 2048, 4096, 8192, 16384],
+        &[2_000, 6_000, 20_000],
+        |p, x| p.kth_of_7_max = x,
+    );
+    params
+}
+
+/// Sets the threshold changed by `set` to the fastest of `candidates`, as measured by selecting from
+/// slices of the lengths in `lens`. The other thresholds are kept as in `params`.
+fn tune_one<T, G>(
+    params: &mut Params,
+    generate: &mut G,
+    candidates: &[usize],
+    lens: &[usize],
+    set: fn(&mut Params, usize),
+) where
+    T: Ord + Clone,
+    G: FnMut() -> T,
+{
+    let data: Vec<Vec<T>> = lens
+        .iter()
+        .map(|&len| (0..len).map(|_| generate()).collect())
+        .collect();
+    let best = candidates
+        .iter()
+        .map(|&value| {
+            let mut candidate = *params;
+            set(&mut candidate, value);
+            (candidate, value)
+        })
+        .filter(|(candidate, _)| candidate.is_valid())
+        .min_by_key(|(candidate, _)| data.iter().map(|d| measure(d, candidate)).sum::<u128>());
+    if let Some((_, value)) = best {
+        set(params, value);
+    }
+}
+
+/// Returns the fastest of `REPEAT` measurements, in nanoseconds, of selecting from copies of
+/// `data` at indices spread over the whole slice.
+fn measure<T>(data: &[T], params: &Params) -> u128
+where
+    T: Ord + Clone,
+{
+    let rounds = (WORK / data.len()).max(1);
+    let mut copy = data.to_vec();
+    (0..REPEAT)
+        .map(|_| {
+            let start = Instant::now();
+            for round in 0..rounds {
+                copy.clone_from_slice(data);
+                let index = (round * 7919) % data.len();
+                select_with(&mut copy, index, &mut T::lt, params);
+            }
+            start.elapsed().as_nanos()
+        })
+        .min()
+        .unwrap()
+}
@@ -0,0 +1,318 @@
+use core::{cmp::Ordering, mem};
+
+use crate::{
+    math::{ceil, sqrt},
+    params::params,
+    partition_in_blocks, select, select_nth_unstable_by_lt, split_partition,
+};
+
+/// Remembers the outcome of a selection, so that selecting the same index again from a slightly
+/// modified slice can skip most of the work.
+///
+/// After each call to [`SelectionState::select_nth_unstable`], the state stores two values that
+/// bracket the selected element: one close to it from below and one close to it from above. On
+/// the next call with the same index and slice length, the slice is first partitioned around these
+/// values. If the selected rank still falls between them, only the small middle part needs to be
+/// searched. Otherwise, the state falls back to a full selection.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::SelectionState;
+/// let mut state = SelectionState::default();
+/// let mut v: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+///
+/// for tick in 0..10 {
+///     // Modify a few elements and recompute the 90th percentile.
+///     v[tick] += 1000;
+///     let (left, p90, right) = state.select_nth_unstable(&mut v, 900);
+///     assert!(left.iter().all(|x| x <= p90));
+///     assert!(right.iter().all(|x| x >= p90));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectionState<T> {
+    /// The index selected in the previous call.
+    index: usize,
+    /// The length of the slice in the previous call.
+    len: usize,
+    /// A value less than or equal to the previously selected element.
+    lower: Option<T>,
+    /// A value greater than or equal to the previously selected element.
+    upper: Option<T>,
+}
+
+impl<T> Default for SelectionState<T> {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            len: 0,
+            lower: None,
+            upper: None,
+        }
+    }
+}
+
+impl<T> SelectionState<T>
+where
+    T: Ord + Clone,
+{
+    /// Reorders the slice such that the element at `index` is at its final sorted position, like
+    /// [`select_nth_unstable`](crate::select_nth_unstable), reusing the bounds remembered from the
+    /// previous call when possible. Updates the state for the next call.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index >= len()`, meaning it always panics on empty slices.
+    pub fn select_nth_unstable<'a>(
+        &mut self,
+        data: &'a mut [T],
+        index: usize,
+    ) -> (&'a mut [T], &'a mut T, &'a mut [T]) {
+        assert!(index < data.len());
+
+        // If there are less than two elements, there is nothing to do. If `T` is a zero sized type,
+        // it cannot have any meaningful ordering, so we just return.
+        if data.len() < 2 || mem::size_of::<T>() == 0 {
+            return split_partition(data, index);
+        }
+
+        if !(self.index == index && self.len == data.len() && self.select_warm(data, index)) {
+            select(data, index, &mut T::lt);
+        }
+        self.update(data, index);
+        split_partition(data, index)
+    }
+
+    /// Partitions `data` around the remembered bounds and selects `index` from the middle part.
+    /// Returns `false` if the element at `index` is no longer between the bounds.
+    fn select_warm(&self, data: &mut [T], index: usize) -> bool {
+        // Move the elements less than the lower bound to the beginning of the slice, and the
+        // elements greater than the upper bound to the end of the slice.
+        let l = match &self.lower {
+            Some(lower) => {
+                partition_in_blocks(data, lower, &mut T::lt, params::<T>().partition_block)
+            }
+            None => 0,
+        };
+        let r = match &self.upper {
+            Some(upper) => {
+                l + partition_in_blocks(
+                    &mut data[l..],
+                    upper,
+                    &mut T::le,
+                    params::<T>().partition_block,
+                )
+            }
+            None => data.len(),
+        };
+        if !(l..r).contains(&index) {
+            return false;
+        }
+        if r - l > 1 {
+            select(&mut data[l..r], index - l, &mut T::lt);
+        }
+        true
+    }
+
+    /// Remembers bounds for the element at `index` of the partitioned slice `data`. The bounds are
+    /// the largest and smallest elements in evenly spaced samples of about `sqrt(len)` elements
+    /// from each side of the partition, which puts them roughly `sqrt(len)` ranks away from
+    /// `index`.
+    fn update(&mut self, data: &[T], index: usize) {
+        let count = ceil(sqrt(data.len() as f64)) as usize;
+        let (left, rest) = data.split_at(index);
+        let right = &rest[1..];
+        self.index = index;
+        self.len = data.len();
+        self.lower = left
+            .iter()
+            .rev()
+            .step_by((left.len() / count).max(1))
+            .max()
+            .cloned();
+        self.upper = right
+            .iter()
+            .step_by((right.len() / count).max(1))
+            .min()
+            .cloned();
+    }
+}
+
+/// Restores the partitioning of a slice that was previously reordered with
+/// [`select_nth_unstable`](crate::select_nth_unstable) at `index`, after the elements at the
+/// positions in `modified` have been changed.
+///
+/// The modified elements that ended up on the wrong side of the element at `index` are first
+/// swapped with each other in pairs. If the modifications moved equally many elements across the
+/// partition in both directions, this is all that needs to be done and the call takes
+/// *O*(*m* log *m*) time, where *m* is the number of modified positions. Otherwise, the remaining
+/// misplaced elements are moved next to `index`, and a selection is run on the side of the slice
+/// that gained elements. If the element at `index` itself was modified, the whole slice is
+/// selected again.
+///
+/// The positions in `modified` may contain duplicates and are reordered by the call.
+///
+/// Returns a triplet of the subslice prior to `index`, the element at `index`, and the subslice
+/// after `index`, like [`select_nth_unstable`](crate::select_nth_unstable).
+///
+/// # Panics
+///
+/// Panics when `index >= len()` or if any of the positions in `modified` is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{reselect_nth_unstable, select_nth_unstable};
+/// let mut v: Vec<u32> = (0..100).rev().collect();
+/// select_nth_unstable(&mut v, 50);
+///
+/// v[10] = 1000;
+/// v[90] = 0;
+/// let (left, nth, right) = reselect_nth_unstable(&mut v, 50, &mut [10, 90]);
+/// assert_eq!(*nth, 50);
+/// assert!(left.iter().all(|x| x <= nth));
+/// assert!(right.iter().all(|x| x >= nth));
+/// ```
+pub fn reselect_nth_unstable<'a, T>(
+    data: &'a mut [T],
+    index: usize,
+    modified: &mut [usize],
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    T: Ord,
+{
+    reselect_nth_unstable_by_lt(data, index, modified, T::lt)
+}
+
+/// Restores the partitioning of a slice that was previously reordered with a comparator function
+/// at `index`, after the elements at the positions in `modified` have been changed.
+///
+/// See [`reselect_nth_unstable`] for details. `compare` must order the elements like the comparator
+/// the slice was previously reordered with.
+///
+/// # Panics
+///
+/// Panics when `index >= len()` or if any of the positions in `modified` is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{reselect_nth_unstable_by, select_nth_unstable_by};
+/// let mut v: Vec<u32> = (0..100).collect();
+/// select_nth_unstable_by(&mut v, 10, |a, b| b.cmp(a));
+///
+/// v[50] = 1000;
+/// let (_, nth, _) = reselect_nth_unstable_by(&mut v, 10, &mut [50], |a, b| b.cmp(a));
+/// assert_eq!(*nth, 90);
+/// ```
+pub fn reselect_nth_unstable_by<'a, T, F>(
+    data: &'a mut [T],
+    index: usize,
+    modified: &mut [usize],
+    mut compare: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    reselect_nth_unstable_by_lt(data, index, modified, |a, b| {
+        compare(a, b) == Ordering::Less
+    })
+}
+
+/// Restores the partitioning of a slice that was previously reordered with a "less than" function
+/// at `index`, after the elements at the positions in `modified` have been changed.
+///
+/// This is the "less than" form of [`reselect_nth_unstable`], like
+/// [`select_nth_unstable_by_lt`]. `lt` must order the elements like the function the slice was
+/// previously reordered with.
+///
+/// # Panics
+///
+/// Panics when `index >= len()` or if any of the positions in `modified` is out of bounds.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::{reselect_nth_unstable_by_lt, select_nth_unstable_by_lt};
+/// let mut v: Vec<u32> = (0..100).collect();
+/// select_nth_unstable_by_lt(&mut v, 10, |a, b| a > b);
+///
+/// v[5] = 0;
+/// let (_, nth, _) = reselect_nth_unstable_by_lt(&mut v, 10, &mut [5], |a, b| a > b);
+/// assert_eq!(*nth, 88);
+/// ```
+pub fn reselect_nth_unstable_by_lt<'a, T, F>(
+    data: &'a mut [T],
+    index: usize,
+    modified: &mut [usize],
+    mut lt: F,
+) -> (&'a mut [T], &'a mut T, &'a mut [T])
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    assert!(index < data.len());
+    assert!(modified.iter().all(|&i| i < data.len()));
+
+    if data.len() < 2 || mem::size_of::<T>() == 0 {
+        return split_partition(data, index);
+    }
+    if modified.contains(&index) {
+        return select_nth_unstable_by_lt(data, index, lt);
+    }
+
+    // Keep the modified positions that are on the wrong side of the element at `index`, in
+    // ascending order. The positions before `index` come first, followed by those after it.
+    modified.sort_unstable();
+    let mut count = 0;
+    for i in 0..modified.len() {
+        let pos = modified[i];
+        let is_misplaced = match pos.cmp(&index) {
+            Ordering::Less => lt(&data[index], &data[pos]),
+            _ => lt(&data[pos], &data[index]),
+        };
+        if is_misplaced && (count == 0 || modified[count - 1] != pos) {
+            modified[count] = pos;
+            count += 1;
+        }
+    }
+    let misplaced = &modified[..count];
+    let a = misplaced.partition_point(|&pos| pos < index);
+    let (left, right) = misplaced.split_at(a);
+
+    match left.len().cmp(&right.len()) {
+        Ordering::Equal => {
+            for (&l, &r) in left.iter().zip(right) {
+                data.swap(l, r);
+            }
+        }
+        Ordering::Greater => {
+            // Pair the misplaced elements farthest from `index`, and move the rest to the end of
+            // the left part.
+            let e = left.len() - right.len();
+            let (paired, excess) = left.split_at(right.len());
+            for (&l, &r) in paired.iter().zip(right) {
+                data.swap(l, r);
+            }
+            for (j, &pos) in excess.iter().enumerate().rev() {
+                data.swap(pos, index - e + j);
+            }
+            select_nth_unstable_by_lt(&mut data[index - e..], e, lt);
+        }
+        Ordering::Less => {
+            // Pair the misplaced elements farthest from `index`, and move the rest to the
+            // beginning of the right part.
+            let e = right.len() - left.len();
+            let (excess, paired) = right.split_at(e);
+            for (&l, &r) in left.iter().zip(paired) {
+                data.swap(l, r);
+            }
+            for (j, &pos) in excess.iter().enumerate() {
+                data.swap(pos, index + 1 + j);
+            }
+            select_nth_unstable_by_lt(&mut data[..=index + e], index, lt);
+        }
+    }
+    split_partition(data, index)
+}
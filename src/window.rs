@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use crate::{math::floor, OrderStatistics};
+
+/// Tracks a quantile of the last `window` values of a stream.
+///
+/// The values in the window are kept both in arrival order, to know which value to evict, and in
+/// an [`OrderStatistics`] collection, to find the value at the target rank. Pushing a value and
+/// querying the quantile both take *O*(log *w*) time, where *w* is the size of the window.
+///
+/// # Examples
+///
+/// ```
+/// use turboselect::WindowQuantile;
+/// let mut p90 = WindowQuantile::new(10, 0.9);
+/// for latency in [12, 15, 11, 90, 14, 13, 12, 16, 11, 13, 12, 14] {
+///     p90.push(latency);
+/// }
+/// // The window contains the last 10 values.
+/// assert_eq!(p90.quantile(), Some(&16));
+/// ```
+pub struct WindowQuantile<T> {
+    /// The maximum number of values in the window.
+    window: usize,
+    /// The quantile to track, in the range `[0.0, 1.0]`.
+    q: f64,
+    /// The values in the window in arrival order.
+    values: VecDeque<T>,
+    /// The values in the window in sorted order.
+    sorted: OrderStatistics<T>,
+}
+
+impl<T> WindowQuantile<T>
+where
+    T: Ord + Clone,
+{
+    /// Returns `true` if no values have been pushed.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the number of values in the window.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns a tracker for the quantile `q` of the last `window` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window == 0` or if `q` is not in the range `[0.0, 1.0]`.
+    pub fn new(window: usize, q: f64) -> Self {
+        assert!(window > 0, "the window must not be empty");
+        assert!(
+            (0.0..=1.0).contains(&q),
+            "`q` must be in the range [0.0, 1.0]"
+        );
+        Self {
+            window,
+            q,
+            values: VecDeque::with_capacity(window),
+            sorted: OrderStatistics::new(),
+        }
+    }
+
+    /// Pushes `value` into the window. If the window was full, the oldest value is evicted and
+    /// returned.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        let evicted = if self.values.len() == self.window {
+            let oldest = self.values.pop_front()?;
+            self.sorted.remove(&oldest);
+            Some(oldest)
+        } else {
+            None
+        };
+        self.sorted.insert(value.clone());
+        self.values.push_back(value);
+        evicted
+    }
+
+    /// Returns the value at rank `floor(q * (len - 1))` among the values in the window, or `None`
+    /// if the window is empty.
+    pub fn quantile(&self) -> Option<&T> {
+        let last = self.values.len().checked_sub(1)?;
+        let rank = floor(self.q * last as f64) as usize;
+        self.sorted.kth(rank)
+    }
+}
+
+/// Serializes the window size, the quantile and the values in arrival order. The sorted values are
+/// rebuilt from them when deserializing.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for WindowQuantile<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("WindowQuantile", 3)?;
+        state.serialize_field("window", &self.window)?;
+        state.serialize_field("q", &self.q)?;
+        state.serialize_field("values", &self.values)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for WindowQuantile<T>
+where
+    T: Ord + Clone + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "WindowQuantile")]
+        struct State<T> {
+            window: usize,
+            q: f64,
+            values: VecDeque<T>,
+        }
+
+        let state = State::<T>::deserialize(deserializer)?;
+        if state.window == 0 {
+            return Err(D::Error::custom("the window must not be empty"));
+        }
+        if !(0.0..=1.0).contains(&state.q) {
+            return Err(D::Error::custom("`q` must be in the range [0.0, 1.0]"));
+        }
+        if state.values.len() > state.window {
+            return Err(D::Error::custom("more values than fit in the window"));
+        }
+        let mut sorted = OrderStatistics::new();
+        for value in &state.values {
+            sorted.insert(value.clone());
+        }
+        Ok(Self {
+            window: state.window,
+            q: state.q,
+            values: state.values,
+            sorted,
+        })
+    }
+}
@@ -1,5 +1,18 @@
 #![allow(dead_code)]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+use core::cell::Cell;
+#[cfg(feature = "std")]
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    string::String,
+    vec::Vec,
+};
+
 use crate::math::{exp2, floor, log, powf};
 
 /// A pseudorandom number generator that uses the WyRand algorithm.
@@ -8,6 +21,26 @@ pub struct WyRng {
     state: u64,
 }
 
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The state of the generator that seeds the generators returned by `thread_rng`. It is
+    /// seeded from the random keys of the standard library's `HashMap`.
+    static THREAD_STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish());
+}
+
+/// Returns a new PRNG with a seed drawn from a thread-local generator. The thread-local generator
+/// is seeded randomly when first used on each thread, so that the sequences differ between calls,
+/// threads and runs of the program, without having to pass a PRNG around.
+#[cfg(feature = "std")]
+pub(crate) fn thread_rng() -> WyRng {
+    THREAD_STATE.with(|state| {
+        let mut rng = WyRng { state: state.get() };
+        let split = rng.split();
+        state.set(rng.state);
+        split
+    })
+}
+
 /// An iterator over `count` sequential pseudorandom `usize`s in the range `[0, bound)`. Modified
 /// from
 ///
@@ -102,16 +135,66 @@ impl<'a> Iterator for HiddenShuffle<'a> {
 }
 
 impl WyRng {
+    /// Returns a string of printable ASCII characters, with a length in the range `[low, high)`.
+    #[cfg(feature = "std")]
+    pub fn ascii_string(&mut self, low: usize, high: usize) -> String {
+        let len = self.bounded_usize(low, high);
+        (0..len)
+            .map(|_| self.bounded_u8(b' ', b'~' + 1) as char)
+            .collect()
+    }
+
     /// Returns a `bool`.
     pub fn bool(&mut self) -> bool {
         self.u64() > u64::MAX / 2
     }
 
+    /// Returns a `f32` in the range `[low, high)`.
+    pub fn bounded_f32(&mut self, low: f32, high: f32) -> f32 {
+        self.f32() * (high - low) + low
+    }
+
     /// Returns a `f64` in the range `[low, high)`.
     pub fn bounded_f64(&mut self, low: f64, high: f64) -> f64 {
         self.f64() * (high - low) + low
     }
 
+    /// Returns an `i8` in the range `[low, high)`.
+    pub fn bounded_i8(&mut self, low: i8, high: i8) -> i8 {
+        let range = high.wrapping_sub(low) as u8;
+        low.wrapping_add(self.bounded_u8(0, range) as i8)
+    }
+
+    /// Returns an `i16` in the range `[low, high)`.
+    pub fn bounded_i16(&mut self, low: i16, high: i16) -> i16 {
+        let range = high.wrapping_sub(low) as u16;
+        low.wrapping_add(self.bounded_u16(0, range) as i16)
+    }
+
+    /// Returns an `i32` in the range `[low, high)`.
+    pub fn bounded_i32(&mut self, low: i32, high: i32) -> i32 {
+        let range = high.wrapping_sub(low) as u32;
+        low.wrapping_add(self.bounded_u32(0, range) as i32)
+    }
+
+    /// Returns an `i64` in the range `[low, high)`.
+    pub fn bounded_i64(&mut self, low: i64, high: i64) -> i64 {
+        let range = high.wrapping_sub(low) as u64;
+        low.wrapping_add(self.bounded_u64(0, range) as i64)
+    }
+
+    /// Returns an `i128` in the range `[low, high)`.
+    pub fn bounded_i128(&mut self, low: i128, high: i128) -> i128 {
+        let range = high.wrapping_sub(low) as u128;
+        low.wrapping_add(self.bounded_u128(0, range) as i128)
+    }
+
+    /// Returns an `isize` in the range `[low, high)`.
+    pub fn bounded_isize(&mut self, low: isize, high: isize) -> isize {
+        let range = high.wrapping_sub(low) as usize;
+        low.wrapping_add(self.bounded_usize(0, range) as isize)
+    }
+
     /// Returns a `u8` in the range `[low, high)`.
     pub fn bounded_u8(&mut self, low: u8, high: u8) -> u8 {
         let range = high - low;
@@ -216,9 +299,10 @@ impl WyRng {
         }
     }
 
-    /// Returns a `usize` in the range `[0, bound)`.
+    /// Returns a `usize` in the range `[low, high)`.
     pub fn bounded_usize(&mut self, low: usize, high: usize) -> usize {
         match core::mem::size_of::<usize>() {
+            2 => self.bounded_u16(low as u16, high as u16) as usize,
             4 => self.bounded_u32(low as u32, high as u32) as usize,
             8 => self.bounded_u64(low as u64, high as u64) as usize,
             16 => self.bounded_u128(low as u128, high as u128) as usize,
@@ -226,11 +310,46 @@ impl WyRng {
         }
     }
 
+    /// Returns a `f32` in the range `[0, 1)`.
+    pub fn f32(&mut self) -> f32 {
+        ((self.u32() >> 8) as f32) * exp2(-24_f64) as f32
+    }
+
     /// Returns a `f64` in the range `[0, 1)`.
     pub fn f64(&mut self) -> f64 {
         ((self.u64() >> 11) as f64) * exp2(-53_f64)
     }
 
+    /// Returns an `i8`.
+    pub fn i8(&mut self) -> i8 {
+        self.u8() as i8
+    }
+
+    /// Returns an `i16`.
+    pub fn i16(&mut self) -> i16 {
+        self.u16() as i16
+    }
+
+    /// Returns an `i32`.
+    pub fn i32(&mut self) -> i32 {
+        self.u32() as i32
+    }
+
+    /// Returns an `i64`.
+    pub fn i64(&mut self) -> i64 {
+        self.u64() as i64
+    }
+
+    /// Returns an `i128`.
+    pub fn i128(&mut self) -> i128 {
+        self.u128() as i128
+    }
+
+    /// Returns an `isize`.
+    pub fn isize(&mut self) -> isize {
+        self.usize() as isize
+    }
+
     /// Returns a new PRNG initialized with the given seed. If the seed is set to 0, the seed is
     /// based on the address of the PRNG. This should yield an unique sequence for each run of the
     /// program.
@@ -257,6 +376,41 @@ impl WyRng {
         HiddenShuffle::new(self, bound, count)
     }
 
+    /// Returns a new PRNG seeded with the next output of this one. The sequence of the new PRNG is
+    /// unrelated to the rest of this one's, but it's reproducible: splitting a PRNG created with
+    /// the same seed always gives the same PRNGs. This is useful for deriving a PRNG for each
+    /// worker in parallel tests and benchmarks.
+    pub fn split(&mut self) -> Self {
+        // Avoid the special meaning of a zero seed in `new`.
+        Self::new(self.u64() | 1)
+    }
+
+    /// Returns a string of `char`s drawn uniformly from all Unicode scalar values, with a length in
+    /// `char`s in the range `[low, high)`.
+    #[cfg(feature = "std")]
+    pub fn utf8_string(&mut self, low: usize, high: usize) -> String {
+        const SURROGATES: u32 = 0xe000 - 0xd800;
+        let len = self.bounded_usize(low, high);
+        (0..len)
+            .map(|_| {
+                // Skip over the surrogate code points, which are not valid `char`s.
+                let x = self.bounded_u32(0, char::MAX as u32 + 1 - SURROGATES);
+                let x = if x >= 0xd800 { x + SURROGATES } else { x };
+                char::from_u32(x).unwrap()
+            })
+            .collect()
+    }
+
+    /// Returns a vector with a length in the range `[low, high)`, with elements generated by `f`.
+    #[cfg(feature = "std")]
+    pub fn vec<T, F>(&mut self, low: usize, high: usize, mut f: F) -> Vec<T>
+    where
+        F: FnMut(&mut Self) -> T,
+    {
+        let len = self.bounded_usize(low, high);
+        (0..len).map(|_| f(self)).collect()
+    }
+
     /// Returns a `u8`.
     pub fn u8(&mut self) -> u8 {
         (self.u64() >> 56) as u8
@@ -286,6 +440,7 @@ impl WyRng {
     /// Returns a `usize`.
     pub fn usize(&mut self) -> usize {
         match core::mem::size_of::<usize>() {
+            2 => self.u16() as usize,
             4 => self.u32() as usize,
             8 => self.u64() as usize,
             16 => self.u128() as usize,